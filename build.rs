@@ -1,3 +1,32 @@
+use std::env;
+use std::fs;
+use std::path::Path;
+
 fn main() {
     built::write_built_file().expect("Failed to acquire build-time information");
+    write_features_file().expect("Failed to record enabled cargo features");
+}
+
+/// Emit the active cargo features next to `built.rs` so `openit version` can report them.
+fn write_features_file() -> std::io::Result<()> {
+    let mut features: Vec<String> = env::vars()
+        .filter_map(|(key, _)| {
+            key.strip_prefix("CARGO_FEATURE_")
+                .map(|name| name.to_ascii_lowercase().replace('_', "-"))
+        })
+        .filter(|name| name != "default")
+        .collect();
+    features.sort();
+
+    let list = features
+        .iter()
+        .map(|feature| format!("{feature:?}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    let out_dir = env::var("OUT_DIR").expect("OUT_DIR is set by cargo");
+    fs::write(
+        Path::new(&out_dir).join("features.rs"),
+        format!("#[allow(dead_code)]\npub static ENABLED_FEATURES: &[&str] = &[{list}];\n"),
+    )
 }
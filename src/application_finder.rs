@@ -1,11 +1,25 @@
-use crate::cache::DesktopCache;
+use crate::cache::{DesktopCache, LookupEpoch, NoHandlerMimes};
+use crate::config::TieBreaker;
 use crate::desktop_parser::DesktopFile;
-use crate::mime_associations::MimeAssociations;
+use crate::mime_associations::{normalize_desktop_id, MimeAssociations};
 use crate::mime_pattern;
+use crate::mime_subclasses::MimeSubclasses;
+use log::debug;
 use serde::{Deserialize, Serialize};
+use std::cell::{OnceCell, RefCell};
+use std::collections::hash_map::DefaultHasher;
 use std::collections::{HashMap, HashSet};
 use std::fmt;
-use std::path::PathBuf;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// Generic openers that only forward the resource to another handler.
+const OPENER_WRAPPERS: &[&str] = &["xdg-open", "gio", "openit"];
+
+/// Score of the first XDG association; later ones get `XDG_WEIGHT / (priority + 1)`.
+const XDG_WEIGHT: f32 = 100.0;
+const DEFAULT_WEIGHT: f32 = 100.0;
+const PREFERRED_WEIGHT: f32 = 100.0;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ApplicationEntry {
@@ -20,13 +34,77 @@ pub struct ApplicationEntry {
     pub action_id: Option<String>,
     pub requires_terminal: bool,
     pub is_terminal_emulator: bool,
+    /// Working directory from the desktop entry's `Path=` key.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<PathBuf>,
+    /// `PrefersNonDefaultGPU=true`: launch on the discrete GPU.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub prefers_non_default_gpu: bool,
+    /// Feed the target file on stdin and wait for the handler (`stdin_input` / `--pipe`).
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stdin_input: bool,
+    /// `mimeapps.list` section that associated this entry with the MIME type.
+    #[serde(default)]
+    pub association: Association,
+    /// Parent MIME type this entry was matched through when nothing handled the
+    /// requested type directly.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_mime: Option<String>,
+    /// `StartupNotify=true`: export a startup ID so the launch gets feedback.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub startup_notify: bool,
+    /// Ask before launching this entry.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub confirm: bool,
+    /// Relevance computed by `find_for_mime`; higher sorts first.
+    #[serde(default)]
+    pub score: f32,
+    /// `DBusActivatable=true`: the application can be opened over D-Bus.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub dbus_activatable: bool,
+    /// `StartupWMClass=`: the window class the application's windows carry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub startup_wm_class: Option<String>,
+    /// `SingleMainWindow=true`: the application never opens a second main window.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub single_main_window: bool,
+    /// `GenericName=`, e.g. `Web Browser`, in the finder's locale.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub generic_name: Option<String>,
+    /// `Keywords=` in the finder's locale, matched by `--match` along with the name.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    /// Where this entry came from; only desktop files have actions to run.
+    #[serde(skip)]
+    pub source: ApplicationSource,
+}
+
+/// Provenance of an entry in `mimeapps.list`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Association {
+    /// Listed under `[Default Applications]`.
+    Default,
+    /// Listed under `[Added Associations]` only.
+    Added,
+    /// Found by scanning desktop files.
+    #[default]
+    None,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
 pub enum ApplicationSource {
+    #[default]
     Available,
-    Xdg { priority: i32, is_default: bool },
-    Regex { priority: i32 },
+    Xdg {
+        priority: i32,
+        is_default: bool,
+    },
+    Regex {
+        priority: i32,
+    },
+    /// A built-in stdin filter offered when no desktop entry handles the file.
+    Fallback,
 }
 
 impl ApplicationEntry {
@@ -49,6 +127,20 @@ impl ApplicationEntry {
                 .categories
                 .iter()
                 .any(|category| category == "TerminalEmulator"),
+            working_dir: entry.working_dir(),
+            prefers_non_default_gpu: entry.prefers_non_default_gpu,
+            stdin_input: false,
+            association: Association::None,
+            parent_mime: None,
+            startup_notify: entry.startup_notify,
+            confirm: false,
+            score: 0.0,
+            dbus_activatable: entry.dbus_activatable,
+            startup_wm_class: entry.startup_wm_class.clone(),
+            single_main_window: entry.single_main_window,
+            generic_name: entry.generic_name.clone(),
+            keywords: entry.keywords.clone(),
+            source: ApplicationSource::Available,
         }
     }
 
@@ -73,15 +165,31 @@ impl ApplicationEntry {
                 .categories
                 .iter()
                 .any(|category| category == "TerminalEmulator"),
+            working_dir: main_entry.working_dir(),
+            prefers_non_default_gpu: main_entry.prefers_non_default_gpu,
+            stdin_input: false,
+            association: Association::None,
+            parent_mime: None,
+            startup_notify: main_entry.startup_notify,
+            confirm: false,
+            score: 0.0,
+            dbus_activatable: main_entry.dbus_activatable,
+            startup_wm_class: main_entry.startup_wm_class.clone(),
+            single_main_window: main_entry.single_main_window,
+            generic_name: main_entry.generic_name.clone(),
+            keywords: main_entry.keywords.clone(),
+            source: ApplicationSource::Available,
         }
     }
 
     pub fn with_source(mut self, source: ApplicationSource) -> Self {
+        self.source = source;
         match source {
-            ApplicationSource::Available => {
+            ApplicationSource::Available | ApplicationSource::Fallback => {
                 self.is_xdg = false;
                 self.xdg_priority = -1;
                 self.is_default = false;
+                self.association = Association::None;
             }
             ApplicationSource::Xdg {
                 priority,
@@ -90,20 +198,66 @@ impl ApplicationEntry {
                 self.is_xdg = true;
                 self.xdg_priority = priority;
                 self.is_default = is_default;
+                self.association = Association::Added;
             }
             ApplicationSource::Regex { priority } => {
                 self.is_xdg = false;
                 self.xdg_priority = priority;
                 self.is_default = false;
+                self.association = Association::None;
             }
         }
         self
     }
+
+    /// The class window rules match on: `StartupWMClass`, else the desktop ID without
+    /// `.desktop`, which most toolkits use as the Wayland app ID.
+    pub fn window_class(&self) -> Option<String> {
+        self.startup_wm_class.clone().or_else(|| {
+            desktop_id_of(&self.desktop_file)
+                .strip_suffix(".desktop")
+                .filter(|id| !id.is_empty())
+                .map(str::to_string)
+        })
+    }
+
+    /// Whether this entry was built from a `[[regex_handlers]]` rule rather than read from
+    /// a desktop file.
+    pub fn is_regex_handler(&self) -> bool {
+        matches!(self.source, ApplicationSource::Regex { .. })
+    }
+
+    /// Whether this entry is one of the built-in fallback openers.
+    pub fn is_fallback_opener(&self) -> bool {
+        self.source == ApplicationSource::Fallback
+    }
 }
 
 pub struct ApplicationFinder {
     desktop_cache: Box<dyn DesktopCache>,
     mime_associations: MimeAssociations,
+    mime_subclasses: MimeSubclasses,
+    skip_unavailable: bool,
+    tie_breaker: TieBreaker,
+    allow_wildcard_handlers: bool,
+    parent_fallback: bool,
+    hide_opener_wrappers: bool,
+    preferred_apps: Vec<String>,
+    /// Desktops from `XDG_CURRENT_DESKTOP` that `OnlyShowIn`/`NotShowIn` are checked
+    /// against; `None` shows entries on every desktop.
+    current_desktops: Option<Vec<String>>,
+    /// Locale used to pick `Name[locale]` and `Comment[locale]`; `None` uses the plain keys.
+    locale: Option<String>,
+    /// `applications/` directories in XDG precedence order; earlier ones shadow later ones.
+    desktop_dirs: Vec<PathBuf>,
+    /// MIME types an earlier lookup found no handlers for, so probing them again skips
+    /// the scan.
+    no_handler_mimes: RefCell<NoHandlerMimes>,
+    /// Where `no_handler_mimes` is persisted; `None` remembers them for this run only.
+    no_handler_mimes_path: Option<PathBuf>,
+    /// Hash of every input besides the desktop cache that decides whether a type has
+    /// handlers; computed on the first lookup, after the builders ran.
+    lookup_fingerprint: OnceCell<u64>,
 }
 
 impl fmt::Debug for ApplicationFinder {
@@ -112,6 +266,17 @@ impl fmt::Debug for ApplicationFinder {
             // Desktop cache is a trait object; surface useful summary instead of Debug.
             .field("desktop_cache_len", &self.desktop_cache.len())
             .field("mime_associations", &self.mime_associations)
+            .field("mime_subclasses", &self.mime_subclasses)
+            .field("skip_unavailable", &self.skip_unavailable)
+            .field("tie_breaker", &self.tie_breaker)
+            .field("allow_wildcard_handlers", &self.allow_wildcard_handlers)
+            .field("parent_fallback", &self.parent_fallback)
+            .field("hide_opener_wrappers", &self.hide_opener_wrappers)
+            .field("preferred_apps", &self.preferred_apps)
+            .field("current_desktops", &self.current_desktops)
+            .field("locale", &self.locale)
+            .field("desktop_dirs", &self.desktop_dirs)
+            .field("no_handler_mimes_path", &self.no_handler_mimes_path)
             .finish()
     }
 }
@@ -121,16 +286,289 @@ impl ApplicationFinder {
         Self {
             desktop_cache,
             mime_associations,
+            mime_subclasses: MimeSubclasses::default(),
+            skip_unavailable: false,
+            tie_breaker: TieBreaker::default(),
+            allow_wildcard_handlers: true,
+            parent_fallback: true,
+            hide_opener_wrappers: false,
+            preferred_apps: Vec::new(),
+            current_desktops: Some(crate::xdg::get_desktop_environment_names()),
+            locale: crate::desktop_parser::current_locale(),
+            desktop_dirs: crate::xdg::get_desktop_file_paths(),
+            no_handler_mimes: RefCell::default(),
+            no_handler_mimes_path: None,
+            lookup_fingerprint: OnceCell::new(),
+        }
+    }
+
+    /// Override the directory precedence used to pick between copies of one desktop ID.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_desktop_dirs(mut self, desktop_dirs: Vec<PathBuf>) -> Self {
+        self.desktop_dirs = desktop_dirs;
+        self
+    }
+
+    /// Override the locale taken from `LC_ALL`, `LC_MESSAGES` or `LANG`.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_locale(mut self, locale: Option<String>) -> Self {
+        self.locale = locale;
+        self
+    }
+
+    /// Choose how entries with equal priority are ordered.
+    pub fn with_tie_breaker(mut self, tie_breaker: TieBreaker) -> Self {
+        self.tie_breaker = tie_breaker;
+        self
+    }
+
+    /// Fall back to parent MIME types when nothing handles the requested type directly.
+    pub fn with_mime_subclasses(mut self, mime_subclasses: MimeSubclasses) -> Self {
+        self.mime_subclasses = mime_subclasses;
+        self
+    }
+
+    /// Toggle the fallback to parent MIME types (and `text/plain` for `text/*`).
+    pub fn with_parent_fallback(mut self, parent_fallback: bool) -> Self {
+        self.parent_fallback = parent_fallback;
+        self
+    }
+
+    /// When disabled, `MimeType` patterns containing `*` are ignored while scanning for
+    /// available handlers; explicit XDG associations still apply.
+    pub fn with_wildcard_handlers(mut self, allow_wildcard_handlers: bool) -> Self {
+        self.allow_wildcard_handlers = allow_wildcard_handlers;
+        self
+    }
+
+    /// Hide entries whose `Exec` just forwards to `xdg-open`, `gio` or `openit`.
+    pub fn with_opener_wrappers_hidden(mut self, hide_opener_wrappers: bool) -> Self {
+        self.hide_opener_wrappers = hide_opener_wrappers;
+        self
+    }
+
+    /// Boost the listed desktop IDs above other handlers without an explicit association.
+    pub fn with_preferred_apps(mut self, preferred_apps: Vec<String>) -> Self {
+        self.preferred_apps = preferred_apps;
+        self
+    }
+
+    /// Hide entries whose `TryExec` binary is not installed.
+    pub fn with_skip_unavailable(mut self, skip_unavailable: bool) -> Self {
+        self.skip_unavailable = skip_unavailable;
+        self
+    }
+
+    /// Keep entries whose `OnlyShowIn`/`NotShowIn` exclude the current desktop.
+    pub fn with_all_desktops(mut self, include_all_desktops: bool) -> Self {
+        if include_all_desktops {
+            self.current_desktops = None;
+        }
+        self
+    }
+
+    /// Remember MIME types without handlers across runs in the file at `path`. A file
+    /// that cannot be read starts empty.
+    pub fn with_persisted_no_handler_mimes(mut self, path: PathBuf) -> Self {
+        match NoHandlerMimes::load(&path) {
+            Ok(no_handler_mimes) => *self.no_handler_mimes.get_mut() = no_handler_mimes,
+            Err(err) => debug!("Ignoring negative lookup cache: {err:#}"),
+        }
+        self.no_handler_mimes_path = Some(path);
+        self
+    }
+
+    /// An entry for `entry` with its name, comment, generic name and keywords in the
+    /// finder's locale.
+    fn localized_entry(
+        &self,
+        entry: &crate::desktop_parser::DesktopEntry,
+        path: &Path,
+    ) -> ApplicationEntry {
+        let mut app = ApplicationEntry::from_desktop_entry(entry, path.to_path_buf());
+        if let Some(locale) = &self.locale {
+            app.name = entry.name_for_locale(locale).to_string();
+            app.comment = entry.comment_for_locale(locale).map(str::to_string);
+            app.generic_name = entry.generic_name_for_locale(locale).map(str::to_string);
+            app.keywords = entry.keywords_for_locale(locale).to_vec();
+        }
+        app
+    }
+
+    fn shown_on_current_desktop(&self, entry: &crate::desktop_parser::DesktopEntry) -> bool {
+        let Some(desktops) = &self.current_desktops else {
+            return true;
+        };
+        let shown = entry.shown_in(desktops);
+        if !shown {
+            debug!(
+                "`{}` is not shown on {}",
+                entry.name,
+                if desktops.is_empty() {
+                    "an unnamed desktop".to_string()
+                } else {
+                    desktops.join(":")
+                }
+            );
         }
+        shown
+    }
+
+    fn is_launchable(&self, entry: &crate::desktop_parser::DesktopEntry) -> bool {
+        if !self.shown_on_current_desktop(entry) {
+            return false;
+        }
+        if let Some(wrapper) = opener_wrapper(&entry.exec) {
+            debug!(
+                "`{}` forwards to `{wrapper}` instead of handling the resource itself",
+                entry.name
+            );
+            if self.hide_opener_wrappers {
+                return false;
+            }
+        }
+        !self.skip_unavailable || entry.validate()
     }
 
     pub fn find_for_mime(&self, mime_type: &str, include_actions: bool) -> Vec<ApplicationEntry> {
-        let mut applications = Vec::new();
+        let epoch = self.lookup_epoch();
+        if self.no_handler_mimes.borrow().contains(epoch, mime_type) {
+            debug!("{mime_type} had no handlers in an earlier lookup; skipping the scan");
+            return Vec::new();
+        }
+
         let mut seen = HashSet::new();
+        let mut applications = self.find_direct(mime_type, include_actions, &mut seen);
+
+        if applications.is_empty() && self.parent_fallback {
+            for parent in self.parent_types(mime_type) {
+                let start = applications.len();
+                applications.extend(self.find_direct(&parent, include_actions, &mut seen));
+                for app in &mut applications[start..] {
+                    app.parent_mime = Some(parent.clone());
+                }
+            }
+        }
+
+        self.break_ties(&mut applications);
+        for app in &mut applications {
+            app.score = self.score(app);
+        }
+        // Stable, so tie-broken order survives among equal scores.
+        applications.sort_by(|a, b| b.score.total_cmp(&a.score));
+        if applications.is_empty() {
+            self.remember_no_handlers(epoch, mime_type);
+        }
+        applications
+    }
+
+    fn lookup_epoch(&self) -> LookupEpoch {
+        let fingerprint = *self.lookup_fingerprint.get_or_init(|| {
+            let mut hasher = DefaultHasher::new();
+            self.mime_associations.hash(&mut hasher);
+            self.mime_subclasses.hash(&mut hasher);
+            self.desktop_dirs.hash(&mut hasher);
+            self.current_desktops.hash(&mut hasher);
+            (
+                self.skip_unavailable,
+                self.allow_wildcard_handlers,
+                self.parent_fallback,
+                self.hide_opener_wrappers,
+            )
+                .hash(&mut hasher);
+            hasher.finish()
+        });
+        LookupEpoch {
+            generation: self.desktop_cache.generation(),
+            fingerprint,
+        }
+    }
+
+    /// Saving is best effort: a file that cannot be written only costs the next run a scan.
+    fn remember_no_handlers(&self, epoch: LookupEpoch, mime_type: &str) {
+        let mut no_handler_mimes = self.no_handler_mimes.borrow_mut();
+        if !no_handler_mimes.insert(epoch, mime_type) {
+            return;
+        }
+        if let Some(path) = &self.no_handler_mimes_path {
+            if let Err(err) = no_handler_mimes.save(path) {
+                debug!("Could not save the negative lookup cache: {err:#}");
+            }
+        }
+    }
+
+    /// Relevance of an entry: XDG association rank, the default flag and `preferred_apps`.
+    ///
+    /// XDG entries score `XDG_WEIGHT / (priority + 1)`, so the first association beats the
+    /// second and both beat merely available handlers. A preferred entry outranks every
+    /// association but the default.
+    fn score(&self, app: &ApplicationEntry) -> f32 {
+        let mut score = 0.0;
+        if app.is_xdg {
+            score += XDG_WEIGHT / (app.xdg_priority.max(0) as f32 + 1.0);
+        }
+        if app.is_default {
+            score += DEFAULT_WEIGHT;
+        }
+        let desktop_id = desktop_id_of(&app.desktop_file);
+        if self.preferred_apps.iter().any(|preferred| {
+            desktop_id == preferred.as_str() || desktop_id == format!("{preferred}.desktop")
+        }) {
+            score += PREFERRED_WEIGHT;
+        }
+        score
+    }
+
+    /// Ancestors from shared-mime-info, nearest first, ending with `text/plain` for any
+    /// `text/*` type.
+    fn parent_types(&self, mime_type: &str) -> Vec<String> {
+        let mut parents = self.mime_subclasses.ancestors(mime_type);
+        let normalized = mime_type.trim().to_ascii_lowercase();
+        if normalized.starts_with("text/")
+            && normalized != "text/plain"
+            && !parents.iter().any(|parent| parent == "text/plain")
+        {
+            parents.push("text/plain".to_string());
+        }
+        parents
+    }
+
+    /// Reorder runs of entries sharing the same source and priority so the result
+    /// doesn't depend on cache iteration order or merged `mimeapps.list` files.
+    fn break_ties(&self, applications: &mut [ApplicationEntry]) {
+        let mut start = 0;
+        while start < applications.len() {
+            let key = tie_key(&applications[start]);
+            let end = applications[start..]
+                .iter()
+                .position(|app| tie_key(app) != key)
+                .map_or(applications.len(), |offset| start + offset);
+
+            let run = &mut applications[start..end];
+            match self.tie_breaker {
+                TieBreaker::Alpha => run.sort_by(|a, b| alpha_key(a).cmp(&alpha_key(b))),
+                TieBreaker::Mtime => run.sort_by(|a, b| {
+                    modified_time(b)
+                        .cmp(&modified_time(a))
+                        .then_with(|| alpha_key(a).cmp(&alpha_key(b)))
+                }),
+            }
+
+            start = end;
+        }
+    }
+
+    fn find_direct(
+        &self,
+        mime_type: &str,
+        include_actions: bool,
+        seen: &mut HashSet<String>,
+    ) -> Vec<ApplicationEntry> {
+        let mut applications: Vec<ApplicationEntry> = Vec::new();
 
         let xdg_associations = self.mime_associations.get_associations(mime_type);
 
-        let cache_entries: Vec<(&PathBuf, &DesktopFile)> = self.desktop_cache.iter().collect();
+        let cache_entries = self.entries();
         let mut suffix_map: HashMap<String, usize> = HashMap::new();
 
         for (index, &(path, _)) in cache_entries.iter().enumerate() {
@@ -150,34 +588,60 @@ impl ApplicationFinder {
         for (priority, desktop_id) in xdg_associations.iter().enumerate() {
             if let Some(&entry_index) = suffix_map.get(desktop_id.as_str()) {
                 let (path, desktop_file) = cache_entries[entry_index];
-                if seen.insert(desktop_id.clone()) {
-                    if let Some(entry) = &desktop_file.main_entry {
-                        let priority_i32 = i32::try_from(priority).unwrap_or(i32::MAX);
-                        let is_default = priority == 0;
+                let is_default = priority == 0;
+                let listed_default = self
+                    .mime_associations
+                    .is_listed_default(mime_type, desktop_id);
+                // Key on the ID of the resolved file so different spellings of the same
+                // entry (and the available scan below) collapse onto one candidate.
+                let normalized_id = desktop_id_of(path);
+                if self.mime_associations.is_removed(mime_type, &normalized_id) {
+                    continue;
+                }
 
-                        let app_entry = ApplicationEntry::from_desktop_entry(entry, path.clone())
+                if !seen.insert(normalized_id.clone()) {
+                    if let Some(existing) = applications.iter_mut().find(|app| {
+                        app.action_id.is_none() && desktop_id_of(&app.desktop_file) == normalized_id
+                    }) {
+                        existing.is_default |= is_default;
+                        if listed_default {
+                            existing.association = Association::Default;
+                        }
+                    }
+                    continue;
+                }
+
+                if let Some(entry) = desktop_file
+                    .main_entry
+                    .as_ref()
+                    .filter(|entry| self.is_launchable(entry))
+                {
+                    let priority_i32 = i32::try_from(priority).unwrap_or(i32::MAX);
+
+                    let mut app_entry =
+                        self.localized_entry(entry, path)
                             .with_source(ApplicationSource::Xdg {
                                 priority: priority_i32,
                                 is_default,
                             });
-                        applications.push(app_entry);
-
-                        if include_actions {
-                            for (action_id, action) in &desktop_file.actions {
-                                let action_app = ApplicationEntry::from_desktop_action(
-                                    entry,
-                                    action_id,
-                                    action,
-                                    path.clone(),
-                                )
-                                .with_source(
-                                    ApplicationSource::Xdg {
-                                        priority: priority_i32,
-                                        is_default: false,
-                                    },
-                                );
-                                applications.push(action_app);
-                            }
+                    if listed_default {
+                        app_entry.association = Association::Default;
+                    }
+                    applications.push(app_entry);
+
+                    if include_actions {
+                        for (action_id, action) in desktop_file.ordered_actions() {
+                            let action_app = ApplicationEntry::from_desktop_action(
+                                entry,
+                                action_id,
+                                action,
+                                path.clone(),
+                            )
+                            .with_source(ApplicationSource::Xdg {
+                                priority: priority_i32,
+                                is_default: false,
+                            });
+                            applications.push(action_app);
                         }
                     }
                 }
@@ -187,33 +651,36 @@ impl ApplicationFinder {
         // Add other applications that support this MIME type
         for &(path, desktop_file) in &cache_entries {
             if let Some(entry) = &desktop_file.main_entry {
-                if entry
+                if !self.is_launchable(entry) {
+                    continue;
+                }
+
+                let supported = entry
                     .mime_types
                     .iter()
-                    .any(|pattern| mime_pattern::matches(pattern, mime_type))
+                    .filter(|pattern| self.allow_wildcard_handlers || !pattern.contains('*'))
+                    .any(|pattern| mime_pattern::matches(pattern, mime_type));
+
+                let desktop_id = desktop_id_of(path);
+                if supported
+                    && !self.mime_associations.is_removed(mime_type, &desktop_id)
+                    && seen.insert(desktop_id)
                 {
-                    let desktop_id = path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("")
-                        .to_string();
-
-                    if seen.insert(desktop_id) {
-                        let app = ApplicationEntry::from_desktop_entry(entry, path.clone())
+                    let app = self
+                        .localized_entry(entry, path)
+                        .with_source(ApplicationSource::Available);
+                    applications.push(app);
+
+                    if include_actions {
+                        for (action_id, action) in desktop_file.ordered_actions() {
+                            let action_app = ApplicationEntry::from_desktop_action(
+                                entry,
+                                action_id,
+                                action,
+                                path.clone(),
+                            )
                             .with_source(ApplicationSource::Available);
-                        applications.push(app);
-
-                        if include_actions {
-                            for (action_id, action) in &desktop_file.actions {
-                                let action_app = ApplicationEntry::from_desktop_action(
-                                    entry,
-                                    action_id,
-                                    action,
-                                    path.clone(),
-                                )
-                                .with_source(ApplicationSource::Available);
-                                applications.push(action_app);
-                            }
+                            applications.push(action_app);
                         }
                     }
                 }
@@ -223,28 +690,170 @@ impl ApplicationFinder {
         applications
     }
 
+    /// Every application in the cache, sorted by desktop ID, whatever MIME types it handles.
+    ///
+    /// `NoDisplay=true` and `Hidden=true` entries are skipped unless `include_hidden`. An
+    /// entry associated with one of its MIME types in `mimeapps.list` is marked XDG with
+    /// its best position there; being first for any type makes it a default.
+    pub fn find_all(&self, include_actions: bool, include_hidden: bool) -> Vec<ApplicationEntry> {
+        let mut entries = self.entries();
+        entries.sort_by_key(|(path, _)| desktop_id_of(path));
+
+        let mut applications = Vec::new();
+        for (path, desktop_file) in entries {
+            let Some(entry) = &desktop_file.main_entry else {
+                continue;
+            };
+            if (!include_hidden && (entry.no_display || entry.hidden)) || !self.is_launchable(entry)
+            {
+                continue;
+            }
+            let desktop_id = desktop_id_of(path);
+            let source = self.association_source(entry, &desktop_id);
+            let mut app = self.localized_entry(entry, path).with_source(source);
+            if entry
+                .mime_types
+                .iter()
+                .any(|mime| self.mime_associations.is_listed_default(mime, &desktop_id))
+            {
+                app.association = Association::Default;
+            }
+            applications.push(app);
+
+            if include_actions {
+                // Like `find_for_mime`, actions share the rank but never the default flag.
+                let action_source = match source {
+                    ApplicationSource::Xdg { priority, .. } => ApplicationSource::Xdg {
+                        priority,
+                        is_default: false,
+                    },
+                    other => other,
+                };
+                for (action_id, action) in desktop_file.ordered_actions() {
+                    applications.push(
+                        ApplicationEntry::from_desktop_action(
+                            entry,
+                            action_id,
+                            action,
+                            path.clone(),
+                        )
+                        .with_source(action_source),
+                    );
+                }
+            }
+        }
+        applications
+    }
+
+    /// Visible applications sorted by name for `--any-handler`, with the same filtering as
+    /// MIME lookups.
+    pub fn all_applications(&self) -> Vec<ApplicationEntry> {
+        let mut applications = self.find_all(false, false);
+        applications.sort_by(|a, b| {
+            a.name
+                .to_lowercase()
+                .cmp(&b.name.to_lowercase())
+                .then_with(|| a.desktop_file.cmp(&b.desktop_file))
+        });
+        applications
+    }
+
+    /// Best `mimeapps.list` position of `desktop_id` across the entry's MIME types.
+    fn association_source(
+        &self,
+        entry: &crate::desktop_parser::DesktopEntry,
+        desktop_id: &str,
+    ) -> ApplicationSource {
+        let best = entry
+            .mime_types
+            .iter()
+            .filter_map(|mime| {
+                self.mime_associations
+                    .get_associations(mime)
+                    .iter()
+                    .position(|associated| normalize_desktop_id(associated) == desktop_id)
+            })
+            .min();
+        match best {
+            Some(position) => ApplicationSource::Xdg {
+                priority: i32::try_from(position).unwrap_or(i32::MAX),
+                is_default: position == 0,
+            },
+            None => ApplicationSource::Available,
+        }
+    }
+
+    /// Cache entries, sorted by path, with one file per desktop ID.
+    ///
+    /// As the XDG spec requires, a desktop ID found in several directories resolves to the
+    /// copy in the earliest of `desktop_dirs`, so `~/.local/share/applications` overrides
+    /// `/usr/share/applications`. Files outside every known directory rank last.
+    fn entries(&self) -> Vec<(&PathBuf, &DesktopFile)> {
+        let rank = |path: &Path| {
+            self.desktop_dirs
+                .iter()
+                .position(|dir| path.starts_with(dir))
+                .unwrap_or(usize::MAX)
+        };
+
+        let mut winners: HashMap<String, (usize, &PathBuf, &DesktopFile)> = HashMap::new();
+        for (path, desktop_file) in self.desktop_cache.iter() {
+            let candidate = (rank(path), path, desktop_file);
+            match winners.entry(desktop_id_of(path)) {
+                std::collections::hash_map::Entry::Occupied(mut winner) => {
+                    let (best_rank, best_path, _) = *winner.get();
+                    if (candidate.0, candidate.1) < (best_rank, best_path) {
+                        debug!("{} shadows {}", path.display(), best_path.display());
+                        winner.insert(candidate);
+                    } else {
+                        debug!("{} shadows {}", best_path.display(), path.display());
+                    }
+                }
+                std::collections::hash_map::Entry::Vacant(slot) => {
+                    slot.insert(candidate);
+                }
+            }
+        }
+
+        let mut entries: Vec<(&PathBuf, &DesktopFile)> = winners
+            .into_values()
+            .map(|(_, path, desktop_file)| (path, desktop_file))
+            .collect();
+        entries.sort_by(|a, b| a.0.cmp(b.0));
+        entries
+    }
+
+    /// Whether `entry` declares `mime_type` or one of its parent types.
+    pub fn supports_mime(
+        &self,
+        entry: &crate::desktop_parser::DesktopEntry,
+        mime_type: &str,
+    ) -> bool {
+        std::iter::once(mime_type.to_string())
+            .chain(self.mime_subclasses.ancestors(mime_type))
+            .any(|candidate| {
+                entry
+                    .mime_types
+                    .iter()
+                    .any(|pattern| mime_pattern::matches(pattern, &candidate))
+            })
+    }
+
     pub fn find_terminal_emulators(&self) -> Vec<ApplicationEntry> {
         let mut emulators = Vec::new();
-        let mut seen = HashSet::new();
 
-        for (path, desktop_file) in self.desktop_cache.iter() {
+        for (path, desktop_file) in self.entries() {
             if let Some(entry) = &desktop_file.main_entry {
                 if entry
                     .categories
                     .iter()
                     .any(|category| category == "TerminalEmulator")
+                    && self.shown_on_current_desktop(entry)
                 {
-                    let desktop_id = path
-                        .file_name()
-                        .and_then(|n| n.to_str())
-                        .unwrap_or("")
-                        .to_string();
-
-                    if seen.insert(desktop_id) {
-                        let app = ApplicationEntry::from_desktop_entry(entry, path.clone())
-                            .with_source(ApplicationSource::Available);
-                        emulators.push(app);
-                    }
+                    let app = self
+                        .localized_entry(entry, path)
+                        .with_source(ApplicationSource::Available);
+                    emulators.push(app);
                 }
             }
         }
@@ -253,14 +862,14 @@ impl ApplicationFinder {
     }
 
     pub fn find_desktop_file(&self, desktop_id: &str) -> Option<(&PathBuf, &DesktopFile)> {
-        // First try exact filename match
-        for (path, desktop_file) in self.desktop_cache.iter() {
+        // First try exact filename match, which finds the copy that shadows the others
+        for (path, desktop_file) in self.entries() {
             if path.file_name().and_then(|n| n.to_str()) == Some(desktop_id) {
                 return Some((path, desktop_file));
             }
         }
 
-        // Then try suffix match
+        // Then try suffix match, which can still name a shadowed copy by its path
         for (path, desktop_file) in self.desktop_cache.iter() {
             if path.to_string_lossy().ends_with(desktop_id) {
                 return Some((path, desktop_file));
@@ -273,7 +882,7 @@ impl ApplicationFinder {
     pub fn all_mime_types(&self) -> Vec<String> {
         let mut mime_types = HashSet::new();
 
-        for (_path, desktop_file) in self.desktop_cache.iter() {
+        for (_path, desktop_file) in self.entries() {
             if let Some(entry) = &desktop_file.main_entry {
                 for mime in &entry.mime_types {
                     mime_types.insert(mime.clone());
@@ -285,11 +894,54 @@ impl ApplicationFinder {
     }
 }
 
+/// Desktop ID first, keeping the main entry ahead of its actions.
+/// Entries only compete for tie-breaking within the same band and parent type.
+fn tie_key(app: &ApplicationEntry) -> (bool, i32, Option<&str>) {
+    (app.is_xdg, app.xdg_priority, app.parent_mime.as_deref())
+}
+
+/// Desktop ID (file name) of a desktop file path.
+fn desktop_id_of(path: &std::path::Path) -> String {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("")
+        .to_string()
+}
+
+/// The generic opener an `Exec` line starts with, if any.
+fn opener_wrapper(exec: &str) -> Option<&'static str> {
+    let program = shell_words::split(exec).ok()?.into_iter().next()?;
+    let name = Path::new(&program).file_name()?.to_str()?;
+    OPENER_WRAPPERS
+        .iter()
+        .copied()
+        .find(|wrapper| *wrapper == name)
+}
+
+/// Actions only sort after their main entry; the stable sorts keep `Actions=` order.
+fn alpha_key(app: &ApplicationEntry) -> (&str, bool) {
+    let desktop_id = app
+        .desktop_file
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    (desktop_id, app.action_id.is_some())
+}
+
+fn modified_time(app: &ApplicationEntry) -> Option<std::time::SystemTime> {
+    std::fs::metadata(&app.desktop_file)
+        .and_then(|metadata| metadata.modified())
+        .ok()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::desktop_parser::DesktopEntry;
+    use crate::test_support::EnvVarGuard;
+    use serial_test::serial;
     use std::collections::HashMap;
+    use tempfile::TempDir;
 
     fn create_test_desktop_entry(name: &str, mime_types: Vec<&str>) -> DesktopEntry {
         DesktopEntry {
@@ -314,6 +966,26 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn test_entry_kind_comes_from_its_source_not_its_file_name() {
+        let entry = create_test_desktop_entry("Lookalike", vec!["text/plain"]);
+        for name in ["regex-handler-1.desktop", "fallback-less.desktop"] {
+            let installed = ApplicationEntry::from_desktop_entry(
+                &entry,
+                PathBuf::from("/usr/share/applications").join(name),
+            );
+            assert!(!installed.is_regex_handler(), "{name}");
+            assert!(!installed.is_fallback_opener(), "{name}");
+        }
+
+        let regex = ApplicationEntry::from_desktop_entry(&entry, PathBuf::from("a.desktop"))
+            .with_source(ApplicationSource::Regex { priority: 3 });
+        assert!(regex.is_regex_handler());
+        let fallback = ApplicationEntry::from_desktop_entry(&entry, PathBuf::from("a.desktop"))
+            .with_source(ApplicationSource::Fallback);
+        assert!(fallback.is_fallback_opener());
+    }
+
     fn create_test_desktop_file(entry: DesktopEntry) -> DesktopFile {
         DesktopFile {
             main_entry: Some(entry),
@@ -361,6 +1033,149 @@ mod tests {
         assert_eq!(apps[0].xdg_priority, -1);
     }
 
+    #[test]
+    fn test_find_for_mime_falls_back_to_parent_types() {
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        let entry = create_test_desktop_entry("PlainEditor", vec!["text/plain"]);
+        cache.insert(
+            PathBuf::from("/usr/share/applications/plaineditor.desktop"),
+            create_test_desktop_file(entry),
+        );
+        let python = create_test_desktop_entry("PyIde", vec!["text/x-python3"]);
+        cache.insert(
+            PathBuf::from("/usr/share/applications/pyide.desktop"),
+            create_test_desktop_file(python),
+        );
+
+        let subclasses = MimeSubclasses::with_parents(HashMap::from([
+            ("text/x-python".to_string(), vec!["text/plain".to_string()]),
+            ("text/plain".to_string(), vec!["text/x-python".to_string()]),
+        ]));
+        let finder = ApplicationFinder::new(cache, MimeAssociations::default())
+            .with_mime_subclasses(subclasses);
+
+        let apps = finder.find_for_mime("text/x-python", false);
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0].name, "PlainEditor");
+
+        // Direct handlers win; parents are only consulted when nothing matches.
+        let apps = finder.find_for_mime("text/plain", false);
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0].name, "PlainEditor");
+
+        // Any text type ultimately falls back to text/plain.
+        let apps = finder.find_for_mime("text/x-unknown", false);
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0].parent_mime.as_deref(), Some("text/plain"));
+        assert!(finder
+            .find_for_mime("application/x-unknown", false)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_find_for_mime_follows_subclass_chain_from_mime_database() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mime_dir = temp_dir.path().join("mime");
+        std::fs::create_dir_all(&mime_dir).unwrap();
+        let subclasses_file = mime_dir.join("subclasses");
+        std::fs::write(
+            &subclasses_file,
+            "application/x-fake-child application/x-fake-parent\n\
+             application/x-fake-parent application/x-fake-root\n",
+        )
+        .unwrap();
+
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        cache.insert(
+            PathBuf::from("/usr/share/applications/rootviewer.desktop"),
+            create_test_desktop_file(create_test_desktop_entry(
+                "RootViewer",
+                vec!["application/x-fake-root"],
+            )),
+        );
+
+        let subclasses = MimeSubclasses::load_from_files(&[subclasses_file]);
+        let finder = ApplicationFinder::new(cache, MimeAssociations::default())
+            .with_mime_subclasses(subclasses.clone());
+
+        let apps = finder.find_for_mime("application/x-fake-child", false);
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0].name, "RootViewer");
+        assert_eq!(
+            apps[0].parent_mime.as_deref(),
+            Some("application/x-fake-root")
+        );
+
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        cache.insert(
+            PathBuf::from("/usr/share/applications/rootviewer.desktop"),
+            create_test_desktop_file(create_test_desktop_entry(
+                "RootViewer",
+                vec!["application/x-fake-root"],
+            )),
+        );
+        let disabled = ApplicationFinder::new(cache, MimeAssociations::default())
+            .with_mime_subclasses(subclasses)
+            .with_parent_fallback(false);
+        assert!(disabled
+            .find_for_mime("application/x-fake-child", false)
+            .is_empty());
+    }
+
+    #[test]
+    fn test_find_for_mime_tie_breaker_alpha() {
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        for name in ["Zed", "Atom"] {
+            let entry = create_test_desktop_entry(name, vec!["text/plain"]);
+            cache.insert(
+                PathBuf::from(format!(
+                    "/usr/share/applications/{}.desktop",
+                    name.to_lowercase()
+                )),
+                create_test_desktop_file(entry),
+            );
+        }
+
+        let finder = ApplicationFinder::new(cache, MimeAssociations::default())
+            .with_tie_breaker(TieBreaker::Alpha);
+
+        let names: Vec<_> = finder
+            .find_for_mime("text/plain", false)
+            .into_iter()
+            .map(|app| app.name)
+            .collect();
+        assert_eq!(names, vec!["Atom", "Zed"]);
+    }
+
+    #[test]
+    fn test_find_for_mime_tie_breaker_mtime() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        let now = std::time::SystemTime::now();
+
+        for (name, age_secs) in [("Atom", 3600), ("Zed", 0)] {
+            let path = temp_dir
+                .path()
+                .join(format!("{}.desktop", name.to_lowercase()));
+            let file = std::fs::File::create(&path).unwrap();
+            file.set_modified(now - std::time::Duration::from_secs(age_secs))
+                .unwrap();
+
+            let entry = create_test_desktop_entry(name, vec!["text/plain"]);
+            cache.insert(path, create_test_desktop_file(entry));
+        }
+
+        let finder = ApplicationFinder::new(cache, MimeAssociations::default())
+            .with_tie_breaker(TieBreaker::Mtime);
+
+        let names: Vec<_> = finder
+            .find_for_mime("text/plain", false)
+            .into_iter()
+            .map(|app| app.name)
+            .collect();
+        assert_eq!(names, vec!["Zed", "Atom"]);
+    }
+
     #[test]
     fn test_find_for_mime_with_wildcard_mime_type() {
         let mut cache = Box::new(crate::cache::MemoryCache::new());
@@ -378,6 +1193,85 @@ mod tests {
         assert_eq!(apps[0].name, "WildcardViewer");
     }
 
+    #[test]
+    fn test_find_for_mime_hides_opener_wrappers() {
+        let build = || {
+            let mut cache = Box::new(crate::cache::MemoryCache::new());
+            for (id, exec) in [
+                ("viewer", "viewer %f"),
+                ("forwarder", "xdg-open %u"),
+                ("gio-open", "/usr/bin/gio open %U"),
+                ("self", "openit %f"),
+            ] {
+                let entry = DesktopEntry {
+                    exec: exec.to_string(),
+                    ..create_test_desktop_entry(id, vec!["image/png"])
+                };
+                cache.insert(
+                    PathBuf::from(format!("/usr/share/applications/{id}.desktop")),
+                    create_test_desktop_file(entry),
+                );
+            }
+            ApplicationFinder::new(cache, MimeAssociations::default())
+        };
+
+        let shown = build().find_for_mime("image/png", false);
+        assert_eq!(shown.len(), 4);
+
+        let names: Vec<_> = build()
+            .with_opener_wrappers_hidden(true)
+            .find_for_mime("image/png", false)
+            .into_iter()
+            .map(|app| app.name)
+            .collect();
+        assert_eq!(names, vec!["viewer"]);
+    }
+
+    #[test]
+    fn test_find_for_mime_without_wildcard_handlers() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        for (file, name, mime_types) in [
+            ("wildcardviewer.desktop", "WildcardViewer", "image/*;"),
+            ("pngviewer.desktop", "PngViewer", "image/png;"),
+            ("everything.desktop", "Everything", "*/*;image/png;"),
+        ] {
+            crate::test_support::create_test_desktop_file(
+                temp_dir.path(),
+                file,
+                &format!(
+                    "[Desktop Entry]\nType=Application\nName={name}\nExec={} %F\nMimeType={mime_types}\n",
+                    name.to_lowercase()
+                ),
+            );
+        }
+        let cache = Box::new(
+            crate::cache::MemoryCache::from_dirs(&[temp_dir.path().to_path_buf()]).unwrap(),
+        );
+
+        let mut associations = HashMap::new();
+        associations.insert(
+            "image/png".to_string(),
+            vec!["wildcardviewer.desktop".to_string()],
+        );
+        let finder =
+            ApplicationFinder::new(cache, MimeAssociations::with_associations(associations))
+                .with_wildcard_handlers(false);
+
+        let names: Vec<_> = finder
+            .find_for_mime("image/png", false)
+            .into_iter()
+            .map(|app| app.name)
+            .collect();
+        assert_eq!(names, vec!["WildcardViewer", "Everything", "PngViewer"]);
+
+        let names: Vec<_> = finder
+            .find_for_mime("image/jpeg", false)
+            .into_iter()
+            .map(|app| app.name)
+            .collect();
+        assert!(names.is_empty());
+    }
+
     #[test]
     fn test_find_for_mime_with_xdg_associations() {
         let mut cache = Box::new(crate::cache::MemoryCache::new());
@@ -404,6 +1298,54 @@ mod tests {
         assert_eq!(apps[0].xdg_priority, 0);
     }
 
+    fn ranking_finder() -> ApplicationFinder {
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        for name in ["Atom", "Kate", "Vim", "Zed"] {
+            cache.insert(
+                PathBuf::from(format!(
+                    "/usr/share/applications/{}.desktop",
+                    name.to_lowercase()
+                )),
+                create_test_desktop_file(create_test_desktop_entry(name, vec!["text/plain"])),
+            );
+        }
+
+        let associations = MimeAssociations::with_associations(HashMap::from([(
+            "text/plain".to_string(),
+            vec!["vim.desktop".to_string(), "kate.desktop".to_string()],
+        )]));
+        ApplicationFinder::new(cache, associations).with_tie_breaker(TieBreaker::Alpha)
+    }
+
+    fn names(apps: &[ApplicationEntry]) -> Vec<&str> {
+        apps.iter().map(|app| app.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_find_for_mime_sorts_by_descending_score() {
+        let apps = ranking_finder().find_for_mime("text/plain", false);
+
+        assert_eq!(names(&apps), vec!["Vim", "Kate", "Atom", "Zed"]);
+        assert!(apps.windows(2).all(|pair| pair[0].score >= pair[1].score));
+        assert!(apps[0].score > apps[1].score);
+        assert!(apps[1].score > apps[2].score);
+        assert_eq!(apps[2].score, apps[3].score);
+    }
+
+    #[test]
+    fn test_preferred_apps_rank_after_default() {
+        let apps = ranking_finder()
+            .with_preferred_apps(vec!["zed.desktop".to_string(), "atom".to_string()])
+            .find_for_mime("text/plain", false);
+
+        assert_eq!(names(&apps), vec!["Vim", "Atom", "Zed", "Kate"]);
+
+        let apps = ranking_finder()
+            .with_preferred_apps(vec!["vim.desktop".to_string(), "kate.desktop".to_string()])
+            .find_for_mime("text/plain", false);
+        assert_eq!(names(&apps), vec!["Vim", "Kate", "Atom", "Zed"]);
+    }
+
     #[test]
     fn test_find_for_mime_with_actions() {
         let mut cache = Box::new(crate::cache::MemoryCache::new());
@@ -443,6 +1385,197 @@ mod tests {
         assert_eq!(apps[1].exec, "imageviewer --edit %F");
     }
 
+    #[test]
+    fn test_find_all_skips_hidden_entries_and_flags_associations() {
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        let mut viewer =
+            create_test_desktop_file(create_test_desktop_entry("Viewer", vec!["image/png"]));
+        viewer.actions.insert(
+            "slideshow".to_string(),
+            crate::desktop_parser::DesktopAction {
+                name: "Slideshow".to_string(),
+                exec: "viewer --slideshow %F".to_string(),
+                icon: None,
+            },
+        );
+        cache.insert(
+            PathBuf::from("/usr/share/applications/viewer.desktop"),
+            viewer,
+        );
+        cache.insert(
+            PathBuf::from("/usr/share/applications/editor.desktop"),
+            create_test_desktop_file(create_test_desktop_entry(
+                "Editor",
+                vec!["text/plain", "image/png"],
+            )),
+        );
+        cache.insert(
+            PathBuf::from("/usr/share/applications/settings.desktop"),
+            create_test_desktop_file(create_test_desktop_entry("Settings", vec![])),
+        );
+        cache.insert(
+            PathBuf::from("/usr/share/applications/helper.desktop"),
+            create_test_desktop_file(DesktopEntry {
+                no_display: true,
+                ..create_test_desktop_entry("Helper", vec!["image/png"])
+            }),
+        );
+        cache.insert(
+            PathBuf::from("/usr/share/applications/removed.desktop"),
+            create_test_desktop_file(DesktopEntry {
+                hidden: true,
+                ..create_test_desktop_entry("Removed", vec![])
+            }),
+        );
+        let associations = MimeAssociations::from_mimeapps_contents(
+            "[Default Applications]\nimage/png=viewer.desktop;editor.desktop;\n",
+        );
+        let finder = ApplicationFinder::new(cache, associations);
+
+        let visible = finder.find_all(false, false);
+        assert_eq!(names(&visible), vec!["Editor", "Settings", "Viewer"]);
+
+        let editor = &visible[0];
+        assert!(editor.is_xdg && !editor.is_default);
+        assert_eq!(editor.xdg_priority, 1);
+        assert_eq!(editor.association, Association::Default);
+        let settings = &visible[1];
+        assert!(!settings.is_xdg && !settings.is_default);
+        assert_eq!(settings.association, Association::None);
+        let viewer = &visible[2];
+        assert!(viewer.is_xdg && viewer.is_default);
+        assert_eq!(viewer.xdg_priority, 0);
+
+        let everything = finder.find_all(true, true);
+        assert_eq!(
+            names(&everything),
+            vec![
+                "Editor",
+                "Helper",
+                "Removed",
+                "Settings",
+                "Viewer",
+                "Viewer - Slideshow"
+            ]
+        );
+        let slideshow = everything.last().unwrap();
+        assert!(slideshow.is_xdg && !slideshow.is_default);
+    }
+
+    #[test]
+    fn test_all_applications_sorts_by_name_and_skips_hidden() {
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        for (id, name) in [
+            ("b.desktop", "zathura"),
+            ("a.desktop", "Mpv"),
+            ("c.desktop", "imv"),
+        ] {
+            cache.insert(
+                PathBuf::from(format!("/usr/share/applications/{id}")),
+                create_test_desktop_file(create_test_desktop_entry(name, vec![])),
+            );
+        }
+        cache.insert(
+            PathBuf::from("/usr/share/applications/helper.desktop"),
+            create_test_desktop_file(DesktopEntry {
+                no_display: true,
+                ..create_test_desktop_entry("Helper", vec![])
+            }),
+        );
+        let finder = ApplicationFinder::new(cache, MimeAssociations::default());
+
+        assert_eq!(
+            names(&finder.all_applications()),
+            vec!["imv", "Mpv", "zathura"]
+        );
+    }
+
+    #[test]
+    fn test_find_for_mime_uses_localized_names() {
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        cache.insert(
+            PathBuf::from("/usr/share/applications/gedit.desktop"),
+            create_test_desktop_file(DesktopEntry {
+                localized_names: HashMap::from([("de".to_string(), "Texteditor".to_string())]),
+                ..create_test_desktop_entry("Text Editor", vec!["text/plain"])
+            }),
+        );
+        let finder = ApplicationFinder::new(cache, MimeAssociations::default());
+
+        let german = finder.with_locale(Some("de_DE.UTF-8".to_string()));
+        assert_eq!(
+            names(&german.find_for_mime("text/plain", false)),
+            vec!["Texteditor"]
+        );
+
+        let unlocalized = german.with_locale(None);
+        assert_eq!(
+            names(&unlocalized.find_for_mime("text/plain", false)),
+            vec!["Text Editor"]
+        );
+
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        cache.insert(
+            PathBuf::from("/usr/share/applications/firefox.desktop"),
+            create_test_desktop_file(DesktopEntry {
+                generic_name: Some("Web Browser".to_string()),
+                localized_generic_names: HashMap::from([(
+                    "de".to_string(),
+                    "Webbrowser".to_string(),
+                )]),
+                keywords: vec!["Internet".to_string()],
+                localized_keywords: HashMap::from([("de".to_string(), vec!["Netz".to_string()])]),
+                ..create_test_desktop_entry("Firefox", vec!["text/html"])
+            }),
+        );
+        let finder = ApplicationFinder::new(cache, MimeAssociations::default())
+            .with_locale(Some("de_DE".to_string()));
+        let apps = finder.find_for_mime("text/html", false);
+        assert_eq!(apps[0].generic_name.as_deref(), Some("Webbrowser"));
+        assert_eq!(apps[0].keywords, vec!["Netz"]);
+
+        let japanese = unlocalized.with_locale(Some("ja_JP".to_string()));
+        assert_eq!(
+            names(&japanese.find_for_mime("text/plain", false)),
+            vec!["Text Editor"]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_finder_follows_lang_through_the_locale_fallback_chain() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = crate::test_support::create_test_desktop_file(
+            temp_dir.path(),
+            "gedit.desktop",
+            "[Desktop Entry]\nName=Text Editor\nName[de]=Texteditor\nName[de_DE]=Texteditor (Deutschland)\n\
+             Comment=Edit text files\nComment[de]=Textdateien bearbeiten\nExec=gedit %U\nMimeType=text/plain;\n",
+        );
+        let desktop_file = DesktopFile::parse(&path).unwrap();
+
+        let _lc_all = EnvVarGuard::set("LC_ALL", "");
+        let _lc_messages = EnvVarGuard::set("LC_MESSAGES", "");
+        for (lang, name, comment) in [
+            (
+                "de_DE.UTF-8",
+                "Texteditor (Deutschland)",
+                "Textdateien bearbeiten",
+            ),
+            ("de_AT.UTF-8", "Texteditor", "Textdateien bearbeiten"),
+            ("fr_FR.UTF-8", "Text Editor", "Edit text files"),
+            ("C.UTF-8", "Text Editor", "Edit text files"),
+        ] {
+            let _lang = EnvVarGuard::set("LANG", lang);
+            let mut cache = Box::new(crate::cache::MemoryCache::new());
+            cache.insert(path.clone(), desktop_file.clone());
+            let finder = ApplicationFinder::new(cache, MimeAssociations::default());
+
+            let apps = finder.find_for_mime("text/plain", false);
+            assert_eq!(names(&apps), vec![name], "LANG={lang}");
+            assert_eq!(apps[0].comment.as_deref(), Some(comment), "LANG={lang}");
+        }
+    }
+
     #[test]
     fn test_find_for_mime_without_actions() {
         let mut cache = Box::new(crate::cache::MemoryCache::new());
@@ -573,6 +1706,120 @@ mod tests {
         assert!(apps[0].is_xdg); // Should be marked as XDG since it was found there first
     }
 
+    #[test]
+    fn test_user_desktop_file_shadows_system_copy() {
+        // The user directory sorts after the system one, so only precedence can pick it.
+        let user_dir = PathBuf::from("/var/home/me/.local/share/applications");
+        let system_dir = PathBuf::from("/usr/share/applications");
+
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        for (dir, name) in [(&system_dir, "Firefox"), (&user_dir, "Firefox (user)")] {
+            let entry = create_test_desktop_entry(name, vec!["text/html"]);
+            cache.insert(dir.join("firefox.desktop"), create_test_desktop_file(entry));
+        }
+        let mut associations_map = HashMap::new();
+        associations_map.insert("text/html".to_string(), vec!["firefox.desktop".to_string()]);
+
+        let finder =
+            ApplicationFinder::new(cache, MimeAssociations::with_associations(associations_map))
+                .with_desktop_dirs(vec![user_dir.clone(), system_dir.clone()]);
+
+        let apps = finder.find_for_mime("text/html", false);
+        assert_eq!(names(&apps), vec!["Firefox (user)"]);
+        assert_eq!(apps[0].desktop_file, user_dir.join("firefox.desktop"));
+        assert!(apps[0].is_xdg);
+
+        assert_eq!(
+            names(&finder.find_all(false, false)),
+            vec!["Firefox (user)"]
+        );
+        let (path, _) = finder.find_desktop_file("firefox.desktop").unwrap();
+        assert_eq!(*path, user_dir.join("firefox.desktop"));
+        // A full path still reaches the shadowed copy.
+        let (path, _) = finder
+            .find_desktop_file("/usr/share/applications/firefox.desktop")
+            .unwrap();
+        assert_eq!(*path, system_dir.join("firefox.desktop"));
+    }
+
+    #[test]
+    fn test_find_for_mime_dedups_default_and_added_sections() {
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        cache.insert(
+            PathBuf::from("/usr/share/applications/editor.desktop"),
+            create_test_desktop_file(create_test_desktop_entry("Editor", vec!["text/plain"])),
+        );
+        cache.insert(
+            PathBuf::from("/usr/share/applications/notes.desktop"),
+            create_test_desktop_file(create_test_desktop_entry("Notes", vec!["text/plain"])),
+        );
+
+        let associations = MimeAssociations::from_mimeapps_contents(
+            "[Default Applications]\n\
+             text/plain=editor.desktop;\n\
+             \n\
+             [Added Associations]\n\
+             text/plain=applications/editor.desktop;notes.desktop;\n",
+        );
+        let finder = ApplicationFinder::new(cache, associations);
+
+        let apps = finder.find_for_mime("text/plain", false);
+        let editors: Vec<_> = apps.iter().filter(|app| app.name == "Editor").collect();
+        assert_eq!(editors.len(), 1);
+        assert!(editors[0].is_default);
+        assert_eq!(editors[0].association, Association::Default);
+
+        let notes = apps.iter().find(|app| app.name == "Notes").unwrap();
+        assert!(!notes.is_default);
+        assert_eq!(notes.association, Association::Added);
+        assert_eq!(notes.xdg_priority, 2);
+        assert_eq!(apps.len(), 2);
+    }
+
+    #[test]
+    fn test_find_for_mime_drops_removed_associations() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let user = temp_dir.path().join("user-mimeapps.list");
+        let system = temp_dir.path().join("system-mimeapps.list");
+        std::fs::write(
+            &user,
+            "[Removed Associations]\n\
+             text/plain=oldeditor.desktop;scanned.desktop;\n",
+        )
+        .unwrap();
+        std::fs::write(
+            &system,
+            "[Default Applications]\n\
+             text/plain=oldeditor.desktop;\n\
+             \n\
+             [Added Associations]\n\
+             text/plain=editor.desktop;\n",
+        )
+        .unwrap();
+
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        for (id, name) in [
+            ("oldeditor.desktop", "OldEditor"),
+            ("editor.desktop", "Editor"),
+            ("scanned.desktop", "Scanned"),
+        ] {
+            cache.insert(
+                PathBuf::from(format!("/usr/share/applications/{id}")),
+                create_test_desktop_file(create_test_desktop_entry(name, vec!["text/plain"])),
+            );
+        }
+
+        let associations = MimeAssociations::load_from_files(&[user, system]);
+        let finder = ApplicationFinder::new(cache, associations);
+
+        let names: Vec<_> = finder
+            .find_for_mime("text/plain", false)
+            .into_iter()
+            .map(|app| app.name)
+            .collect();
+        assert_eq!(names, vec!["Editor"]);
+    }
+
     #[test]
     fn test_find_for_mime_multiple_xdg_priorities() {
         let mut cache = Box::new(crate::cache::MemoryCache::new());
@@ -614,6 +1861,152 @@ mod tests {
         assert_eq!(second_app.xdg_priority, 1);
     }
 
+    #[test]
+    fn test_find_for_mime_ranks_exact_associations_before_wildcard_keys() {
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        for (id, name) in [("feh", "Feh"), ("imv", "Imv"), ("gimp", "Gimp")] {
+            cache.insert(
+                PathBuf::from(format!("/usr/share/applications/{id}.desktop")),
+                create_test_desktop_file(create_test_desktop_entry(
+                    name,
+                    vec!["image/png", "image/jpeg"],
+                )),
+            );
+        }
+        let associations = MimeAssociations::from_mimeapps_contents(
+            "[Default Applications]\nimage/*=imv.desktop;\nimage/png=feh.desktop;\n\n\
+             [Added Associations]\nimage/*=gimp.desktop;\n",
+        );
+        let finder = ApplicationFinder::new(cache, associations);
+
+        let png = finder.find_for_mime("image/png", false);
+        let ranked: Vec<(&str, i32, bool)> = png
+            .iter()
+            .filter(|app| app.is_xdg)
+            .map(|app| (app.name.as_str(), app.xdg_priority, app.is_default))
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![("Feh", 0, true), ("Imv", 1, false), ("Gimp", 2, false)]
+        );
+
+        let jpeg = finder.find_for_mime("image/jpeg", false);
+        let default = jpeg.iter().find(|app| app.is_default).unwrap();
+        assert_eq!(default.name, "Imv");
+        assert_eq!(default.xdg_priority, 0);
+    }
+
+    #[test]
+    fn test_find_for_mime_skip_unavailable_filters_missing_try_exec() {
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+
+        let mut missing = create_test_desktop_entry("MissingApp", vec!["text/plain"]);
+        missing.try_exec = Some("/nonexistent/bin/missing-app".to_string());
+        cache.insert(
+            PathBuf::from("/usr/share/applications/missingapp.desktop"),
+            create_test_desktop_file(missing),
+        );
+
+        let present = create_test_desktop_entry("PresentApp", vec!["text/plain"]);
+        cache.insert(
+            PathBuf::from("/usr/share/applications/presentapp.desktop"),
+            create_test_desktop_file(present),
+        );
+
+        let mut associations_map = HashMap::new();
+        associations_map.insert(
+            "text/plain".to_string(),
+            vec!["missingapp.desktop".to_string()],
+        );
+        let associations = MimeAssociations::with_associations(associations_map);
+
+        let finder = ApplicationFinder::new(cache, associations).with_skip_unavailable(true);
+
+        let apps = finder.find_for_mime("text/plain", false);
+        assert_eq!(apps.len(), 1);
+        assert_eq!(apps[0].name, "PresentApp");
+    }
+
+    fn desktop_scoped_cache() -> Box<crate::cache::MemoryCache> {
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        let mut entries = vec![
+            create_test_desktop_entry("KdePanel", vec!["text/plain"]),
+            create_test_desktop_entry("GnomeHater", vec!["text/plain"]),
+            create_test_desktop_entry("Editor", vec!["text/plain"]),
+            create_test_desktop_entry("Konsole", vec![]),
+            create_test_desktop_entry("Foot", vec![]),
+        ];
+        entries[0].only_show_in = vec!["KDE".to_string()];
+        entries[1].not_show_in = vec!["GNOME".to_string()];
+        entries[3].only_show_in = vec!["KDE".to_string()];
+        for terminal in &mut entries[3..] {
+            terminal.categories = vec!["TerminalEmulator".to_string()];
+        }
+        for entry in entries {
+            cache.insert(
+                PathBuf::from(format!(
+                    "/usr/share/applications/{}.desktop",
+                    entry.name.to_lowercase()
+                )),
+                create_test_desktop_file(entry),
+            );
+        }
+        cache
+    }
+
+    #[test]
+    #[serial]
+    fn test_find_for_mime_filters_entries_for_other_desktops() {
+        let _desktop = EnvVarGuard::set("XDG_CURRENT_DESKTOP", "ubuntu:GNOME");
+        let finder = ApplicationFinder::new(desktop_scoped_cache(), MimeAssociations::default());
+        assert_eq!(
+            names(&finder.find_for_mime("text/plain", false)),
+            vec!["Editor"]
+        );
+        assert_eq!(names(&finder.find_terminal_emulators()), vec!["Foot"]);
+
+        let _desktop = EnvVarGuard::set("XDG_CURRENT_DESKTOP", "kde");
+        let finder = ApplicationFinder::new(desktop_scoped_cache(), MimeAssociations::default());
+        assert_eq!(
+            names(&finder.find_for_mime("text/plain", false)),
+            vec!["Editor", "GnomeHater", "KdePanel"]
+        );
+        assert_eq!(
+            names(&finder.find_terminal_emulators()),
+            vec!["Foot", "Konsole"]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_with_all_desktops_disables_the_show_in_filter() {
+        let _desktop = EnvVarGuard::set("XDG_CURRENT_DESKTOP", "GNOME");
+        let finder = ApplicationFinder::new(desktop_scoped_cache(), MimeAssociations::default())
+            .with_all_desktops(true);
+        assert_eq!(
+            names(&finder.find_for_mime("text/plain", false)),
+            vec!["Editor", "GnomeHater", "KdePanel"]
+        );
+        assert_eq!(finder.find_terminal_emulators().len(), 2);
+    }
+
+    #[test]
+    fn test_find_for_mime_keeps_missing_try_exec_by_default() {
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+
+        let mut missing = create_test_desktop_entry("MissingApp", vec!["text/plain"]);
+        missing.try_exec = Some("/nonexistent/bin/missing-app".to_string());
+        cache.insert(
+            PathBuf::from("/usr/share/applications/missingapp.desktop"),
+            create_test_desktop_file(missing),
+        );
+
+        let finder = ApplicationFinder::new(cache, MimeAssociations::default());
+
+        let apps = finder.find_for_mime("text/plain", false);
+        assert_eq!(apps.len(), 1);
+    }
+
     #[test]
     fn test_find_for_mime_no_matching_mime_type() {
         let mut cache = Box::new(crate::cache::MemoryCache::new());
@@ -630,6 +2023,141 @@ mod tests {
         assert!(apps.is_empty());
     }
 
+    /// Delegates to `inner`, counting the full scans made through `iter`.
+    struct CountingCache<C> {
+        inner: C,
+        scans: std::rc::Rc<std::cell::Cell<usize>>,
+    }
+
+    impl<C: DesktopCache> DesktopCache for CountingCache<C> {
+        fn load(&mut self) -> anyhow::Result<()> {
+            self.inner.load()
+        }
+        fn save(&self) -> anyhow::Result<()> {
+            self.inner.save()
+        }
+        fn get(&self, path: &Path) -> Option<&DesktopFile> {
+            self.inner.get(path)
+        }
+        fn insert(&mut self, path: PathBuf, desktop_file: DesktopFile) {
+            self.inner.insert(path, desktop_file);
+        }
+        fn remove(&mut self, path: &Path) -> Option<DesktopFile> {
+            self.inner.remove(path)
+        }
+        fn clear(&mut self) {
+            self.inner.clear();
+        }
+        fn is_empty(&self) -> bool {
+            self.inner.is_empty()
+        }
+        fn len(&self) -> usize {
+            self.inner.len()
+        }
+        fn iter(&self) -> Box<dyn Iterator<Item = (&PathBuf, &DesktopFile)> + '_> {
+            self.scans.set(self.scans.get() + 1);
+            self.inner.iter()
+        }
+        fn needs_invalidation(&self) -> bool {
+            self.inner.needs_invalidation()
+        }
+        fn invalidate_expired(&mut self) {
+            self.inner.invalidate_expired();
+        }
+        fn generation(&self) -> u64 {
+            self.inner.generation()
+        }
+    }
+
+    fn counting_finder<C: DesktopCache + 'static>(
+        inner: C,
+    ) -> (ApplicationFinder, std::rc::Rc<std::cell::Cell<usize>>) {
+        let scans = std::rc::Rc::default();
+        let cache = CountingCache {
+            inner,
+            scans: std::rc::Rc::clone(&scans),
+        };
+        let finder = ApplicationFinder::new(Box::new(cache), MimeAssociations::default());
+        (finder, scans)
+    }
+
+    #[test]
+    fn test_find_for_mime_skips_the_scan_for_types_without_handlers() {
+        let mut cache = crate::cache::MemoryCache::new();
+        cache.insert(
+            PathBuf::from("/usr/share/applications/texteditor.desktop"),
+            create_test_desktop_file(create_test_desktop_entry("TextEditor", vec!["text/plain"])),
+        );
+        let (finder, scans) = counting_finder(cache);
+
+        assert!(finder.find_for_mime("image/png", false).is_empty());
+        let first = scans.get();
+        assert!(first > 0);
+        assert!(finder.find_for_mime("image/png", true).is_empty());
+        assert_eq!(scans.get(), first);
+
+        // Types with handlers are looked up every time.
+        assert_eq!(finder.find_for_mime("text/plain", false).len(), 1);
+        let after_hit = scans.get();
+        assert!(after_hit > first);
+        assert_eq!(finder.find_for_mime("text/plain", false).len(), 1);
+        assert!(scans.get() > after_hit);
+    }
+
+    #[test]
+    fn test_persisted_no_handler_mimes_expire_when_the_cache_is_repopulated() {
+        use crate::cache::FileSystemCache;
+
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("desktop_cache.json");
+        let negative_path = temp_dir.path().join("no_handler_mimes.json");
+        let editor = crate::test_support::create_test_desktop_file(
+            temp_dir.path(),
+            "texteditor.desktop",
+            "",
+        );
+        let viewer =
+            crate::test_support::create_test_desktop_file(temp_dir.path(), "viewer.desktop", "");
+
+        let mut cache = FileSystemCache::new(cache_path.clone());
+        cache.clear();
+        cache.insert(
+            editor,
+            create_test_desktop_file(create_test_desktop_entry("TextEditor", vec!["text/plain"])),
+        );
+        cache.save().unwrap();
+
+        let lookup = |cache: FileSystemCache| {
+            let (finder, scans) = counting_finder(cache);
+            let finder = finder.with_persisted_no_handler_mimes(negative_path.clone());
+            let found = finder.find_for_mime("image/png", false).len();
+            (found, scans.get())
+        };
+        let loaded = || {
+            let mut cache = FileSystemCache::new(cache_path.clone());
+            cache.load().unwrap();
+            cache
+        };
+
+        let (found, scans) = lookup(loaded());
+        assert_eq!(found, 0);
+        assert!(scans > 0);
+        assert!(negative_path.exists());
+
+        // A later run with the same cache trusts the saved negative lookup.
+        assert_eq!(lookup(loaded()), (0, 0));
+
+        let mut repopulated = loaded();
+        repopulated.insert(
+            viewer,
+            create_test_desktop_file(create_test_desktop_entry("Viewer", vec!["image/png"])),
+        );
+        repopulated.save().unwrap();
+        let (found, scans) = lookup(loaded());
+        assert_eq!(found, 1);
+        assert!(scans > 0);
+    }
+
     #[test]
     fn test_application_entry_from_desktop_entry() {
         let entry = create_test_desktop_entry("FromEntry", vec!["text/plain"]);
@@ -1,10 +1,17 @@
 use crate::desktop_parser::DesktopFile;
 use anyhow::{Context, Result};
+use log::{debug, info};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashMap, VecDeque};
 use std::fs;
+use std::io::Write;
 use std::path::{Path, PathBuf};
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use walkdir::WalkDir;
+
+/// Entries older than this are reparsed.
+const DEFAULT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
 
 /// Trait for desktop file caching strategies
 pub trait DesktopCache {
@@ -42,6 +49,48 @@ pub trait DesktopCache {
 
     /// Invalidate expired entries
     fn invalidate_expired(&mut self);
+
+    /// Changes whenever entries are inserted, removed or dropped; lookups remembered
+    /// against one generation are stale once it moves on.
+    fn generation(&self) -> u64;
+}
+
+/// Version of the `desktop_cache.json` layout; bump it whenever `CacheEntry` or the
+/// desktop-entry types change shape so older files are rebuilt instead of misread.
+const CACHE_VERSION: u32 = 1;
+
+/// On-disk envelope: `{ "version": N, "generation": G, "entries": { ... },
+/// "access_order": [ ... ], "trimmed": { ... } }`.
+#[derive(Serialize)]
+struct CacheFileRef<'a> {
+    version: u32,
+    generation: u64,
+    rebuilt_at: Option<SystemTime>,
+    entries: HashMap<&'a PathBuf, &'a CacheEntry>,
+    access_order: Vec<&'a PathBuf>,
+    trimmed: HashMap<&'a PathBuf, SystemTime>,
+}
+
+/// Files written before `access_order` existed load with every entry least recently used.
+#[derive(Deserialize)]
+struct CacheFile {
+    #[serde(default)]
+    generation: u64,
+    #[serde(default)]
+    rebuilt_at: Option<SystemTime>,
+    entries: HashMap<PathBuf, CacheEntry>,
+    #[serde(default)]
+    access_order: VecDeque<PathBuf>,
+    #[serde(default)]
+    trimmed: HashMap<PathBuf, SystemTime>,
+}
+
+/// Only the version, so a file in another layout is recognised without parsing its entries.
+/// Unversioned files from before the envelope have no `version` key.
+#[derive(Deserialize)]
+struct CacheHeader {
+    #[serde(default)]
+    version: Option<u32>,
 }
 
 /// Cache entry with metadata
@@ -88,24 +137,115 @@ pub struct FileSystemCache {
     cache_path: PathBuf,
     entries: HashMap<PathBuf, CacheEntry>,
     max_age: Duration,
+    /// `save` writes at most this many entries, the most recently used ones. The cache in
+    /// memory always holds every entry, since the finder reads applications from it.
+    max_entries: usize,
+    /// Cached paths, most recently used first; `get` promotes through a shared reference.
+    /// Left empty by an unbounded cache, which never trims.
+    access_order: RefCell<VecDeque<PathBuf>>,
+    /// Paths the last save left out, with their modification times. Parsing one of them
+    /// again unchanged is not a change to the cache.
+    trimmed: HashMap<PathBuf, SystemTime>,
+    /// Persisted with the entries, so negative lookups saved by an earlier run stay valid
+    /// until the cache changes.
+    generation: u64,
+    /// When `clear` last emptied the cache for a full repopulation.
+    rebuilt_at: Option<SystemTime>,
 }
 
 impl FileSystemCache {
     pub fn new(cache_path: PathBuf) -> Self {
-        Self {
-            cache_path,
-            entries: HashMap::new(),
-            max_age: Duration::from_secs(24 * 60 * 60), // 24 hours
-        }
+        Self::with_max_age(cache_path, DEFAULT_MAX_AGE)
     }
 
-    #[allow(dead_code)]
     pub fn with_max_age(cache_path: PathBuf, max_age: Duration) -> Self {
+        Self::with_capacity(cache_path, usize::MAX, max_age)
+    }
+
+    /// A cache of at most `max_entries` entries, with the default maximum age.
+    pub fn with_max_entries(cache_path: PathBuf, max_entries: Option<usize>) -> Self {
+        match max_entries {
+            Some(max_entries) => Self::with_capacity(cache_path, max_entries, DEFAULT_MAX_AGE),
+            None => Self::new(cache_path),
+        }
+    }
+
+    pub fn with_capacity(cache_path: PathBuf, max_entries: usize, max_age: Duration) -> Self {
         Self {
             cache_path,
             entries: HashMap::new(),
             max_age,
+            max_entries,
+            access_order: RefCell::new(VecDeque::new()),
+            trimmed: HashMap::new(),
+            generation: 0,
+            rebuilt_at: None,
+        }
+    }
+
+    /// `None` for a cache that was never rebuilt, or saved before rebuilds were recorded.
+    pub fn rebuilt_at(&self) -> Option<SystemTime> {
+        self.rebuilt_at
+    }
+
+    fn is_bounded(&self) -> bool {
+        self.max_entries != usize::MAX
+    }
+
+    /// Move `path` to the front of the access order.
+    fn promote(&self, path: &Path) {
+        if !self.is_bounded() {
+            return;
+        }
+        let mut order = self.access_order.borrow_mut();
+        if order.front().is_some_and(|front| front == path) {
+            return;
+        }
+        if let Some(position) = order.iter().position(|cached| cached == path) {
+            order.remove(position);
+        }
+        order.push_front(path.to_path_buf());
+    }
+
+    /// The paths `save` writes, most recently used first, and the ones it leaves out.
+    fn split_for_save(&self) -> (Vec<&PathBuf>, Vec<&PathBuf>) {
+        if !self.is_bounded() {
+            return (self.entries.keys().collect(), Vec::new());
+        }
+        let order = self.access_order.borrow();
+        let mut kept: Vec<&PathBuf> = Vec::new();
+        let mut trimmed: Vec<&PathBuf> = Vec::new();
+        for path in order.iter() {
+            // Borrow from `entries`, which outlives the access order borrow.
+            let Some((path, _)) = self.entries.get_key_value(path) else {
+                continue;
+            };
+            if kept.len() < self.max_entries {
+                kept.push(path);
+            } else {
+                trimmed.push(path);
+            }
+        }
+        (kept, trimmed)
+    }
+
+    /// Keep the access order to cached paths only, appending any it is missing as least
+    /// recently used.
+    fn reconcile_access_order(&mut self) {
+        if !self.is_bounded() {
+            self.access_order.get_mut().clear();
+            return;
         }
+        let order = self.access_order.get_mut();
+        let mut seen = std::collections::HashSet::new();
+        order.retain(|path| self.entries.contains_key(path) && seen.insert(path.clone()));
+        let mut missing: Vec<&PathBuf> = self
+            .entries
+            .keys()
+            .filter(|path| !seen.contains(*path))
+            .collect();
+        missing.sort();
+        order.extend(missing.into_iter().cloned());
     }
 }
 
@@ -117,7 +257,25 @@ impl DesktopCache for FileSystemCache {
 
         let contents = fs::read_to_string(&self.cache_path).context("Failed to read cache file")?;
 
-        self.entries = serde_json::from_str(&contents).context("Failed to parse cache file")?;
+        let header: CacheHeader =
+            serde_json::from_str(&contents).context("Failed to parse cache file")?;
+        if header.version != Some(CACHE_VERSION) {
+            info!(
+                "Discarding desktop cache {} with version {}; expected {CACHE_VERSION}",
+                self.cache_path.display(),
+                header.version.map_or("none".to_string(), |v| v.to_string())
+            );
+            self.entries.clear();
+            return Ok(());
+        }
+
+        let file: CacheFile =
+            serde_json::from_str(&contents).context("Failed to parse cache file")?;
+        self.entries = file.entries;
+        *self.access_order.get_mut() = file.access_order;
+        self.trimmed = file.trimmed;
+        self.generation = file.generation;
+        self.rebuilt_at = file.rebuilt_at;
 
         // Remove expired entries after loading
         self.invalidate_expired();
@@ -125,20 +283,57 @@ impl DesktopCache for FileSystemCache {
         Ok(())
     }
 
+    /// Write to a temporary file beside the cache and rename it into place, so an
+    /// interrupted save never leaves a truncated cache behind.
     fn save(&self) -> Result<()> {
-        if let Some(parent) = self.cache_path.parent() {
-            fs::create_dir_all(parent).context("Failed to create cache directory")?;
+        let parent = self
+            .cache_path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        fs::create_dir_all(parent).context("Failed to create cache directory")?;
+
+        let (kept, trimmed) = self.split_for_save();
+        if !trimmed.is_empty() {
+            debug!(
+                "Saving the {} most recently used of {} desktop cache entries",
+                kept.len(),
+                self.entries.len()
+            );
         }
-
-        let json = serde_json::to_string(&self.entries).context("Failed to serialize cache")?;
-
-        fs::write(&self.cache_path, json).context("Failed to write cache file")?;
+        let json = serde_json::to_string(&CacheFileRef {
+            version: CACHE_VERSION,
+            generation: self.generation,
+            rebuilt_at: self.rebuilt_at,
+            entries: kept
+                .iter()
+                .map(|path| (*path, &self.entries[*path]))
+                .collect(),
+            access_order: if self.is_bounded() { kept } else { Vec::new() },
+            trimmed: trimmed
+                .into_iter()
+                .map(|path| (path, self.entries[path].last_modified))
+                .collect(),
+        })
+        .context("Failed to serialize cache")?;
+
+        let mut file = tempfile::Builder::new()
+            .prefix(".desktop_cache.")
+            .tempfile_in(parent)
+            .context("Failed to create a temporary cache file")?;
+        file.write_all(json.as_bytes())
+            .and_then(|()| file.as_file().sync_all())
+            .context("Failed to write cache file")?;
+        file.persist(&self.cache_path)
+            .context("Failed to replace cache file")?;
 
         Ok(())
     }
 
     fn get(&self, path: &Path) -> Option<&DesktopFile> {
-        self.entries.get(path).map(|entry| &entry.desktop_file)
+        let entry = self.entries.get(path)?;
+        self.promote(path);
+        Some(&entry.desktop_file)
     }
 
     fn insert(&mut self, path: PathBuf, desktop_file: DesktopFile) {
@@ -147,15 +342,49 @@ impl DesktopCache for FileSystemCache {
             .unwrap_or_else(|_| SystemTime::now());
 
         let entry = CacheEntry::new(desktop_file, last_modified);
+        // A file the last save left out comes back unchanged on every scan; it stays least
+        // recently used so the saved entries do not churn.
+        let reparsed = self
+            .trimmed
+            .remove(&path)
+            .is_some_and(|modified| modified == last_modified);
+        if self.entries.contains_key(&path) {
+            self.promote(&path);
+        } else if self.is_bounded() {
+            let order = self.access_order.get_mut();
+            if reparsed {
+                order.push_back(path.clone());
+            } else {
+                order.push_front(path.clone());
+            }
+        }
         self.entries.insert(path, entry);
+        if !reparsed {
+            self.generation += 1;
+        }
     }
 
     fn remove(&mut self, path: &Path) -> Option<DesktopFile> {
-        self.entries.remove(path).map(|entry| entry.desktop_file)
+        let removed = self.entries.remove(path)?;
+        if self.is_bounded() {
+            self.access_order.get_mut().retain(|cached| cached != path);
+        }
+        self.generation += 1;
+        Some(removed.desktop_file)
     }
 
+    /// Starts a new generation from the clock: a cache rebuilt after its file was lost
+    /// must not count back up to a generation an older negative lookup recorded.
     fn clear(&mut self) {
         self.entries.clear();
+        self.access_order.get_mut().clear();
+        self.trimmed.clear();
+        let now = SystemTime::now();
+        let micros = now.duration_since(UNIX_EPOCH).map_or(0, |elapsed| {
+            u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX)
+        });
+        self.generation = self.generation.saturating_add(1).max(micros);
+        self.rebuilt_at = Some(now);
     }
 
     fn is_empty(&self) -> bool {
@@ -182,8 +411,17 @@ impl DesktopCache for FileSystemCache {
 
     fn invalidate_expired(&mut self) {
         let max_age = self.max_age;
+        let before = self.entries.len();
         self.entries
             .retain(|path, entry| !entry.is_expired(path, max_age));
+        if self.entries.len() != before {
+            self.generation += 1;
+        }
+        self.reconcile_access_order();
+    }
+
+    fn generation(&self) -> u64 {
+        self.generation
     }
 }
 
@@ -191,16 +429,62 @@ impl DesktopCache for FileSystemCache {
 #[derive(Debug)]
 pub struct MemoryCache {
     entries: HashMap<PathBuf, DesktopFile>,
+    generation: u64,
 }
 
 impl MemoryCache {
     pub fn new() -> Self {
         Self {
             entries: HashMap::new(),
+            generation: 0,
+        }
+    }
+
+    /// Build a cache from every `.desktop` file under `dirs`; missing directories and
+    /// files that cannot be read or parsed are skipped.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn from_dirs(dirs: &[PathBuf]) -> Result<Self> {
+        let mut cache = Self::new();
+        for dir in dirs {
+            for path in desktop_files_in(dir, true) {
+                match DesktopFile::parse(&path) {
+                    Ok(desktop_file) => {
+                        cache.entries.insert(path, desktop_file);
+                    }
+                    Err(err) => debug!("Skipping {}: {err:#}", path.display()),
+                }
+            }
         }
+        Ok(cache)
     }
 }
 
+/// How many directory levels below an `applications/` directory a recursive scan visits.
+pub(crate) const MAX_SCAN_DEPTH: usize = 8;
+
+/// `.desktop` files in `dir`, skipping hidden entries under it; empty if `dir` is missing.
+///
+/// With `recursive`, subdirectories such as `applications/kde/` are walked up to
+/// `MAX_SCAN_DEPTH` levels deep; otherwise only the top level is read.
+pub(crate) fn desktop_files_in(dir: &Path, recursive: bool) -> impl Iterator<Item = PathBuf> {
+    WalkDir::new(dir)
+        .follow_links(false)
+        .max_depth(if recursive { MAX_SCAN_DEPTH } else { 1 })
+        .into_iter()
+        .filter_entry(|e| {
+            e.depth() == 0
+                || e.file_name()
+                    .to_str()
+                    .map(|s| !s.starts_with('.'))
+                    .unwrap_or(false)
+        })
+        .filter_map(|e| e.ok())
+        .map(|entry| entry.into_path())
+        .filter(|path| {
+            path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("desktop")
+        })
+}
+
 impl Default for MemoryCache {
     fn default() -> Self {
         Self::new()
@@ -224,14 +508,18 @@ impl DesktopCache for MemoryCache {
 
     fn insert(&mut self, path: PathBuf, desktop_file: DesktopFile) {
         self.entries.insert(path, desktop_file);
+        self.generation += 1;
     }
 
     fn remove(&mut self, path: &Path) -> Option<DesktopFile> {
-        self.entries.remove(path)
+        let removed = self.entries.remove(path)?;
+        self.generation += 1;
+        Some(removed)
     }
 
     fn clear(&mut self) {
         self.entries.clear();
+        self.generation += 1;
     }
 
     fn is_empty(&self) -> bool {
@@ -254,6 +542,61 @@ impl DesktopCache for MemoryCache {
     fn invalidate_expired(&mut self) {
         // Memory cache doesn't have expiration
     }
+
+    fn generation(&self) -> u64 {
+        self.generation
+    }
+}
+
+/// What a remembered negative lookup depends on: the desktop cache generation, and a
+/// fingerprint of the associations, MIME database and finder options.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LookupEpoch {
+    pub generation: u64,
+    pub fingerprint: u64,
+}
+
+/// MIME types a lookup found no handlers for, kept in `no_handler_mimes.json` beside the
+/// desktop cache. Entries from another epoch are ignored and replaced.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NoHandlerMimes {
+    epoch: LookupEpoch,
+    mimes: BTreeSet<String>,
+}
+
+impl NoHandlerMimes {
+    /// The negative lookups at `path`; a missing file remembers none.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).with_context(|| format!("Failed to read {}", path.display())),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        fs::write(path, serde_json::to_string(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    pub fn contains(&self, epoch: LookupEpoch, mime_type: &str) -> bool {
+        self.epoch == epoch && self.mimes.contains(mime_type)
+    }
+
+    /// Remember `mime_type` for `epoch`, forgetting types recorded under another one.
+    /// Returns whether it was new.
+    pub fn insert(&mut self, epoch: LookupEpoch, mime_type: &str) -> bool {
+        if self.epoch != epoch {
+            self.epoch = epoch;
+            self.mimes.clear();
+        }
+        self.mimes.insert(mime_type.to_string())
+    }
 }
 
 #[cfg(test)]
@@ -304,6 +647,53 @@ mod tests {
         assert!(cache.get(&path).is_none());
     }
 
+    #[test]
+    fn test_memory_cache_from_dirs() {
+        let temp_dir = TempDir::new().unwrap();
+        let nested = temp_dir.path().join("vendor");
+        fs::create_dir_all(&nested).unwrap();
+        let entry = "[Desktop Entry]\nType=Application\nName=Viewer\nExec=viewer %f\n";
+        fs::write(temp_dir.path().join("viewer.desktop"), entry).unwrap();
+        fs::write(nested.join("nested.desktop"), entry).unwrap();
+        fs::write(temp_dir.path().join(".hidden.desktop"), entry).unwrap();
+        fs::write(temp_dir.path().join("notes.txt"), "not a desktop file").unwrap();
+
+        let cache = MemoryCache::from_dirs(&[
+            temp_dir.path().to_path_buf(),
+            temp_dir.path().join("missing"),
+        ])
+        .unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&temp_dir.path().join("viewer.desktop")).is_some());
+        assert!(cache.get(&nested.join("nested.desktop")).is_some());
+    }
+
+    #[test]
+    fn test_memory_cache_from_dirs_skips_unparsable_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let entry = "[Desktop Entry]\nType=Application\nName=Viewer\nExec=viewer %f\n";
+        fs::write(temp_dir.path().join("viewer.desktop"), entry).unwrap();
+        fs::write(
+            temp_dir.path().join("nameless.desktop"),
+            "[Desktop Entry]\nExec=x\n",
+        )
+        .unwrap();
+        fs::write(
+            temp_dir.path().join("latin1.desktop"),
+            b"[Desktop Entry]\nName=Caf\xe9\nExec=cafe\n",
+        )
+        .unwrap();
+
+        let cache = MemoryCache::from_dirs(&[temp_dir.path().to_path_buf()]).unwrap();
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache
+            .get(&temp_dir.path().join("nameless.desktop"))
+            .is_none());
+        assert!(cache.get(&temp_dir.path().join("latin1.desktop")).is_some());
+    }
+
     #[test]
     fn test_memory_cache_clear() {
         let mut cache = MemoryCache::new();
@@ -409,6 +799,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_filesystem_cache_save_replaces_a_truncated_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("cache.json");
+        let path = temp_dir.path().join("app.desktop");
+        fs::write(&path, "[Desktop Entry]").unwrap();
+
+        // What an interrupted `fs::write` used to leave behind.
+        fs::write(&cache_path, "{\"/usr/share/applications/firefox.des").unwrap();
+        let mut cache = FileSystemCache::new(cache_path.clone());
+        assert!(cache.load().is_err());
+
+        cache.clear();
+        cache.insert(path.clone(), create_test_desktop_file());
+        cache.save().unwrap();
+
+        let mut reloaded = FileSystemCache::new(cache_path);
+        reloaded.load().unwrap();
+        assert_eq!(reloaded.len(), 1);
+        assert!(reloaded.get(&path).is_some());
+        let leftovers: Vec<_> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .filter_map(|entry| entry.ok()?.file_name().into_string().ok())
+            .filter(|name| name.starts_with(".desktop_cache."))
+            .collect();
+        assert!(leftovers.is_empty(), "{leftovers:?}");
+    }
+
     #[test]
     fn test_filesystem_cache_load_nonexistent() {
         let temp_dir = TempDir::new().unwrap();
@@ -434,6 +852,219 @@ mod tests {
         assert!(cache.load().is_err());
     }
 
+    #[test]
+    fn test_filesystem_cache_save_writes_a_versioned_envelope() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("cache.json");
+        let mut cache = FileSystemCache::new(cache_path.clone());
+        cache.insert(
+            PathBuf::from("/test/app.desktop"),
+            create_test_desktop_file(),
+        );
+        cache.save().unwrap();
+
+        let json: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&cache_path).unwrap()).unwrap();
+        assert_eq!(json["version"], CACHE_VERSION);
+        assert!(json["entries"]["/test/app.desktop"].is_object());
+    }
+
+    #[test]
+    fn test_filesystem_cache_discards_unversioned_and_future_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("app.desktop");
+        fs::write(&path, "[Desktop Entry]").unwrap();
+        let entry = CacheEntry::new(create_test_desktop_file(), SystemTime::now());
+
+        // v0: the bare map written before the envelope existed.
+        let v0 = temp_dir.path().join("v0.json");
+        fs::write(
+            &v0,
+            serde_json::to_string(&HashMap::from([(path.clone(), entry)])).unwrap(),
+        )
+        .unwrap();
+        let mut cache = FileSystemCache::new(v0);
+        cache.load().unwrap();
+        assert!(cache.is_empty());
+
+        let future = temp_dir.path().join("future.json");
+        fs::write(
+            &future,
+            r#"{"version": 999, "entries": {"/x.desktop": {"shape": "unknown"}}, "extra": true}"#,
+        )
+        .unwrap();
+        let mut cache = FileSystemCache::new(future);
+        cache.load().unwrap();
+        assert!(cache.is_empty());
+    }
+
+    /// `count` desktop files on disk, so loading does not expire their entries.
+    fn desktop_paths(dir: &Path, count: usize) -> Vec<PathBuf> {
+        (0..count)
+            .map(|i| {
+                let path = dir.join(format!("app{i}.desktop"));
+                fs::write(&path, "[Desktop Entry]").unwrap();
+                path
+            })
+            .collect()
+    }
+
+    fn cached_paths(cache: &FileSystemCache) -> Vec<PathBuf> {
+        let mut paths: Vec<PathBuf> = cache.iter().map(|(path, _)| path.clone()).collect();
+        paths.sort();
+        paths
+    }
+
+    #[test]
+    fn test_filesystem_cache_saves_only_the_most_recently_used_at_capacity() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("cache.json");
+        let paths = desktop_paths(temp_dir.path(), 4);
+        let mut cache =
+            FileSystemCache::with_capacity(cache_path.clone(), 3, Duration::from_secs(60));
+
+        for path in &paths[..3] {
+            cache.insert(path.clone(), create_test_desktop_file());
+        }
+        // Replacing an entry marks it recently used; app1 is now the least recently used.
+        cache.insert(paths[0].clone(), create_test_desktop_file());
+        assert!(cache.get(&paths[2]).is_some());
+        cache.insert(paths[3].clone(), create_test_desktop_file());
+
+        // Every entry stays visible in memory, beyond the cap.
+        assert_eq!(cached_paths(&cache), paths);
+        cache.save().unwrap();
+
+        let mut reloaded = FileSystemCache::with_capacity(cache_path, 3, Duration::from_secs(60));
+        reloaded.load().unwrap();
+        assert_eq!(
+            cached_paths(&reloaded),
+            vec![paths[0].clone(), paths[2].clone(), paths[3].clone()]
+        );
+        assert!(reloaded.trimmed.contains_key(&paths[1]));
+    }
+
+    #[test]
+    fn test_reparsing_a_trimmed_entry_is_not_a_change() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("cache.json");
+        let paths = desktop_paths(temp_dir.path(), 4);
+        let mut cache =
+            FileSystemCache::with_capacity(cache_path.clone(), 2, Duration::from_secs(60));
+        for path in &paths {
+            cache.insert(path.clone(), create_test_desktop_file());
+        }
+        cache.save().unwrap();
+
+        // A later run finds the two files left out of the file and parses them again.
+        let mut reloaded =
+            FileSystemCache::with_capacity(cache_path.clone(), 2, Duration::from_secs(60));
+        reloaded.load().unwrap();
+        assert_eq!(reloaded.len(), 2);
+        let generation = reloaded.generation();
+        for path in &paths[..2] {
+            reloaded.insert(path.clone(), create_test_desktop_file());
+        }
+        assert_eq!(cached_paths(&reloaded), paths);
+        assert_eq!(reloaded.generation(), generation);
+
+        // The same two entries are saved again, so the next run sees the same split.
+        reloaded.save().unwrap();
+        let mut again = FileSystemCache::with_capacity(cache_path, 2, Duration::from_secs(60));
+        again.load().unwrap();
+        assert_eq!(cached_paths(&again), paths[2..].to_vec());
+
+        // A trimmed file that changed on disk is a real change.
+        let generation = again.generation();
+        fs::write(&paths[0], "[Desktop Entry]\nName=Changed\n").unwrap();
+        let modified = SystemTime::now() + Duration::from_secs(5);
+        fs::File::options()
+            .write(true)
+            .open(&paths[0])
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        again.insert(paths[0].clone(), create_test_desktop_file());
+        assert_ne!(again.generation(), generation);
+    }
+
+    #[test]
+    fn test_unbounded_filesystem_cache_keeps_no_access_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let paths = desktop_paths(temp_dir.path(), 3);
+        let mut cache = FileSystemCache::new(temp_dir.path().join("cache.json"));
+
+        for path in &paths {
+            cache.insert(path.clone(), create_test_desktop_file());
+        }
+        assert!(cache.get(&paths[0]).is_some());
+        cache.remove(&paths[1]);
+        assert_eq!(cache.len(), 2);
+        assert!(cache.access_order.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_filesystem_cache_save_and_load_keep_the_access_order() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("cache.json");
+        let paths = desktop_paths(temp_dir.path(), 3);
+
+        let mut cache =
+            FileSystemCache::with_capacity(cache_path.clone(), 3, Duration::from_secs(60));
+        for path in &paths {
+            cache.insert(path.clone(), create_test_desktop_file());
+        }
+        assert!(cache.get(&paths[0]).is_some());
+        cache.save().unwrap();
+
+        // A smaller cache loads every saved entry but saves only the two most recently used.
+        let mut reloaded =
+            FileSystemCache::with_capacity(cache_path.clone(), 2, Duration::from_secs(60));
+        reloaded.load().unwrap();
+        assert_eq!(
+            reloaded.access_order.borrow().iter().collect::<Vec<_>>(),
+            vec![&paths[0], &paths[2], &paths[1]]
+        );
+        assert_eq!(cached_paths(&reloaded), paths);
+        reloaded.save().unwrap();
+
+        let mut smaller = FileSystemCache::with_capacity(cache_path, 2, Duration::from_secs(60));
+        smaller.load().unwrap();
+        assert_eq!(
+            cached_paths(&smaller),
+            vec![paths[0].clone(), paths[2].clone()]
+        );
+    }
+
+    #[test]
+    fn test_cache_generation_moves_on_every_change_and_survives_save() {
+        let mut memory = MemoryCache::new();
+        let start = memory.generation();
+        memory.insert(PathBuf::from("/a.desktop"), create_test_desktop_file());
+        assert!(memory.generation() > start);
+        let after_insert = memory.generation();
+        assert!(memory.remove(Path::new("/missing.desktop")).is_none());
+        assert_eq!(memory.generation(), after_insert);
+
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("cache.json");
+        let desktop_path = temp_dir.path().join("test.desktop");
+        fs::write(&desktop_path, "").unwrap();
+
+        let mut cache = FileSystemCache::new(cache_path.clone());
+        cache.insert(desktop_path.clone(), create_test_desktop_file());
+        let first_epoch = cache.generation();
+        cache.clear();
+        // A rebuild from scratch never counts back up to an earlier generation.
+        assert!(cache.generation() > first_epoch + 1000);
+        cache.insert(desktop_path, create_test_desktop_file());
+        cache.save().unwrap();
+
+        let mut loaded = FileSystemCache::new(cache_path);
+        loaded.load().unwrap();
+        assert_eq!(loaded.generation(), cache.generation());
+    }
+
     #[test]
     fn test_filesystem_cache_with_max_age() {
         let temp_dir = TempDir::new().unwrap();
@@ -1,4 +1,7 @@
+use crate::commands::CommandContext;
+use crate::completion::{complete_handler, complete_mime};
 use clap::{ArgAction, Args as ClapArgs, Parser, Subcommand, ValueEnum};
+use clap_complete::engine::ArgValueCompleter;
 use clap_complete::Shell;
 use std::path::PathBuf;
 
@@ -34,6 +37,21 @@ pub enum TerminalModeArg {
     Launcher,
 }
 
+/// When `--any-handler` offers every installed application.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum AnyHandlerArg {
+    /// Only when nothing handles the MIME type.
+    Empty,
+    /// After the matching handlers, every time.
+    Always,
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum GpuArg {
+    Discrete,
+    Integrated,
+}
+
 #[derive(Parser, Debug)]
 #[command(
     author = "Your Name",
@@ -49,12 +67,22 @@ pub struct Cli {
     #[command(flatten)]
     pub open: OpenArgs,
 
+    /// Answer every confirmation prompt with "yes" instead of reading stdin
+    /// (also enabled by `OPENIT_ASSUME_YES=1`).
+    #[arg(short = 'y', long, global = true)]
+    pub assume_yes: bool,
+
     /// Optional subcommand for explicit operations.
     #[command(subcommand)]
     pub command: Option<Command>,
 }
 
 impl Cli {
+    /// Shared state for whichever command runs.
+    pub fn context(&self) -> CommandContext {
+        CommandContext::new(self.assume_yes || crate::prompt::assume_yes_from_env())
+    }
+
     /// Convert parsed CLI data into a concrete command to execute.
     pub fn into_command(self) -> Command {
         match self.command {
@@ -82,7 +110,7 @@ pub struct OpenArgs {
     #[arg(short, long)]
     pub actions: bool,
 
-    /// Clear the desktop file cache
+    /// Clear the desktop file cache (deprecated: use `openit cache clear`)
     #[arg(long)]
     pub clear_cache: bool,
 
@@ -121,6 +149,83 @@ pub struct OpenArgs {
     /// Override how terminal applications are launched (current terminal or external launcher)
     #[arg(long = "terminal-mode", value_enum)]
     pub terminal_mode: Option<TerminalModeArg>,
+
+    /// Hide applications whose `TryExec` binary is not installed.
+    #[arg(long)]
+    pub skip_unavailable: bool,
+
+    /// Also list entries whose `OnlyShowIn`/`NotShowIn` exclude the current desktop.
+    #[arg(long)]
+    pub include_all_desktops: bool,
+
+    /// Drop the query string from URIs before regex handlers match them; the full URI is still opened.
+    #[arg(long)]
+    pub strip_query: bool,
+
+    /// Offer every installed application when nothing handles the MIME type, or always
+    /// with `--any-handler=always`.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "WHEN",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "empty"
+    )]
+    pub any_handler: Option<AnyHandlerArg>,
+
+    /// Launch this desktop entry directly, skipping MIME matching and the selector.
+    #[arg(long = "with", value_name = "DESKTOP_ID")]
+    pub with_handler: Option<String>,
+
+    /// Run this desktop action of the winning handler (or the `--with` one), skipping the selector.
+    #[arg(long, value_name = "ACTION_ID", conflicts_with_all = ["json", "match_query"])]
+    pub action: Option<String>,
+
+    /// Launch the Nth most recently used handler for the resource's MIME type (1 = most recent).
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = ["with_handler", "match_query"],
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..)
+    )]
+    pub recent: Option<usize>,
+
+    /// Launch the `--with` handler even if it does not declare the resource's MIME type.
+    #[arg(long, requires = "with_handler")]
+    pub force: bool,
+
+    /// Log how long each phase takes (cache load, MIME detection, finder query, selector).
+    #[arg(long)]
+    pub timings: bool,
+
+    /// Feed the file to the handler on stdin and wait for it to exit.
+    #[arg(long)]
+    pub pipe: bool,
+
+    /// Open `.url`, `.webloc` and `Type=Link` `.desktop` files themselves instead of the URL they point to.
+    #[arg(long)]
+    pub literal: bool,
+
+    /// Launch on the given GPU, overriding the entry's `PrefersNonDefaultGPU`.
+    #[arg(long, value_enum)]
+    pub gpu: Option<GpuArg>,
+
+    /// Launch the handler whose name clearly best matches QUERY; otherwise seed the selector with it.
+    #[arg(long = "match", value_name = "QUERY", conflicts_with = "with_handler")]
+    pub match_query: Option<String>,
+
+    /// Print the command that would run, shell-quoted, instead of launching it.
+    #[arg(long, visible_alias = "print-command")]
+    pub dry_run: bool,
+
+    /// Wait for the handler to exit instead of detaching it.
+    #[arg(long)]
+    pub wait: bool,
+
+    /// With `--wait`, kill the handler's process group after SECS seconds.
+    #[arg(long, value_name = "SECS", requires = "wait")]
+    pub wait_timeout: Option<u64>,
 }
 
 #[derive(Subcommand, Debug, Clone)]
@@ -135,44 +240,83 @@ pub enum Command {
     Remove(RemoveArgs),
     /// Unset the default handlers for a MIME type or extension.
     Unset(UnsetArgs),
+    /// Set default handlers for many MIME types from a TOML file in a single save.
+    Apply(ApplyArgs),
     /// List configured handlers.
     List(ListArgs),
     /// Get available applications for a MIME type or extension.
     Get(GetArgs),
     /// Generate a shell completion script.
     Completions(CompletionsArgs),
+    /// Diagnose how a resource would be opened and report broken associations.
+    Doctor(DoctorArgs),
+    /// Show version, build details, enabled features, and resolved paths.
+    Version(VersionArgs),
+    /// Print the detected MIME type of a resource without opening it.
+    Mime(MimeArgs),
+    /// Show every parsed field of a desktop entry and its actions.
+    Info(InfoArgs),
+    /// Report what openit detects on this system.
+    Query(QueryArgs),
+    /// List detected terminal emulators and mark the one `Terminal=true` handlers would use.
+    ListTerminals(ListTerminalsArgs),
+    /// Inspect openit's own configuration.
+    Config(ConfigArgs),
+    /// Import settings and handlers from another opener's configuration.
+    Import(ImportArgs),
+    /// Debug regex handlers without launching anything.
+    Regex(RegexArgs),
+    /// List the MIME types a handler is associated with in `mimeapps.list`.
+    Handler(HandlerArgs),
+    /// Inspect, rebuild or clear the desktop file cache.
+    Cache(CacheArgs),
 }
 
 #[derive(ClapArgs, Debug, Clone)]
 pub struct EditArgs {
     /// MIME type or file extension to update.
-    #[arg(value_name = "MIME_OR_EXT")]
+    #[arg(value_name = "MIME_OR_EXT", add = ArgValueCompleter::new(complete_mime))]
     pub mime: String,
     /// Desktop file to apply (e.g. `code.desktop`).
-    #[arg(value_name = "HANDLER")]
+    #[arg(value_name = "HANDLER", add = ArgValueCompleter::new(complete_handler))]
     pub handler: String,
     /// Expand wildcard MIME patterns to the currently known concrete MIME keys.
     #[arg(long)]
     pub expand_wildcards: bool,
+    /// Insert the handler at this position (0 = first) instead of replacing or appending.
+    #[arg(long, value_name = "N")]
+    pub priority: Option<usize>,
+    /// Print the changes to `mimeapps.list` instead of writing them.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Print the `--dry-run` changes as JSON.
+    #[arg(long, requires = "dry_run")]
+    pub json: bool,
 }
 
 #[derive(ClapArgs, Debug, Clone)]
 pub struct RemoveArgs {
     /// MIME type or file extension to update.
-    #[arg(value_name = "MIME_OR_EXT")]
+    #[arg(value_name = "MIME_OR_EXT", add = ArgValueCompleter::new(complete_mime))]
     pub mime: String,
     /// Desktop file to remove (e.g. `code.desktop`).
-    #[arg(value_name = "HANDLER")]
+    #[arg(value_name = "HANDLER", add = ArgValueCompleter::new(complete_handler))]
     pub handler: String,
     /// Expand wildcard MIME patterns to the currently known concrete MIME keys.
     #[arg(long)]
     pub expand_wildcards: bool,
+    /// Print the changes to `mimeapps.list` instead of writing them.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Print the `--dry-run` changes as JSON.
+    #[arg(long, requires = "dry_run")]
+    pub json: bool,
 }
 
 #[derive(ClapArgs, Debug, Clone)]
 pub struct UnsetArgs {
     /// MIME type or file extension to update.
-    #[arg(value_name = "MIME_OR_EXT")]
+    #[arg(value_name = "MIME_OR_EXT", add = ArgValueCompleter::new(complete_mime))]
     pub mime: String,
     /// Expand wildcard MIME patterns to the currently known concrete MIME keys.
     #[arg(long)]
@@ -184,12 +328,44 @@ pub struct ListArgs {
     /// Output handler info as JSON.
     #[arg(long)]
     pub json: bool,
+    /// Only list MIME types matching this glob (e.g. `image/*`).
+    #[arg(long, value_name = "GLOB")]
+    pub mime: Option<String>,
+    /// Only list MIME types whose handlers include this desktop ID.
+    #[arg(long, value_name = "DESKTOP_ID")]
+    pub handler: Option<String>,
+    /// Show only the effective default of each MIME type, merged across every `mimeapps.list`.
+    #[arg(long)]
+    pub default_only: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct ApplyArgs {
+    /// TOML file mapping MIME types or extensions to a handler or a list of handlers.
+    #[arg(value_name = "FILE")]
+    pub file: PathBuf,
+    /// Print the resulting `mimeapps.list` instead of writing it.
+    #[arg(long)]
+    pub dry_run: bool,
+    /// Expand wildcard MIME patterns to the currently known concrete MIME keys.
+    #[arg(long)]
+    pub expand_wildcards: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct HandlerArgs {
+    /// Desktop ID to look up (e.g. `firefox.desktop`); every handler when omitted.
+    #[arg(value_name = "HANDLER", add = ArgValueCompleter::new(complete_handler))]
+    pub handler: Option<String>,
+    /// Output the associations as JSON.
+    #[arg(long)]
+    pub json: bool,
 }
 
 #[derive(ClapArgs, Debug, Clone)]
 pub struct GetArgs {
     /// MIME type or file extension to query.
-    #[arg(value_name = "MIME_OR_EXT")]
+    #[arg(value_name = "MIME_OR_EXT", add = ArgValueCompleter::new(complete_mime))]
     pub mime: String,
     /// Output as JSON.
     #[arg(long)]
@@ -197,6 +373,12 @@ pub struct GetArgs {
     /// Show desktop actions as separate entries.
     #[arg(short, long)]
     pub actions: bool,
+    /// Print only the default handler's desktop ID and the resolved path of its `Exec` binary.
+    #[arg(long, conflicts_with = "actions")]
+    pub exec: bool,
+    /// Show each `mimeapps.list` consulted, what it lists for the type, and the merged order.
+    #[arg(long, conflicts_with_all = ["actions", "exec"])]
+    pub explain: bool,
 }
 
 #[derive(ClapArgs, Debug, Clone)]
@@ -207,11 +389,158 @@ pub struct CompletionsArgs {
     /// Optional output path (prints to stdout when not provided).
     #[arg(long)]
     pub output: Option<PathBuf>,
+    /// Overwrite the `--output` file if it already exists.
+    #[arg(long, requires = "output")]
+    pub force: bool,
     /// Override the binary name used in the generated script.
     #[arg(long, default_value = "openit")]
     pub bin_name: String,
 }
 
+#[derive(ClapArgs, Debug, Clone)]
+pub struct DoctorArgs {
+    /// Resource to diagnose; accepts filesystem paths or URIs.
+    pub target: Option<String>,
+    /// Output the report as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct MimeArgs {
+    /// Resource to inspect; accepts filesystem paths or URIs.
+    pub target: String,
+    /// Output as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct InfoArgs {
+    /// Desktop ID (e.g. `firefox.desktop`) or path to a `.desktop` file.
+    #[arg(value_name = "DESKTOP_ID")]
+    pub desktop_id: String,
+    /// Output the parsed desktop file as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+#[command(group(clap::ArgGroup::new("subject").required(true)))]
+pub struct QueryArgs {
+    /// List detected terminal emulators; the same report as `list-terminals`.
+    #[arg(long, group = "subject")]
+    pub terminals: bool,
+    /// List the candidates `open` would offer for a MIME type or extension, best first.
+    #[arg(value_name = "MIME_OR_EXT", group = "subject", add = ArgValueCompleter::new(complete_mime))]
+    pub mime: Option<String>,
+    /// Print this field of each candidate instead of its desktop ID, e.g. `window_class`.
+    #[arg(
+        long,
+        value_name = "NAME",
+        requires = "mime",
+        conflicts_with = "terminals"
+    )]
+    pub field: Option<String>,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct ListTerminalsArgs {
+    /// Output the emulators and the selected launcher as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct ConfigArgs {
+    #[command(subcommand)]
+    pub action: ConfigAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ConfigAction {
+    /// Check that every regex handler's command is installed; exits non-zero otherwise.
+    Validate,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct CacheArgs {
+    #[command(subcommand)]
+    pub action: CacheAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum CacheAction {
+    /// Show the entry count, file size, last rebuild time and the directories scanned.
+    Status(CacheStatusArgs),
+    /// Reparse every desktop file into a fresh cache.
+    Rebuild,
+    /// Delete the cache file.
+    Clear,
+    /// Print the path of the cache file.
+    Path,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct CacheStatusArgs {
+    /// Output the status as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct RegexArgs {
+    #[command(subcommand)]
+    pub action: RegexAction,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum RegexAction {
+    /// Show every handler matching a target, its captured groups and the expanded exec.
+    Test(RegexTestArgs),
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct RegexTestArgs {
+    /// Path or URI to match against `regex_handlers.toml`.
+    pub target: String,
+    /// Output the matches as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct ImportArgs {
+    #[command(subcommand)]
+    pub source: ImportSource,
+}
+
+#[derive(Subcommand, Debug, Clone)]
+pub enum ImportSource {
+    /// Convert `~/.config/handlr/handlr.toml` into openit's config and regex handlers.
+    Handlr(ImportHandlrArgs),
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct ImportHandlrArgs {
+    /// handlr config to read (defaults to `~/.config/handlr/handlr.toml`).
+    #[arg(long, value_name = "PATH")]
+    pub from: Option<PathBuf>,
+    /// Write the imported settings; without it only the summary is printed.
+    #[arg(long, conflicts_with = "dry_run")]
+    pub write: bool,
+    /// Only print what would be written (the default).
+    #[arg(long)]
+    pub dry_run: bool,
+}
+
+#[derive(ClapArgs, Debug, Clone)]
+pub struct VersionArgs {
+    /// Output the version report as JSON.
+    #[arg(long)]
+    pub json: bool,
+}
+
 impl OpenArgs {
     /// Validate arguments and return errors for invalid combinations.
     #[allow(dead_code)]
@@ -238,9 +567,18 @@ impl OpenArgs {
         }
     }
 
+    /// Phase timings are logged with `--timings` or any `--verbose` level.
+    pub fn timings_enabled(&self) -> bool {
+        self.timings || self.verbose > 0
+    }
+
     pub fn terminal_mode_override(&self) -> Option<crate::config::TerminalExecution> {
         self.terminal_mode.map(Into::into)
     }
+
+    pub fn gpu_override(&self) -> Option<crate::gpu::GpuPreference> {
+        self.gpu.map(Into::into)
+    }
 }
 
 impl From<TerminalModeArg> for crate::config::TerminalExecution {
@@ -252,6 +590,15 @@ impl From<TerminalModeArg> for crate::config::TerminalExecution {
     }
 }
 
+impl From<GpuArg> for crate::gpu::GpuPreference {
+    fn from(value: GpuArg) -> Self {
+        match value {
+            GpuArg::Discrete => crate::gpu::GpuPreference::Discrete,
+            GpuArg::Integrated => crate::gpu::GpuPreference::Integrated,
+        }
+    }
+}
+
 pub fn show_build_info() {
     println!("Version: {}", crate::built_info::PKG_VERSION);
 
@@ -328,11 +675,34 @@ mod tests {
                 assert_eq!(args.mime, "text/plain");
                 assert_eq!(args.handler, "helix.desktop");
                 assert!(!args.expand_wildcards);
+                assert!(!args.dry_run);
             }
             _ => panic!("Expected set command"),
         }
     }
 
+    #[test]
+    fn test_cli_edit_dry_run_json() {
+        let cli = Cli::try_parse_from([
+            "openit",
+            "remove",
+            "--dry-run",
+            "--json",
+            "text/plain",
+            "helix.desktop",
+        ])
+        .unwrap();
+        match cli.into_command() {
+            Command::Remove(args) => assert!(args.dry_run && args.json),
+            other => panic!("Expected remove command, got {other:?}"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["openit", "set", "--json", "text/plain", "helix.desktop"])
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_cli_default_open_command() {
         let cli = Cli::try_parse_from(["openit", "file.txt"]).unwrap();
@@ -344,6 +714,218 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_cli_doctor_subcommand() {
+        let cli = Cli::try_parse_from(["openit", "doctor", "--json", "file.txt"]).unwrap();
+        match cli.into_command() {
+            Command::Doctor(args) => {
+                assert_eq!(args.target.as_deref(), Some("file.txt"));
+                assert!(args.json);
+            }
+            other => panic!("Expected doctor command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_cli_open_recent() {
+        let cli = Cli::try_parse_from(["openit", "open", "--recent", "2", "photo.png"]).unwrap();
+        match cli.into_command() {
+            Command::Open(args) => assert_eq!(args.recent, Some(2)),
+            other => panic!("Expected open command, got {other:?}"),
+        }
+
+        assert!(Cli::try_parse_from(["openit", "open", "--recent", "0", "photo.png"]).is_err());
+        assert!(Cli::try_parse_from([
+            "openit",
+            "open",
+            "--recent",
+            "1",
+            "--with",
+            "imv.desktop",
+            "photo.png"
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_cli_open_with_handler_override() {
+        let cli = Cli::try_parse_from([
+            "openit",
+            "open",
+            "--with",
+            "code.desktop",
+            "--force",
+            "notes.md",
+        ])
+        .unwrap();
+        match cli.into_command() {
+            Command::Open(args) => {
+                assert_eq!(args.with_handler.as_deref(), Some("code.desktop"));
+                assert!(args.force);
+                assert_eq!(args.target.as_deref(), Some("notes.md"));
+            }
+            other => panic!("Expected open command, got {other:?}"),
+        }
+
+        assert!(Cli::try_parse_from(["openit", "open", "--force", "notes.md"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_open_any_handler() {
+        let parse = |args: &[&str]| match Cli::try_parse_from(args).unwrap().into_command() {
+            Command::Open(args) => args,
+            other => panic!("Expected open command, got {other:?}"),
+        };
+
+        let args = parse(&["openit", "open", "--any-handler", "blob.bin"]);
+        assert_eq!(args.any_handler, Some(AnyHandlerArg::Empty));
+        assert_eq!(args.target.as_deref(), Some("blob.bin"));
+
+        let args = parse(&["openit", "open", "--any-handler=always", "blob.bin"]);
+        assert_eq!(args.any_handler, Some(AnyHandlerArg::Always));
+        assert_eq!(parse(&["openit", "open", "blob.bin"]).any_handler, None);
+    }
+
+    #[test]
+    fn test_cli_mime_subcommand() {
+        let cli = Cli::try_parse_from(["openit", "mime", "--json", "photo.png"]).unwrap();
+        match cli.into_command() {
+            Command::Mime(args) => {
+                assert_eq!(args.target, "photo.png");
+                assert!(args.json);
+            }
+            other => panic!("Expected mime command, got {other:?}"),
+        }
+
+        assert!(Cli::try_parse_from(["openit", "mime"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_info_subcommand() {
+        let cli = Cli::try_parse_from(["openit", "info", "firefox.desktop", "--json"]).unwrap();
+        match cli.into_command() {
+            Command::Info(args) => {
+                assert_eq!(args.desktop_id, "firefox.desktop");
+                assert!(args.json);
+            }
+            other => panic!("Expected info command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_cli_query_subcommand() {
+        let cli = Cli::try_parse_from(["openit", "query", "--terminals"]).unwrap();
+        match cli.into_command() {
+            Command::Query(args) => assert!(args.terminals),
+            other => panic!("Expected query command, got {other:?}"),
+        }
+
+        assert!(Cli::try_parse_from(["openit", "query"]).is_err());
+
+        let cli = Cli::try_parse_from(["openit", "query", "text/html", "--field", "window_class"])
+            .unwrap();
+        match cli.into_command() {
+            Command::Query(args) => {
+                assert_eq!(args.mime.as_deref(), Some("text/html"));
+                assert_eq!(args.field.as_deref(), Some("window_class"));
+            }
+            other => panic!("Expected query command, got {other:?}"),
+        }
+        assert!(Cli::try_parse_from(["openit", "query", "--terminals", "text/html"]).is_err());
+        assert!(
+            Cli::try_parse_from(["openit", "query", "--terminals", "--field", "name"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_config_validate_subcommand() {
+        let cli = Cli::try_parse_from(["openit", "config", "validate"]).unwrap();
+        match cli.into_command() {
+            Command::Config(args) => assert!(matches!(args.action, ConfigAction::Validate)),
+            other => panic!("Expected config command, got {other:?}"),
+        }
+
+        assert!(Cli::try_parse_from(["openit", "config"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_regex_test_subcommand() {
+        let cli =
+            Cli::try_parse_from(["openit", "regex", "test", "--json", "https://a.b"]).unwrap();
+        match cli.into_command() {
+            Command::Regex(args) => match args.action {
+                RegexAction::Test(test) => {
+                    assert_eq!(test.target, "https://a.b");
+                    assert!(test.json);
+                }
+            },
+            other => panic!("Expected regex command, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn test_cli_cache_subcommand() {
+        let cli = Cli::try_parse_from(["openit", "cache", "status", "--json"]).unwrap();
+        match cli.into_command() {
+            Command::Cache(args) => {
+                assert!(matches!(args.action, CacheAction::Status(status) if status.json))
+            }
+            other => panic!("Expected cache command, got {other:?}"),
+        }
+
+        let cli = Cli::try_parse_from(["openit", "cache", "rebuild"]).unwrap();
+        assert!(matches!(
+            cli.into_command(),
+            Command::Cache(CacheArgs {
+                action: CacheAction::Rebuild
+            })
+        ));
+        assert!(Cli::try_parse_from(["openit", "cache", "clear", "--json"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_import_handlr_subcommand() {
+        let cli = Cli::try_parse_from(["openit", "import", "handlr", "--write"]).unwrap();
+        match cli.into_command() {
+            Command::Import(ImportArgs {
+                source: ImportSource::Handlr(args),
+            }) => {
+                assert!(args.write);
+                assert!(args.from.is_none());
+            }
+            other => panic!("Expected import command, got {other:?}"),
+        }
+
+        assert!(
+            Cli::try_parse_from(["openit", "import", "handlr", "--write", "--dry-run"]).is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_apply_subcommand() {
+        let cli =
+            Cli::try_parse_from(["openit", "apply", "associations.toml", "--dry-run"]).unwrap();
+        match cli.into_command() {
+            Command::Apply(args) => {
+                assert_eq!(args.file, PathBuf::from("associations.toml"));
+                assert!(args.dry_run);
+                assert!(!args.expand_wildcards);
+            }
+            other => panic!("Expected apply command, got {other:?}"),
+        }
+
+        assert!(Cli::try_parse_from(["openit", "apply"]).is_err());
+    }
+
+    #[test]
+    fn test_cli_version_subcommand() {
+        let cli = Cli::try_parse_from(["openit", "version", "--json"]).unwrap();
+        match cli.into_command() {
+            Command::Version(args) => assert!(args.json),
+            other => panic!("Expected version command, got {other:?}"),
+        }
+    }
+
     #[test]
     fn test_cli_parse_help() {
         Cli::command().debug_assert();
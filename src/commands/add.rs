@@ -1,5 +1,6 @@
 use crate::cli::EditArgs;
 use crate::commands::{CommandContext, CommandExecutor};
+use crate::mimeapps::MimeApps;
 use anyhow::Result;
 
 pub struct AddCommand {
@@ -17,9 +18,19 @@ impl CommandExecutor for AddCommand {
         let mime = ctx.normalize_mime_input(&self.args.mime)?;
         ctx.ensure_handler_exists(&self.args.handler)?;
 
-        let mut apps = ctx.load_mimeapps()?;
-        apps.add_handler(&mime, self.args.handler.clone(), self.args.expand_wildcards);
-        ctx.save_mimeapps(&apps)?;
+        let edit = |apps: &mut MimeApps| match self.args.priority {
+            Some(position) => apps.insert_handler_at(
+                &mime,
+                self.args.handler.clone(),
+                position,
+                self.args.expand_wildcards,
+            ),
+            None => apps.add_handler(&mime, self.args.handler.clone(), self.args.expand_wildcards),
+        };
+        if self.args.dry_run {
+            return ctx.preview_mimeapps(edit, self.args.json);
+        }
+        ctx.edit_mimeapps(edit)?;
 
         println!("Added handler {} for {}", self.args.handler, mime);
         Ok(())
@@ -0,0 +1,235 @@
+use crate::cli::ApplyArgs;
+use crate::commands::{CommandContext, CommandExecutor};
+use crate::mime_relations::MimeRelations;
+use crate::mimeapps::MimeApps;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub struct ApplyCommand {
+    args: ApplyArgs,
+}
+
+impl ApplyCommand {
+    pub fn new(args: ApplyArgs) -> Self {
+        Self { args }
+    }
+}
+
+impl CommandExecutor for ApplyCommand {
+    fn execute(self, ctx: &CommandContext) -> Result<()> {
+        let file = AssociationFile::load(&self.args.file)?;
+        let batch = Batch::resolve(file, |input| ctx.normalize_mime_input(input))
+            .with_context(|| format!("Invalid associations in {}", self.args.file.display()))?;
+        for (_, handlers) in &batch.entries {
+            for handler in handlers {
+                ctx.ensure_handler_exists(handler)?;
+            }
+        }
+
+        if self.args.dry_run {
+            let mut apps = ctx.load_mimeapps()?;
+            batch.apply(&mut apps, self.args.expand_wildcards);
+            return apps.write(&mut io::stdout().lock());
+        }
+
+        ctx.edit_mimeapps(|apps| batch.apply(apps, self.args.expand_wildcards))?;
+        println!(
+            "Set default handlers for {} MIME type(s) from {}",
+            batch.entries.len(),
+            self.args.file.display()
+        );
+        let relations = MimeRelations::load();
+        for (mime, _) in &batch.entries {
+            if let Some(warning) = relations.unknown_type_warning(mime) {
+                eprintln!("Warning: {warning}");
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A provisioning file: MIME types or extensions mapped to one desktop ID or a list of
+/// them in preference order.
+#[derive(Debug, Deserialize)]
+#[serde(transparent)]
+struct AssociationFile(BTreeMap<String, Handlers>);
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Handlers {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl AssociationFile {
+    fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&contents).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    fn parse(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+}
+
+/// Every mapping validated up front, so a bad entry aborts before `mimeapps.list` is touched.
+#[derive(Debug)]
+struct Batch {
+    entries: Vec<(String, Vec<String>)>,
+}
+
+impl Batch {
+    fn resolve(file: AssociationFile, normalize: impl Fn(&str) -> Result<String>) -> Result<Self> {
+        let mut sources: BTreeMap<String, String> = BTreeMap::new();
+        let mut entries = Vec::new();
+        for (key, handlers) in file.0 {
+            let mime = normalize(&key)?;
+            if let Some(first) = sources.insert(mime.clone(), key.clone()) {
+                anyhow::bail!("`{first}` and `{key}` both set {mime}");
+            }
+
+            let handlers = match handlers {
+                Handlers::One(handler) => vec![handler],
+                Handlers::Many(handlers) => handlers,
+            };
+            if handlers.is_empty() {
+                anyhow::bail!("`{key}` lists no handlers");
+            }
+            entries.push((mime, handlers));
+        }
+        Ok(Self { entries })
+    }
+
+    fn apply(&self, apps: &mut MimeApps, expand_wildcards: bool) {
+        for (mime, handlers) in &self.entries {
+            apps.set_handler(mime, handlers.clone(), expand_wildcards);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::mime::normalize_mime_input;
+    use crate::test_support::{ConfigEnvGuard, ValidationEnvGuard};
+    use serial_test::serial;
+    use std::path::PathBuf;
+    use tempfile::TempDir;
+
+    const ASSOCIATIONS: &str = r#"
+"text/plain" = ["helix.desktop", "nvim.desktop"]
+".pdf" = "org.pwmt.zathura.desktop"
+"image/png" = ["imv.desktop", "gimp.desktop", "imv.desktop"]
+"#;
+
+    fn apply_args(file: PathBuf, dry_run: bool) -> ApplyArgs {
+        ApplyArgs {
+            file,
+            dry_run,
+            expand_wildcards: false,
+        }
+    }
+
+    #[test]
+    fn resolve_normalizes_keys_and_keeps_handler_order() {
+        let file = AssociationFile::parse(ASSOCIATIONS).unwrap();
+        let batch = Batch::resolve(file, normalize_mime_input).unwrap();
+
+        assert_eq!(
+            batch.entries,
+            vec![
+                (
+                    "application/pdf".to_string(),
+                    vec!["org.pwmt.zathura.desktop".to_string()]
+                ),
+                (
+                    "image/png".to_string(),
+                    vec!["imv.desktop", "gimp.desktop", "imv.desktop"]
+                        .into_iter()
+                        .map(String::from)
+                        .collect()
+                ),
+                (
+                    "text/plain".to_string(),
+                    vec!["helix.desktop".to_string(), "nvim.desktop".to_string()]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn resolve_rejects_duplicate_and_empty_entries() {
+        let duplicate =
+            AssociationFile::parse("\".md\" = \"a.desktop\"\n\"text/markdown\" = \"b.desktop\"\n")
+                .unwrap();
+        let err = Batch::resolve(duplicate, normalize_mime_input)
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("both set text/markdown"), "{err}");
+
+        let empty = AssociationFile::parse("\"text/plain\" = []\n").unwrap();
+        assert!(Batch::resolve(empty, normalize_mime_input).is_err());
+
+        assert!(AssociationFile::parse("\"text/plain\" = 3\n").is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn apply_writes_every_mapping_in_one_save() {
+        let temp_dir = TempDir::new().unwrap();
+        let _config = ConfigEnvGuard::set(temp_dir.path());
+        let _validation = ValidationEnvGuard::enable();
+        let mimeapps = temp_dir.path().join("mimeapps.list");
+        fs::write(
+            &mimeapps,
+            "[Default Applications]\ntext/plain=gedit.desktop;\nvideo/mp4=mpv.desktop;\n",
+        )
+        .unwrap();
+        let file = temp_dir.path().join("associations.toml");
+        fs::write(&file, ASSOCIATIONS).unwrap();
+
+        ApplyCommand::new(apply_args(file, false))
+            .execute(&CommandContext::default())
+            .unwrap();
+
+        let contents = fs::read_to_string(&mimeapps).unwrap();
+        assert!(contents.contains("text/plain=helix.desktop;nvim.desktop;\n"));
+        assert!(contents.contains("application/pdf=org.pwmt.zathura.desktop;\n"));
+        assert!(contents.contains("image/png=imv.desktop;gimp.desktop;\n"));
+        assert!(contents.contains("video/mp4=mpv.desktop;\n"));
+        assert!(!contents.contains("gedit"));
+    }
+
+    #[test]
+    #[serial]
+    fn dry_run_and_invalid_files_leave_mimeapps_untouched() {
+        let temp_dir = TempDir::new().unwrap();
+        let _config = ConfigEnvGuard::set(temp_dir.path());
+        let _validation = ValidationEnvGuard::enable();
+        let mimeapps = temp_dir.path().join("mimeapps.list");
+        let original = "[Default Applications]\ntext/plain=gedit.desktop;\n";
+        fs::write(&mimeapps, original).unwrap();
+
+        let file = temp_dir.path().join("associations.toml");
+        fs::write(&file, ASSOCIATIONS).unwrap();
+        ApplyCommand::new(apply_args(file.clone(), true))
+            .execute(&CommandContext::default())
+            .unwrap();
+        assert_eq!(fs::read_to_string(&mimeapps).unwrap(), original);
+
+        fs::write(
+            &file,
+            "\"text/plain\" = \"helix.desktop\"\n\"image/\" = \"imv.desktop\"\n",
+        )
+        .unwrap();
+        assert!(ApplyCommand::new(apply_args(file, false))
+            .execute(&CommandContext::default())
+            .is_err());
+        assert_eq!(fs::read_to_string(&mimeapps).unwrap(), original);
+    }
+}
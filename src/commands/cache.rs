@@ -0,0 +1,246 @@
+use crate::cache::{DesktopCache, FileSystemCache};
+use crate::cli::{CacheAction, CacheArgs};
+use crate::commands::{CommandContext, CommandExecutor};
+use crate::config::Config;
+use crate::open_it::OpenIt;
+use anyhow::Result;
+use serde::Serialize;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+pub struct CacheCommand {
+    args: CacheArgs,
+    /// `applications/` directories to scan, in XDG precedence order.
+    desktop_dirs: Vec<PathBuf>,
+}
+
+impl CacheCommand {
+    pub fn new(args: CacheArgs) -> Self {
+        Self {
+            args,
+            desktop_dirs: crate::xdg::get_desktop_file_paths(),
+        }
+    }
+
+    #[cfg(test)]
+    fn with_desktop_dirs(mut self, desktop_dirs: Vec<PathBuf>) -> Self {
+        self.desktop_dirs = desktop_dirs;
+        self
+    }
+}
+
+impl CommandExecutor for CacheCommand {
+    fn execute(self, _ctx: &CommandContext) -> Result<()> {
+        let config = Config::load(None).unwrap_or_default();
+        let recursive = config.recursive_scan;
+        let path = OpenIt::cache_path();
+        match self.args.action {
+            CacheAction::Status(args) => {
+                let status = CacheStatus::collect(&path, &self.desktop_dirs, recursive);
+                if args.json {
+                    println!("{}", serde_json::to_string_pretty(&status)?);
+                } else {
+                    print!("{}", status.render(SystemTime::now()));
+                }
+            }
+            CacheAction::Rebuild => {
+                let cache = OpenIt::rebuild_desktop_cache(
+                    &self.desktop_dirs,
+                    recursive,
+                    config.cache_max_entries,
+                )?;
+                println!(
+                    "Rebuilt {} with {} entries from {} director{}",
+                    path.display(),
+                    cache.len(),
+                    self.desktop_dirs.len(),
+                    if self.desktop_dirs.len() == 1 {
+                        "y"
+                    } else {
+                        "ies"
+                    }
+                );
+            }
+            CacheAction::Clear => {
+                let existed = path.exists();
+                OpenIt::clear_cache()?;
+                if existed {
+                    println!("Removed {}", path.display());
+                } else {
+                    println!("No cache at {}", path.display());
+                }
+            }
+            CacheAction::Path => println!("{}", path.display()),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct CacheStatus {
+    path: PathBuf,
+    exists: bool,
+    /// Entries still valid; ones whose desktop file changed or vanished are not counted.
+    entries: usize,
+    size_bytes: Option<u64>,
+    /// Seconds since the Unix epoch; `None` if the cache predates rebuild tracking.
+    rebuilt_at: Option<u64>,
+    recursive: bool,
+    directories: Vec<PathBuf>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl CacheStatus {
+    /// Reads the cache file as-is; unlike `open`, nothing is rebuilt or saved.
+    fn collect(path: &Path, desktop_dirs: &[PathBuf], recursive: bool) -> Self {
+        let mut cache = FileSystemCache::new(path.to_path_buf());
+        let error = cache.load().err().map(|err| format!("{err:#}"));
+        Self {
+            path: path.to_path_buf(),
+            exists: path.exists(),
+            entries: cache.len(),
+            size_bytes: fs::metadata(path).ok().map(|metadata| metadata.len()),
+            rebuilt_at: cache
+                .rebuilt_at()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|elapsed| elapsed.as_secs()),
+            recursive,
+            directories: desktop_dirs.to_vec(),
+            error,
+        }
+    }
+
+    fn render(&self, now: SystemTime) -> String {
+        let mut out = format!("Path: {}\n", self.path.display());
+        if !self.exists {
+            out.push_str("No cache file; the next run builds it\n");
+        } else if let Some(error) = &self.error {
+            out.push_str(&format!("Unreadable: {error}\n"));
+        } else {
+            out.push_str(&format!("Entries: {}\n", self.entries));
+            if let Some(size) = self.size_bytes {
+                out.push_str(&format!("Size: {size} bytes\n"));
+            }
+            let rebuilt = match self.rebuilt_at {
+                Some(secs) => {
+                    let rebuilt_at = UNIX_EPOCH + Duration::from_secs(secs);
+                    describe_age(now.duration_since(rebuilt_at).unwrap_or_default())
+                }
+                None => "unknown".to_string(),
+            };
+            out.push_str(&format!("Last rebuild: {rebuilt}\n"));
+        }
+
+        let scan = if self.recursive { " (recursive)" } else { "" };
+        out.push_str(&format!("Directories{scan}:\n"));
+        for dir in &self.directories {
+            out.push_str(&format!("  {}\n", dir.display()));
+        }
+        out
+    }
+}
+
+fn describe_age(age: Duration) -> String {
+    let secs = age.as_secs();
+    let (count, unit) = match secs {
+        0..=59 => (secs, "second"),
+        60..=3599 => (secs / 60, "minute"),
+        3600..=86_399 => (secs / 3600, "hour"),
+        _ => (secs / 86_400, "day"),
+    };
+    let plural = if count == 1 { "" } else { "s" };
+    format!("{count} {unit}{plural} ago")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::CacheStatusArgs;
+    use crate::test_support::CacheEnvGuard;
+    use serial_test::serial;
+    use tempfile::TempDir;
+
+    const VIEWER: &str = "[Desktop Entry]\nType=Application\nName=Viewer\nExec=viewer %f\n";
+
+    fn run(action: CacheAction, desktop_dirs: &[PathBuf]) {
+        CacheCommand::new(CacheArgs { action })
+            .with_desktop_dirs(desktop_dirs.to_vec())
+            .execute(&CommandContext::default())
+            .unwrap();
+    }
+
+    /// A cache path inside a temp dir, plus an `applications/` directory with one entry.
+    fn environment() -> (TempDir, CacheEnvGuard, PathBuf, Vec<PathBuf>) {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("cache").join("desktop_cache.json");
+        let guard = CacheEnvGuard::set(&cache_path);
+        let applications = temp_dir.path().join("applications");
+        fs::create_dir_all(&applications).unwrap();
+        fs::write(applications.join("viewer.desktop"), VIEWER).unwrap();
+        (temp_dir, guard, cache_path, vec![applications])
+    }
+
+    #[test]
+    #[serial]
+    fn rebuild_then_clear_round_trips_the_cache_file() {
+        let (_dir, _guard, cache_path, dirs) = environment();
+        run(CacheAction::Path, &dirs);
+
+        run(CacheAction::Rebuild, &dirs);
+        let mut cache = FileSystemCache::new(cache_path.clone());
+        cache.load().unwrap();
+        assert_eq!(cache.len(), 1);
+        assert!(cache.rebuilt_at().is_some());
+
+        run(CacheAction::Clear, &dirs);
+        assert!(!cache_path.exists());
+        run(CacheAction::Clear, &dirs);
+    }
+
+    #[test]
+    #[serial]
+    fn status_reports_entries_size_and_scanned_directories() {
+        let (_dir, _guard, cache_path, dirs) = environment();
+
+        let missing = CacheStatus::collect(&cache_path, &dirs, false);
+        assert!(!missing.exists);
+        assert!(missing
+            .render(SystemTime::now())
+            .contains("No cache file; the next run builds it\n"));
+
+        run(CacheAction::Rebuild, &dirs);
+        run(CacheAction::Status(CacheStatusArgs { json: true }), &dirs);
+        let status = CacheStatus::collect(&cache_path, &dirs, true);
+        assert_eq!(status.entries, 1);
+        assert_eq!(
+            status.size_bytes,
+            Some(fs::metadata(&cache_path).unwrap().len())
+        );
+
+        let rebuilt_at = UNIX_EPOCH + Duration::from_secs(status.rebuilt_at.unwrap());
+        let rendered = status.render(rebuilt_at + Duration::from_secs(2 * 3600 + 5));
+        assert!(rendered.contains("Entries: 1\n"), "{rendered}");
+        assert!(
+            rendered.contains("Last rebuild: 2 hours ago\n"),
+            "{rendered}"
+        );
+        assert!(rendered.ends_with(&format!(
+            "Directories (recursive):\n  {}\n",
+            dirs[0].display()
+        )));
+
+        let json = serde_json::to_value(&status).unwrap();
+        assert_eq!(json["entries"], 1);
+        assert_eq!(json["directories"][0], dirs[0].to_string_lossy().as_ref());
+        assert!(json.get("error").is_none());
+    }
+
+    #[test]
+    fn ages_use_the_largest_whole_unit() {
+        assert_eq!(describe_age(Duration::from_secs(1)), "1 second ago");
+        assert_eq!(describe_age(Duration::from_secs(59 * 60)), "59 minutes ago");
+        assert_eq!(describe_age(Duration::from_secs(86_400)), "1 day ago");
+    }
+}
@@ -1,8 +1,10 @@
 use crate::cli::{Cli, CompletionsArgs};
 use crate::commands::{CommandContext, CommandExecutor};
-use anyhow::Result;
+use anyhow::{Context, Result};
 use clap::CommandFactory;
+use clap_complete::Shell;
 use std::fs;
+use std::path::Path;
 
 pub struct CompletionsCommand {
     args: CompletionsArgs,
@@ -16,22 +18,99 @@ impl CompletionsCommand {
 
 impl CommandExecutor for CompletionsCommand {
     fn execute(self, _ctx: &CommandContext) -> Result<()> {
-        let mut command = Cli::command();
         let shell = self.args.shell;
         let bin_name = self.args.bin_name;
 
         if let Some(path) = self.args.output {
-            if let Some(parent) = path.parent() {
-                fs::create_dir_all(parent)?;
-            }
-            let mut file = fs::File::create(&path)?;
-            clap_complete::generate(shell, &mut command, bin_name.clone(), &mut file);
+            write_completions(shell, bin_name, &path, self.args.force)?;
             println!("Generated {shell} completions at {}", path.display());
         } else {
             let mut stdout = std::io::stdout();
-            clap_complete::generate(shell, &mut command, bin_name, &mut stdout);
+            clap_complete::generate(shell, &mut Cli::command(), bin_name, &mut stdout);
         }
 
         Ok(())
     }
 }
+
+/// Write the script to `path`, creating parent directories; an existing file is only
+/// replaced with `force`.
+fn write_completions(shell: Shell, bin_name: String, path: &Path, force: bool) -> Result<()> {
+    if let Some(parent) = path
+        .parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+    {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true);
+    if force {
+        options.create(true).truncate(true);
+    } else {
+        options.create_new(true);
+    }
+    let mut file = options.open(path).map_err(|err| {
+        if err.kind() == std::io::ErrorKind::AlreadyExists {
+            anyhow::anyhow!(
+                "{} already exists; pass --force to overwrite it",
+                path.display()
+            )
+        } else {
+            anyhow::Error::new(err).context(format!("Failed to create {}", path.display()))
+        }
+    })?;
+
+    clap_complete::generate(shell, &mut Cli::command(), bin_name, &mut file);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn write(path: &Path, force: bool) -> Result<()> {
+        write_completions(Shell::Bash, "openit".to_string(), path, force)
+    }
+
+    #[test]
+    fn creates_parent_directories() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir
+            .path()
+            .join("share/bash-completion/completions/openit");
+
+        write(&path, false).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("openit"));
+    }
+
+    #[test]
+    fn existing_file_needs_force() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("openit.bash");
+        fs::write(&path, "stale").unwrap();
+
+        let err = write(&path, false).unwrap_err();
+        assert!(err.to_string().contains("pass --force"), "{err}");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "stale");
+
+        write(&path, true).unwrap();
+        assert_ne!(fs::read_to_string(&path).unwrap(), "stale");
+    }
+
+    #[test]
+    fn creation_errors_name_the_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let blocker = temp_dir.path().join("file");
+        fs::write(&blocker, "").unwrap();
+        let path = blocker.join("completions/openit");
+
+        let err = write(&path, false).unwrap_err();
+        assert!(
+            format!("{err:#}").contains(&blocker.join("completions").display().to_string()),
+            "{err:#}"
+        );
+    }
+}
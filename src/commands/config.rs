@@ -0,0 +1,109 @@
+use crate::cli::{ConfigAction, ConfigArgs};
+use crate::commands::{CommandContext, CommandExecutor};
+use crate::config::Config;
+use crate::open_it::OpenIt;
+use crate::regex_handlers::RegexHandlerStore;
+use anyhow::Result;
+
+pub struct ConfigCommand {
+    args: ConfigArgs,
+}
+
+impl ConfigCommand {
+    pub fn new(args: ConfigArgs) -> Self {
+        Self { args }
+    }
+}
+
+impl CommandExecutor for ConfigCommand {
+    fn execute(self, ctx: &CommandContext) -> Result<()> {
+        match self.args.action {
+            ConfigAction::Validate => {
+                let store = ctx.load_regex_handlers()?;
+                let (report, invalid) = validation_report(&store);
+                print!("{report}");
+
+                let config = Config::load(None).unwrap_or_default();
+                let stale_pins = OpenIt::stale_pinned_terminals(&config, &ctx.application_finder());
+                print!("{}", terminal_pin_report(&stale_pins));
+
+                if invalid > 0 {
+                    anyhow::bail!("{invalid} regex handler(s) point to missing commands");
+                }
+                if !stale_pins.is_empty() {
+                    anyhow::bail!(
+                        "{} terminal pin(s) name unknown terminals",
+                        stale_pins.len()
+                    );
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Human-readable `config validate` output and the number of invalid handlers.
+fn validation_report(store: &RegexHandlerStore) -> (String, usize) {
+    let invalid = store.validate_all();
+    let mut report = String::new();
+
+    for handler in store.handlers() {
+        match invalid.iter().find(|(exec, _)| *exec == handler.exec) {
+            Some((exec, err)) => report.push_str(&format!("invalid  {exec}: {err}\n")),
+            None => report.push_str(&format!("ok       {}\n", handler.exec)),
+        }
+    }
+    report.push_str(&format!(
+        "{} of {} regex handler(s) valid\n",
+        store.len() - invalid.len(),
+        store.len()
+    ));
+
+    (report, invalid.len())
+}
+
+fn terminal_pin_report(stale_pins: &[String]) -> String {
+    stale_pins
+        .iter()
+        .map(|problem| format!("invalid  {problem}\n"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn report_marks_each_handler_and_counts_invalid_ones() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("regex_handlers.toml");
+        fs::write(
+            &path,
+            r#"
+[[handlers]]
+exec = "sh %u"
+regexes = ["^a"]
+priority = 2
+
+[[handlers]]
+exec = "openit-definitely-missing-binary %u"
+regexes = ["^b"]
+priority = 1
+"#,
+        )
+        .unwrap();
+        let store = RegexHandlerStore::load(Some(path)).unwrap();
+
+        let (report, invalid) = validation_report(&store);
+        assert_eq!(invalid, 1);
+        assert_eq!(
+            report,
+            "ok       sh %u\n\
+             invalid  openit-definitely-missing-binary %u: \
+             `openit-definitely-missing-binary` was not found on PATH\n\
+             1 of 2 regex handler(s) valid\n"
+        );
+    }
+}
@@ -1,16 +1,26 @@
 use crate::application_finder::ApplicationFinder;
+use crate::config::Config;
 use crate::mime_associations::MimeAssociations;
-use crate::mimeapps::MimeApps;
+use crate::mime_subclasses::MimeSubclasses;
+use crate::mimeapps::{DiffKind, MimeApps, MimeDiff};
 use crate::open_it::OpenIt;
+use crate::regex_handlers::RegexHandlerStore;
 use anyhow::Result;
 use std::path::Path;
 
 pub const SKIP_HANDLER_VALIDATION_ENV: &str = "OPEN_WITH_SKIP_HANDLER_VALIDATION";
 
 #[derive(Debug, Default)]
-pub struct CommandContext;
+pub struct CommandContext {
+    /// Answer confirmation prompts with "yes" without reading stdin.
+    pub assume_yes: bool,
+}
 
 impl CommandContext {
+    pub fn new(assume_yes: bool) -> Self {
+        Self { assume_yes }
+    }
+
     pub fn normalize_mime_input(&self, input: &str) -> Result<String> {
         super::mime::normalize_mime_input(input)
     }
@@ -19,8 +29,28 @@ impl CommandContext {
         MimeApps::load_from_disk(None)
     }
 
-    pub fn save_mimeapps(&self, apps: &MimeApps) -> Result<()> {
-        apps.save_to_disk(None)
+    /// Lock `mimeapps.list`, apply `edit` to its current contents and save atomically.
+    pub fn edit_mimeapps(&self, edit: impl FnOnce(&mut MimeApps)) -> Result<()> {
+        let _lock = MimeApps::lock(None)?;
+        let mut apps = MimeApps::load_from_disk(None)?;
+        edit(&mut apps);
+        let backup = Config::load(None).unwrap_or_default().backup_mimeapps;
+        apps.save_to_disk(None, backup)
+    }
+
+    /// Apply `edit` to a copy of `mimeapps.list` and print what would change, without saving.
+    pub fn preview_mimeapps(&self, edit: impl FnOnce(&mut MimeApps), json: bool) -> Result<()> {
+        let current = MimeApps::load_from_disk(None)?;
+        let mut edited = current.clone();
+        edit(&mut edited);
+        let diffs = current.diff(&edited);
+
+        if json {
+            println!("{}", serde_json::to_string_pretty(&diffs)?);
+        } else {
+            print!("{}", render_diff(&diffs));
+        }
+        Ok(())
     }
 
     pub fn ensure_handler_exists(&self, handler: &str) -> Result<()> {
@@ -28,8 +58,59 @@ impl CommandContext {
     }
 
     pub fn application_finder(&self) -> ApplicationFinder {
-        ApplicationFinder::new(OpenIt::load_desktop_cache(), MimeAssociations::load())
+        let config = Config::load(None).unwrap_or_default();
+        let finder = ApplicationFinder::new(
+            OpenIt::load_desktop_cache(config.recursive_scan, config.cache_max_entries),
+            MimeAssociations::load(),
+        )
+        .with_mime_subclasses(MimeSubclasses::load())
+        .with_tie_breaker(config.tie_breaker)
+        .with_wildcard_handlers(config.allow_wildcard_handlers)
+        .with_parent_fallback(config.mime_fallback)
+        .with_opener_wrappers_hidden(config.hide_xdg_open_wrappers)
+        .with_all_desktops(config.include_all_desktops)
+        .with_preferred_apps(config.preferred_apps);
+        if config.persist_no_handler_mimes {
+            finder.with_persisted_no_handler_mimes(OpenIt::no_handler_mimes_path())
+        } else {
+            finder
+        }
+    }
+
+    pub fn load_regex_handlers(&self) -> Result<RegexHandlerStore> {
+        let normalize = Config::load(None).unwrap_or_default().uri_match_normalize;
+        Ok(RegexHandlerStore::load(None)?.with_uri_match_normalize(normalize))
+    }
+}
+
+/// `+` for new entries, `-` for dropped ones and `~` for changed handler lists, grouped
+/// under their section header.
+fn render_diff(diffs: &[MimeDiff]) -> String {
+    if diffs.is_empty() {
+        return "No changes to mimeapps.list\n".to_string();
+    }
+
+    let mut out = String::new();
+    let mut section = "";
+    for diff in diffs {
+        if diff.section != section {
+            section = diff.section;
+            out.push_str(&format!("[{section}]\n"));
+        }
+        let line = match diff.kind {
+            DiffKind::Added => format!("+ {}={};", diff.mime, diff.new_handlers.join(";")),
+            DiffKind::Removed => format!("- {}={};", diff.mime, diff.old_handlers.join(";")),
+            DiffKind::Changed => format!(
+                "~ {}={}; -> {};",
+                diff.mime,
+                diff.old_handlers.join(";"),
+                diff.new_handlers.join(";")
+            ),
+        };
+        out.push_str(&line);
+        out.push('\n');
     }
+    out
 }
 
 fn ensure_handler_exists(handler: &str) -> Result<()> {
@@ -46,7 +127,8 @@ fn ensure_handler_exists(handler: &str) -> Result<()> {
         return Ok(());
     }
 
-    let cache = OpenIt::load_desktop_cache();
+    let config = Config::load(None).unwrap_or_default();
+    let cache = OpenIt::load_desktop_cache(config.recursive_scan, config.cache_max_entries);
     let finder = ApplicationFinder::new(cache, MimeAssociations::default());
 
     if finder.find_desktop_file(handler).is_none() {
@@ -62,3 +144,28 @@ fn ensure_handler_exists(handler: &str) -> Result<()> {
 fn should_skip_handler_validation() -> bool {
     cfg!(test) && std::env::var(SKIP_HANDLER_VALIDATION_ENV).is_ok()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn render_diff_groups_changes_by_section() {
+        let before = MimeApps::parse(
+            "[Default Applications]\ntext/plain=helix.desktop;\nvideo/mp4=mpv.desktop;\n",
+        );
+        let mut after = before.clone();
+        after.insert_handler_at("text/plain", "nvim.desktop".into(), 0, false);
+        after.remove_handler("video/mp4", Some("mpv.desktop"), false);
+        after.add_handler("application/pdf", "zathura.desktop".into(), false);
+
+        assert_eq!(
+            render_diff(&before.diff(&after)),
+            "[Default Applications]\n\
+             + application/pdf=zathura.desktop;\n\
+             ~ text/plain=helix.desktop; -> nvim.desktop;helix.desktop;\n\
+             - video/mp4=mpv.desktop;\n"
+        );
+        assert_eq!(render_diff(&[]), "No changes to mimeapps.list\n");
+    }
+}
@@ -0,0 +1,753 @@
+use crate::application_finder::ApplicationFinder;
+use crate::cli::DoctorArgs;
+use crate::commands::{CommandContext, CommandExecutor};
+use crate::config::Config;
+use crate::mime_associations::normalize_desktop_id;
+use crate::mime_pattern;
+use crate::mime_relations::{MimeRelation, MimeRelations};
+use crate::mimeapps::{DesktopList, MimeApps};
+use crate::open_it::OpenIt;
+use crate::regex_handlers::RegexHandlerStore;
+use crate::target::LaunchTarget;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+pub struct DoctorCommand {
+    args: DoctorArgs,
+}
+
+impl DoctorCommand {
+    pub fn new(args: DoctorArgs) -> Self {
+        Self { args }
+    }
+}
+
+impl CommandExecutor for DoctorCommand {
+    fn execute(self, ctx: &CommandContext) -> Result<()> {
+        let finder = ctx.application_finder();
+        let regex_handlers = ctx.load_regex_handlers()?;
+        let mimeapps_files = crate::xdg::get_mimeapps_list_files();
+
+        let mut report = build_report(
+            self.args.target.as_deref(),
+            &mimeapps_files,
+            &finder,
+            &regex_handlers,
+            &MimeRelations::load(),
+        )?;
+        let config = Config::load(None).unwrap_or_default();
+        report.stale_terminal_pins = OpenIt::stale_pinned_terminals(&config, &finder);
+
+        if self.args.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print!("{}", report.render());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct DoctorReport {
+    target: Option<TargetReport>,
+    mimeapps_files: Vec<MimeAppsFileReport>,
+    matched_applications: Vec<MatchedApplication>,
+    regex_handler: Option<RegexHandlerReport>,
+    dangling_handlers: Vec<DanglingHandler>,
+    pseudo_type_defaults: Vec<PseudoTypeDefault>,
+    association_conflicts: Vec<AssociationConflict>,
+    /// `terminal_for_handlers`/`terminal_for_directory` pins naming no detected emulator.
+    stale_terminal_pins: Vec<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct TargetReport {
+    input: String,
+    resolved: String,
+    kind: &'static str,
+    mime_type: String,
+    detection: &'static str,
+}
+
+#[derive(Debug, Serialize)]
+struct MimeAppsFileReport {
+    path: PathBuf,
+    default_apps: BTreeMap<String, Vec<String>>,
+    added_associations: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Serialize)]
+struct MatchedApplication {
+    name: String,
+    desktop_file: PathBuf,
+    directory: Option<PathBuf>,
+    is_default: bool,
+    is_xdg: bool,
+}
+
+#[derive(Debug, Serialize)]
+struct RegexHandlerReport {
+    exec: String,
+    priority: i32,
+    patterns: Vec<String>,
+    notes: Option<String>,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+struct DanglingHandler {
+    desktop_id: String,
+    mime: String,
+    source: PathBuf,
+}
+
+/// A default set only under an alias or pseudo-type: detection yields `relation.real`,
+/// which no `[Default Applications]` entry covers, so the default never applies.
+#[derive(Debug, Serialize)]
+struct PseudoTypeDefault {
+    mime: String,
+    relation: MimeRelation,
+    handlers: Vec<String>,
+    source: PathBuf,
+}
+
+/// A default whose `[Added Associations]` listing leaves the intent unclear.
+#[derive(Debug, Serialize, PartialEq, Eq)]
+struct AssociationConflict {
+    mime: String,
+    desktop_id: String,
+    kind: ConflictKind,
+    source: PathBuf,
+    suggestion: String,
+}
+
+#[derive(Debug, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum ConflictKind {
+    /// The same file lists the handler under both sections.
+    DefaultAndAdded,
+    /// Other files add handlers for the type but not the default, and the file setting the
+    /// default adds none.
+    DefaultNotAdded,
+}
+
+fn build_report(
+    raw_target: Option<&str>,
+    mimeapps_files: &[PathBuf],
+    finder: &ApplicationFinder,
+    regex_handlers: &RegexHandlerStore,
+    relations: &MimeRelations,
+) -> Result<DoctorReport> {
+    let target = raw_target
+        .map(|raw| {
+            OpenIt::resolve_launch_target(raw).map(|resolved| {
+                let mime_type = OpenIt::mime_for_target(&resolved);
+                (raw, resolved, mime_type)
+            })
+        })
+        .transpose()?;
+
+    let mime_filter = target.as_ref().map(|(_, _, mime)| mime.as_str());
+
+    let mut mimeapps_reports = Vec::new();
+    let mut dangling_handlers = Vec::new();
+    let mut pseudo_type_defaults = Vec::new();
+    let mut real_defaults = Vec::new();
+    let mut association_conflicts = Vec::new();
+    let mut listed_defaults = Vec::new();
+    // Per type, each handler listed under `[Added Associations]` and the file listing it.
+    let mut added_anywhere: BTreeMap<String, Vec<(String, PathBuf)>> = BTreeMap::new();
+
+    for path in mimeapps_files {
+        let apps = match MimeApps::load_from_disk(Some(path.clone())) {
+            Ok(apps) => apps,
+            Err(err) => {
+                log::debug!("Skipping {}: {err}", path.display());
+                continue;
+            }
+        };
+
+        for section in [apps.default_apps(), apps.added_associations()] {
+            for (mime, handlers) in section {
+                for handler in handlers.iter() {
+                    if finder.find_desktop_file(handler).is_none() {
+                        let dangling = DanglingHandler {
+                            desktop_id: handler.clone(),
+                            mime: mime.clone(),
+                            source: path.clone(),
+                        };
+                        if !dangling_handlers.contains(&dangling) {
+                            dangling_handlers.push(dangling);
+                        }
+                    }
+                }
+            }
+        }
+
+        for (mime, handlers) in apps.added_associations() {
+            added_anywhere.entry(mime.clone()).or_default().extend(
+                handlers
+                    .iter()
+                    .map(|h| (normalize_desktop_id(h).to_string(), path.clone())),
+            );
+        }
+
+        for (mime, handlers) in apps.default_apps() {
+            let added = apps.added_handlers_for(mime);
+            for handler in handlers.iter() {
+                let desktop_id = normalize_desktop_id(handler);
+                let also_added = added.is_some_and(|added| {
+                    added.iter().any(|h| normalize_desktop_id(h) == desktop_id)
+                });
+                if also_added {
+                    association_conflicts.push(AssociationConflict {
+                        mime: mime.clone(),
+                        desktop_id: desktop_id.to_string(),
+                        kind: ConflictKind::DefaultAndAdded,
+                        source: path.clone(),
+                        suggestion: format!(
+                            "drop it from [Added Associations]; the default entry already offers it for {mime}"
+                        ),
+                    });
+                }
+                listed_defaults.push((mime.clone(), desktop_id.to_string(), path.clone()));
+            }
+        }
+
+        for (mime, handlers) in apps.default_apps() {
+            real_defaults.push(mime.to_ascii_lowercase());
+            if let Some(relation) = relations.related(mime) {
+                pseudo_type_defaults.push(PseudoTypeDefault {
+                    mime: mime.clone(),
+                    relation,
+                    handlers: handlers.iter().cloned().collect(),
+                    source: path.clone(),
+                });
+            }
+        }
+
+        mimeapps_reports.push(MimeAppsFileReport {
+            path: path.clone(),
+            default_apps: filter_section(apps.default_apps(), mime_filter),
+            added_associations: filter_section(apps.added_associations(), mime_filter),
+        });
+    }
+
+    pseudo_type_defaults.retain(|pseudo| !real_defaults.contains(&pseudo.relation.real));
+
+    // Only added associations from other files count: a file that sets the default and
+    // lists other handlers has followed the `default_and_added` advice.
+    for (mime, desktop_id, source) in listed_defaults {
+        let Some(added) = added_anywhere.get(&mime) else {
+            continue;
+        };
+        if added.iter().any(|(_, path)| *path == source)
+            || added.iter().any(|(handler, _)| *handler == desktop_id)
+        {
+            continue;
+        }
+        let added_in = added[0].1.display();
+        association_conflicts.push(AssociationConflict {
+            suggestion: format!(
+                "list it under [Added Associations] for {mime} in {added_in}, so it stays offered if the default changes"
+            ),
+            mime,
+            desktop_id,
+            kind: ConflictKind::DefaultNotAdded,
+            source,
+        });
+    }
+
+    let mut matched_applications = Vec::new();
+    let mut regex_handler = None;
+
+    if let Some((_, resolved, mime_type)) = &target {
+        matched_applications = finder
+            .find_for_mime(mime_type, false)
+            .into_iter()
+            .map(|app| MatchedApplication {
+                directory: app.desktop_file.parent().map(Path::to_path_buf),
+                name: app.name,
+                desktop_file: app.desktop_file,
+                is_default: app.is_default,
+                is_xdg: app.is_xdg,
+            })
+            .collect();
+
+        regex_handler = regex_handlers
+            .find_handler(&regex_handlers.candidate(resolved))
+            .map(|handler| RegexHandlerReport {
+                exec: handler.exec.clone(),
+                priority: handler.priority,
+                patterns: handler.patterns().to_vec(),
+                notes: handler.notes.clone(),
+            });
+    }
+
+    let target = target.map(|(raw, resolved, mime_type)| TargetReport {
+        input: raw.to_string(),
+        resolved: resolved.as_command_argument().into_owned(),
+        kind: match resolved {
+            LaunchTarget::File(_) => "file",
+            LaunchTarget::Uri(_) => "uri",
+        },
+        detection: OpenIt::mime_detection_for_target(&resolved),
+        mime_type,
+    });
+
+    Ok(DoctorReport {
+        target,
+        mimeapps_files: mimeapps_reports,
+        matched_applications,
+        regex_handler,
+        dangling_handlers,
+        pseudo_type_defaults,
+        association_conflicts,
+        stale_terminal_pins: Vec::new(),
+    })
+}
+
+fn filter_section(
+    section: &BTreeMap<String, DesktopList>,
+    mime_filter: Option<&str>,
+) -> BTreeMap<String, Vec<String>> {
+    section
+        .iter()
+        .filter(|(mime, _)| mime_filter.is_none_or(|target| mime_pattern::matches(mime, target)))
+        .map(|(mime, handlers)| (mime.clone(), handlers.iter().cloned().collect()))
+        .collect()
+}
+
+impl DoctorReport {
+    fn render(&self) -> String {
+        let mut out = String::new();
+
+        if let Some(target) = &self.target {
+            out.push_str(&format!("Target: {} ({})\n", target.resolved, target.kind));
+            out.push_str(&format!(
+                "MIME type: {} (detected via {})\n",
+                target.mime_type, target.detection
+            ));
+        } else {
+            out.push_str("Target: none (showing global diagnostics)\n");
+        }
+
+        out.push_str(&format!(
+            "\nmimeapps.list files ({}):\n",
+            self.mimeapps_files.len()
+        ));
+        if self.mimeapps_files.is_empty() {
+            out.push_str("  none found\n");
+        }
+        for file in &self.mimeapps_files {
+            out.push_str(&format!("  {}\n", file.path.display()));
+            if file.default_apps.is_empty() && file.added_associations.is_empty() {
+                out.push_str("    (no relevant entries)\n");
+            }
+            for (mime, handlers) in &file.default_apps {
+                out.push_str(&format!("    [default] {mime}: {}\n", handlers.join("; ")));
+            }
+            for (mime, handlers) in &file.added_associations {
+                out.push_str(&format!("    [added] {mime}: {}\n", handlers.join("; ")));
+            }
+        }
+
+        if self.target.is_some() {
+            out.push_str(&format!(
+                "\nMatched applications ({}):\n",
+                self.matched_applications.len()
+            ));
+            if self.matched_applications.is_empty() {
+                out.push_str("  none\n");
+            }
+            for app in &self.matched_applications {
+                let marker = if app.is_default {
+                    "★ "
+                } else if app.is_xdg {
+                    "▶ "
+                } else {
+                    "  "
+                };
+                out.push_str(&format!(
+                    "  {marker}{} ({})\n",
+                    app.name,
+                    app.desktop_file.display()
+                ));
+                if let Some(directory) = &app.directory {
+                    out.push_str(&format!("      from {}\n", directory.display()));
+                }
+            }
+
+            out.push_str("\nRegex handler:\n");
+            match &self.regex_handler {
+                Some(handler) => out.push_str(&format!(
+                    "  {} (priority {}) [{}]\n",
+                    handler.exec,
+                    handler.priority,
+                    handler.patterns.join(", ")
+                )),
+                None => out.push_str("  none would fire\n"),
+            }
+        }
+
+        out.push_str(&format!(
+            "\nDangling handlers ({}):\n",
+            self.dangling_handlers.len()
+        ));
+        if self.dangling_handlers.is_empty() {
+            out.push_str("  none\n");
+        }
+        for dangling in &self.dangling_handlers {
+            out.push_str(&format!(
+                "  {} for {} (referenced in {})\n",
+                dangling.desktop_id,
+                dangling.mime,
+                dangling.source.display()
+            ));
+        }
+
+        out.push_str(&format!(
+            "\nPseudo-type defaults without a real default ({}):\n",
+            self.pseudo_type_defaults.len()
+        ));
+        if self.pseudo_type_defaults.is_empty() {
+            out.push_str("  none\n");
+        }
+        for pseudo in &self.pseudo_type_defaults {
+            out.push_str(&format!(
+                "  {} ({}) -> {} (set in {}; files are detected as {})\n",
+                pseudo.mime,
+                pseudo.relation.describe(),
+                pseudo.handlers.join("; "),
+                pseudo.source.display(),
+                pseudo.relation.real
+            ));
+        }
+
+        out.push_str(&format!(
+            "\nDefault and added association conflicts ({}):\n",
+            self.association_conflicts.len()
+        ));
+        if self.association_conflicts.is_empty() {
+            out.push_str("  none\n");
+        }
+        for conflict in &self.association_conflicts {
+            let problem = match conflict.kind {
+                ConflictKind::DefaultAndAdded => "default and added",
+                ConflictKind::DefaultNotAdded => "default but not added",
+            };
+            out.push_str(&format!(
+                "  {} for {}: {problem} (in {})\n    suggestion: {}\n",
+                conflict.desktop_id,
+                conflict.mime,
+                conflict.source.display(),
+                conflict.suggestion
+            ));
+        }
+
+        if !self.stale_terminal_pins.is_empty() {
+            out.push_str(&format!(
+                "\nStale terminal pins ({}):\n",
+                self.stale_terminal_pins.len()
+            ));
+            for problem in &self.stale_terminal_pins {
+                out.push_str(&format!("  {problem}\n"));
+            }
+        }
+
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{DesktopCache, MemoryCache};
+    use crate::desktop_parser::DesktopFile;
+    use crate::mime_associations::MimeAssociations;
+    use crate::test_support::create_test_desktop_file;
+    use std::fs;
+    use tempfile::TempDir;
+
+    fn doctor_fixture() -> (TempDir, PathBuf, ApplicationFinder) {
+        let temp_dir = TempDir::new().unwrap();
+        let apps_dir = temp_dir.path().join("applications");
+        fs::create_dir_all(&apps_dir).unwrap();
+
+        let desktop_path = create_test_desktop_file(
+            &apps_dir,
+            "editor.desktop",
+            "[Desktop Entry]\nName=Editor\nExec=editor %F\nMimeType=text/plain;\n",
+        );
+
+        let mimeapps_path = temp_dir.path().join("mimeapps.list");
+        fs::write(
+            &mimeapps_path,
+            "[Default Applications]\ntext/plain=ghost.desktop;editor.desktop;\n",
+        )
+        .unwrap();
+
+        let mut cache = Box::new(MemoryCache::new());
+        cache.insert(
+            desktop_path.clone(),
+            DesktopFile::parse(&desktop_path).unwrap(),
+        );
+        let finder = ApplicationFinder::new(cache, MimeAssociations::default());
+
+        (temp_dir, mimeapps_path, finder)
+    }
+
+    #[test]
+    fn report_mentions_dangling_handler() {
+        let (temp_dir, mimeapps_path, finder) = doctor_fixture();
+        let target_path = temp_dir.path().join("notes.txt");
+        fs::write(&target_path, "hello").unwrap();
+
+        let regex_handlers =
+            RegexHandlerStore::load(Some(temp_dir.path().join("none.toml"))).unwrap();
+        let report = build_report(
+            Some(target_path.to_str().unwrap()),
+            std::slice::from_ref(&mimeapps_path),
+            &finder,
+            &regex_handlers,
+            &MimeRelations::default(),
+        )
+        .unwrap();
+
+        assert_eq!(report.dangling_handlers.len(), 1);
+        assert_eq!(report.dangling_handlers[0].desktop_id, "ghost.desktop");
+        assert_eq!(report.matched_applications.len(), 1);
+        assert_eq!(
+            report.target.as_ref().unwrap().mime_type,
+            "text/plain".to_string()
+        );
+
+        let rendered = report.render();
+        assert!(rendered.contains("ghost.desktop for text/plain"));
+        assert!(rendered.contains(&mimeapps_path.display().to_string()));
+        assert!(!rendered.contains("Stale terminal pins"));
+    }
+
+    #[test]
+    fn report_lists_stale_terminal_pins() {
+        let (temp_dir, mimeapps_path, finder) = doctor_fixture();
+        let regex_handlers =
+            RegexHandlerStore::load(Some(temp_dir.path().join("none.toml"))).unwrap();
+        let mut report = build_report(
+            None,
+            std::slice::from_ref(&mimeapps_path),
+            &finder,
+            &regex_handlers,
+            &MimeRelations::default(),
+        )
+        .unwrap();
+        let config = Config {
+            terminal_for_directory: Some("kitty".to_string()),
+            ..Config::default()
+        };
+        report.stale_terminal_pins = OpenIt::stale_pinned_terminals(&config, &finder);
+
+        assert!(report.render().ends_with(
+            "\nStale terminal pins (1):\n  Config `terminal_for_directory` names unknown \
+             terminal `kitty`; detected terminals: none\n"
+        ));
+    }
+
+    #[test]
+    fn report_json_without_target() {
+        let (temp_dir, mimeapps_path, finder) = doctor_fixture();
+        let regex_handlers =
+            RegexHandlerStore::load(Some(temp_dir.path().join("none.toml"))).unwrap();
+
+        let report = build_report(
+            None,
+            std::slice::from_ref(&mimeapps_path),
+            &finder,
+            &regex_handlers,
+            &MimeRelations::default(),
+        )
+        .unwrap();
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert!(json["target"].is_null());
+        assert_eq!(
+            json["dangling_handlers"][0]["desktop_id"],
+            serde_json::json!("ghost.desktop")
+        );
+        assert_eq!(
+            json["mimeapps_files"][0]["default_apps"]["text/plain"],
+            serde_json::json!(["ghost.desktop", "editor.desktop"])
+        );
+    }
+
+    #[test]
+    fn flags_pseudo_type_defaults_without_a_real_default() {
+        let (temp_dir, _, finder) = doctor_fixture();
+        let mimeapps_path = temp_dir.path().join("firefox-mimeapps.list");
+        let fixture = include_str!("testdata/mimeapps_firefox_pseudo.list");
+        fs::write(&mimeapps_path, fixture).unwrap();
+        let regex_handlers =
+            RegexHandlerStore::load(Some(temp_dir.path().join("none.toml"))).unwrap();
+
+        let report = build_report(
+            None,
+            std::slice::from_ref(&mimeapps_path),
+            &finder,
+            &regex_handlers,
+            &MimeRelations::default(),
+        )
+        .unwrap();
+
+        // text/html has its own default; application/xhtml+xml does not.
+        let flagged: Vec<&str> = report
+            .pseudo_type_defaults
+            .iter()
+            .map(|pseudo| pseudo.mime.as_str())
+            .collect();
+        assert_eq!(
+            flagged,
+            vec![
+                "application/x-extension-xht",
+                "application/x-extension-xhtml"
+            ]
+        );
+        assert!(report.render().contains(
+            "  application/x-extension-xhtml (pseudo-type for application/xhtml+xml) -> firefox.desktop"
+        ));
+
+        fs::write(
+            &mimeapps_path,
+            format!("{fixture}\n[Default Applications]\napplication/xhtml+xml=firefox.desktop;\n"),
+        )
+        .unwrap();
+        let report = build_report(
+            None,
+            std::slice::from_ref(&mimeapps_path),
+            &finder,
+            &regex_handlers,
+            &MimeRelations::default(),
+        )
+        .unwrap();
+        assert!(report.pseudo_type_defaults.is_empty());
+    }
+
+    #[test]
+    fn flags_defaults_listed_in_both_sections_or_missing_from_added() {
+        let (temp_dir, _, finder) = doctor_fixture();
+        let user = temp_dir.path().join("user-mimeapps.list");
+        let system = temp_dir.path().join("system-mimeapps.list");
+        fs::write(
+            &user,
+            "[Default Applications]\ntext/plain=editor.desktop;\nimage/png=viewer.desktop;\n\n\
+             [Added Associations]\ntext/plain=editor.desktop;other.desktop;\n",
+        )
+        .unwrap();
+        fs::write(
+            &system,
+            "[Default Applications]\nvideo/mp4=player.desktop;\n\n\
+             [Added Associations]\nimage/png=gimp.desktop;\nvideo/mp4=player.desktop;\n",
+        )
+        .unwrap();
+        let regex_handlers =
+            RegexHandlerStore::load(Some(temp_dir.path().join("none.toml"))).unwrap();
+
+        let report = build_report(
+            None,
+            &[user.clone(), system.clone()],
+            &finder,
+            &regex_handlers,
+            &MimeRelations::default(),
+        )
+        .unwrap();
+
+        let flagged: Vec<(&str, &str, &ConflictKind)> = report
+            .association_conflicts
+            .iter()
+            .map(|c| (c.mime.as_str(), c.desktop_id.as_str(), &c.kind))
+            .collect();
+        assert_eq!(
+            flagged,
+            vec![
+                (
+                    "text/plain",
+                    "editor.desktop",
+                    &ConflictKind::DefaultAndAdded
+                ),
+                (
+                    "video/mp4",
+                    "player.desktop",
+                    &ConflictKind::DefaultAndAdded
+                ),
+                (
+                    "image/png",
+                    "viewer.desktop",
+                    &ConflictKind::DefaultNotAdded
+                ),
+            ]
+        );
+        assert_eq!(report.association_conflicts[2].source, user);
+
+        let rendered = report.render();
+        assert!(rendered.contains("Default and added association conflicts (3):\n"));
+        assert!(rendered.contains("  viewer.desktop for image/png: default but not added"));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json["association_conflicts"][0]["kind"],
+            serde_json::json!("default_and_added")
+        );
+    }
+
+    #[test]
+    fn following_a_conflict_suggestion_never_raises_the_other_conflict() {
+        let (temp_dir, _, finder) = doctor_fixture();
+        let user = temp_dir.path().join("user-mimeapps.list");
+        let system = temp_dir.path().join("system-mimeapps.list");
+        let regex_handlers =
+            RegexHandlerStore::load(Some(temp_dir.path().join("none.toml"))).unwrap();
+        let conflicts = |user_contents: &str, system_contents: &str| {
+            fs::write(&user, user_contents).unwrap();
+            fs::write(&system, system_contents).unwrap();
+            build_report(
+                None,
+                &[user.clone(), system.clone()],
+                &finder,
+                &regex_handlers,
+                &MimeRelations::default(),
+            )
+            .unwrap()
+            .association_conflicts
+        };
+
+        // `default_and_added`: drop editor.desktop from the user file's added list.
+        let before = conflicts(
+            "[Default Applications]\ntext/plain=editor.desktop;\n\n\
+             [Added Associations]\ntext/plain=editor.desktop;other.desktop;\n",
+            "",
+        );
+        assert_eq!(before.len(), 1);
+        assert_eq!(before[0].kind, ConflictKind::DefaultAndAdded);
+        let after = conflicts(
+            "[Default Applications]\ntext/plain=editor.desktop;\n\n\
+             [Added Associations]\ntext/plain=other.desktop;\n",
+            "",
+        );
+        assert!(after.is_empty(), "{after:?}");
+
+        // `default_not_added`: add viewer.desktop in the file it names.
+        let system_added = "[Added Associations]\nimage/png=gimp.desktop;\n";
+        let before = conflicts(
+            "[Default Applications]\nimage/png=viewer.desktop;\n",
+            system_added,
+        );
+        assert_eq!(before.len(), 1);
+        assert_eq!(before[0].kind, ConflictKind::DefaultNotAdded);
+        assert!(before[0].suggestion.contains(&system.display().to_string()));
+        let after = conflicts(
+            "[Default Applications]\nimage/png=viewer.desktop;\n",
+            "[Added Associations]\nimage/png=gimp.desktop;viewer.desktop;\n",
+        );
+        assert!(after.is_empty(), "{after:?}");
+    }
+}
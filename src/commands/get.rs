@@ -1,8 +1,14 @@
 use crate::application_finder::ApplicationFinder;
 use crate::cli::GetArgs;
 use crate::commands::{CommandContext, CommandExecutor};
+use crate::executor::ApplicationExecutor;
+use crate::mime_associations::{FileContribution, MimeAssociations};
+use crate::mime_relations::{MimeRelation, MimeRelations};
+use crate::mimeapps::{DesktopList, MimeApps};
 use anyhow::Result;
+use serde::Serialize;
 use std::collections::{BTreeMap, HashSet};
+use std::path::{Path, PathBuf};
 use wildmatch::WildMatch;
 
 pub struct GetCommand {
@@ -20,10 +26,32 @@ impl CommandExecutor for GetCommand {
         let pattern = ctx.normalize_mime_input(&self.args.mime)?;
         let finder = ctx.application_finder();
 
+        if self.args.exec {
+            if pattern.contains('*') {
+                anyhow::bail!("--exec needs a concrete MIME type, not the pattern `{pattern}`");
+            }
+            return handle_exec_query(&finder, &pattern, self.args.json);
+        }
+
+        if self.args.explain {
+            let chain =
+                ResolutionChain::collect(&crate::xdg::get_mimeapps_list_files(), &pattern, |id| {
+                    finder.find_desktop_file(id).map(|(path, _)| path.clone())
+                });
+            if self.args.json {
+                println!("{}", serde_json::to_string_pretty(&chain)?);
+            } else {
+                print!("{}", chain.render());
+            }
+            return Ok(());
+        }
+
         if pattern.contains('*') {
             handle_wildcard_query(&finder, &pattern, &self.args)?;
         } else {
-            handle_exact_query(&finder, &pattern, &self.args)?;
+            let mimeapps = ctx.load_mimeapps()?;
+            let lists = MimeAppsLists::for_mime(&mimeapps, &pattern, &MimeRelations::load());
+            handle_exact_query(&finder, &pattern, &lists, &self.args)?;
         }
 
         Ok(())
@@ -87,7 +115,221 @@ fn handle_wildcard_query(finder: &ApplicationFinder, pattern: &str, args: &GetAr
     Ok(())
 }
 
-fn handle_exact_query(finder: &ApplicationFinder, pattern: &str, args: &GetArgs) -> Result<()> {
+/// What `openit` would run for a MIME type: the default desktop ID and its `Exec` binary.
+#[derive(Debug, Serialize)]
+struct ResolvedDefault {
+    mimetype: String,
+    desktop_id: String,
+    desktop_file: PathBuf,
+    exec: String,
+    program: String,
+    /// Absolute path of `program`; `None` when it is not installed.
+    path: Option<PathBuf>,
+}
+
+impl ResolvedDefault {
+    /// Resolve the default handler through the merged `mimeapps.list` precedence.
+    fn resolve(finder: &ApplicationFinder, mime: &str) -> Result<Self> {
+        let app = finder
+            .find_for_mime(mime, false)
+            .into_iter()
+            .find(|app| app.is_default)
+            .ok_or_else(|| anyhow::anyhow!("No default handler configured for {mime}"))?;
+
+        let program = ApplicationExecutor::base_command_parts(&app.exec)?
+            .into_iter()
+            .next()
+            .unwrap_or_default();
+        let path = if program.contains('/') {
+            Some(PathBuf::from(&program)).filter(|path| path.is_file())
+        } else {
+            which::which(&program).ok()
+        };
+
+        Ok(Self {
+            mimetype: mime.to_string(),
+            desktop_id: app
+                .desktop_file
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_default(),
+            desktop_file: app.desktop_file.clone(),
+            exec: app.exec,
+            program,
+            path,
+        })
+    }
+
+    /// `<desktop id>\t<binary path>`, or the bare program marked `(not found)`.
+    fn render(&self) -> String {
+        let binary = self.path.as_deref().map(Path::display).map_or_else(
+            || format!("{} (not found)", self.program),
+            |path| path.to_string(),
+        );
+        format!("{}\t{binary}\n", self.desktop_id)
+    }
+}
+
+fn handle_exec_query(finder: &ApplicationFinder, mime: &str, json: bool) -> Result<()> {
+    let resolved = ResolvedDefault::resolve(finder, mime)?;
+    if json {
+        println!("{}", serde_json::to_string_pretty(&resolved)?);
+    } else {
+        print!("{}", resolved.render());
+    }
+    Ok(())
+}
+
+/// Why a MIME type opens with what it does: every `mimeapps.list` consulted, highest
+/// precedence first, and the order their entries merge into.
+#[derive(Debug, Serialize)]
+struct ResolutionChain {
+    mimetype: String,
+    files: Vec<FileContribution>,
+    merged: Vec<ResolvedHandler>,
+}
+
+#[derive(Debug, Serialize)]
+struct ResolvedHandler {
+    desktop_id: String,
+    /// `None` when no installed desktop file provides the ID.
+    desktop_file: Option<PathBuf>,
+}
+
+impl ResolutionChain {
+    fn collect(files: &[PathBuf], mime: &str, resolve: impl Fn(&str) -> Option<PathBuf>) -> Self {
+        let merged = MimeAssociations::load_from_files(files)
+            .get_associations(mime)
+            .into_iter()
+            .map(|desktop_id| ResolvedHandler {
+                desktop_file: resolve(&desktop_id),
+                desktop_id,
+            })
+            .collect();
+
+        Self {
+            mimetype: mime.to_string(),
+            files: MimeAssociations::contributions(files, mime),
+            merged,
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = format!("MIME type: {}\n", self.mimetype);
+        if self.files.is_empty() {
+            out.push_str("No mimeapps.list files found\n");
+        } else {
+            out.push_str("mimeapps.list files, highest precedence first:\n");
+        }
+        for (index, file) in self.files.iter().enumerate() {
+            out.push_str(&format!("{}. {}\n", index + 1, file.path.display()));
+            if file.is_empty() {
+                out.push_str("   (nothing listed)\n");
+            }
+            for (section, handlers) in [
+                ("Default Applications", &file.default_applications),
+                ("Added Associations", &file.added_associations),
+                ("Removed Associations", &file.removed_associations),
+            ] {
+                if !handlers.is_empty() {
+                    out.push_str(&format!("   {section}: {}\n", handlers.join("; ")));
+                }
+            }
+        }
+
+        if self.merged.is_empty() {
+            out.push_str("Merged order: (none)\n");
+        } else {
+            out.push_str("Merged order:\n");
+        }
+        for (index, handler) in self.merged.iter().enumerate() {
+            let resolved = handler.desktop_file.as_deref().map_or_else(
+                || "not found".to_string(),
+                |path| path.display().to_string(),
+            );
+            out.push_str(&format!(
+                "{}. {} -> {resolved}\n",
+                index + 1,
+                handler.desktop_id
+            ));
+        }
+        out
+    }
+}
+
+/// The user's `mimeapps.list` entries for one MIME type, kept apart by section.
+#[derive(Debug, Serialize)]
+struct MimeAppsLists {
+    default_handlers: Vec<String>,
+    added_associations: Vec<String>,
+    /// The real type when the queried one is an alias or pseudo-type.
+    related_type: Option<MimeRelation>,
+    /// Alias and pseudo-type keys that stand for the queried type, with their handlers.
+    pseudo_types: BTreeMap<String, Vec<String>>,
+}
+
+impl MimeAppsLists {
+    fn for_mime(apps: &MimeApps, mime: &str, relations: &MimeRelations) -> Self {
+        let collect = |list: Option<&DesktopList>| {
+            list.map(|list| list.iter().cloned().collect())
+                .unwrap_or_default()
+        };
+
+        let mut pseudo_types: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for section in [apps.default_apps(), apps.added_associations()] {
+            for (key, handlers) in section {
+                if relations
+                    .related(key)
+                    .is_some_and(|relation| relation.real.eq_ignore_ascii_case(mime))
+                {
+                    let entry = pseudo_types.entry(key.clone()).or_default();
+                    for handler in handlers.iter() {
+                        if !entry.contains(handler) {
+                            entry.push(handler.clone());
+                        }
+                    }
+                }
+            }
+        }
+
+        Self {
+            default_handlers: collect(apps.handlers_for(mime)),
+            added_associations: collect(apps.added_handlers_for(mime)),
+            related_type: relations.related(mime),
+            pseudo_types,
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        if !self.default_handlers.is_empty() {
+            out.push_str(&format!(
+                "Default handlers: {}\n",
+                self.default_handlers.join("; ")
+            ));
+        }
+        if !self.added_associations.is_empty() {
+            out.push_str(&format!(
+                "Added associations: {}\n",
+                self.added_associations.join("; ")
+            ));
+        }
+        if let Some(relation) = &self.related_type {
+            out.push_str(&format!("Relation: {}\n", relation.describe()));
+        }
+        for (key, handlers) in &self.pseudo_types {
+            out.push_str(&format!("Also listed as {key}: {}\n", handlers.join("; ")));
+        }
+        out
+    }
+}
+
+fn handle_exact_query(
+    finder: &ApplicationFinder,
+    pattern: &str,
+    lists: &MimeAppsLists,
+    args: &GetArgs,
+) -> Result<()> {
     let applications = finder.find_for_mime(pattern, args.actions);
 
     if args.json {
@@ -95,11 +337,16 @@ fn handle_exact_query(finder: &ApplicationFinder, pattern: &str, args: &GetArgs)
         let output = serde_json::json!({
             "mimetype": pattern,
             "xdg_associations": xdg_associations,
+            "default_handlers": lists.default_handlers,
+            "added_associations": lists.added_associations,
+            "related_type": lists.related_type,
+            "pseudo_types": lists.pseudo_types,
             "applications": applications,
         });
         println!("{}", serde_json::to_string_pretty(&output)?);
     } else {
         println!("MIME type: {}", pattern);
+        print!("{}", lists.render());
         if applications.is_empty() {
             println!("No applications found for this MIME type.");
         } else {
@@ -134,3 +381,174 @@ fn handle_exact_query(finder: &ApplicationFinder, pattern: &str, args: &GetArgs)
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{DesktopCache, MemoryCache};
+    use crate::desktop_parser::{DesktopEntry, DesktopFile};
+    use crate::mime_associations::MimeAssociations;
+    use std::collections::HashMap;
+
+    #[test]
+    fn surfaces_default_and_added_lists_separately() {
+        let apps = MimeApps::parse(
+            "[Default Applications]\n\
+             text/plain=helix.desktop;\n\
+             \n\
+             [Added Associations]\n\
+             text/plain=gedit.desktop;code.desktop;\n",
+        );
+
+        let lists = MimeAppsLists::for_mime(&apps, "text/plain", &MimeRelations::default());
+        assert_eq!(lists.default_handlers, vec!["helix.desktop"]);
+        assert_eq!(
+            lists.added_associations,
+            vec!["gedit.desktop", "code.desktop"]
+        );
+
+        let json = serde_json::to_value(&lists).unwrap();
+        assert_eq!(json["added_associations"][1], "code.desktop");
+
+        let rendered = lists.render();
+        assert!(rendered.contains("Default handlers: helix.desktop\n"));
+        assert!(rendered.contains("Added associations: gedit.desktop; code.desktop\n"));
+
+        let empty = MimeAppsLists::for_mime(&apps, "image/png", &MimeRelations::default());
+        assert!(empty.render().is_empty());
+    }
+
+    #[test]
+    fn relates_firefox_pseudo_types_to_their_real_type() {
+        let apps = MimeApps::parse(include_str!("testdata/mimeapps_firefox_pseudo.list"));
+        let relations = MimeRelations::default();
+
+        let html = MimeAppsLists::for_mime(&apps, "text/html", &relations);
+        assert_eq!(
+            html.pseudo_types.keys().collect::<Vec<_>>(),
+            vec![
+                "application/x-extension-htm",
+                "application/x-extension-html",
+                "application/x-extension-shtml"
+            ]
+        );
+        assert_eq!(
+            html.pseudo_types["application/x-extension-html"],
+            vec!["firefox.desktop", "chromium.desktop"]
+        );
+        assert!(html
+            .render()
+            .contains("Also listed as application/x-extension-htm: firefox.desktop\n"));
+
+        let pseudo = MimeAppsLists::for_mime(&apps, "application/x-extension-xhtml", &relations);
+        assert_eq!(pseudo.default_handlers, vec!["firefox.desktop"]);
+        assert!(pseudo
+            .render()
+            .contains("Relation: pseudo-type for application/xhtml+xml\n"));
+        let json = serde_json::to_value(&pseudo).unwrap();
+        assert_eq!(json["related_type"]["kind"], "extension_pseudo_type");
+    }
+
+    fn pdf_finder(exec: &str) -> ApplicationFinder {
+        let mut cache = Box::new(MemoryCache::new());
+        cache.insert(
+            PathBuf::from("/usr/share/applications/org.pwmt.zathura.desktop"),
+            DesktopFile {
+                main_entry: Some(DesktopEntry {
+                    name: "Zathura".to_string(),
+                    exec: exec.to_string(),
+                    mime_types: vec!["application/pdf".to_string()],
+                    ..DesktopEntry::default()
+                }),
+                actions: HashMap::new(),
+            },
+        );
+        let associations = HashMap::from([(
+            "application/pdf".to_string(),
+            vec!["org.pwmt.zathura.desktop".to_string()],
+        )]);
+        ApplicationFinder::new(cache, MimeAssociations::with_associations(associations))
+    }
+
+    #[test]
+    fn exec_query_resolves_default_binary_on_path() {
+        let resolved =
+            ResolvedDefault::resolve(&pdf_finder("sh -c true %f"), "application/pdf").unwrap();
+
+        assert_eq!(resolved.desktop_id, "org.pwmt.zathura.desktop");
+        assert_eq!(resolved.program, "sh");
+        assert_eq!(resolved.path, which::which("sh").ok());
+        assert!(resolved.render().starts_with("org.pwmt.zathura.desktop\t/"));
+    }
+
+    #[test]
+    fn exec_query_reports_missing_binary_of_existing_default() {
+        let resolved = ResolvedDefault::resolve(
+            &pdf_finder("openit-definitely-missing-viewer %f"),
+            "application/pdf",
+        )
+        .unwrap();
+
+        assert_eq!(resolved.desktop_id, "org.pwmt.zathura.desktop");
+        assert_eq!(resolved.path, None);
+        assert_eq!(
+            resolved.render(),
+            "org.pwmt.zathura.desktop\topenit-definitely-missing-viewer (not found)\n"
+        );
+        let json = serde_json::to_value(&resolved).unwrap();
+        assert_eq!(json["path"], serde_json::Value::Null);
+    }
+
+    #[test]
+    fn exec_query_without_default_fails() {
+        let err = ResolvedDefault::resolve(&pdf_finder("zathura %f"), "image/png").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "No default handler configured for image/png"
+        );
+    }
+
+    #[test]
+    fn explain_shows_a_user_file_overriding_a_system_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let user = temp_dir.path().join("config/mimeapps.list");
+        let system = temp_dir.path().join("system/applications/mimeapps.list");
+        for (path, contents) in [
+            (&user, "[Default Applications]\ntext/plain=helix.desktop;\n"),
+            (
+                &system,
+                "[Default Applications]\ntext/plain=gedit.desktop;\n\n[Added Associations]\ntext/*=kate.desktop;\n",
+            ),
+        ] {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, contents).unwrap();
+        }
+        let installed = PathBuf::from("/usr/share/applications/helix.desktop");
+
+        let chain = ResolutionChain::collect(&[user.clone(), system.clone()], "text/plain", |id| {
+            (id == "helix.desktop").then(|| installed.clone())
+        });
+
+        assert_eq!(chain.files[0].default_applications, vec!["helix.desktop"]);
+        assert_eq!(chain.files[1].default_applications, vec!["gedit.desktop"]);
+        assert_eq!(chain.files[1].added_associations, vec!["kate.desktop"]);
+        assert_eq!(
+            chain.render(),
+            format!(
+                "MIME type: text/plain\n\
+                 mimeapps.list files, highest precedence first:\n\
+                 1. {}\n   Default Applications: helix.desktop\n\
+                 2. {}\n   Default Applications: gedit.desktop\n   Added Associations: kate.desktop\n\
+                 Merged order:\n\
+                 1. helix.desktop -> /usr/share/applications/helix.desktop\n\
+                 2. kate.desktop -> not found\n",
+                user.display(),
+                system.display()
+            )
+        );
+
+        let json = serde_json::to_value(&chain).unwrap();
+        assert_eq!(json["merged"][1]["desktop_file"], serde_json::Value::Null);
+        assert_eq!(json["files"][0]["path"], user.display().to_string());
+    }
+}
@@ -0,0 +1,174 @@
+use crate::cli::HandlerArgs;
+use crate::commands::list::desktop_id_key;
+use crate::commands::{CommandContext, CommandExecutor};
+use crate::mime_associations::{HandlerAssociation, MimeAssociations};
+use anyhow::Result;
+use serde::Serialize;
+
+pub struct HandlerCommand {
+    args: HandlerArgs,
+}
+
+impl HandlerCommand {
+    pub fn new(args: HandlerArgs) -> Self {
+        Self { args }
+    }
+}
+
+impl CommandExecutor for HandlerCommand {
+    fn execute(self, _ctx: &CommandContext) -> Result<()> {
+        let report =
+            HandlerReport::collect(&MimeAssociations::load(), self.args.handler.as_deref());
+        if self.args.json {
+            println!("{}", serde_json::to_string_pretty(&report.handlers)?);
+        } else {
+            print!("{}", report.render());
+        }
+        Ok(())
+    }
+}
+
+/// A handler and every MIME key that lists it.
+#[derive(Debug, Serialize)]
+struct HandlerEntry {
+    handler: String,
+    mimes: Vec<HandlerAssociation>,
+}
+
+#[derive(Debug)]
+struct HandlerReport {
+    /// The requested handler, for the "nothing found" message.
+    requested: Option<String>,
+    handlers: Vec<HandlerEntry>,
+}
+
+impl HandlerReport {
+    /// `handler` matches with or without a directory prefix or `.desktop` suffix.
+    fn collect(associations: &MimeAssociations, handler: Option<&str>) -> Self {
+        let wanted = handler.map(desktop_id_key);
+        let handlers = associations
+            .reverse_index()
+            .into_iter()
+            .filter(|(id, _)| {
+                wanted
+                    .as_ref()
+                    .is_none_or(|wanted| desktop_id_key(id) == *wanted)
+            })
+            .map(|(handler, mimes)| HandlerEntry { handler, mimes })
+            .collect();
+        Self {
+            requested: handler.map(str::to_string),
+            handlers,
+        }
+    }
+
+    /// One block per handler; defaults are marked, added associations show their rank.
+    fn render(&self) -> String {
+        if self.handlers.is_empty() {
+            return match &self.requested {
+                Some(handler) => format!("{handler} is not associated with any MIME type\n"),
+                None => "No associations found\n".to_string(),
+            };
+        }
+
+        let mut out = String::new();
+        for entry in &self.handlers {
+            let width = entry
+                .mimes
+                .iter()
+                .map(|association| association.mime.len())
+                .max()
+                .unwrap_or(0);
+            out.push_str(&format!("{}\n", entry.handler));
+            for association in &entry.mimes {
+                let role = if association.is_default {
+                    "default".to_string()
+                } else {
+                    format!("added (#{})", association.position + 1)
+                };
+                out.push_str(&format!("  {:<width$}  {role}\n", association.mime));
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MIMEAPPS: &str = "[Default Applications]
+text/html=firefox.desktop;
+x-scheme-handler/http=firefox.desktop;
+x-scheme-handler/https=firefox.desktop;
+image/png=imv.desktop;
+
+[Added Associations]
+application/pdf=org.pwmt.zathura.desktop;firefox.desktop;
+image/png=firefox.desktop;
+
+[Removed Associations]
+x-scheme-handler/https=firefox.desktop;
+";
+
+    fn mimes(entry: &HandlerEntry) -> Vec<&str> {
+        entry.mimes.iter().map(|m| m.mime.as_str()).collect()
+    }
+
+    #[test]
+    fn lists_every_mime_of_one_handler() {
+        let associations = MimeAssociations::from_mimeapps_contents(MIMEAPPS);
+
+        let report = HandlerReport::collect(&associations, Some("firefox"));
+        assert_eq!(report.handlers.len(), 1);
+        assert_eq!(
+            mimes(&report.handlers[0]),
+            vec![
+                "application/pdf",
+                "image/png",
+                "text/html",
+                "x-scheme-handler/http"
+            ]
+        );
+
+        let pdf = &report.handlers[0].mimes[0];
+        assert_eq!(pdf.position, 1);
+        assert!(!pdf.is_default);
+        assert!(report.handlers[0].mimes[2].is_default);
+    }
+
+    #[test]
+    fn render_marks_defaults_and_ranks_added_associations() {
+        let associations = MimeAssociations::from_mimeapps_contents(MIMEAPPS);
+
+        let rendered = HandlerReport::collect(&associations, Some("firefox.desktop")).render();
+        assert_eq!(
+            rendered,
+            "firefox.desktop\n\
+             \x20 application/pdf        added (#2)\n\
+             \x20 image/png              added (#2)\n\
+             \x20 text/html              default\n\
+             \x20 x-scheme-handler/http  default\n"
+        );
+        assert_eq!(
+            HandlerReport::collect(&associations, Some("gimp")).render(),
+            "gimp is not associated with any MIME type\n"
+        );
+    }
+
+    #[test]
+    fn without_a_handler_every_handler_is_listed() {
+        let associations = MimeAssociations::from_mimeapps_contents(MIMEAPPS);
+
+        let report = HandlerReport::collect(&associations, None);
+        let handlers: Vec<&str> = report.handlers.iter().map(|h| h.handler.as_str()).collect();
+        assert_eq!(
+            handlers,
+            vec!["firefox.desktop", "imv.desktop", "org.pwmt.zathura.desktop"]
+        );
+
+        let json = serde_json::to_value(&report.handlers).unwrap();
+        assert_eq!(json[1]["mimes"][0]["mime"], "image/png");
+        assert_eq!(json[1]["mimes"][0]["is_default"], true);
+    }
+}
@@ -0,0 +1,434 @@
+use crate::cli::{ImportArgs, ImportSource};
+use crate::commands::{CommandContext, CommandExecutor};
+use crate::config::Config;
+use crate::regex_handlers::{RegexHandlerDefinition, RegexHandlerStore};
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::fs;
+use std::path::Path;
+use toml::{Table, Value};
+
+/// Profile name the imported handlr `selector` command is stored under.
+const HANDLR_PROFILE: &str = "handlr";
+
+pub struct ImportCommand {
+    args: ImportArgs,
+}
+
+impl ImportCommand {
+    pub fn new(args: ImportArgs) -> Self {
+        Self { args }
+    }
+}
+
+impl CommandExecutor for ImportCommand {
+    fn execute(self, _ctx: &CommandContext) -> Result<()> {
+        let ImportSource::Handlr(args) = self.args.source;
+        let source = args.from.unwrap_or_else(Config::handlr_config_path);
+        let config_path = Config::config_path();
+        let handlers_path = RegexHandlerStore::config_path();
+
+        let handlr = HandlrConfig::load(&source)?;
+        let existing_config = read_table(&config_path)?;
+        let existing_handlers = RegexHandlerStore::read_definitions(&handlers_path)?;
+        let plan = ImportPlan::build(&handlr, &existing_config, &existing_handlers)?;
+
+        println!("Importing {}", source.display());
+        print!("{}", plan.render(&config_path, &handlers_path));
+
+        if !args.write {
+            println!("\nDry run: nothing written. Re-run with --write to apply.");
+            return Ok(());
+        }
+
+        plan.apply(
+            existing_config,
+            existing_handlers,
+            &config_path,
+            &handlers_path,
+        )?;
+        println!();
+        if !plan.settings.is_empty() {
+            println!("Wrote {}", config_path.display());
+        }
+        if !plan.handlers.is_empty() {
+            println!("Wrote {}", handlers_path.display());
+        }
+        Ok(())
+    }
+}
+
+/// The parts of handlr's `handlr.toml` that openit understands.
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct HandlrConfig {
+    enable_selector: Option<bool>,
+    selector: Option<String>,
+    term_exec_args: Option<String>,
+    expand_wildcards: Option<bool>,
+    handlers: Vec<RegexHandlerDefinition>,
+}
+
+impl HandlrConfig {
+    fn load(path: &Path) -> Result<Self> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read handlr config at {}", path.display()))?;
+        Self::parse(&contents)
+            .with_context(|| format!("Failed to parse handlr config at {}", path.display()))
+    }
+
+    fn parse(contents: &str) -> Result<Self> {
+        Ok(toml::from_str(contents)?)
+    }
+}
+
+/// A config key to set, addressed by its dotted path in `config.toml`.
+#[derive(Debug, PartialEq)]
+struct Setting {
+    path: Vec<&'static str>,
+    value: Value,
+}
+
+impl Setting {
+    fn new(path: &[&'static str], value: impl Into<Value>) -> Self {
+        Self {
+            path: path.to_vec(),
+            value: value.into(),
+        }
+    }
+
+    fn key(&self) -> String {
+        self.path.join(".")
+    }
+}
+
+/// What importing would change, computed without touching any file.
+#[derive(Debug, Default)]
+struct ImportPlan {
+    settings: Vec<Setting>,
+    handlers: Vec<RegexHandlerDefinition>,
+    /// Handlers already present in `regex_handlers.toml` with the same exec and regexes.
+    unchanged_handlers: Vec<String>,
+    /// Values openit already sets differently; they are kept and only reported.
+    conflicts: Vec<String>,
+}
+
+impl ImportPlan {
+    fn build(
+        handlr: &HandlrConfig,
+        existing_config: &Table,
+        existing_handlers: &[RegexHandlerDefinition],
+    ) -> Result<Self> {
+        let mut plan = Self::default();
+
+        let mut candidates = Vec::new();
+        if let Some(enabled) = handlr.enable_selector {
+            candidates.push(Setting::new(&["open_with"], enabled));
+        }
+        if let Some(term_exec_args) = &handlr.term_exec_args {
+            candidates.push(Setting::new(&["term_exec_args"], term_exec_args.as_str()));
+        }
+        if let Some(expand) = handlr.expand_wildcards {
+            candidates.push(Setting::new(&["expand_wildcards"], expand));
+        }
+        if let Some(selector) = handlr.selector.as_deref().map(str::trim) {
+            let mut parts = shell_words::split(selector)
+                .with_context(|| format!("Failed to parse handlr selector `{selector}`"))?;
+            if !parts.is_empty() {
+                let command = parts.remove(0);
+                let mut profile = Table::new();
+                profile.insert("command".into(), command.into());
+                profile.insert("args".into(), parts.into());
+                profile.insert("entry_template".into(), "{name}".into());
+                profile.insert("selector_type".into(), "gui".into());
+                candidates.push(Setting::new(&["selectors", HANDLR_PROFILE], profile));
+                candidates.push(Setting::new(&["default", "gui"], HANDLR_PROFILE));
+            }
+        }
+
+        for setting in candidates {
+            match lookup(existing_config, &setting.path) {
+                Some(current) if *current == setting.value => {}
+                Some(current) => plan.conflicts.push(format!(
+                    "{}: openit has {}, handlr has {} (kept openit value)",
+                    setting.key(),
+                    inline(current),
+                    inline(&setting.value)
+                )),
+                None => plan.settings.push(setting),
+            }
+        }
+
+        for handler in &handlr.handlers {
+            if existing_handlers.iter().any(|existing| {
+                existing.exec == handler.exec && existing.regexes == handler.regexes
+            }) {
+                plan.unchanged_handlers.push(handler.exec.clone());
+                continue;
+            }
+
+            let clashing = existing_handlers.iter().find(|existing| {
+                existing.exec != handler.exec
+                    && existing
+                        .regexes
+                        .iter()
+                        .any(|pattern| handler.regexes.contains(pattern))
+            });
+            match clashing {
+                Some(existing) => plan.conflicts.push(format!(
+                    "handler `{}`: its regexes are already handled by `{}` (skipped)",
+                    handler.exec, existing.exec
+                )),
+                None => plan.handlers.push(RegexHandlerDefinition {
+                    exec: handler.exec.clone(),
+                    regexes: handler.regexes.clone(),
+                    terminal: handler.terminal,
+                    ..RegexHandlerDefinition::default()
+                }),
+            }
+        }
+
+        Ok(plan)
+    }
+
+    /// Diff-style summary: `+` for writes, `=` for entries already present, `!` for conflicts.
+    fn render(&self, config_path: &Path, handlers_path: &Path) -> String {
+        let mut out = format!("--- {}\n", config_path.display());
+        if self.settings.is_empty() {
+            out.push_str("  (no changes)\n");
+        }
+        for setting in &self.settings {
+            out.push_str(&format!(
+                "+ {} = {}\n",
+                setting.key(),
+                inline(&setting.value)
+            ));
+        }
+
+        out.push_str(&format!("--- {}\n", handlers_path.display()));
+        if self.handlers.is_empty() && self.unchanged_handlers.is_empty() {
+            out.push_str("  (no changes)\n");
+        }
+        for handler in &self.handlers {
+            out.push_str(&format!(
+                "+ exec = {:?}, regexes = {:?}{}\n",
+                handler.exec,
+                handler.regexes,
+                if handler.terminal {
+                    ", terminal = true"
+                } else {
+                    ""
+                }
+            ));
+        }
+        for exec in &self.unchanged_handlers {
+            out.push_str(&format!("= exec = {exec:?} (already present)\n"));
+        }
+
+        if !self.conflicts.is_empty() {
+            out.push_str("Conflicts:\n");
+            for conflict in &self.conflicts {
+                out.push_str(&format!("! {conflict}\n"));
+            }
+        }
+        out
+    }
+
+    fn apply(
+        &self,
+        mut config: Table,
+        mut handlers: Vec<RegexHandlerDefinition>,
+        config_path: &Path,
+        handlers_path: &Path,
+    ) -> Result<()> {
+        if !self.settings.is_empty() {
+            for setting in &self.settings {
+                insert(&mut config, &setting.path, setting.value.clone());
+            }
+            write_table(config_path, &config)?;
+        }
+
+        if !self.handlers.is_empty() {
+            handlers.extend(self.handlers.iter().cloned());
+            RegexHandlerStore::write_definitions(handlers_path, &handlers)?;
+        }
+        Ok(())
+    }
+}
+
+/// `config.toml` as a raw table, so unknown keys survive the rewrite; empty if missing.
+fn read_table(path: &Path) -> Result<Table> {
+    if !path.exists() {
+        return Ok(Table::new());
+    }
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read config file at {}", path.display()))?;
+    contents
+        .parse()
+        .with_context(|| format!("Failed to parse config file at {}", path.display()))
+}
+
+fn write_table(path: &Path, table: &Table) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, toml::to_string_pretty(table)?)
+        .with_context(|| format!("Failed to write config file at {}", path.display()))
+}
+
+/// Value at a dotted path; `open_with` is also found under its `enable_selector` alias.
+fn lookup<'a>(table: &'a Table, path: &[&str]) -> Option<&'a Value> {
+    if path == ["open_with"] {
+        return table
+            .get("open_with")
+            .or_else(|| table.get("enable_selector"));
+    }
+
+    let (last, parents) = path.split_last()?;
+    let mut current = table;
+    for key in parents {
+        current = current.get(*key)?.as_table()?;
+    }
+    current.get(*last)
+}
+
+fn insert(table: &mut Table, path: &[&str], value: Value) {
+    let Some((last, parents)) = path.split_last() else {
+        return;
+    };
+    let mut current = table;
+    for key in parents {
+        current = current
+            .entry(key.to_string())
+            .or_insert_with(|| Value::Table(Table::new()))
+            .as_table_mut()
+            .expect("config sections are tables");
+    }
+    current.insert(last.to_string(), value);
+}
+
+/// One-line TOML rendering of a value for the summary.
+fn inline(value: &Value) -> String {
+    match value {
+        Value::Table(table) => {
+            let fields: Vec<String> = table
+                .iter()
+                .map(|(key, value)| format!("{key} = {}", inline(value)))
+                .collect();
+            format!("{{ {} }}", fields.join(", "))
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const BASIC: &str = include_str!("testdata/handlr_basic.toml");
+    const CONFLICTING: &str = include_str!("testdata/handlr_conflicting.toml");
+
+    fn plan(handlr: &str, config: &str, handlers: &[RegexHandlerDefinition]) -> ImportPlan {
+        let handlr = HandlrConfig::parse(handlr).unwrap();
+        ImportPlan::build(&handlr, &config.parse().unwrap(), handlers).unwrap()
+    }
+
+    fn handler(exec: &str, regexes: &[&str]) -> RegexHandlerDefinition {
+        RegexHandlerDefinition {
+            exec: exec.to_string(),
+            regexes: regexes.iter().map(|r| r.to_string()).collect(),
+            ..RegexHandlerDefinition::default()
+        }
+    }
+
+    #[test]
+    fn basic_fixture_maps_selector_and_handlers() {
+        let plan = plan(BASIC, "", &[]);
+
+        let keys: Vec<String> = plan.settings.iter().map(Setting::key).collect();
+        assert_eq!(
+            keys,
+            vec![
+                "open_with",
+                "term_exec_args",
+                "expand_wildcards",
+                "selectors.handlr",
+                "default.gui"
+            ]
+        );
+        assert_eq!(plan.settings[0].value, Value::Boolean(true));
+        assert_eq!(plan.settings[1].value, Value::from("-e"));
+
+        let profile = plan.settings[3].value.as_table().unwrap();
+        assert_eq!(profile["command"].as_str(), Some("rofi"));
+        assert_eq!(
+            profile["args"],
+            Value::from(vec!["-dmenu", "-i", "-p", "Open With: "])
+        );
+
+        let execs: Vec<&str> = plan.handlers.iter().map(|h| h.exec.as_str()).collect();
+        assert_eq!(execs, vec!["freetube %u", "foot -e nvim %f"]);
+        assert!(plan.handlers[1].terminal);
+        assert!(plan.conflicts.is_empty());
+    }
+
+    #[test]
+    fn conflicting_settings_and_handlers_are_reported_not_overwritten() {
+        let existing_config = "open_with = true\nterm_exec_args = \"-e\"\n";
+        let existing = [
+            handler("mpv %U", &["\\.mkv$"]),
+            handler("freetube %u", &["youtu\\.?be"]),
+        ];
+        let plan = plan(CONFLICTING, existing_config, &existing);
+
+        let keys: Vec<String> = plan.settings.iter().map(Setting::key).collect();
+        assert_eq!(keys, Vec::<String>::new());
+        assert_eq!(plan.unchanged_handlers, vec!["freetube %u"]);
+        assert!(plan.handlers.is_empty());
+        assert_eq!(
+            plan.conflicts,
+            vec![
+                "open_with: openit has true, handlr has false (kept openit value)",
+                "handler `vlc %U`: its regexes are already handled by `mpv %U` (skipped)",
+            ]
+        );
+
+        let rendered = plan.render(Path::new("config.toml"), Path::new("regex_handlers.toml"));
+        assert_eq!(
+            rendered,
+            "--- config.toml\n  (no changes)\n\
+             --- regex_handlers.toml\n\
+             = exec = \"freetube %u\" (already present)\n\
+             Conflicts:\n\
+             ! open_with: openit has true, handlr has false (kept openit value)\n\
+             ! handler `vlc %U`: its regexes are already handled by `mpv %U` (skipped)\n"
+        );
+    }
+
+    #[test]
+    fn apply_keeps_unrelated_config_and_appends_handlers() {
+        let temp_dir = TempDir::new().unwrap();
+        let config_path = temp_dir.path().join("config.toml");
+        let handlers_path = temp_dir.path().join("regex_handlers.toml");
+        let existing_config: Table = "tie_breaker = \"mtime\"\n".parse().unwrap();
+        let existing = vec![handler("mpv %U", &["\\.mkv$"])];
+
+        let handlr = HandlrConfig::parse(BASIC).unwrap();
+        let plan = ImportPlan::build(&handlr, &existing_config, &existing).unwrap();
+        plan.apply(existing_config, existing, &config_path, &handlers_path)
+            .unwrap();
+
+        let config = Config::load(Some(config_path)).unwrap();
+        assert_eq!(config.tie_breaker, crate::config::TieBreaker::Mtime);
+        assert_eq!(config.selector.defaults.gui.as_str(), HANDLR_PROFILE);
+        assert_eq!(
+            config.get_selector_profile(HANDLR_PROFILE).unwrap().command,
+            "rofi"
+        );
+
+        let written = RegexHandlerStore::read_definitions(&handlers_path).unwrap();
+        let execs: Vec<&str> = written.iter().map(|h| h.exec.as_str()).collect();
+        assert_eq!(execs, vec!["mpv %U", "freetube %u", "foot -e nvim %f"]);
+    }
+}
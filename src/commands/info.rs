@@ -0,0 +1,162 @@
+use crate::application_finder::ApplicationFinder;
+use crate::cli::InfoArgs;
+use crate::commands::{CommandContext, CommandExecutor};
+use crate::desktop_parser::DesktopFile;
+use anyhow::Result;
+use serde_json::Value;
+use std::path::{Path, PathBuf};
+
+pub struct InfoCommand {
+    args: InfoArgs,
+}
+
+impl InfoCommand {
+    pub fn new(args: InfoArgs) -> Self {
+        Self { args }
+    }
+}
+
+impl CommandExecutor for InfoCommand {
+    fn execute(self, ctx: &CommandContext) -> Result<()> {
+        let finder = ctx.application_finder();
+        let (path, desktop_file) = lookup_desktop_file(&finder, &self.args.desktop_id)?;
+
+        if self.args.json {
+            println!("{}", serde_json::to_string_pretty(&desktop_file)?);
+        } else {
+            print!("{}", render(&path, &desktop_file)?);
+        }
+
+        Ok(())
+    }
+}
+
+/// Resolve a desktop ID through the finder, or parse a path to a `.desktop` file directly.
+fn lookup_desktop_file(finder: &ApplicationFinder, input: &str) -> Result<(PathBuf, DesktopFile)> {
+    let path = Path::new(input);
+    if (path.is_absolute() || input.contains('/')) && path.is_file() {
+        return Ok((path.to_path_buf(), DesktopFile::parse(path)?));
+    }
+
+    finder
+        .find_desktop_file(input)
+        .map(|(path, desktop_file)| (path.clone(), desktop_file.clone()))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "Desktop entry `{}` not found in available applications",
+                input
+            )
+        })
+}
+
+fn render(path: &Path, desktop_file: &DesktopFile) -> Result<String> {
+    let mut out = format!("Desktop file: {}\n", path.display());
+
+    match &desktop_file.main_entry {
+        Some(entry) => {
+            out.push_str("\n[Desktop Entry]\n");
+            render_fields(&mut out, serde_json::to_value(entry)?);
+        }
+        None => out.push_str("\nNo [Desktop Entry] group\n"),
+    }
+
+    for (action_id, action) in desktop_file.ordered_actions() {
+        out.push_str(&format!("\n[Desktop Action {action_id}]\n"));
+        render_fields(&mut out, serde_json::to_value(action)?);
+    }
+
+    Ok(out)
+}
+
+fn render_fields(out: &mut String, value: Value) {
+    let Value::Object(fields) = value else {
+        return;
+    };
+
+    let width = fields.keys().map(String::len).max().unwrap_or(0);
+    for (key, value) in fields {
+        let rendered = match value {
+            Value::Null => "-".to_string(),
+            Value::String(text) => text,
+            Value::Array(items) => items
+                .iter()
+                .map(|item| {
+                    item.as_str()
+                        .map_or_else(|| item.to_string(), str::to_string)
+                })
+                .collect::<Vec<_>>()
+                .join(";"),
+            other => other.to_string(),
+        };
+        out.push_str(&format!("  {key:<width$}  {rendered}\n"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::{DesktopCache, MemoryCache};
+    use crate::mime_associations::MimeAssociations;
+    use crate::test_support::create_test_desktop_file;
+    use tempfile::TempDir;
+
+    const FIREFOX: &str = "[Desktop Entry]
+Type=Application
+Name=Firefox
+Exec=firefox %u
+Icon=firefox
+MimeType=text/html;x-scheme-handler/https;
+Actions=new-window;
+
+[Desktop Action new-window]
+Name=New Window
+Exec=firefox --new-window %u
+";
+
+    fn finder_with(path: &Path) -> ApplicationFinder {
+        let mut cache = Box::new(MemoryCache::new());
+        cache.insert(path.to_path_buf(), DesktopFile::parse(path).unwrap());
+        ApplicationFinder::new(cache, MimeAssociations::default())
+    }
+
+    #[test]
+    fn looks_up_by_desktop_id_and_renders_all_fields() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = create_test_desktop_file(temp_dir.path(), "firefox.desktop", FIREFOX);
+        let finder = finder_with(&path);
+
+        let (found, desktop_file) = lookup_desktop_file(&finder, "firefox.desktop").unwrap();
+        assert_eq!(found, path);
+
+        let rendered = render(&found, &desktop_file).unwrap();
+        assert!(rendered.contains("[Desktop Entry]"));
+        assert!(rendered.contains("name "));
+        assert!(rendered.contains("Firefox"));
+        assert!(rendered.contains("text/html;x-scheme-handler/https"));
+        assert!(rendered.contains("try_exec"));
+        assert!(rendered.contains("[Desktop Action new-window]"));
+        assert!(rendered.contains("firefox --new-window %u"));
+    }
+
+    #[test]
+    fn parses_full_path_without_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = create_test_desktop_file(temp_dir.path(), "firefox.desktop", FIREFOX);
+        let finder =
+            ApplicationFinder::new(Box::new(MemoryCache::new()), MimeAssociations::default());
+
+        let (_, desktop_file) = lookup_desktop_file(&finder, &path.to_string_lossy()).unwrap();
+        let json = serde_json::to_value(&desktop_file).unwrap();
+        assert_eq!(json["main_entry"]["name"], "Firefox");
+        assert_eq!(json["actions"]["new-window"]["name"], "New Window");
+    }
+
+    #[test]
+    fn unknown_desktop_id_errors() {
+        let finder =
+            ApplicationFinder::new(Box::new(MemoryCache::new()), MimeAssociations::default());
+
+        let err = lookup_desktop_file(&finder, "missing.desktop").unwrap_err();
+        assert!(err.to_string().contains("not found"));
+    }
+}
@@ -1,7 +1,13 @@
 use crate::cli::ListArgs;
 use crate::commands::{CommandContext, CommandExecutor};
+use crate::mime_associations::{normalize_desktop_id, MimeAssociations};
+use crate::mime_relations::{MimeRelation, MimeRelations};
+use crate::mimeapps::{DesktopList, MimeApps};
 use anyhow::Result;
 use itertools::Itertools;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use wildmatch::WildMatch;
 
 pub struct ListCommand {
     args: ListArgs,
@@ -15,40 +21,372 @@ impl ListCommand {
 
 impl CommandExecutor for ListCommand {
     fn execute(self, ctx: &CommandContext) -> Result<()> {
+        if self.args.default_only {
+            let defaults = DefaultListing::collect(&MimeAssociations::load(), &self.args);
+            if self.args.json {
+                println!("{}", serde_json::to_string_pretty(&defaults)?);
+            } else {
+                print!("{}", defaults.render());
+            }
+            return Ok(());
+        }
+
         let apps = ctx.load_mimeapps()?;
+        let finder = ctx.application_finder();
+        let listing = Listing::collect(&apps, &self.args, &MimeRelations::load(), |handler| {
+            finder.find_desktop_file(handler).is_some()
+        });
 
         if self.args.json {
-            let payload = serde_json::json!({
-                "default_apps": apps
-                    .default_apps()
-                    .iter()
-                    .map(|(mime, handlers)| {
-                        serde_json::json!({
-                            "mime": mime,
-                            "handlers": handlers.iter().cloned().collect::<Vec<_>>()
-                        })
-                    })
-                    .collect::<Vec<_>>(),
-                "added_associations": apps
-                    .added_associations()
-                    .iter()
-                    .map(|(mime, handlers)| {
-                        serde_json::json!({
-                            "mime": mime,
-                            "handlers": handlers.iter().cloned().collect::<Vec<_>>()
-                        })
-                    })
-                    .collect::<Vec<_>>(),
-            });
-
-            println!("{}", serde_json::to_string_pretty(&payload)?);
+            println!("{}", serde_json::to_string_pretty(&listing)?);
         } else {
-            for (mime, handlers) in apps.default_apps() {
-                let joined = handlers.iter().map(|h| h.as_str()).join("; ");
-                println!("{mime}: {joined}");
-            }
+            print!("{}", listing.render());
         }
 
         Ok(())
     }
 }
+
+/// One `mimeapps.list` line: a MIME type and its handlers in preference order.
+#[derive(Debug, Serialize)]
+struct ListRow {
+    mime: String,
+    handlers: Vec<String>,
+    /// Handlers that do not resolve to an installed desktop file.
+    dangling: Vec<String>,
+    /// The real type when `mime` is an alias or pseudo-type such as `application/x-extension-html`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    related: Option<MimeRelation>,
+}
+
+#[derive(Debug, Serialize)]
+struct Listing {
+    default_apps: Vec<ListRow>,
+    added_associations: Vec<ListRow>,
+}
+
+impl Listing {
+    /// Rows matching `--mime` and `--handler`; `resolves` reports whether a handler exists.
+    fn collect(
+        apps: &MimeApps,
+        args: &ListArgs,
+        relations: &MimeRelations,
+        resolves: impl Fn(&str) -> bool,
+    ) -> Self {
+        let filter = RowFilter::new(args);
+        let rows = |section: &BTreeMap<String, DesktopList>| -> Vec<ListRow> {
+            section
+                .iter()
+                .filter(|(mime, handlers)| filter.matches(mime, handlers.iter()))
+                .map(|(mime, handlers)| ListRow {
+                    mime: mime.clone(),
+                    handlers: handlers.iter().cloned().collect(),
+                    dangling: handlers
+                        .iter()
+                        .filter(|handler| !resolves(handler.as_str()))
+                        .cloned()
+                        .collect(),
+                    related: relations.related(mime),
+                })
+                .collect()
+        };
+
+        Self {
+            default_apps: rows(apps.default_apps()),
+            added_associations: rows(apps.added_associations()),
+        }
+    }
+
+    /// Default applications with the handler column aligned; dangling handlers are marked
+    /// and pseudo-types name their real type.
+    fn render(&self) -> String {
+        let width = self
+            .default_apps
+            .iter()
+            .map(|row| row.mime.len())
+            .max()
+            .unwrap_or(0);
+
+        let mut out = String::new();
+        for row in &self.default_apps {
+            let handlers = row
+                .handlers
+                .iter()
+                .map(|handler| {
+                    if row.dangling.contains(handler) {
+                        format!("{handler} (missing)")
+                    } else {
+                        handler.clone()
+                    }
+                })
+                .join("; ");
+            match &row.related {
+                Some(relation) => out.push_str(&format!(
+                    "{:<width$}  {handlers}  ({})\n",
+                    row.mime,
+                    relation.describe()
+                )),
+                None => out.push_str(&format!("{:<width$}  {handlers}\n", row.mime)),
+            }
+        }
+        out
+    }
+}
+
+/// One MIME type and the handler it opens with once every `mimeapps.list` is merged.
+#[derive(Debug, Serialize)]
+struct DefaultRow {
+    mime: String,
+    desktop_id: String,
+}
+
+/// `list --default-only`: effective defaults sorted by MIME type.
+#[derive(Debug, Serialize)]
+struct DefaultListing {
+    defaults: Vec<DefaultRow>,
+}
+
+impl DefaultListing {
+    fn collect(associations: &MimeAssociations, args: &ListArgs) -> Self {
+        let filter = RowFilter::new(args);
+        let defaults = associations
+            .effective_defaults()
+            .into_iter()
+            .filter(|(mime, desktop_id)| filter.matches(mime, std::iter::once(desktop_id)))
+            .map(|(mime, desktop_id)| DefaultRow { mime, desktop_id })
+            .collect();
+        Self { defaults }
+    }
+
+    fn render(&self) -> String {
+        let width = self
+            .defaults
+            .iter()
+            .map(|row| row.mime.len())
+            .max()
+            .unwrap_or(0);
+        self.defaults
+            .iter()
+            .map(|row| format!("{:<width$}  {}\n", row.mime, row.desktop_id))
+            .collect()
+    }
+}
+
+/// `--mime` and `--handler` as given on the command line.
+struct RowFilter {
+    mime: Option<WildMatch>,
+    handler: Option<String>,
+}
+
+impl RowFilter {
+    fn new(args: &ListArgs) -> Self {
+        Self {
+            mime: args
+                .mime
+                .as_deref()
+                .map(|pattern| WildMatch::new(&pattern.trim().to_ascii_lowercase())),
+            handler: args.handler.as_deref().map(desktop_id_key),
+        }
+    }
+
+    fn matches<'a>(&self, mime: &str, mut handlers: impl Iterator<Item = &'a String>) -> bool {
+        self.mime
+            .as_ref()
+            .is_none_or(|matcher| matcher.matches(&mime.to_ascii_lowercase()))
+            && self
+                .handler
+                .as_ref()
+                .is_none_or(|wanted| handlers.any(|handler| desktop_id_key(handler) == *wanted))
+    }
+}
+
+/// Desktop ID without a directory prefix or `.desktop` suffix, so `--handler imv` and
+/// `--handler imv.desktop` select the same rows.
+pub(super) fn desktop_id_key(handler: &str) -> String {
+    let id = normalize_desktop_id(handler);
+    id.strip_suffix(".desktop").unwrap_or(id).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const MIMEAPPS: &str = "[Default Applications]
+application/pdf=org.pwmt.zathura.desktop;
+image/png=imv.desktop;gimp.desktop;
+image/jpeg=imv.desktop;
+text/plain=nvim.desktop;
+
+[Added Associations]
+application/epub+zip=org.pwmt.zathura.desktop;
+";
+
+    fn load(contents: &str) -> (TempDir, MimeApps) {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("mimeapps.list");
+        fs::write(&path, contents).unwrap();
+        let apps = MimeApps::load_from_disk(Some(path)).unwrap();
+        (temp_dir, apps)
+    }
+
+    fn args(mime: Option<&str>, handler: Option<&str>) -> ListArgs {
+        ListArgs {
+            json: false,
+            mime: mime.map(str::to_string),
+            handler: handler.map(str::to_string),
+            default_only: false,
+        }
+    }
+
+    fn installed(handler: &str) -> bool {
+        handler != "gimp.desktop"
+    }
+
+    fn collect(apps: &MimeApps, args: &ListArgs) -> Listing {
+        Listing::collect(apps, args, &MimeRelations::default(), installed)
+    }
+
+    fn mimes(rows: &[ListRow]) -> Vec<&str> {
+        rows.iter().map(|row| row.mime.as_str()).collect()
+    }
+
+    #[test]
+    fn handler_filter_finds_every_mime_using_it() {
+        let (_dir, apps) = load(MIMEAPPS);
+
+        let listing = collect(&apps, &args(None, Some("org.pwmt.zathura")));
+        assert_eq!(mimes(&listing.default_apps), vec!["application/pdf"]);
+        assert_eq!(
+            mimes(&listing.added_associations),
+            vec!["application/epub+zip"]
+        );
+    }
+
+    #[test]
+    fn mime_glob_filters_rows_and_keeps_handler_order() {
+        let (_dir, apps) = load(MIMEAPPS);
+
+        let listing = collect(&apps, &args(Some("image/*"), None));
+        assert_eq!(
+            mimes(&listing.default_apps),
+            vec!["image/jpeg", "image/png"]
+        );
+        assert!(listing.added_associations.is_empty());
+
+        let png = &listing.default_apps[1];
+        assert_eq!(png.handlers, vec!["imv.desktop", "gimp.desktop"]);
+        assert_eq!(png.dangling, vec!["gimp.desktop"]);
+    }
+
+    #[test]
+    fn exact_mime_filter_matches_one_key_in_each_section() {
+        let (_dir, apps) = load(MIMEAPPS);
+
+        let listing = collect(&apps, &args(Some("Image/PNG"), None));
+        assert_eq!(mimes(&listing.default_apps), vec!["image/png"]);
+        assert!(listing.added_associations.is_empty());
+
+        let listing = collect(&apps, &args(Some("application/epub+zip"), None));
+        assert!(listing.default_apps.is_empty());
+        assert_eq!(
+            mimes(&listing.added_associations),
+            vec!["application/epub+zip"]
+        );
+    }
+
+    #[test]
+    fn unmatched_mime_filter_lists_nothing() {
+        let (_dir, apps) = load(MIMEAPPS);
+
+        let listing = collect(&apps, &args(Some("video/*"), None));
+        assert!(listing.default_apps.is_empty());
+        assert!(listing.added_associations.is_empty());
+        assert_eq!(listing.render(), "");
+        let json = serde_json::to_value(&listing).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"default_apps": [], "added_associations": []})
+        );
+    }
+
+    #[test]
+    fn json_reports_dangling_handlers() {
+        let (_dir, apps) = load(MIMEAPPS);
+
+        let listing = collect(&apps, &args(Some("image/png"), Some("imv")));
+        let json = serde_json::to_value(&listing).unwrap();
+        assert_eq!(json["default_apps"][0]["mime"], "image/png");
+        assert_eq!(
+            json["default_apps"][0]["handlers"],
+            serde_json::json!(["imv.desktop", "gimp.desktop"])
+        );
+        assert_eq!(
+            json["default_apps"][0]["dangling"],
+            serde_json::json!(["gimp.desktop"])
+        );
+    }
+
+    #[test]
+    fn render_aligns_columns_and_marks_missing_handlers() {
+        let (_dir, apps) = load(MIMEAPPS);
+
+        let rendered = collect(&apps, &args(None, None)).render();
+        assert_eq!(
+            rendered,
+            "application/pdf  org.pwmt.zathura.desktop\n\
+             image/jpeg       imv.desktop\n\
+             image/png        imv.desktop; gimp.desktop (missing)\n\
+             text/plain       nvim.desktop\n"
+        );
+    }
+
+    #[test]
+    fn default_only_lists_merged_defaults_sorted_by_mime() {
+        let associations = MimeAssociations::from_mimeapps_contents(
+            "[Default Applications]\ntext/plain=nvim.desktop;\napplication/pdf=org.pwmt.zathura.desktop;\n\
+             image/png=imv.desktop;gimp.desktop;\n\n\
+             [Added Associations]\nimage/jpeg=imv.desktop;\n\n\
+             [Removed Associations]\nimage/png=imv.desktop;\n",
+        );
+
+        let listing = DefaultListing::collect(&associations, &args(None, None));
+        assert_eq!(
+            listing.render(),
+            "application/pdf  org.pwmt.zathura.desktop\n\
+             image/png        gimp.desktop\n\
+             text/plain       nvim.desktop\n"
+        );
+
+        let images = DefaultListing::collect(&associations, &args(Some("image/*"), Some("gimp")));
+        let json = serde_json::to_value(&images).unwrap();
+        assert_eq!(
+            json,
+            serde_json::json!({"defaults": [{"mime": "image/png", "desktop_id": "gimp.desktop"}]})
+        );
+    }
+
+    #[test]
+    fn pseudo_types_name_their_real_type() {
+        let (_dir, apps) = load(include_str!("testdata/mimeapps_firefox_pseudo.list"));
+
+        let listing = collect(&apps, &args(Some("application/x-extension-*"), None));
+        assert_eq!(
+            listing.default_apps[1]
+                .related
+                .as_ref()
+                .map(|r| r.real.as_str()),
+            Some("text/html")
+        );
+        let rendered = listing.render();
+        assert!(rendered.contains(
+            "application/x-extension-html   firefox.desktop  (pseudo-type for text/html)\n"
+        ));
+
+        let plain = collect(&apps, &args(Some("text/html"), None));
+        assert!(plain.default_apps[0].related.is_none());
+        let json = serde_json::to_value(&plain).unwrap();
+        assert!(json["default_apps"][0].get("related").is_none());
+    }
+}
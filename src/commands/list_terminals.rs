@@ -0,0 +1,210 @@
+use crate::application_finder::ApplicationEntry;
+use crate::cli::ListTerminalsArgs;
+use crate::commands::{CommandContext, CommandExecutor};
+use crate::config::{Config, TerminalPurpose};
+use crate::executor::TerminalLauncher;
+use crate::open_it::{resolve_terminal_launcher_with, TerminalEnv};
+use anyhow::Result;
+use serde::Serialize;
+
+pub struct ListTerminalsCommand {
+    args: ListTerminalsArgs,
+}
+
+impl ListTerminalsCommand {
+    pub fn new(args: ListTerminalsArgs) -> Self {
+        Self { args }
+    }
+}
+
+impl CommandExecutor for ListTerminalsCommand {
+    fn execute(self, ctx: &CommandContext) -> Result<()> {
+        let report = TerminalReport::detect(ctx);
+        if self.args.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print!("{}", report.render());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct TerminalRow {
+    desktop_id: String,
+    name: String,
+    exec: String,
+    /// Whether `Terminal=true` handlers would currently launch in this emulator.
+    selected: bool,
+}
+
+/// What terminal resolution settles on: a command, and the emulator it came from if any.
+#[derive(Debug, Serialize)]
+struct SelectedTerminal {
+    command: Vec<String>,
+    desktop_id: Option<String>,
+}
+
+/// Every `TerminalEmulator` entry in the desktop cache, plus what `resolve_terminal_launcher`
+/// picks for a `Terminal=true` handler, which may be a command rather than one of them.
+#[derive(Debug, Serialize)]
+pub(super) struct TerminalReport {
+    terminals: Vec<TerminalRow>,
+    selected: Option<SelectedTerminal>,
+    /// Why nothing would be selected, e.g. "No terminal emulator found".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl TerminalReport {
+    pub(super) fn detect(ctx: &CommandContext) -> Self {
+        let config = Config::load(None).unwrap_or_default();
+        let finder = ctx.application_finder();
+        let launcher = resolve_terminal_launcher_with(
+            &config,
+            &finder,
+            &TerminalEnv::current(),
+            TerminalPurpose::Handler,
+        );
+        Self::collect(&finder.find_terminal_emulators(), launcher)
+    }
+
+    pub(super) fn collect(
+        emulators: &[ApplicationEntry],
+        launcher: Result<TerminalLauncher>,
+    ) -> Self {
+        let (selected, error) = match launcher {
+            Ok(launcher) => (Some(launcher), None),
+            Err(err) => (None, Some(format!("{err:#}"))),
+        };
+
+        let mut terminals: Vec<TerminalRow> = emulators
+            .iter()
+            .map(|app| TerminalRow {
+                desktop_id: desktop_id(&app.desktop_file),
+                name: app.name.clone(),
+                exec: app.exec.clone(),
+                selected: selected.as_ref().is_some_and(|launcher| {
+                    launcher.desktop_file.as_ref() == Some(&app.desktop_file)
+                }),
+            })
+            .collect();
+        terminals.sort_by(|a, b| a.desktop_id.cmp(&b.desktop_id));
+
+        Self {
+            terminals,
+            selected: selected.map(|launcher| SelectedTerminal {
+                desktop_id: launcher.desktop_file.as_deref().map(desktop_id),
+                command: launcher.command,
+            }),
+            error,
+        }
+    }
+
+    /// Emulators sorted by desktop ID with `*` on the selected one, then the launcher
+    /// `Terminal=true` handlers would use.
+    pub(super) fn render(&self) -> String {
+        let mut out = String::new();
+        if self.terminals.is_empty() {
+            out.push_str("No terminal emulators detected\n");
+        }
+        let id_width = column_width(self.terminals.iter().map(|row| &row.desktop_id));
+        let name_width = column_width(self.terminals.iter().map(|row| &row.name));
+        for row in &self.terminals {
+            let marker = if row.selected { '*' } else { ' ' };
+            out.push_str(&format!(
+                "{marker} {:<id_width$}  {:<name_width$}  {}\n",
+                row.desktop_id, row.name, row.exec
+            ));
+        }
+
+        match (&self.selected, &self.error) {
+            (Some(selected), _) => {
+                let command = shell_words::join(&selected.command);
+                match &selected.desktop_id {
+                    Some(id) => out.push_str(&format!("\nSelected: {command} ({id})\n")),
+                    None => out.push_str(&format!("\nSelected: {command}\n")),
+                }
+            }
+            (None, Some(error)) => out.push_str(&format!("\nNone selected: {error}\n")),
+            (None, None) => {}
+        }
+        out
+    }
+}
+
+fn column_width<'a>(values: impl Iterator<Item = &'a String>) -> usize {
+    values.map(String::len).max().unwrap_or(0)
+}
+
+fn desktop_id(path: &std::path::Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::desktop_parser::DesktopEntry;
+    use std::path::PathBuf;
+
+    fn emulator(desktop_id: &str, name: &str, exec: &str) -> ApplicationEntry {
+        let entry = DesktopEntry {
+            name: name.to_string(),
+            exec: exec.to_string(),
+            categories: vec!["TerminalEmulator".to_string()],
+            ..DesktopEntry::default()
+        };
+        ApplicationEntry::from_desktop_entry(
+            &entry,
+            PathBuf::from("/usr/share/applications").join(desktop_id),
+        )
+    }
+
+    fn emulators() -> Vec<ApplicationEntry> {
+        vec![
+            emulator("kitty.desktop", "kitty", "kitty --single-instance"),
+            emulator("foot.desktop", "Foot", "foot"),
+        ]
+    }
+
+    #[test]
+    fn terminals_are_sorted_and_the_selected_one_is_marked() {
+        let launcher = TerminalLauncher::new(vec!["kitty".to_string()])
+            .with_desktop_file(PathBuf::from("/usr/share/applications/kitty.desktop"));
+        let rendered = TerminalReport::collect(&emulators(), Ok(launcher)).render();
+
+        assert_eq!(
+            rendered,
+            "  foot.desktop   Foot   foot\n\
+             * kitty.desktop  kitty  kitty --single-instance\n\
+             \n\
+             Selected: kitty (kitty.desktop)\n"
+        );
+    }
+
+    #[test]
+    fn launchers_outside_the_cache_and_failures_are_reported() {
+        let launcher = TerminalLauncher::new(vec!["wezterm".to_string(), "start".to_string()]);
+        let report = TerminalReport::collect(&emulators(), Ok(launcher));
+        assert!(report.terminals.iter().all(|row| !row.selected));
+        assert!(report.render().ends_with("\nSelected: wezterm start\n"));
+
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(
+            json["selected"]["command"],
+            serde_json::json!(["wezterm", "start"])
+        );
+        assert!(json["selected"]["desktop_id"].is_null());
+        assert_eq!(json["terminals"][0]["desktop_id"], "foot.desktop");
+
+        let report =
+            TerminalReport::collect(&[], Err(anyhow::anyhow!("No terminal emulator found")));
+        assert_eq!(
+            report.render(),
+            "No terminal emulators detected\n\nNone selected: No terminal emulator found\n"
+        );
+        assert!(serde_json::to_value(&report).unwrap()["selected"].is_null());
+    }
+}
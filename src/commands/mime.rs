@@ -1,5 +1,38 @@
+use crate::cli::MimeArgs;
+use crate::commands::{CommandContext, CommandExecutor};
+use crate::open_it::OpenIt;
 use anyhow::Result;
 
+pub struct MimeCommand {
+    args: MimeArgs,
+}
+
+impl MimeCommand {
+    pub fn new(args: MimeArgs) -> Self {
+        Self { args }
+    }
+}
+
+impl CommandExecutor for MimeCommand {
+    fn execute(self, _ctx: &CommandContext) -> Result<()> {
+        println!("{}", render_mime(&self.args.target, self.args.json)?);
+        Ok(())
+    }
+}
+
+fn render_mime(raw_target: &str, json: bool) -> Result<String> {
+    let target = OpenIt::resolve_launch_target(raw_target)?;
+    let mime = OpenIt::mime_for_target(&target);
+
+    if json {
+        Ok(serde_json::to_string_pretty(
+            &serde_json::json!({ "mime": mime }),
+        )?)
+    } else {
+        Ok(mime)
+    }
+}
+
 pub fn normalize_mime_input(input: &str) -> Result<String> {
     let trimmed = input.trim();
 
@@ -57,6 +90,25 @@ mod tests {
         assert_eq!(normalize_mime_input("image/*").unwrap(), "image/*");
     }
 
+    #[test]
+    fn prints_detected_mime_for_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("notes.md");
+        std::fs::write(&path, "# Notes").unwrap();
+        let raw = path.to_string_lossy();
+
+        assert_eq!(render_mime(&raw, false).unwrap(), "text/markdown");
+
+        let json: serde_json::Value =
+            serde_json::from_str(&render_mime(&raw, true).unwrap()).unwrap();
+        assert_eq!(json["mime"], "text/markdown");
+    }
+
+    #[test]
+    fn mime_for_missing_file_errors() {
+        assert!(render_mime("/definitely/missing/file.txt", false).is_err());
+    }
+
     #[test]
     fn rejects_invalid_mime() {
         let err = normalize_mime_input("invalid/").unwrap_err();
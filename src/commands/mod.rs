@@ -2,42 +2,75 @@ use crate::cli::Command;
 use anyhow::Result;
 
 mod add;
+mod apply;
+mod cache;
 mod completions;
+mod config;
 mod context;
+mod doctor;
 mod get;
+mod handler;
+mod import;
+mod info;
 mod list;
+mod list_terminals;
 mod mime;
 mod open;
+mod query;
+mod regex_test;
 mod remove;
 mod set;
 mod unset;
+mod version;
 
 pub use add::AddCommand;
+pub use apply::ApplyCommand;
+pub use cache::CacheCommand;
 pub use completions::CompletionsCommand;
+pub use config::ConfigCommand;
 pub use context::CommandContext;
+pub use doctor::DoctorCommand;
 pub use get::GetCommand;
+pub use handler::HandlerCommand;
+pub use import::ImportCommand;
+pub use info::InfoCommand;
 pub use list::ListCommand;
+pub use list_terminals::ListTerminalsCommand;
+pub use mime::MimeCommand;
 pub use open::OpenCommand;
+pub use query::QueryCommand;
+pub use regex_test::RegexCommand;
 pub use remove::RemoveCommand;
 pub use set::SetCommand;
 pub use unset::UnsetCommand;
+pub use version::VersionCommand;
 
 pub trait CommandExecutor {
     fn execute(self, ctx: &CommandContext) -> Result<()>;
 }
 
-pub fn dispatch(command: Command) -> Result<()> {
-    let ctx = CommandContext::default();
-
+pub fn dispatch(command: Command, ctx: &CommandContext) -> Result<()> {
     match command {
-        Command::Open(args) => OpenCommand::new(args).execute(&ctx),
-        Command::Set(args) => SetCommand::new(args).execute(&ctx),
-        Command::Add(args) => AddCommand::new(args).execute(&ctx),
-        Command::Remove(args) => RemoveCommand::new(args).execute(&ctx),
-        Command::Unset(args) => UnsetCommand::new(args).execute(&ctx),
-        Command::List(args) => ListCommand::new(args).execute(&ctx),
-        Command::Get(args) => GetCommand::new(args).execute(&ctx),
-        Command::Completions(args) => CompletionsCommand::new(args).execute(&ctx),
+        Command::Open(args) => OpenCommand::new(args).execute(ctx),
+        Command::Set(args) => SetCommand::new(args).execute(ctx),
+        Command::Add(args) => AddCommand::new(args).execute(ctx),
+        Command::Remove(args) => RemoveCommand::new(args).execute(ctx),
+        Command::Unset(args) => UnsetCommand::new(args).execute(ctx),
+        Command::Apply(args) => ApplyCommand::new(args).execute(ctx),
+        Command::List(args) => ListCommand::new(args).execute(ctx),
+        Command::Get(args) => GetCommand::new(args).execute(ctx),
+        Command::Completions(args) => CompletionsCommand::new(args).execute(ctx),
+        Command::Doctor(args) => DoctorCommand::new(args).execute(ctx),
+        Command::Version(args) => VersionCommand::new(args).execute(ctx),
+        Command::Mime(args) => MimeCommand::new(args).execute(ctx),
+        Command::Info(args) => InfoCommand::new(args).execute(ctx),
+        Command::Query(args) => QueryCommand::new(args).execute(ctx),
+        Command::ListTerminals(args) => ListTerminalsCommand::new(args).execute(ctx),
+        Command::Config(args) => ConfigCommand::new(args).execute(ctx),
+        Command::Import(args) => ImportCommand::new(args).execute(ctx),
+        Command::Regex(args) => RegexCommand::new(args).execute(ctx),
+        Command::Handler(args) => HandlerCommand::new(args).execute(ctx),
+        Command::Cache(args) => CacheCommand::new(args).execute(ctx),
     }
 }
 
@@ -45,9 +78,8 @@ pub fn dispatch(command: Command) -> Result<()> {
 mod tests {
     use super::*;
     use crate::cli::{Command, EditArgs, RemoveArgs, UnsetArgs};
-    use crate::test_support::{ConfigEnvGuard, ValidationEnvGuard};
+    use crate::test_support::{ConfigEnvGuard, EnvVarGuard, ValidationEnvGuard};
     use serial_test::serial;
-    use std::env;
     use std::fs;
     use tempfile::TempDir;
 
@@ -58,31 +90,62 @@ mod tests {
         let _config_guard = ConfigEnvGuard::set(temp_config.path());
         let _validation = ValidationEnvGuard::enable();
 
-        dispatch(Command::Set(EditArgs {
-            mime: "text/plain".into(),
-            handler: "helix.desktop".into(),
-            expand_wildcards: false,
-        }))
+        dispatch(
+            Command::Set(EditArgs {
+                mime: "text/plain".into(),
+                handler: "helix.desktop".into(),
+                expand_wildcards: false,
+                priority: None,
+                dry_run: false,
+                json: false,
+            }),
+            &CommandContext::default(),
+        )
         .unwrap();
 
         let config_path = temp_config.path().join("mimeapps.list");
         let contents = fs::read_to_string(&config_path).unwrap();
         assert!(contents.contains("text/plain=helix.desktop;"));
 
-        dispatch(Command::Add(EditArgs {
-            mime: "text/plain".into(),
-            handler: "code.desktop".into(),
-            expand_wildcards: false,
-        }))
+        dispatch(
+            Command::Add(EditArgs {
+                mime: "text/plain".into(),
+                handler: "code.desktop".into(),
+                expand_wildcards: false,
+                priority: None,
+                dry_run: false,
+                json: false,
+            }),
+            &CommandContext::default(),
+        )
         .unwrap();
 
         let contents = fs::read_to_string(&config_path).unwrap();
         assert!(contents.contains("text/plain=helix.desktop;code.desktop;"));
 
-        dispatch(Command::Unset(UnsetArgs {
-            mime: "text/plain".into(),
-            expand_wildcards: false,
-        }))
+        dispatch(
+            Command::Set(EditArgs {
+                mime: "text/plain".into(),
+                handler: "vim.desktop".into(),
+                expand_wildcards: false,
+                priority: Some(0),
+                dry_run: false,
+                json: false,
+            }),
+            &CommandContext::default(),
+        )
+        .unwrap();
+
+        let contents = fs::read_to_string(&config_path).unwrap();
+        assert!(contents.contains("text/plain=vim.desktop;helix.desktop;code.desktop;"));
+
+        dispatch(
+            Command::Unset(UnsetArgs {
+                mime: "text/plain".into(),
+                expand_wildcards: false,
+            }),
+            &CommandContext::default(),
+        )
         .unwrap();
 
         let contents = fs::read_to_string(&config_path).unwrap();
@@ -96,25 +159,42 @@ mod tests {
         let _config_guard = ConfigEnvGuard::set(temp_config.path());
         let _validation = ValidationEnvGuard::enable();
 
-        dispatch(Command::Set(EditArgs {
-            mime: "text/plain".into(),
-            handler: "helix.desktop".into(),
-            expand_wildcards: false,
-        }))
+        dispatch(
+            Command::Set(EditArgs {
+                mime: "text/plain".into(),
+                handler: "helix.desktop".into(),
+                expand_wildcards: false,
+                priority: None,
+                dry_run: false,
+                json: false,
+            }),
+            &CommandContext::default(),
+        )
         .unwrap();
 
-        dispatch(Command::Add(EditArgs {
-            mime: "text/plain".into(),
-            handler: "code.desktop".into(),
-            expand_wildcards: false,
-        }))
+        dispatch(
+            Command::Add(EditArgs {
+                mime: "text/plain".into(),
+                handler: "code.desktop".into(),
+                expand_wildcards: false,
+                priority: None,
+                dry_run: false,
+                json: false,
+            }),
+            &CommandContext::default(),
+        )
         .unwrap();
 
-        dispatch(Command::Remove(RemoveArgs {
-            mime: "text/plain".into(),
-            handler: "helix.desktop".into(),
-            expand_wildcards: false,
-        }))
+        dispatch(
+            Command::Remove(RemoveArgs {
+                mime: "text/plain".into(),
+                handler: "helix.desktop".into(),
+                expand_wildcards: false,
+                dry_run: false,
+                json: false,
+            }),
+            &CommandContext::default(),
+        )
         .unwrap();
 
         let contents = fs::read_to_string(temp_config.path().join("mimeapps.list")).unwrap();
@@ -122,19 +202,66 @@ mod tests {
         assert!(!contents.contains("helix.desktop"));
     }
 
+    #[test]
+    #[serial]
+    fn dispatch_dry_run_leaves_mimeapps_untouched() {
+        let temp_config = TempDir::new().unwrap();
+        let _config_guard = ConfigEnvGuard::set(temp_config.path());
+        let _validation = ValidationEnvGuard::enable();
+        let config_path = temp_config.path().join("mimeapps.list");
+        let original = "[Default Applications]\ntext/plain=helix.desktop;\n";
+        fs::write(&config_path, original).unwrap();
+
+        for command in [
+            Command::Set(EditArgs {
+                mime: "text/plain".into(),
+                handler: "vim.desktop".into(),
+                expand_wildcards: false,
+                priority: None,
+                dry_run: true,
+                json: false,
+            }),
+            Command::Add(EditArgs {
+                mime: "image/png".into(),
+                handler: "imv.desktop".into(),
+                expand_wildcards: false,
+                priority: None,
+                dry_run: true,
+                json: true,
+            }),
+            Command::Remove(RemoveArgs {
+                mime: "text/plain".into(),
+                handler: "helix.desktop".into(),
+                expand_wildcards: false,
+                dry_run: true,
+                json: false,
+            }),
+        ] {
+            dispatch(command, &CommandContext::default()).unwrap();
+        }
+
+        assert_eq!(fs::read_to_string(&config_path).unwrap(), original);
+    }
+
     #[test]
     #[serial]
     fn dispatch_add_missing_handler_errors() {
-        env::remove_var(ValidationEnvGuard::KEY);
+        let _validation = EnvVarGuard::unset(ValidationEnvGuard::KEY);
 
         let temp_config = TempDir::new().unwrap();
         let _config_guard = ConfigEnvGuard::set(temp_config.path());
 
-        let result = dispatch(Command::Add(EditArgs {
-            mime: "text/plain".into(),
-            handler: "nonexistent.desktop".into(),
-            expand_wildcards: false,
-        }));
+        let result = dispatch(
+            Command::Add(EditArgs {
+                mime: "text/plain".into(),
+                handler: "nonexistent.desktop".into(),
+                expand_wildcards: false,
+                priority: None,
+                dry_run: false,
+                json: false,
+            }),
+            &CommandContext::default(),
+        );
 
         assert!(result.is_err());
         let message = format!("{}", result.unwrap_err());
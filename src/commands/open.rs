@@ -3,6 +3,7 @@ use crate::cli::OpenArgs;
 use crate::commands::{CommandContext, CommandExecutor};
 use crate::config;
 use crate::open_it::OpenIt;
+use crate::timings;
 use anyhow::Result;
 use std::fs;
 
@@ -17,7 +18,7 @@ impl OpenCommand {
 }
 
 impl CommandExecutor for OpenCommand {
-    fn execute(self, _ctx: &CommandContext) -> Result<()> {
+    fn execute(self, ctx: &CommandContext) -> Result<()> {
         let args = self.args;
 
         if let Err(message) = args.validate() {
@@ -40,9 +41,15 @@ impl CommandExecutor for OpenCommand {
             _ => "debug",
         };
 
-        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(level)).init();
+        let filter = if args.timings {
+            format!("{level},{}=info", timings::TARGET)
+        } else {
+            level.to_string()
+        };
+
+        env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(filter)).init();
 
-        let app = OpenIt::new(args)?;
+        let app = OpenIt::new(args)?.with_assume_yes(ctx.assume_yes);
         app.run()
     }
 }
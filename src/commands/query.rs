@@ -0,0 +1,275 @@
+use crate::application_finder::ApplicationEntry;
+use crate::cli::QueryArgs;
+use crate::commands::list_terminals::TerminalReport;
+use crate::commands::{CommandContext, CommandExecutor};
+use crate::mime_relations::MimeRelations;
+use anyhow::Result;
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use serde_json::Value;
+
+/// Computed by `ApplicationEntry::window_class` rather than stored, but selectable too.
+const WINDOW_CLASS_FIELD: &str = "window_class";
+
+pub struct QueryCommand {
+    args: QueryArgs,
+}
+
+impl QueryCommand {
+    pub fn new(args: QueryArgs) -> Self {
+        Self { args }
+    }
+}
+
+impl CommandExecutor for QueryCommand {
+    fn execute(self, ctx: &CommandContext) -> Result<()> {
+        if self.args.terminals {
+            print!("{}", TerminalReport::detect(ctx).render());
+        }
+
+        if let Some(input) = &self.args.mime {
+            let mime = ctx.normalize_mime_input(input)?;
+            let finder = ctx.application_finder();
+            let relations = MimeRelations::load();
+            if let Some(relation) = relations.related(&mime) {
+                eprintln!(
+                    "Note: {mime}: {}; handlers of {} are listed after its own",
+                    relation.describe(),
+                    relation.real
+                );
+            }
+            let candidates =
+                related_candidates(&mime, &relations, |mime| finder.find_for_mime(mime, false));
+            let field = self.args.field.as_deref().unwrap_or("desktop_id");
+            print!("{}", render_field(&candidates, field)?);
+        }
+
+        Ok(())
+    }
+}
+
+/// Candidates for `mime`, then those of the real type when `mime` is an alias or an
+/// `application/x-extension-*` pseudo-type, without listing a handler twice.
+fn related_candidates(
+    mime: &str,
+    relations: &MimeRelations,
+    find: impl Fn(&str) -> Vec<ApplicationEntry>,
+) -> Vec<ApplicationEntry> {
+    let mut candidates = find(mime);
+    if let Some(relation) = relations.related(mime) {
+        for app in find(&relation.real) {
+            let listed = candidates.iter().any(|listed| {
+                listed.desktop_file == app.desktop_file && listed.action_id == app.action_id
+            });
+            if !listed {
+                candidates.push(app);
+            }
+        }
+    }
+    candidates
+}
+
+/// `field` of each candidate, one line per candidate so lines stay aligned with the
+/// candidate order; a missing value prints as an empty line.
+fn render_field(candidates: &[ApplicationEntry], field: &str) -> Result<String> {
+    let fields = entry_fields();
+    if field != "desktop_id" && field != WINDOW_CLASS_FIELD && !fields.contains(&field) {
+        anyhow::bail!(
+            "Unknown field `{field}`; expected one of: desktop_id, {WINDOW_CLASS_FIELD}, {}",
+            fields.join(", ")
+        );
+    }
+
+    let mut out = String::new();
+    for app in candidates {
+        let value = match field {
+            "desktop_id" => app
+                .desktop_file
+                .file_name()
+                .map(|name| Value::from(name.to_string_lossy().into_owned())),
+            WINDOW_CLASS_FIELD => app.window_class().map(Value::from),
+            _ => serde_json::to_value(app)?.get(field).cloned(),
+        };
+        match value {
+            None | Some(Value::Null) => {}
+            Some(Value::String(text)) => out.push_str(&text),
+            Some(other) => out.push_str(&other.to_string()),
+        }
+        out.push('\n');
+    }
+    Ok(out)
+}
+
+/// Field names of `ApplicationEntry`'s serde model, including ones skipped when empty.
+///
+/// The derived `Deserialize` hands its field list to `deserialize_struct`; this
+/// deserializer records it and stops.
+fn entry_fields() -> &'static [&'static str] {
+    struct FieldNames<'a>(&'a mut &'static [&'static str]);
+
+    impl<'de> Deserializer<'de> for FieldNames<'_> {
+        type Error = de::value::Error;
+
+        fn deserialize_any<V: Visitor<'de>>(self, _: V) -> Result<V::Value, Self::Error> {
+            Err(de::Error::custom("only struct field names are recorded"))
+        }
+
+        fn deserialize_struct<V: Visitor<'de>>(
+            self,
+            _: &'static str,
+            fields: &'static [&'static str],
+            _: V,
+        ) -> Result<V::Value, Self::Error> {
+            *self.0 = fields;
+            Err(de::Error::custom("only struct field names are recorded"))
+        }
+
+        serde::forward_to_deserialize_any! {
+            bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string bytes
+            byte_buf option unit unit_struct newtype_struct seq tuple tuple_struct map enum
+            identifier ignored_any
+        }
+    }
+
+    let mut fields: &'static [&'static str] = &[];
+    let _ = ApplicationEntry::deserialize(FieldNames(&mut fields));
+    fields
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::desktop_parser::DesktopEntry;
+    use std::path::PathBuf;
+
+    fn emulator(desktop_id: &str, exec: &str) -> ApplicationEntry {
+        let entry = DesktopEntry {
+            name: desktop_id.trim_end_matches(".desktop").to_string(),
+            exec: exec.to_string(),
+            categories: vec!["TerminalEmulator".to_string()],
+            ..DesktopEntry::default()
+        };
+        ApplicationEntry::from_desktop_entry(
+            &entry,
+            PathBuf::from("/usr/share/applications").join(desktop_id),
+        )
+    }
+
+    /// `query --terminals` is an alias for `list-terminals` and prints the same report.
+    #[test]
+    fn terminals_are_sorted_with_aligned_exec_lines() {
+        let rendered = TerminalReport::collect(
+            &[
+                emulator("kitty.desktop", "kitty --single-instance"),
+                emulator("foot.desktop", "foot"),
+            ],
+            Err(anyhow::anyhow!("No terminal emulator found")),
+        )
+        .render();
+
+        assert_eq!(
+            rendered.lines().collect::<Vec<_>>(),
+            [
+                "  foot.desktop   foot   foot",
+                "  kitty.desktop  kitty  kitty --single-instance",
+                "",
+                "None selected: No terminal emulator found",
+            ]
+        );
+    }
+
+    fn candidates() -> Vec<ApplicationEntry> {
+        let firefox = DesktopEntry {
+            name: "Firefox".to_string(),
+            exec: "firefox %u".to_string(),
+            startup_wm_class: Some("firefox".to_string()),
+            single_main_window: true,
+            ..DesktopEntry::default()
+        };
+        let gimp = DesktopEntry {
+            name: "GIMP".to_string(),
+            exec: "gimp %U".to_string(),
+            ..DesktopEntry::default()
+        };
+        vec![
+            ApplicationEntry::from_desktop_entry(
+                &firefox,
+                PathBuf::from("/usr/share/applications/firefox.desktop"),
+            ),
+            ApplicationEntry::from_desktop_entry(
+                &gimp,
+                PathBuf::from("/usr/share/applications/org.gimp.GIMP.desktop"),
+            ),
+        ]
+    }
+
+    #[test]
+    fn field_prints_one_value_per_candidate() {
+        let candidates = candidates();
+
+        assert_eq!(
+            render_field(&candidates, "window_class").unwrap(),
+            "firefox\norg.gimp.GIMP\n"
+        );
+        assert_eq!(
+            render_field(&candidates, "startup_wm_class").unwrap(),
+            "firefox\n\n"
+        );
+        assert_eq!(
+            render_field(&candidates, "single_main_window").unwrap(),
+            "true\n\n"
+        );
+        assert_eq!(
+            render_field(&candidates, "name").unwrap(),
+            "Firefox\nGIMP\n"
+        );
+        assert_eq!(
+            render_field(&candidates, "desktop_id").unwrap(),
+            "firefox.desktop\norg.gimp.GIMP.desktop\n"
+        );
+        assert_eq!(render_field(&candidates, "score").unwrap(), "0.0\n0.0\n");
+    }
+
+    #[test]
+    fn unknown_field_lists_the_valid_names() {
+        let err = render_field(&candidates(), "wm_klass")
+            .unwrap_err()
+            .to_string();
+
+        assert!(err.starts_with("Unknown field `wm_klass`"), "{err}");
+        assert!(err.contains("window_class"), "{err}");
+        assert!(err.contains("single_main_window"), "{err}");
+        assert!(entry_fields().contains(&"startup_wm_class"));
+    }
+
+    #[test]
+    fn pseudo_types_also_list_the_real_types_handlers() {
+        let find = |mime: &str| {
+            let [firefox, gimp] = <[ApplicationEntry; 2]>::try_from(candidates()).unwrap();
+            match mime {
+                "application/x-extension-html" => vec![firefox],
+                "text/html" => vec![gimp, firefox],
+                _ => Vec::new(),
+            }
+        };
+        let relations = MimeRelations::default();
+
+        let related = related_candidates("application/x-extension-html", &relations, find);
+        assert_eq!(
+            render_field(&related, "desktop_id").unwrap(),
+            "firefox.desktop\norg.gimp.GIMP.desktop\n"
+        );
+        assert_eq!(related_candidates("text/html", &relations, find).len(), 2);
+    }
+
+    #[test]
+    fn json_carries_window_fields_only_when_set() {
+        let candidates = candidates();
+        let firefox = serde_json::to_value(&candidates[0]).unwrap();
+        let gimp = serde_json::to_value(&candidates[1]).unwrap();
+
+        assert_eq!(firefox["startup_wm_class"], "firefox");
+        assert_eq!(firefox["single_main_window"], true);
+        assert!(gimp.get("startup_wm_class").is_none());
+        assert!(gimp.get("single_main_window").is_none());
+    }
+}
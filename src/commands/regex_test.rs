@@ -0,0 +1,212 @@
+use crate::cli::{RegexAction, RegexArgs};
+use crate::commands::{CommandContext, CommandExecutor};
+use crate::open_it::OpenIt;
+use crate::regex_handlers::{CaptureGroup, RegexHandlerStore};
+use anyhow::Result;
+use serde::Serialize;
+
+pub struct RegexCommand {
+    args: RegexArgs,
+}
+
+impl RegexCommand {
+    pub fn new(args: RegexArgs) -> Self {
+        Self { args }
+    }
+}
+
+impl CommandExecutor for RegexCommand {
+    fn execute(self, ctx: &CommandContext) -> Result<()> {
+        match self.args.action {
+            RegexAction::Test(args) => {
+                let store = ctx.load_regex_handlers()?;
+                let report = MatchReport::collect(&store, &args.target);
+                if args.json {
+                    println!("{}", serde_json::to_string_pretty(&report)?);
+                } else {
+                    print!("{}", report.render());
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Handlers matching one target, in the order `open` would try them.
+#[derive(Debug, Serialize)]
+struct MatchReport {
+    /// The string the regexes see: a canonical path, or the URI after `uri_match_normalize`.
+    candidate: String,
+    matches: Vec<HandlerMatch>,
+}
+
+#[derive(Debug, Serialize)]
+struct HandlerMatch {
+    priority: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    notes: Option<String>,
+    regex: String,
+    captures: Vec<CaptureGroup>,
+    /// `exec` after capture-group expansion; `None` when expansion fails.
+    exec: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+impl MatchReport {
+    /// Targets are resolved like `open` does; one that cannot be resolved, such as a path
+    /// that does not exist yet, is matched as typed.
+    fn collect(store: &RegexHandlerStore, raw_target: &str) -> Self {
+        let candidate = OpenIt::resolve_launch_target(raw_target)
+            .map(|target| store.candidate(&target))
+            .unwrap_or_else(|_| raw_target.to_string());
+
+        let matches = store
+            .matching(&candidate)
+            .filter_map(|handler| {
+                let (regex, captures) = handler.capture_groups(&candidate)?;
+                let (exec, error) = match handler.expand_exec(&candidate) {
+                    Ok(exec) => (Some(exec), None),
+                    Err(err) => (None, Some(err.to_string())),
+                };
+                Some(HandlerMatch {
+                    priority: handler.priority,
+                    notes: handler.notes.clone(),
+                    regex: regex.to_string(),
+                    captures,
+                    exec,
+                    error,
+                })
+            })
+            .collect();
+
+        Self { candidate, matches }
+    }
+
+    fn render(&self) -> String {
+        if self.matches.is_empty() {
+            return format!("No regex handler matches {}\n", self.candidate);
+        }
+
+        let mut out = format!("Target: {}\n", self.candidate);
+        for (position, found) in self.matches.iter().enumerate() {
+            let selected = if position == 0 { "  (selected)" } else { "" };
+            out.push_str(&format!(
+                "{}. priority {}  {}{selected}\n",
+                position + 1,
+                found.priority,
+                found.regex
+            ));
+            if let Some(notes) = &found.notes {
+                out.push_str(&format!("   notes: {notes}\n"));
+            }
+            for group in &found.captures {
+                let label = match &group.name {
+                    Some(name) => format!("${} {name}", group.index),
+                    None => format!("${}", group.index),
+                };
+                let value = group.value.as_deref().unwrap_or("(unmatched)");
+                out.push_str(&format!("   {label} = {value}\n"));
+            }
+            match (&found.exec, &found.error) {
+                (Some(exec), _) => out.push_str(&format!("   exec: {exec}\n")),
+                (None, Some(error)) => out.push_str(&format!("   error: {error}\n")),
+                (None, None) => {}
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const HANDLERS: &str = r#"
+[[handlers]]
+exec = "firefox --new-tab https://github.com/${owner}/${repo}"
+regexes = ['^https://github\.com/(?P<owner>[^/]+)/(?P<repo>[^/]+)(/tree/(\w+))?$']
+priority = 10
+notes = "GitHub repositories"
+
+[[handlers]]
+exec = "xdg-open %u"
+regexes = ['^https://']
+priority = 1
+
+[[handlers]]
+exec = "broken $9"
+regexes = ['^https://github\.com/']
+priority = 5
+"#;
+
+    fn store() -> (TempDir, RegexHandlerStore) {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("regex_handlers.toml");
+        fs::write(&path, HANDLERS).unwrap();
+        let store = RegexHandlerStore::load(Some(path)).unwrap();
+        (temp_dir, store)
+    }
+
+    #[test]
+    fn reports_every_match_in_priority_order() {
+        let (_dir, store) = store();
+        let report = MatchReport::collect(&store, "https://github.com/CaddyGlow/open-with");
+
+        let priorities: Vec<i32> = report.matches.iter().map(|m| m.priority).collect();
+        assert_eq!(priorities, vec![10, 5, 1]);
+        assert_eq!(
+            report.matches[0].exec.as_deref(),
+            Some("firefox --new-tab https://github.com/CaddyGlow/open-with")
+        );
+        assert!(report.matches[1].exec.is_none());
+        assert!(report.matches[1]
+            .error
+            .as_deref()
+            .unwrap()
+            .contains("capture group `9`"));
+    }
+
+    #[test]
+    fn render_shows_groups_and_marks_the_winner() {
+        let (_dir, store) = store();
+        let rendered =
+            MatchReport::collect(&store, "https://github.com/CaddyGlow/open-with").render();
+
+        assert!(
+            rendered.starts_with("Target: https://github.com/CaddyGlow/open-with\n1. priority 10")
+        );
+        assert!(rendered.contains("(selected)\n   notes: GitHub repositories\n"));
+        assert!(rendered.contains("   $1 owner = CaddyGlow\n"));
+        assert!(rendered.contains("   $3 = (unmatched)\n"));
+        assert!(rendered.contains("   error: Regex handler `broken $9`"));
+        assert!(rendered
+            .contains("3. priority 1  ^https://\n   $0 = https://\n   exec: xdg-open '%u'\n"));
+    }
+
+    #[test]
+    fn json_carries_structured_captures() {
+        let (_dir, store) = store();
+        let report = MatchReport::collect(&store, "https://github.com/a/b/tree/main");
+        let json = serde_json::to_value(&report).unwrap();
+
+        assert_eq!(json["candidate"], "https://github.com/a/b/tree/main");
+        assert_eq!(json["matches"][0]["captures"][2]["name"], "repo");
+        assert_eq!(json["matches"][0]["captures"][4]["value"], "main");
+        assert!(json["matches"][0].get("error").is_none());
+    }
+
+    #[test]
+    fn unmatched_target_says_so() {
+        let (_dir, store) = store();
+        let report = MatchReport::collect(&store, "mailto:someone@example.com");
+
+        assert!(report.matches.is_empty());
+        assert_eq!(
+            report.render(),
+            "No regex handler matches mailto:someone@example.com\n"
+        );
+    }
+}
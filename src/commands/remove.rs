@@ -1,5 +1,6 @@
 use crate::cli::RemoveArgs;
 use crate::commands::{CommandContext, CommandExecutor};
+use crate::mimeapps::MimeApps;
 use anyhow::Result;
 
 pub struct RemoveCommand {
@@ -16,13 +17,17 @@ impl CommandExecutor for RemoveCommand {
     fn execute(self, ctx: &CommandContext) -> Result<()> {
         let mime = ctx.normalize_mime_input(&self.args.mime)?;
 
-        let mut apps = ctx.load_mimeapps()?;
-        apps.remove_handler(
-            &mime,
-            Some(self.args.handler.as_str()),
-            self.args.expand_wildcards,
-        );
-        ctx.save_mimeapps(&apps)?;
+        let edit = |apps: &mut MimeApps| {
+            apps.remove_handler(
+                &mime,
+                Some(self.args.handler.as_str()),
+                self.args.expand_wildcards,
+            )
+        };
+        if self.args.dry_run {
+            return ctx.preview_mimeapps(edit, self.args.json);
+        }
+        ctx.edit_mimeapps(edit)?;
 
         println!("Removed handler {} from {}", self.args.handler, mime);
         Ok(())
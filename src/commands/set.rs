@@ -1,5 +1,7 @@
 use crate::cli::EditArgs;
 use crate::commands::{CommandContext, CommandExecutor};
+use crate::mime_relations::MimeRelations;
+use crate::mimeapps::MimeApps;
 use anyhow::Result;
 
 pub struct SetCommand {
@@ -17,15 +19,34 @@ impl CommandExecutor for SetCommand {
         let mime = ctx.normalize_mime_input(&self.args.mime)?;
         ctx.ensure_handler_exists(&self.args.handler)?;
 
-        let mut apps = ctx.load_mimeapps()?;
-        apps.set_handler(
-            &mime,
-            vec![self.args.handler.clone()],
-            self.args.expand_wildcards,
-        );
-        ctx.save_mimeapps(&apps)?;
+        let edit = |apps: &mut MimeApps| match self.args.priority {
+            Some(position) => apps.insert_handler_at(
+                &mime,
+                self.args.handler.clone(),
+                position,
+                self.args.expand_wildcards,
+            ),
+            None => apps.set_handler(
+                &mime,
+                vec![self.args.handler.clone()],
+                self.args.expand_wildcards,
+            ),
+        };
+        if self.args.dry_run {
+            return ctx.preview_mimeapps(edit, self.args.json);
+        }
+        ctx.edit_mimeapps(edit)?;
 
-        println!("Set default handler for {mime} -> {}", self.args.handler);
+        match self.args.priority {
+            Some(position) => println!(
+                "Inserted handler {} for {mime} at position {position}",
+                self.args.handler
+            ),
+            None => println!("Set default handler for {mime} -> {}", self.args.handler),
+        }
+        if let Some(warning) = MimeRelations::load().unknown_type_warning(&mime) {
+            eprintln!("Warning: {warning}");
+        }
         Ok(())
     }
 }
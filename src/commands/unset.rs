@@ -16,9 +16,7 @@ impl CommandExecutor for UnsetCommand {
     fn execute(self, ctx: &CommandContext) -> Result<()> {
         let mime = ctx.normalize_mime_input(&self.args.mime)?;
 
-        let mut apps = ctx.load_mimeapps()?;
-        apps.remove_handler(&mime, None, self.args.expand_wildcards);
-        ctx.save_mimeapps(&apps)?;
+        ctx.edit_mimeapps(|apps| apps.remove_handler(&mime, None, self.args.expand_wildcards))?;
 
         println!("Unset handlers for {}", mime);
         Ok(())
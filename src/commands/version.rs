@@ -0,0 +1,232 @@
+use crate::built_info;
+use crate::cli::VersionArgs;
+use crate::commands::{CommandContext, CommandExecutor};
+use crate::config::Config;
+use crate::mimeapps::MimeApps;
+use crate::open_it::OpenIt;
+use crate::regex_handlers::RegexHandlerStore;
+use anyhow::Result;
+use serde::Serialize;
+use std::fmt::Write as _;
+use std::path::PathBuf;
+
+pub struct VersionCommand {
+    args: VersionArgs,
+}
+
+impl VersionCommand {
+    pub fn new(args: VersionArgs) -> Self {
+        Self { args }
+    }
+}
+
+impl CommandExecutor for VersionCommand {
+    fn execute(self, _ctx: &CommandContext) -> Result<()> {
+        let config = Config::load(None).unwrap_or_default();
+        let report = VersionReport::collect(&config);
+
+        if self.args.json {
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            print!("{}", report.render());
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct VersionReport {
+    version: &'static str,
+    git_commit: Option<&'static str>,
+    git_branch: Option<&'static str>,
+    git_dirty: Option<bool>,
+    built_time: &'static str,
+    rustc: &'static str,
+    target: &'static str,
+    profile: &'static str,
+    features: Vec<&'static str>,
+    paths: PathsReport,
+    selectors: Vec<SelectorReport>,
+    environment: EnvironmentReport,
+}
+
+#[derive(Debug, Serialize)]
+struct PathsReport {
+    config: PathBuf,
+    cache: PathBuf,
+    mimeapps: PathBuf,
+    regex_handlers: PathBuf,
+}
+
+#[derive(Debug, Serialize)]
+struct SelectorReport {
+    profile: String,
+    command: String,
+    path: Option<PathBuf>,
+}
+
+#[derive(Debug, Serialize)]
+struct EnvironmentReport {
+    desktop: Option<String>,
+    session_type: Option<String>,
+    wayland: bool,
+    x11: bool,
+}
+
+impl VersionReport {
+    fn collect(config: &Config) -> Self {
+        let mut selectors: Vec<SelectorReport> = config
+            .selector_profiles
+            .iter()
+            .map(|(id, profile)| SelectorReport {
+                profile: id.to_string(),
+                command: profile.command.clone(),
+                path: which::which(&profile.command).ok(),
+            })
+            .collect();
+        selectors.sort_by(|a, b| a.profile.cmp(&b.profile));
+
+        Self {
+            version: built_info::PKG_VERSION,
+            git_commit: built_info::GIT_COMMIT_HASH,
+            git_branch: built_info::GIT_HEAD_REF,
+            git_dirty: built_info::GIT_DIRTY,
+            built_time: built_info::BUILT_TIME_UTC,
+            rustc: built_info::RUSTC_VERSION,
+            target: built_info::TARGET,
+            profile: built_info::PROFILE,
+            features: built_info::ENABLED_FEATURES.to_vec(),
+            paths: PathsReport {
+                config: Config::config_path(),
+                cache: OpenIt::cache_path(),
+                mimeapps: MimeApps::default_path(),
+                regex_handlers: RegexHandlerStore::config_path(),
+            },
+            selectors,
+            environment: EnvironmentReport {
+                desktop: non_empty_var("XDG_CURRENT_DESKTOP"),
+                session_type: non_empty_var("XDG_SESSION_TYPE"),
+                wayland: non_empty_var("WAYLAND_DISPLAY").is_some(),
+                x11: non_empty_var("DISPLAY").is_some(),
+            },
+        }
+    }
+
+    fn render(&self) -> String {
+        let mut out = String::new();
+        let unknown = "unknown";
+
+        let _ = writeln!(out, "openit {}", self.version);
+        let _ = writeln!(out, "Commit: {}", self.git_commit.unwrap_or(unknown));
+        let _ = writeln!(out, "Branch: {}", self.git_branch.unwrap_or(unknown));
+        let dirty = match self.git_dirty {
+            Some(true) => "dirty",
+            Some(false) => "clean",
+            None => unknown,
+        };
+        let _ = writeln!(out, "Git status: {dirty}");
+        let _ = writeln!(out, "Built: {}", self.built_time);
+        let _ = writeln!(out, "Rustc: {}", self.rustc);
+        let _ = writeln!(out, "Target: {} ({})", self.target, self.profile);
+        let features = if self.features.is_empty() {
+            "none".to_string()
+        } else {
+            self.features.join(", ")
+        };
+        let _ = writeln!(out, "Features: {features}");
+
+        let _ = writeln!(out, "Paths:");
+        let _ = writeln!(out, "  config: {}", self.paths.config.display());
+        let _ = writeln!(out, "  cache: {}", self.paths.cache.display());
+        let _ = writeln!(out, "  mimeapps: {}", self.paths.mimeapps.display());
+        let _ = writeln!(
+            out,
+            "  regex handlers: {}",
+            self.paths.regex_handlers.display()
+        );
+
+        let _ = writeln!(out, "Selectors:");
+        for selector in &self.selectors {
+            let location = selector
+                .path
+                .as_ref()
+                .map(|path| path.display().to_string())
+                .unwrap_or_else(|| "not found".to_string());
+            let _ = writeln!(
+                out,
+                "  {} ({}): {location}",
+                selector.profile, selector.command
+            );
+        }
+
+        let _ = writeln!(out, "Environment:");
+        let _ = writeln!(
+            out,
+            "  desktop: {}",
+            self.environment.desktop.as_deref().unwrap_or(unknown)
+        );
+        let _ = writeln!(
+            out,
+            "  session: {}",
+            self.environment.session_type.as_deref().unwrap_or(unknown)
+        );
+        let _ = writeln!(
+            out,
+            "  wayland: {}, x11: {}",
+            self.environment.wayland, self.environment.x11
+        );
+
+        out
+    }
+}
+
+fn non_empty_var(key: &str) -> Option<String> {
+    std::env::var(key).ok().filter(|value| !value.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn features_match_compiled_cfg() {
+        let report = VersionReport::collect(&Config::default());
+
+        assert_eq!(
+            report.features.contains(&"icon-picker"),
+            cfg!(feature = "icon-picker")
+        );
+        assert!(!report.features.contains(&"default"));
+    }
+
+    #[test]
+    fn json_report_contains_expected_keys() {
+        let report = VersionReport::collect(&Config::default());
+        let value = serde_json::to_value(&report).unwrap();
+
+        for key in [
+            "version",
+            "git_commit",
+            "built_time",
+            "rustc",
+            "target",
+            "profile",
+            "features",
+            "paths",
+            "selectors",
+            "environment",
+        ] {
+            assert!(value.get(key).is_some(), "missing key `{key}`");
+        }
+        for key in ["config", "cache", "mimeapps", "regex_handlers"] {
+            assert!(value["paths"].get(key).is_some(), "missing path `{key}`");
+        }
+        assert_eq!(value["version"], env!("CARGO_PKG_VERSION"));
+
+        let rendered = report.render();
+        assert!(rendered.starts_with(&format!("openit {}", env!("CARGO_PKG_VERSION"))));
+        assert!(rendered.contains("Features:"));
+        assert!(rendered.contains("fzf (fzf)"));
+    }
+}
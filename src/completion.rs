@@ -0,0 +1,135 @@
+use crate::application_finder::ApplicationFinder;
+use crate::cache::{DesktopCache, FileSystemCache};
+use crate::mime_associations::MimeAssociations;
+use crate::open_it::OpenIt;
+use clap_complete::engine::CompletionCandidate;
+use std::collections::BTreeSet;
+use std::ffi::OsStr;
+
+/// URL schemes offered even when no installed application declares them.
+const COMMON_SCHEMES: &[&str] = &[
+    "http", "https", "mailto", "ftp", "file", "magnet", "terminal",
+];
+
+/// Dynamic completer for `MIME_OR_EXT` arguments.
+pub fn complete_mime(current: &OsStr) -> Vec<CompletionCandidate> {
+    candidates(mime_candidates(load_cache(), &current.to_string_lossy()))
+}
+
+/// Dynamic completer for `HANDLER` arguments.
+pub fn complete_handler(current: &OsStr) -> Vec<CompletionCandidate> {
+    candidates(handler_candidates(
+        load_cache().as_ref(),
+        &current.to_string_lossy(),
+    ))
+}
+
+/// The on-disk desktop cache as it is; completion never scans or rewrites it.
+fn load_cache() -> Box<dyn DesktopCache> {
+    let mut cache = FileSystemCache::new(OpenIt::cache_path());
+    if cache.load().is_err() {
+        cache.clear();
+    }
+    Box::new(cache)
+}
+
+fn candidates(values: Vec<String>) -> Vec<CompletionCandidate> {
+    values.into_iter().map(CompletionCandidate::new).collect()
+}
+
+/// MIME types declared by cached applications plus common scheme handlers, starting with `prefix`.
+fn mime_candidates(cache: Box<dyn DesktopCache>, prefix: &str) -> Vec<String> {
+    let finder = ApplicationFinder::new(cache, MimeAssociations::default());
+    finder
+        .all_mime_types()
+        .into_iter()
+        .chain(
+            COMMON_SCHEMES
+                .iter()
+                .map(|scheme| format!("x-scheme-handler/{scheme}")),
+        )
+        .filter(|mime| mime.starts_with(prefix))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+/// Desktop IDs of cached applications starting with `prefix`.
+fn handler_candidates(cache: &dyn DesktopCache, prefix: &str) -> Vec<String> {
+    cache
+        .iter()
+        .filter_map(|(path, _)| path.file_name()?.to_str().map(str::to_string))
+        .filter(|id| id.starts_with(prefix))
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cache::MemoryCache;
+    use crate::test_support::{create_test_desktop_file, CacheEnvGuard};
+    use serial_test::serial;
+    use tempfile::TempDir;
+
+    fn fake_cache() -> (TempDir, MemoryCache) {
+        let temp_dir = TempDir::new().unwrap();
+        create_test_desktop_file(
+            temp_dir.path(),
+            "imv.desktop",
+            "[Desktop Entry]\nName=imv\nExec=imv %F\nMimeType=image/png;image/jpeg;\n",
+        );
+        create_test_desktop_file(
+            temp_dir.path(),
+            "firefox.desktop",
+            "[Desktop Entry]\nName=Firefox\nExec=firefox %u\nMimeType=text/html;x-scheme-handler/http;\n",
+        );
+        let cache = MemoryCache::from_dirs(&[temp_dir.path().to_path_buf()]).unwrap();
+        (temp_dir, cache)
+    }
+
+    #[test]
+    fn mime_candidates_merge_cached_types_and_common_schemes() {
+        let (_dir, cache) = fake_cache();
+
+        assert_eq!(
+            mime_candidates(Box::new(cache), "image/"),
+            vec!["image/jpeg", "image/png"]
+        );
+
+        let (_dir, cache) = fake_cache();
+        let schemes = mime_candidates(Box::new(cache), "x-scheme-handler/");
+        assert_eq!(
+            schemes
+                .iter()
+                .filter(|m| *m == "x-scheme-handler/http")
+                .count(),
+            1
+        );
+        assert!(schemes.contains(&"x-scheme-handler/mailto".to_string()));
+    }
+
+    #[test]
+    fn handler_candidates_list_desktop_ids() {
+        let (_dir, cache) = fake_cache();
+
+        assert_eq!(
+            handler_candidates(&cache, ""),
+            vec!["firefox.desktop", "imv.desktop"]
+        );
+        assert_eq!(handler_candidates(&cache, "im"), vec!["imv.desktop"]);
+    }
+
+    #[test]
+    #[serial]
+    fn completers_read_cache_without_creating_it() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache_path = temp_dir.path().join("desktop_cache.json");
+        let _guard = CacheEnvGuard::set(&cache_path);
+
+        assert!(complete_handler(OsStr::new("")).is_empty());
+        assert!(!complete_mime(OsStr::new("x-scheme-handler/")).is_empty());
+        assert!(!cache_path.exists());
+    }
+}
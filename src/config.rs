@@ -1,11 +1,13 @@
+use crate::gpu::GpuSettings;
 use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
-use std::borrow::Borrow;
-use std::collections::HashMap;
+use std::borrow::{Borrow, Cow};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt;
 use std::fs;
 use std::ops::Deref;
 use std::path::{Path, PathBuf};
+use wildmatch::WildMatch;
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 #[serde(transparent)]
@@ -114,24 +116,136 @@ impl SelectorSettings {
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
 #[serde(rename_all = "lowercase")]
 pub enum TerminalExecution {
     Current,
+    #[default]
     Launcher,
 }
 
-impl Default for TerminalExecution {
-    fn default() -> Self {
-        Self::Launcher
+/// Graphical session the selector runs in, used to pick `args_wayland` or `args_x11`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SessionType {
+    Wayland,
+    X11,
+    Unknown,
+}
+
+impl SessionType {
+    /// Detect the session from `XDG_SESSION_TYPE`, then `WAYLAND_DISPLAY`, then `DISPLAY`.
+    pub fn detect() -> Self {
+        let var = |key| std::env::var(key).ok().filter(|value| !value.is_empty());
+        Self::from_env(
+            var("XDG_SESSION_TYPE").as_deref(),
+            var("WAYLAND_DISPLAY").is_some(),
+            var("DISPLAY").is_some(),
+        )
+    }
+
+    fn from_env(session_type: Option<&str>, wayland_display: bool, display: bool) -> Self {
+        match session_type.map(str::to_ascii_lowercase).as_deref() {
+            Some("wayland") => return SessionType::Wayland,
+            Some("x11") => return SessionType::X11,
+            _ => {}
+        }
+
+        if wayland_display {
+            SessionType::Wayland
+        } else if display {
+            SessionType::X11
+        } else {
+            SessionType::Unknown
+        }
+    }
+}
+
+/// Terminal multiplexer the selector runs inside, used to pick `context_args`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Multiplexer {
+    Tmux,
+    Zellij,
+    None,
+}
+
+impl Multiplexer {
+    /// Detect the multiplexer from `TMUX`, then `ZELLIJ`; zellij sets `ZELLIJ=0`, so any
+    /// value counts.
+    pub fn detect() -> Self {
+        Self::from_env(
+            std::env::var_os("TMUX").is_some_and(|value| !value.is_empty()),
+            std::env::var_os("ZELLIJ").is_some(),
+        )
+    }
+
+    fn from_env(tmux: bool, zellij: bool) -> Self {
+        if tmux {
+            Multiplexer::Tmux
+        } else if zellij {
+            Multiplexer::Zellij
+        } else {
+            Multiplexer::None
+        }
+    }
+
+    /// The `context_args` key for this multiplexer.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Multiplexer::Tmux => "tmux",
+            Multiplexer::Zellij => "zellij",
+            Multiplexer::None => "none",
+        }
     }
 }
 
+/// What a terminal is launched for, so each purpose can pin its own emulator.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalPurpose {
+    /// A `Terminal=true` handler opening a file or URI.
+    Handler,
+    /// A terminal handler opening a directory.
+    Directory,
+}
+
+/// Ordering applied to handlers that share the same priority.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TieBreaker {
+    /// Alphabetical by desktop ID.
+    #[default]
+    Alpha,
+    /// Most recently modified desktop file first.
+    Mtime,
+}
+
+/// How a URI is rewritten before regex handlers match it; the launched URI is unchanged.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum UriMatchNormalize {
+    /// Match the URI as given.
+    #[default]
+    None,
+    /// Drop the `?query` part, e.g. tracking parameters.
+    StripQuery,
+    /// Drop the `#fragment` part.
+    StripFragment,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(default)]
 pub struct SelectorProfile {
     pub command: String,
     pub args: Vec<String>,
+    /// Appended to `args` in a Wayland session.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub args_wayland: Vec<String>,
+    /// Appended to `args` in an X11 session (e.g. rofi `-normal-window`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub args_x11: Vec<String>,
+    /// Appended to `args` inside a multiplexer, keyed by `tmux`, `zellij` or `none`
+    /// (e.g. fzf `--tmux` for a popup).
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub context_args: HashMap<String, Vec<String>>,
     pub env: HashMap<String, String>,
     pub entry_template: String,
     pub marker_default: Option<String>,
@@ -140,6 +254,112 @@ pub struct SelectorProfile {
     pub prompt_template: Option<String>,
     pub header_template: Option<String>,
     pub selector_type: SelectorProfileType,
+    /// Extra args appended when `--match` seeds the selector; `{query}` is substituted.
+    pub query_args: Vec<String>,
+    /// Extra args for the run that reads an "Open with command…" command line, which
+    /// must print the typed text (fzf needs `--print-query`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub command_args: Vec<String>,
+}
+
+impl SelectorProfile {
+    /// Base `args` followed by the session-specific ones for `session` and the
+    /// `context_args` for `multiplexer`.
+    pub fn args_for(&self, session: SessionType, multiplexer: Multiplexer) -> Vec<String> {
+        let extra = match session {
+            SessionType::Wayland => self.args_wayland.as_slice(),
+            SessionType::X11 => self.args_x11.as_slice(),
+            SessionType::Unknown => &[],
+        };
+        let context = self
+            .context_args
+            .get(multiplexer.as_str())
+            .map_or(&[][..], Vec::as_slice);
+        self.args
+            .iter()
+            .chain(extra)
+            .chain(context)
+            .cloned()
+            .collect()
+    }
+
+    /// Minimal profile for a selector with no `[selectors.<name>]` table: entries are
+    /// written to stdin one per line and the chosen line is read back, like dmenu.
+    pub fn dmenu_style(command: &str, selector_type: SelectorProfileType) -> Self {
+        Self {
+            command: command.to_string(),
+            entry_template: "{marker}{name}{comment}".to_string(),
+            selector_type,
+            ..Self::default()
+        }
+    }
+
+    /// Check that the profile's command is installed.
+    pub fn validate(&self) -> Result<()> {
+        which::which(&self.command).map(|_| ()).map_err(|_| {
+            anyhow::anyhow!(
+                "Selector command `{}` was not found on PATH; install it or change the profile's `command`",
+                self.command
+            )
+        })
+    }
+}
+
+/// `[launch_env_policy]` table: inherited variables withheld from launched applications.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct LaunchEnvPolicy {
+    /// Variables removed from the inherited environment; `*` matches any run (`DIRENV_*`).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unset: Vec<String>,
+    /// Inherit only these variables (or patterns); cannot be combined with `unset`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub keep_only: Option<Vec<String>>,
+}
+
+impl LaunchEnvPolicy {
+    pub fn validate(&self) -> Result<()> {
+        if self.keep_only.is_some() && !self.unset.is_empty() {
+            anyhow::bail!("[launch_env_policy] sets both `unset` and `keep_only`; use one of them");
+        }
+        Ok(())
+    }
+
+    /// Whether the inherited variable `name` is withheld from launched applications.
+    pub fn scrubs(&self, name: &str) -> bool {
+        let matches = |patterns: &[String]| {
+            patterns
+                .iter()
+                .any(|pattern| WildMatch::new(pattern).matches(name))
+        };
+        match &self.keep_only {
+            Some(keep) => !matches(keep),
+            None => matches(&self.unset),
+        }
+    }
+}
+
+/// `[mime]` table: how the MIME type of a target is detected.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct MimeDetection {
+    /// Run `file --mime-type` on files whose extension has no known type.
+    pub use_file_command: bool,
+}
+
+/// `[applications."<desktop id>"]` table: launch settings for one application.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct ApplicationOverride {
+    /// Replaces `per_mime_prefix` and `app_launch_prefix`; an empty string disables them.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub launch_prefix: Option<String>,
+    /// Extra environment variables for the application's process.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    /// Overrides the entry's `Terminal=` key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminal: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -156,7 +376,72 @@ pub struct Config {
     pub header_template: String,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub app_launch_prefix: Option<String>,
+    /// Launch prefixes by MIME pattern (`application/pdf`, `image/*`); the most specific
+    /// match replaces `app_launch_prefix`, and an empty string disables it.
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub per_mime_prefix: HashMap<String, String>,
+    /// Per-application overrides keyed by desktop ID (`org.mozilla.firefox.desktop`).
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    pub applications: HashMap<String, ApplicationOverride>,
     pub terminal_execution: TerminalExecution,
+    /// Terminal for `Terminal=true` handlers: a desktop ID (`foot.desktop`) or a command.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminal: Option<String>,
+    /// Terminal desktop ID for `Terminal=true` handlers; overrides `terminal`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminal_for_handlers: Option<String>,
+    /// Terminal desktop ID for terminal handlers opening a directory; overrides `terminal`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub terminal_for_directory: Option<String>,
+    pub tie_breaker: TieBreaker,
+    /// Rewrite URIs before regex handlers match them (`none`, `strip_query`, `strip_fragment`).
+    pub uri_match_normalize: UriMatchNormalize,
+    pub allow_wildcard_handlers: bool,
+    /// Fall back to parent MIME types when nothing handles the exact type.
+    pub mime_fallback: bool,
+    /// Hide entries whose `Exec` only forwards to `xdg-open`, `gio` or `openit`.
+    pub hide_xdg_open_wrappers: bool,
+    /// Keep entries whose `OnlyShowIn`/`NotShowIn` exclude the current desktop.
+    pub include_all_desktops: bool,
+    /// Keep the previous `mimeapps.list` as `mimeapps.list.bak` when editing it.
+    pub backup_mimeapps: bool,
+    /// Desktop IDs ranked above other handlers without an explicit default.
+    pub preferred_apps: Vec<String>,
+    /// Also scan subdirectories of `applications/` (e.g. `applications/kde/`).
+    pub recursive_scan: bool,
+    /// Open `DBusActivatable=true` applications over D-Bus, falling back to `Exec`.
+    pub dbus_activation: bool,
+    /// Offer an "Open with command…" selector entry for typing an arbitrary command.
+    pub custom_command_entry: bool,
+    /// Seconds `--wait` gives a handler before killing it, unless `--wait-timeout` is set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wait_timeout: Option<u64>,
+    /// Command that focuses the open window of a `SingleMainWindow=true` application
+    /// instead of launching another copy; `{class}` is the window class, and exit status
+    /// 0 means a window was focused.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub focus_command: Option<String>,
+    /// Send a desktop notification naming the handler after each successful launch.
+    pub notify_on_launch: bool,
+    /// Command `notify_on_launch` runs instead of `notify-send`; `{app}`, `{target}` and
+    /// `{message}` are filled in.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub notify_command: Option<String>,
+    /// Remember MIME types without handlers across runs, until the desktop cache or
+    /// `mimeapps.list` changes.
+    pub persist_no_handler_mimes: bool,
+    /// Save at most this many desktop files to the cache file, the most recently used;
+    /// unbounded when unset. Every installed application is still found.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cache_max_entries: Option<usize>,
+    /// Offer installed stdin filters such as `jq .` or `less` for local files no desktop
+    /// entry handles.
+    pub fallback_openers: bool,
+    /// Record desktop entry launches in `history.json` for `--recent`.
+    pub record_history: bool,
+    pub gpu: GpuSettings,
+    pub launch_env_policy: LaunchEnvPolicy,
+    pub mime: MimeDetection,
 }
 
 impl Default for Config {
@@ -184,6 +469,14 @@ impl Default for Config {
                 prompt_template: None,
                 header_template: None,
                 selector_type: SelectorProfileType::Tui,
+                query_args: vec!["--query".to_string(), "{query}".to_string()],
+                args_wayland: Vec::new(),
+                args_x11: Vec::new(),
+                context_args: HashMap::from([(
+                    "tmux".to_string(),
+                    vec!["--tmux".to_string(), "center,60%".to_string()],
+                )]),
+                command_args: vec!["--print-query".to_string()],
             },
         );
 
@@ -207,6 +500,11 @@ impl Default for Config {
                 prompt_template: None,
                 header_template: None,
                 selector_type: SelectorProfileType::Gui,
+                query_args: vec!["--search".to_string(), "{query}".to_string()],
+                args_wayland: Vec::new(),
+                args_x11: Vec::new(),
+                context_args: HashMap::new(),
+                command_args: Vec::new(),
             },
         );
 
@@ -227,9 +525,64 @@ impl Default for Config {
                 prompt_template: None,
                 header_template: None,
                 selector_type: SelectorProfileType::Gui,
+                query_args: vec!["-filter".to_string(), "{query}".to_string()],
+                args_wayland: Vec::new(),
+                args_x11: Vec::new(),
+                context_args: HashMap::new(),
+                command_args: Vec::new(),
             },
         );
 
+        // wofi echoes the chosen line back; there is no index mode.
+        selector_profiles.insert(
+            SelectorProfileId::from("wofi"),
+            SelectorProfile {
+                command: "wofi".to_string(),
+                args: vec![
+                    "--dmenu".to_string(),
+                    "--prompt".to_string(),
+                    "{prompt}".to_string(),
+                ],
+                env: HashMap::new(),
+                entry_template: "{marker}{name}{comment}".to_string(),
+                marker_default: Some("★".to_string()),
+                marker_xdg: Some("▶".to_string()),
+                marker_available: Some("   ".to_string()),
+                prompt_template: None,
+                header_template: None,
+                selector_type: SelectorProfileType::Gui,
+                query_args: vec!["--search".to_string(), "{query}".to_string()],
+                args_wayland: Vec::new(),
+                args_x11: Vec::new(),
+                context_args: HashMap::new(),
+                command_args: Vec::new(),
+            },
+        );
+
+        // dmenu and bemenu echo the selected line, matched against `entry_template`.
+        for command in ["dmenu", "bemenu"] {
+            selector_profiles.insert(
+                SelectorProfileId::from(command),
+                SelectorProfile {
+                    command: command.to_string(),
+                    args: vec!["-p".to_string(), "{prompt}".to_string()],
+                    env: HashMap::new(),
+                    entry_template: "{marker}{name}{comment}".to_string(),
+                    marker_default: Some("★".to_string()),
+                    marker_xdg: Some("▶".to_string()),
+                    marker_available: Some("   ".to_string()),
+                    prompt_template: None,
+                    header_template: None,
+                    selector_type: SelectorProfileType::Gui,
+                    query_args: Vec::new(),
+                    args_wayland: Vec::new(),
+                    args_x11: Vec::new(),
+                    context_args: HashMap::new(),
+                    command_args: Vec::new(),
+                },
+            );
+        }
+
         Self {
             selector: SelectorSettings::default(),
             selector_profiles,
@@ -239,7 +592,34 @@ impl Default for Config {
             prompt_template: "Open '{file|truncate:20}' with: ".to_string(),
             header_template: "★=Default ▶=XDG Associated  =Available".to_string(),
             app_launch_prefix: None,
+            per_mime_prefix: HashMap::new(),
+            applications: HashMap::new(),
             terminal_execution: TerminalExecution::default(),
+            terminal: None,
+            terminal_for_handlers: None,
+            terminal_for_directory: None,
+            tie_breaker: TieBreaker::default(),
+            uri_match_normalize: UriMatchNormalize::default(),
+            allow_wildcard_handlers: true,
+            mime_fallback: true,
+            hide_xdg_open_wrappers: false,
+            include_all_desktops: false,
+            backup_mimeapps: false,
+            preferred_apps: Vec::new(),
+            recursive_scan: true,
+            dbus_activation: false,
+            custom_command_entry: false,
+            wait_timeout: None,
+            focus_command: None,
+            notify_on_launch: false,
+            notify_command: None,
+            persist_no_handler_mimes: false,
+            cache_max_entries: None,
+            fallback_openers: true,
+            record_history: true,
+            gpu: GpuSettings::default(),
+            launch_env_policy: LaunchEnvPolicy::default(),
+            mime: MimeDetection::default(),
         }
     }
 }
@@ -252,7 +632,198 @@ impl Config {
         let config = toml::from_str::<Config>(&contents)
             .with_context(|| format!("Failed to parse config file at {}", path.display()))?;
 
-        Ok(config)
+        Ok(Self::merge(Self::default(), config))
+    }
+
+    /// Overlay a loaded config on `base`.
+    ///
+    /// Selector profiles are merged by name, so a partial config keeps the built-in
+    /// profiles. Other fields take the overlay value only when it differs from the
+    /// struct default.
+    pub fn merge(base: Config, overlay: Config) -> Config {
+        let defaults = Config::default();
+
+        let mut selector_profiles = base.selector_profiles;
+        selector_profiles.extend(overlay.selector_profiles);
+        let mut per_mime_prefix = base.per_mime_prefix;
+        per_mime_prefix.extend(overlay.per_mime_prefix);
+        let mut applications = base.applications;
+        applications.extend(overlay.applications);
+
+        Config {
+            selector: SelectorSettings {
+                open_with: pick(
+                    base.selector.open_with,
+                    overlay.selector.open_with,
+                    &defaults.selector.open_with,
+                ),
+                defaults: SelectorDefaults {
+                    gui: pick(
+                        base.selector.defaults.gui,
+                        overlay.selector.defaults.gui,
+                        &defaults.selector.defaults.gui,
+                    ),
+                    tui: pick(
+                        base.selector.defaults.tui,
+                        overlay.selector.defaults.tui,
+                        &defaults.selector.defaults.tui,
+                    ),
+                },
+                term_exec_args: pick(
+                    base.selector.term_exec_args,
+                    overlay.selector.term_exec_args,
+                    &defaults.selector.term_exec_args,
+                ),
+                expand_wildcards: pick(
+                    base.selector.expand_wildcards,
+                    overlay.selector.expand_wildcards,
+                    &defaults.selector.expand_wildcards,
+                ),
+            },
+            selector_profiles,
+            marker_default: pick(
+                base.marker_default,
+                overlay.marker_default,
+                &defaults.marker_default,
+            ),
+            marker_xdg: pick(base.marker_xdg, overlay.marker_xdg, &defaults.marker_xdg),
+            marker_available: pick(
+                base.marker_available,
+                overlay.marker_available,
+                &defaults.marker_available,
+            ),
+            prompt_template: pick(
+                base.prompt_template,
+                overlay.prompt_template,
+                &defaults.prompt_template,
+            ),
+            header_template: pick(
+                base.header_template,
+                overlay.header_template,
+                &defaults.header_template,
+            ),
+            app_launch_prefix: pick(
+                base.app_launch_prefix,
+                overlay.app_launch_prefix,
+                &defaults.app_launch_prefix,
+            ),
+            per_mime_prefix,
+            applications,
+            terminal_execution: pick(
+                base.terminal_execution,
+                overlay.terminal_execution,
+                &defaults.terminal_execution,
+            ),
+            terminal: pick(base.terminal, overlay.terminal, &defaults.terminal),
+            terminal_for_handlers: pick(
+                base.terminal_for_handlers,
+                overlay.terminal_for_handlers,
+                &defaults.terminal_for_handlers,
+            ),
+            terminal_for_directory: pick(
+                base.terminal_for_directory,
+                overlay.terminal_for_directory,
+                &defaults.terminal_for_directory,
+            ),
+            tie_breaker: pick(base.tie_breaker, overlay.tie_breaker, &defaults.tie_breaker),
+            uri_match_normalize: pick(
+                base.uri_match_normalize,
+                overlay.uri_match_normalize,
+                &defaults.uri_match_normalize,
+            ),
+            allow_wildcard_handlers: pick(
+                base.allow_wildcard_handlers,
+                overlay.allow_wildcard_handlers,
+                &defaults.allow_wildcard_handlers,
+            ),
+            mime_fallback: pick(
+                base.mime_fallback,
+                overlay.mime_fallback,
+                &defaults.mime_fallback,
+            ),
+            hide_xdg_open_wrappers: pick(
+                base.hide_xdg_open_wrappers,
+                overlay.hide_xdg_open_wrappers,
+                &defaults.hide_xdg_open_wrappers,
+            ),
+            include_all_desktops: pick(
+                base.include_all_desktops,
+                overlay.include_all_desktops,
+                &defaults.include_all_desktops,
+            ),
+            backup_mimeapps: pick(
+                base.backup_mimeapps,
+                overlay.backup_mimeapps,
+                &defaults.backup_mimeapps,
+            ),
+            preferred_apps: pick(
+                base.preferred_apps,
+                overlay.preferred_apps,
+                &defaults.preferred_apps,
+            ),
+            recursive_scan: pick(
+                base.recursive_scan,
+                overlay.recursive_scan,
+                &defaults.recursive_scan,
+            ),
+            dbus_activation: pick(
+                base.dbus_activation,
+                overlay.dbus_activation,
+                &defaults.dbus_activation,
+            ),
+            custom_command_entry: pick(
+                base.custom_command_entry,
+                overlay.custom_command_entry,
+                &defaults.custom_command_entry,
+            ),
+            wait_timeout: pick(
+                base.wait_timeout,
+                overlay.wait_timeout,
+                &defaults.wait_timeout,
+            ),
+            focus_command: pick(
+                base.focus_command,
+                overlay.focus_command,
+                &defaults.focus_command,
+            ),
+            notify_on_launch: pick(
+                base.notify_on_launch,
+                overlay.notify_on_launch,
+                &defaults.notify_on_launch,
+            ),
+            notify_command: pick(
+                base.notify_command,
+                overlay.notify_command,
+                &defaults.notify_command,
+            ),
+            persist_no_handler_mimes: pick(
+                base.persist_no_handler_mimes,
+                overlay.persist_no_handler_mimes,
+                &defaults.persist_no_handler_mimes,
+            ),
+            cache_max_entries: pick(
+                base.cache_max_entries,
+                overlay.cache_max_entries,
+                &defaults.cache_max_entries,
+            ),
+            fallback_openers: pick(
+                base.fallback_openers,
+                overlay.fallback_openers,
+                &defaults.fallback_openers,
+            ),
+            record_history: pick(
+                base.record_history,
+                overlay.record_history,
+                &defaults.record_history,
+            ),
+            gpu: pick(base.gpu, overlay.gpu, &defaults.gpu),
+            launch_env_policy: pick(
+                base.launch_env_policy,
+                overlay.launch_env_policy,
+                &defaults.launch_env_policy,
+            ),
+            mime: pick(base.mime, overlay.mime, &defaults.mime),
+        }
     }
 
     pub fn load(custom_path: Option<PathBuf>) -> Result<Self> {
@@ -299,10 +870,41 @@ impl Config {
             .join("handlr.toml")
     }
 
+    /// Config key and desktop ID of the terminal pinned for `purpose`, if any.
+    pub fn pinned_terminal(&self, purpose: TerminalPurpose) -> Option<(&'static str, &str)> {
+        let (key, value) = match purpose {
+            TerminalPurpose::Handler => ("terminal_for_handlers", &self.terminal_for_handlers),
+            TerminalPurpose::Directory => ("terminal_for_directory", &self.terminal_for_directory),
+        };
+        value
+            .as_deref()
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(|id| (key, id))
+    }
+
     pub fn get_selector_profile(&self, name: &str) -> Option<&SelectorProfile> {
         self.selector_profiles.get(name)
     }
 
+    /// The profile named `name`, or a dmenu-style one synthesized when `name` is not
+    /// configured but is an installed command (e.g. `selector.defaults.gui = "bemenu"`).
+    pub fn resolve_selector_profile(&self, name: &str) -> Option<Cow<'_, SelectorProfile>> {
+        if let Some(profile) = self.get_selector_profile(name) {
+            return Some(Cow::Borrowed(profile));
+        }
+        which::which(name).ok()?;
+        let selector_type = if self.selector.defaults.tui.as_str() == name {
+            SelectorProfileType::Tui
+        } else {
+            SelectorProfileType::Gui
+        };
+        Some(Cow::Owned(SelectorProfile::dmenu_style(
+            name,
+            selector_type,
+        )))
+    }
+
     pub fn selector_candidates(&self, preferred: SelectorProfileType) -> Vec<SelectorProfileId> {
         let type_order = match preferred {
             SelectorProfileType::Gui => [SelectorProfileType::Gui, SelectorProfileType::Tui],
@@ -332,7 +934,7 @@ impl Config {
                     }
                 })
                 .collect();
-            names.sort();
+            names.sort_by_key(|name| (fallback_rank(name), name.clone()));
 
             for name in names {
                 if !candidates.contains(&name) {
@@ -376,6 +978,20 @@ impl Config {
         }
     }
 
+    /// `get_marker` padded to the widest of the profile's markers, in display columns.
+    pub fn get_padded_marker(
+        &self,
+        selector_profile: &SelectorProfile,
+        marker_type: &str,
+    ) -> String {
+        let width = ["default", "xdg", "available"]
+            .iter()
+            .map(|kind| crate::template::display_width(self.get_marker(selector_profile, kind)))
+            .max()
+            .unwrap_or(0);
+        crate::template::pad_to_width(self.get_marker(selector_profile, marker_type), width)
+    }
+
     pub fn get_prompt_template<'a>(&'a self, selector_profile: &'a SelectorProfile) -> &'a str {
         selector_profile
             .prompt_template
@@ -391,11 +1007,131 @@ impl Config {
     }
 }
 
+/// `overlay` when it was changed from `default`, otherwise `base`.
+/// Built-in profiles tried in this order when the preferred one is missing; other
+/// profiles follow by name.
+const FALLBACK_ORDER: &[&str] = &["fuzzel", "rofi", "wofi", "bemenu", "dmenu", "fzf"];
+
+fn fallback_rank(name: &SelectorProfileId) -> usize {
+    FALLBACK_ORDER
+        .iter()
+        .position(|builtin| *builtin == name.as_str())
+        .unwrap_or(FALLBACK_ORDER.len())
+}
+
+fn pick<T: PartialEq>(base: T, overlay: T, default: &T) -> T {
+    if overlay != *default {
+        overlay
+    } else {
+        base
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::EnvVarGuard;
+    use serial_test::serial;
     use tempfile::TempDir;
 
+    fn rofi_with_session_args() -> SelectorProfile {
+        toml::from_str(
+            r#"
+command = "rofi"
+args = ["-dmenu"]
+args_wayland = ["-normal-window"]
+args_x11 = ["-monitor", "-1"]
+"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    #[serial]
+    fn wayland_session_appends_wayland_args() {
+        let _session = EnvVarGuard::set("XDG_SESSION_TYPE", "");
+        let _wayland = EnvVarGuard::set("WAYLAND_DISPLAY", "wayland-1");
+        let _display = EnvVarGuard::set("DISPLAY", ":0");
+
+        assert_eq!(SessionType::detect(), SessionType::Wayland);
+        assert_eq!(
+            rofi_with_session_args().args_for(SessionType::detect(), Multiplexer::None),
+            vec!["-dmenu", "-normal-window"]
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn x11_session_appends_x11_args() {
+        let _session = EnvVarGuard::set("XDG_SESSION_TYPE", "x11");
+        let _wayland = EnvVarGuard::set("WAYLAND_DISPLAY", "wayland-1");
+
+        assert_eq!(SessionType::detect(), SessionType::X11);
+        assert_eq!(
+            rofi_with_session_args().args_for(SessionType::detect(), Multiplexer::None),
+            vec!["-dmenu", "-monitor", "-1"]
+        );
+    }
+
+    #[test]
+    fn unknown_session_keeps_base_args() {
+        assert_eq!(
+            SessionType::from_env(None, false, false),
+            SessionType::Unknown
+        );
+        assert_eq!(
+            rofi_with_session_args().args_for(SessionType::Unknown, Multiplexer::None),
+            vec!["-dmenu"]
+        );
+    }
+
+    fn fzf_args_in_current_context() -> Vec<String> {
+        Config::default()
+            .get_selector_profile("fzf")
+            .unwrap()
+            .args_for(SessionType::Unknown, Multiplexer::detect())
+    }
+
+    #[test]
+    #[serial]
+    fn tmux_appends_the_fzf_popup_args() {
+        let _tmux = EnvVarGuard::set("TMUX", "/tmp/tmux-1000/default,1234,0");
+        let _zellij = EnvVarGuard::set("ZELLIJ", "0");
+
+        assert_eq!(Multiplexer::detect(), Multiplexer::Tmux);
+        let args = fzf_args_in_current_context();
+        assert_eq!(args[args.len() - 2..], ["--tmux", "center,60%"]);
+    }
+
+    #[test]
+    #[serial]
+    fn zellij_and_no_multiplexer_use_their_own_context_args() {
+        let profile: SelectorProfile = toml::from_str(
+            r#"
+command = "fzf"
+args = ["--reverse"]
+context_args = { tmux = ["--tmux"], zellij = ["--height=100%"], none = ["--height=40%"] }
+"#,
+        )
+        .unwrap();
+
+        let _tmux = EnvVarGuard::set("TMUX", "");
+        let _zellij = EnvVarGuard::set("ZELLIJ", "0");
+        assert_eq!(Multiplexer::detect(), Multiplexer::Zellij);
+        assert_eq!(
+            profile.args_for(SessionType::Unknown, Multiplexer::detect()),
+            vec!["--reverse", "--height=100%"]
+        );
+        assert_eq!(fzf_args_in_current_context().last().unwrap(), "--cycle");
+
+        let _zellij = EnvVarGuard::unset("ZELLIJ");
+        assert_eq!(Multiplexer::detect(), Multiplexer::None);
+        assert_eq!(
+            profile.args_for(SessionType::Unknown, Multiplexer::detect()),
+            vec!["--reverse", "--height=40%"]
+        );
+    }
+
     #[test]
     fn test_default_config() {
         let config = Config::default();
@@ -418,6 +1154,142 @@ mod tests {
 
         assert!(config.app_launch_prefix.is_none());
         assert_eq!(config.terminal_execution, TerminalExecution::Launcher);
+        assert_eq!(config.tie_breaker, TieBreaker::Alpha);
+    }
+
+    #[test]
+    fn test_uri_match_normalize_parses_from_toml() {
+        assert_eq!(
+            Config::default().uri_match_normalize,
+            UriMatchNormalize::None
+        );
+        let config: Config = toml::from_str("uri_match_normalize = \"strip_query\"").unwrap();
+        assert_eq!(config.uri_match_normalize, UriMatchNormalize::StripQuery);
+        assert!(toml::from_str::<Config>("uri_match_normalize = \"strip_all\"").is_err());
+    }
+
+    #[test]
+    fn test_mime_use_file_command_defaults_off() {
+        assert!(!Config::default().mime.use_file_command);
+        let config: Config = toml::from_str("[mime]\nuse_file_command = true\n").unwrap();
+        assert!(config.mime.use_file_command);
+        assert!(toml::from_str::<Config>("[mime]\nuse_file_command = \"yes\"\n").is_err());
+    }
+
+    #[test]
+    fn test_launch_env_policy_modes_are_exclusive() {
+        let config: Config =
+            toml::from_str("[launch_env_policy]\nunset = [\"LD_PRELOAD\", \"DIRENV_*\"]\n")
+                .unwrap();
+        let policy = &config.launch_env_policy;
+        assert!(policy.validate().is_ok());
+        assert!(policy.scrubs("LD_PRELOAD"));
+        assert!(policy.scrubs("DIRENV_DIFF"));
+        assert!(!policy.scrubs("HOME"));
+
+        let keep_only = LaunchEnvPolicy {
+            keep_only: Some(vec!["HOME".to_string()]),
+            ..LaunchEnvPolicy::default()
+        };
+        assert!(!keep_only.scrubs("HOME"));
+        assert!(keep_only.scrubs("LD_PRELOAD"));
+
+        let both = LaunchEnvPolicy {
+            unset: vec!["LD_PRELOAD".to_string()],
+            ..keep_only
+        };
+        assert!(both.validate().is_err());
+    }
+
+    #[test]
+    fn test_tie_breaker_parses_from_toml() {
+        let config: Config = toml::from_str("tie_breaker = \"mtime\"").unwrap();
+        assert_eq!(config.tie_breaker, TieBreaker::Mtime);
+
+        assert!(toml::from_str::<Config>("tie_breaker = \"random\"").is_err());
+    }
+
+    #[test]
+    fn test_per_mime_prefix_parses_from_toml() {
+        let config: Config = toml::from_str(
+            r#"
+app_launch_prefix = "uwsm app --"
+
+[per_mime_prefix]
+"application/pdf" = "flatpak run --command=zathura org.pwmt.zathura"
+"image/*" = ""
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(config.app_launch_prefix.as_deref(), Some("uwsm app --"));
+        assert_eq!(
+            config
+                .per_mime_prefix
+                .get("application/pdf")
+                .map(String::as_str),
+            Some("flatpak run --command=zathura org.pwmt.zathura")
+        );
+        assert_eq!(
+            config.per_mime_prefix.get("image/*").map(String::as_str),
+            Some("")
+        );
+
+        let round_trip: Config = toml::from_str(&toml::to_string(&config).unwrap()).unwrap();
+        assert_eq!(round_trip.per_mime_prefix, config.per_mime_prefix);
+        assert!(!toml::to_string(&Config::default())
+            .unwrap()
+            .contains("per_mime_prefix"));
+    }
+
+    #[test]
+    fn test_merge_combines_per_mime_prefixes() {
+        let base = Config {
+            per_mime_prefix: HashMap::from([
+                ("application/pdf".to_string(), "flatpak run".to_string()),
+                ("image/*".to_string(), "firejail".to_string()),
+            ]),
+            ..Default::default()
+        };
+        let overlay = Config {
+            per_mime_prefix: HashMap::from([("image/*".to_string(), "".to_string())]),
+            ..Default::default()
+        };
+
+        let merged = Config::merge(base, overlay);
+        assert_eq!(merged.per_mime_prefix["application/pdf"], "flatpak run");
+        assert_eq!(merged.per_mime_prefix["image/*"], "");
+    }
+
+    #[test]
+    fn test_application_overrides_parse_from_toml() {
+        let config: Config = toml::from_str(
+            r#"
+[applications."org.mozilla.firefox.desktop"]
+launch_prefix = "flatpak run --command=firefox org.mozilla.firefox"
+
+[applications."code.desktop"]
+env = { ELECTRON_OZONE_PLATFORM_HINT = "wayland" }
+terminal = false
+"#,
+        )
+        .unwrap();
+
+        let firefox = &config.applications["org.mozilla.firefox.desktop"];
+        assert_eq!(
+            firefox.launch_prefix.as_deref(),
+            Some("flatpak run --command=firefox org.mozilla.firefox")
+        );
+        assert!(firefox.env.is_empty());
+        let code = &config.applications["code.desktop"];
+        assert_eq!(code.env["ELECTRON_OZONE_PLATFORM_HINT"], "wayland");
+        assert_eq!(code.terminal, Some(false));
+
+        let merged = Config::merge(Config::default(), config.clone());
+        assert_eq!(merged.applications, config.applications);
+        assert!(!toml::to_string(&Config::default())
+            .unwrap()
+            .contains("applications"));
     }
 
     #[test]
@@ -457,6 +1329,11 @@ mod tests {
             prompt_template: None,
             header_template: None,
             selector_type: SelectorProfileType::Gui,
+            query_args: Vec::new(),
+            args_wayland: Vec::new(),
+            args_x11: Vec::new(),
+            context_args: HashMap::new(),
+            command_args: Vec::new(),
         };
 
         // Test adding directly to the HashMap
@@ -465,7 +1342,7 @@ mod tests {
             .insert(SelectorProfileId::from("custom"), custom_config);
 
         assert!(config.selector_profiles.contains_key("custom"));
-        assert_eq!(config.selector_profiles.len(), 4);
+        assert_eq!(config.selector_profiles.len(), 7);
     }
 
     #[test]
@@ -474,6 +1351,11 @@ mod tests {
 
         let gui_candidates = config.selector_candidates(SelectorProfileType::Gui);
         assert_eq!(gui_candidates.first().unwrap().as_str(), "fuzzel");
+        let gui_names: Vec<&str> = gui_candidates.iter().map(|name| name.as_str()).collect();
+        assert_eq!(
+            &gui_names[..5],
+            ["fuzzel", "rofi", "wofi", "bemenu", "dmenu"]
+        );
         assert!(gui_candidates.iter().any(|name| name.as_str() == "fzf"));
 
         let tui_candidates = config.selector_candidates(SelectorProfileType::Tui);
@@ -529,4 +1411,87 @@ mod tests {
             "unexpected error message: {message}"
         );
     }
+
+    #[test]
+    fn test_selector_profile_validate() {
+        let available = SelectorProfile {
+            command: "sh".to_string(),
+            ..Default::default()
+        };
+        assert!(available.validate().is_ok());
+
+        let missing = SelectorProfile {
+            command: "definitely-missing-selector".to_string(),
+            ..Default::default()
+        };
+        let err = missing.validate().unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("`definitely-missing-selector` was not found on PATH"));
+    }
+
+    #[test]
+    fn test_partial_config_keeps_builtin_profiles() {
+        let temp_dir = TempDir::new().unwrap();
+        let custom_path = temp_dir.path().join("config.toml");
+        fs::write(
+            &custom_path,
+            r#"
+marker_default = "* "
+
+[selectors.custom]
+command = "custom-menu"
+args = ["--dmenu"]
+"#,
+        )
+        .unwrap();
+
+        let loaded = Config::load(Some(custom_path)).unwrap();
+        for name in ["fzf", "fuzzel", "rofi", "wofi", "dmenu", "bemenu", "custom"] {
+            assert!(
+                loaded.get_selector_profile(name).is_some(),
+                "missing profile `{name}`"
+            );
+        }
+        assert_eq!(loaded.marker_default, "* ");
+        assert_eq!(loaded.marker_xdg, Config::default().marker_xdg);
+    }
+
+    #[test]
+    fn test_merge_overrides_profiles_by_name() {
+        let mut overlay = Config::default();
+        overlay.selector_profiles.clear();
+        overlay.selector_profiles.insert(
+            SelectorProfileId::from("fzf"),
+            SelectorProfile {
+                command: "sk".to_string(),
+                ..Default::default()
+            },
+        );
+
+        let merged = Config::merge(Config::default(), overlay);
+        assert_eq!(merged.get_selector_profile("fzf").unwrap().command, "sk");
+        assert_eq!(
+            merged.get_selector_profile("fuzzel").unwrap().command,
+            "fuzzel"
+        );
+    }
+
+    #[test]
+    fn test_merge_keeps_base_scalars_left_at_default() {
+        let base = Config {
+            app_launch_prefix: Some("uwsm app --".to_string()),
+            tie_breaker: TieBreaker::Mtime,
+            ..Default::default()
+        };
+        let overlay = Config {
+            terminal_execution: TerminalExecution::Current,
+            ..Default::default()
+        };
+
+        let merged = Config::merge(base, overlay);
+        assert_eq!(merged.app_launch_prefix.as_deref(), Some("uwsm app --"));
+        assert_eq!(merged.tie_breaker, TieBreaker::Mtime);
+        assert_eq!(merged.terminal_execution, TerminalExecution::Current);
+    }
 }
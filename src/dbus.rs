@@ -0,0 +1,149 @@
+use crate::target::LaunchTarget;
+use anyhow::{Context, Result};
+use log::info;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use url::Url;
+
+/// Seconds `gdbus` waits for the application to answer before we fall back to `Exec`.
+const CALL_TIMEOUT_SECS: &str = "5";
+
+/// Open `target` through `org.freedesktop.Application.Open` on the bus name derived from
+/// `desktop_file`, as the desktop-entry spec describes for `DBusActivatable=true`.
+pub fn open(desktop_file: &Path, target: &LaunchTarget) -> Result<()> {
+    let command = open_command(desktop_file, target)?;
+    let output = Command::new(&command[0])
+        .args(&command[1..])
+        .stdin(Stdio::null())
+        .output()
+        .context("Failed to run gdbus")?;
+    // `open_command` already checked that the ID is a bus name.
+    let name = bus_name(desktop_file).unwrap_or_default();
+    if !output.status.success() {
+        anyhow::bail!(
+            "gdbus call to {name} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    info!(
+        "Opened {} via D-Bus activation of {name}",
+        target.as_command_argument()
+    );
+    Ok(())
+}
+
+/// The `gdbus call` argv [`open`] runs, for `--dry-run`.
+pub fn open_command(desktop_file: &Path, target: &LaunchTarget) -> Result<Vec<String>> {
+    let name = bus_name(desktop_file).ok_or_else(|| {
+        anyhow::anyhow!(
+            "`{}` is not a valid D-Bus name for activation",
+            desktop_file.display()
+        )
+    })?;
+    let object_path = object_path(&name);
+    let uri = target_uri(target)?;
+
+    Ok([
+        "gdbus",
+        "call",
+        "--session",
+        "--timeout",
+        CALL_TIMEOUT_SECS,
+        "--dest",
+        &name,
+        "--object-path",
+        &object_path,
+        "--method",
+        "org.freedesktop.Application.Open",
+    ]
+    .into_iter()
+    .map(str::to_string)
+    .chain([format!("[{}]", gvariant_string(&uri)), "{}".to_string()])
+    .collect())
+}
+
+/// Well-known bus name: the desktop ID without `.desktop`, e.g. `org.gnome.Nautilus`.
+///
+/// The spec only allows activation for IDs that are valid bus names, so plain IDs such
+/// as `firefox.desktop` yield `None`.
+fn bus_name(desktop_file: &Path) -> Option<String> {
+    let id = desktop_file.file_name()?.to_str()?;
+    let name = id.strip_suffix(".desktop")?;
+    let elements: Vec<&str> = name.split('.').collect();
+    let valid = elements.len() >= 2
+        && elements.iter().all(|element| {
+            !element.is_empty()
+                && !element.starts_with(|c: char| c.is_ascii_digit())
+                && element
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        });
+    valid.then(|| name.to_string())
+}
+
+/// `org.gnome.Nautilus` becomes `/org/gnome/Nautilus`; dashes become underscores.
+fn object_path(bus_name: &str) -> String {
+    format!("/{}", bus_name.replace('.', "/").replace('-', "_"))
+}
+
+/// `Open` takes URIs, so local paths become absolute `file://` URIs.
+fn target_uri(target: &LaunchTarget) -> Result<String> {
+    match target {
+        LaunchTarget::Uri(uri) => Ok(uri.to_string()),
+        LaunchTarget::File(path) => {
+            let absolute = std::path::absolute(path)
+                .with_context(|| format!("Failed to resolve {}", path.display()))?;
+            Url::from_file_path(&absolute)
+                .map(String::from)
+                .map_err(|()| anyhow::anyhow!("Cannot express {} as a URI", path.display()))
+        }
+    }
+}
+
+/// Single-quoted GVariant text literal for `gdbus call`.
+fn gvariant_string(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn bus_name_and_object_path_follow_the_desktop_id() {
+        let name = bus_name(Path::new(
+            "/usr/share/applications/org.gnome.Nautilus.desktop",
+        ));
+        assert_eq!(name.as_deref(), Some("org.gnome.Nautilus"));
+        assert_eq!(object_path("org.gnome.Nautilus"), "/org/gnome/Nautilus");
+        assert_eq!(
+            object_path("org.example.image-viewer"),
+            "/org/example/image_viewer"
+        );
+    }
+
+    #[test]
+    fn ids_that_are_not_bus_names_are_rejected() {
+        for id in [
+            "firefox.desktop",
+            "org..app.desktop",
+            "org.2app.desktop",
+            "org.app",
+        ] {
+            assert_eq!(bus_name(Path::new(id)), None, "{id}");
+        }
+    }
+
+    #[test]
+    fn targets_become_quoted_uris() {
+        let file = LaunchTarget::File(PathBuf::from("/tmp/it's here.txt"));
+        let uri = target_uri(&file).unwrap();
+        assert_eq!(uri, "file:///tmp/it's%20here.txt");
+        assert_eq!(gvariant_string(&uri), "'file:///tmp/it\\'s%20here.txt'");
+
+        let web = LaunchTarget::Uri(Url::parse("https://example.com/a").unwrap());
+        assert_eq!(target_uri(&web).unwrap(), "https://example.com/a");
+    }
+}
@@ -1,8 +1,13 @@
 use anyhow::{Context, Result};
+use log::debug;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+
+/// Keys kept per group; real files stay in the hundreds even with every translation, so
+/// anything past this is junk that would only grow memory.
+const MAX_KEYS_PER_GROUP: usize = 8192;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DesktopEntry {
@@ -11,12 +16,19 @@ pub struct DesktopEntry {
     #[serde(default)]
     pub version: Option<String>,
     pub name: String,
+    /// `Name[locale]` values keyed by the bracketed locale, e.g. `de` or `pt_BR`.
+    #[serde(default)]
+    pub localized_names: HashMap<String, String>,
     #[serde(default)]
     pub generic_name: Option<String>,
+    #[serde(default)]
+    pub localized_generic_names: HashMap<String, String>,
     pub exec: String,
     #[serde(default)]
     pub comment: Option<String>,
     #[serde(default)]
+    pub localized_comments: HashMap<String, String>,
+    #[serde(default)]
     pub icon: Option<String>,
     #[serde(default)]
     pub no_display: bool,
@@ -43,6 +55,8 @@ pub struct DesktopEntry {
     #[serde(default)]
     pub keywords: Vec<String>,
     #[serde(default)]
+    pub localized_keywords: HashMap<String, Vec<String>>,
+    #[serde(default)]
     pub startup_notify: bool,
     #[serde(default)]
     pub startup_wm_class: Option<String>,
@@ -60,6 +74,69 @@ impl DesktopEntry {
     fn default_entry_type() -> String {
         "Application".to_string()
     }
+
+    /// The `Name[locale]` best matching `locale` (`lang_COUNTRY.ENCODING@MODIFIER`), or `Name`.
+    pub fn name_for_locale(&self, locale: &str) -> &str {
+        localized_value(&self.localized_names, locale)
+            .map(String::as_str)
+            .unwrap_or(&self.name)
+    }
+
+    /// `GenericName[locale]` for `locale`, or `GenericName`.
+    pub fn generic_name_for_locale(&self, locale: &str) -> Option<&str> {
+        localized_value(&self.localized_generic_names, locale)
+            .map(String::as_str)
+            .or(self.generic_name.as_deref())
+    }
+
+    /// `Comment[locale]` for `locale`, or `Comment`.
+    pub fn comment_for_locale(&self, locale: &str) -> Option<&str> {
+        localized_value(&self.localized_comments, locale)
+            .map(String::as_str)
+            .or(self.comment.as_deref())
+    }
+
+    /// `Keywords[locale]` for `locale`, or `Keywords`.
+    pub fn keywords_for_locale(&self, locale: &str) -> &[String] {
+        localized_value(&self.localized_keywords, locale).unwrap_or(&self.keywords)
+    }
+
+    /// The `Path=` working directory, if set to a non-empty value.
+    pub fn working_dir(&self) -> Option<PathBuf> {
+        self.path
+            .as_deref()
+            .map(str::trim)
+            .filter(|path| !path.is_empty())
+            .map(PathBuf::from)
+    }
+
+    /// Whether `OnlyShowIn` and `NotShowIn` let the entry appear on a desktop named by
+    /// `desktops`, the `XDG_CURRENT_DESKTOP` components in order. The first component
+    /// listed in either key decides; names compare case-insensitively.
+    pub fn shown_in(&self, desktops: &[String]) -> bool {
+        let lists = |names: &[String], desktop: &str| {
+            names.iter().any(|name| name.eq_ignore_ascii_case(desktop))
+        };
+        for desktop in desktops {
+            if lists(&self.only_show_in, desktop) {
+                return true;
+            }
+            if lists(&self.not_show_in, desktop) {
+                return false;
+            }
+        }
+        self.only_show_in.is_empty()
+    }
+
+    /// Check that the binary referenced by `TryExec` is installed.
+    ///
+    /// Entries without a `TryExec` key are always considered valid.
+    pub fn validate(&self) -> bool {
+        match &self.try_exec {
+            Some(try_exec) => which::which(try_exec).is_ok(),
+            None => true,
+        }
+    }
 }
 
 impl Default for DesktopEntry {
@@ -68,9 +145,12 @@ impl Default for DesktopEntry {
             entry_type: DesktopEntry::default_entry_type(),
             version: None,
             name: String::new(),
+            localized_names: HashMap::new(),
             generic_name: None,
+            localized_generic_names: HashMap::new(),
             exec: String::new(),
             comment: None,
+            localized_comments: HashMap::new(),
             icon: None,
             no_display: false,
             hidden: false,
@@ -84,6 +164,7 @@ impl Default for DesktopEntry {
             categories: Vec::new(),
             implements: Vec::new(),
             keywords: Vec::new(),
+            localized_keywords: HashMap::new(),
             startup_notify: false,
             startup_wm_class: None,
             url: None,
@@ -94,6 +175,54 @@ impl Default for DesktopEntry {
     }
 }
 
+/// The locale for localized keys: `LC_ALL`, then `LC_MESSAGES`, then `LANG`. `None` for
+/// the `C` and `POSIX` locales, which use the unlocalized values.
+pub fn current_locale() -> Option<String> {
+    ["LC_ALL", "LC_MESSAGES", "LANG"]
+        .iter()
+        .filter_map(|key| std::env::var(key).ok())
+        .find(|value| !value.is_empty())
+        .filter(|locale| {
+            let base = locale.split(['.', '@']).next().unwrap_or_default();
+            base != "C" && base != "POSIX"
+        })
+}
+
+/// The value for `locale` using the desktop-entry spec's matching order:
+/// `lang_COUNTRY@MODIFIER`, `lang_COUNTRY`, `lang@MODIFIER`, then `lang`. The encoding is
+/// ignored.
+fn localized_value<'a, V>(values: &'a HashMap<String, V>, locale: &str) -> Option<&'a V> {
+    if values.is_empty() {
+        return None;
+    }
+
+    let (rest, modifier) = match locale.split_once('@') {
+        Some((rest, modifier)) => (rest, Some(modifier)),
+        None => (locale, None),
+    };
+    let rest = rest.split('.').next().unwrap_or_default();
+    let (lang, country) = match rest.split_once('_') {
+        Some((lang, country)) => (lang, Some(country)),
+        None => (rest, None),
+    };
+
+    let mut candidates = Vec::new();
+    if let (Some(country), Some(modifier)) = (country, modifier) {
+        candidates.push(format!("{lang}_{country}@{modifier}"));
+    }
+    if let Some(country) = country {
+        candidates.push(format!("{lang}_{country}"));
+    }
+    if let Some(modifier) = modifier {
+        candidates.push(format!("{lang}@{modifier}"));
+    }
+    candidates.push(lang.to_string());
+
+    candidates
+        .iter()
+        .find_map(|candidate| values.get(candidate))
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DesktopAction {
     pub name: String,
@@ -108,9 +237,32 @@ pub struct DesktopFile {
 }
 
 impl DesktopFile {
+    /// Actions in `Actions=` order; groups the key does not declare follow, sorted by ID.
+    pub fn ordered_actions(&self) -> Vec<(&String, &DesktopAction)> {
+        let declared: &[String] = self
+            .main_entry
+            .as_ref()
+            .map_or(&[], |entry| entry.actions.as_slice());
+
+        let mut ordered: Vec<(&String, &DesktopAction)> = declared
+            .iter()
+            .filter_map(|id| self.actions.get_key_value(id))
+            .collect();
+        let mut undeclared: Vec<_> = self
+            .actions
+            .iter()
+            .filter(|(id, _)| !declared.contains(id))
+            .collect();
+        undeclared.sort_by(|a, b| a.0.cmp(b.0));
+        ordered.extend(undeclared);
+        ordered
+    }
+
     pub fn parse(path: &Path) -> Result<Self> {
-        let contents = fs::read_to_string(path)
+        let bytes = fs::read(path)
             .with_context(|| format!("Failed to read desktop file: {}", path.display()))?;
+        // The spec requires UTF-8, but one stray byte in a comment should not hide the app.
+        let contents = String::from_utf8_lossy(&bytes);
 
         let mut main_entry = None;
         let mut actions = HashMap::new();
@@ -158,6 +310,13 @@ impl DesktopFile {
             if let Some(eq_pos) = line.find('=') {
                 let key = line[..eq_pos].trim();
                 let value = line[eq_pos + 1..].trim();
+                if current_fields.len() >= MAX_KEYS_PER_GROUP && !current_fields.contains_key(key) {
+                    debug!(
+                        "Ignoring key {key} in {}: group has over {MAX_KEYS_PER_GROUP} keys",
+                        path.display()
+                    );
+                    continue;
+                }
                 current_fields.insert(key.to_string(), value.to_string());
             }
         }
@@ -197,6 +356,25 @@ impl DesktopFile {
             .unwrap_or_default()
     }
 
+    /// `key[locale]` values keyed by locale; `parse` drops empty ones by returning `None`.
+    fn parse_localized<V>(
+        fields: &HashMap<String, String>,
+        key: &str,
+        parse: impl Fn(Option<&String>) -> Option<V>,
+    ) -> HashMap<String, V> {
+        fields
+            .iter()
+            .filter_map(|(field, value)| {
+                let locale = field
+                    .strip_prefix(key)?
+                    .strip_prefix('[')?
+                    .strip_suffix(']')?;
+                let value = parse(Some(value))?;
+                (!locale.is_empty()).then(|| (locale.to_string(), value))
+            })
+            .collect()
+    }
+
     fn parse_optional_string(value: Option<&String>) -> Option<String> {
         value.and_then(|s| {
             let trimmed = s.trim();
@@ -215,6 +393,8 @@ impl DesktopFile {
         let name = Self::parse_optional_string(fields.get("Name"))
             .ok_or_else(|| anyhow::anyhow!("Missing Name field"))?;
 
+        let localized_names = Self::parse_localized(fields, "Name", Self::parse_optional_string);
+
         let exec = fields
             .get("Exec")
             .ok_or_else(|| anyhow::anyhow!("Missing Exec field"))?
@@ -226,7 +406,11 @@ impl DesktopFile {
 
         let version = Self::parse_optional_string(fields.get("Version"));
         let generic_name = Self::parse_optional_string(fields.get("GenericName"));
+        let localized_generic_names =
+            Self::parse_localized(fields, "GenericName", Self::parse_optional_string);
         let comment = Self::parse_optional_string(fields.get("Comment"));
+        let localized_comments =
+            Self::parse_localized(fields, "Comment", Self::parse_optional_string);
         let icon = Self::parse_optional_string(fields.get("Icon"));
 
         let mime_types = Self::parse_list(fields.get("MimeType"));
@@ -242,6 +426,9 @@ impl DesktopFile {
         let categories = Self::parse_list(fields.get("Categories"));
         let implements = Self::parse_list(fields.get("Implements"));
         let keywords = Self::parse_list(fields.get("Keywords"));
+        let localized_keywords = Self::parse_localized(fields, "Keywords", |value| {
+            Some(Self::parse_list(value)).filter(|keywords| !keywords.is_empty())
+        });
         let startup_notify = Self::parse_bool(fields.get("StartupNotify"));
         let startup_wm_class = Self::parse_optional_string(fields.get("StartupWMClass"));
         let url = Self::parse_optional_string(fields.get("URL"));
@@ -253,9 +440,12 @@ impl DesktopFile {
             entry_type,
             version,
             name,
+            localized_names,
             exec,
             generic_name,
+            localized_generic_names,
             comment,
+            localized_comments,
             icon,
             no_display,
             hidden,
@@ -269,6 +459,7 @@ impl DesktopFile {
             categories,
             implements,
             keywords,
+            localized_keywords,
             startup_notify,
             startup_wm_class,
             url,
@@ -300,6 +491,7 @@ impl DesktopFile {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::Xorshift;
     use std::io::Write;
     use tempfile::NamedTempFile;
 
@@ -330,6 +522,70 @@ NoDisplay=false";
         assert!(entry.categories.is_empty());
     }
 
+    #[test]
+    fn test_parse_localized_names() {
+        let content = r"[Desktop Entry]
+Name=Text Editor
+Name[de]=Texteditor
+Name[de_CH]=Texteditor (Schweiz)
+Name[sr@latin]=Uređivač teksta
+Name[fr]=
+Exec=gedit %U";
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "{content}").unwrap();
+
+        let entry = DesktopFile::parse(temp_file.path())
+            .unwrap()
+            .main_entry
+            .unwrap();
+
+        assert_eq!(entry.name, "Text Editor");
+        assert_eq!(entry.localized_names.len(), 3);
+        assert_eq!(entry.name_for_locale("de"), "Texteditor");
+        assert_eq!(entry.name_for_locale("de_DE.UTF-8"), "Texteditor");
+        assert_eq!(entry.name_for_locale("de_CH.UTF-8"), "Texteditor (Schweiz)");
+        assert_eq!(entry.name_for_locale("sr_RS@latin"), "Uređivač teksta");
+        assert_eq!(entry.name_for_locale("fr_FR"), "Text Editor");
+        assert_eq!(entry.name_for_locale("ja_JP.UTF-8"), "Text Editor");
+    }
+
+    #[test]
+    fn test_parse_localized_generic_name_comment_and_keywords() {
+        let content = r"[Desktop Entry]
+Name=Files
+GenericName=File Manager
+GenericName[de]=Dateimanager
+Comment=Access and organize files
+Comment[de]=Dateien öffnen und ordnen
+Keywords=folder;manager;
+Keywords[de]=Ordner;Verwaltung;
+Keywords[fr]=;
+Exec=nautilus %U";
+
+        let mut temp_file = NamedTempFile::new().unwrap();
+        write!(temp_file, "{content}").unwrap();
+
+        let entry = DesktopFile::parse(temp_file.path())
+            .unwrap()
+            .main_entry
+            .unwrap();
+
+        assert_eq!(entry.generic_name_for_locale("de_DE"), Some("Dateimanager"));
+        assert_eq!(
+            entry.comment_for_locale("de_AT.UTF-8"),
+            Some("Dateien öffnen und ordnen")
+        );
+        assert_eq!(entry.keywords_for_locale("de"), ["Ordner", "Verwaltung"]);
+
+        assert_eq!(entry.generic_name_for_locale("fr_FR"), Some("File Manager"));
+        assert_eq!(
+            entry.comment_for_locale("fr_FR"),
+            Some("Access and organize files")
+        );
+        assert_eq!(entry.keywords_for_locale("fr_FR"), ["folder", "manager"]);
+    }
+
     #[test]
     fn test_parse_desktop_file_with_actions() {
         let content = r"[Desktop Entry]
@@ -606,6 +862,82 @@ Exec=app --last";
         assert!(desktop_file.actions.contains_key("last"));
     }
 
+    #[test]
+    fn test_validate_without_try_exec() {
+        let entry = DesktopEntry {
+            name: "App".to_string(),
+            exec: "app".to_string(),
+            ..DesktopEntry::default()
+        };
+
+        assert!(entry.validate());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_validate_with_existing_try_exec() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let binary = temp_dir.path().join("present-app");
+        fs::write(&binary, "#!/bin/sh\n").unwrap();
+        let mut perms = fs::metadata(&binary).unwrap().permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&binary, perms).unwrap();
+
+        let entry = DesktopEntry {
+            name: "Present".to_string(),
+            exec: "present-app %F".to_string(),
+            try_exec: Some(binary.to_string_lossy().to_string()),
+            ..DesktopEntry::default()
+        };
+
+        assert!(entry.validate());
+    }
+
+    #[test]
+    fn test_validate_with_missing_try_exec() {
+        let entry = DesktopEntry {
+            name: "Missing".to_string(),
+            exec: "missing-app %F".to_string(),
+            try_exec: Some("/nonexistent/bin/missing-app".to_string()),
+            ..DesktopEntry::default()
+        };
+
+        assert!(!entry.validate());
+    }
+
+    #[test]
+    fn test_shown_in_follows_only_show_in_and_not_show_in() {
+        let desktops = |value: &str| value.split(':').map(str::to_string).collect::<Vec<_>>();
+        let kde_only = DesktopEntry {
+            only_show_in: vec!["KDE".to_string()],
+            ..DesktopEntry::default()
+        };
+        assert!(kde_only.shown_in(&desktops("kde")));
+        assert!(kde_only.shown_in(&desktops("ubuntu:KDE")));
+        assert!(!kde_only.shown_in(&desktops("GNOME")));
+        assert!(!kde_only.shown_in(&[]));
+
+        let not_gnome = DesktopEntry {
+            not_show_in: vec!["GNOME".to_string()],
+            ..DesktopEntry::default()
+        };
+        assert!(!not_gnome.shown_in(&desktops("ubuntu:gnome")));
+        assert!(not_gnome.shown_in(&desktops("XFCE")));
+        assert!(not_gnome.shown_in(&[]));
+
+        // The earliest listed desktop wins when the keys disagree.
+        let both = DesktopEntry {
+            only_show_in: vec!["Unity".to_string()],
+            not_show_in: vec!["GNOME".to_string()],
+            ..DesktopEntry::default()
+        };
+        assert!(both.shown_in(&desktops("Unity:GNOME")));
+        assert!(!both.shown_in(&desktops("GNOME:Unity")));
+        assert!(DesktopEntry::default().shown_in(&desktops("GNOME")));
+    }
+
     #[test]
     fn test_parse_desktop_file_with_recognized_keys() {
         let content = r"[Desktop Entry]
@@ -680,4 +1012,84 @@ Exec=full --view %F";
         assert!(desktop_file.actions.contains_key("Edit"));
         assert!(desktop_file.actions.contains_key("View"));
     }
+
+    fn parse_bytes(bytes: &[u8]) -> Result<DesktopFile> {
+        let mut file = NamedTempFile::new().unwrap();
+        file.write_all(bytes).unwrap();
+        DesktopFile::parse(file.path())
+    }
+
+    #[test]
+    fn invalid_utf8_is_replaced_instead_of_failing_the_file() {
+        let desktop_file = parse_bytes(
+            b"[Desktop Entry]\n# caf\xe9\nName=Caf\xe9\nExec=cafe %f\nType=Application\n",
+        )
+        .unwrap();
+
+        let entry = desktop_file.main_entry.unwrap();
+        assert_eq!(entry.name, "Caf\u{fffd}");
+        assert_eq!(entry.exec, "cafe %f");
+    }
+
+    #[test]
+    fn arbitrary_bytes_parse_without_panicking() {
+        for seed in 1..=200 {
+            let mut rng = Xorshift::new(seed);
+            let len = rng.below(2048);
+            let _ = parse_bytes(&rng.bytes(len));
+        }
+
+        let fragments = [
+            "[Desktop Entry]",
+            "[Desktop Action ",
+            "]",
+            "Name",
+            "Name[de_DE@euro]",
+            "Exec",
+            "=",
+            ";",
+            "%f",
+            "\\",
+            "\u{0}",
+            " ",
+        ];
+        for seed in 1..=200 {
+            let mut rng = Xorshift::new(seed);
+            let _ = parse_bytes(rng.ini_like(&fragments, 16).as_bytes());
+        }
+    }
+
+    #[test]
+    fn keys_past_the_group_limit_are_ignored() {
+        let mut content = String::from("[Desktop Entry]\nName=Many\nExec=many\n");
+        for i in 0..MAX_KEYS_PER_GROUP + 100 {
+            content.push_str(&format!("X-Key-{i}=1\n"));
+        }
+        content.push_str("Comment=too late\nName=still replaced\n");
+
+        let entry = parse_bytes(content.as_bytes()).unwrap().main_entry.unwrap();
+        assert_eq!(entry.name, "still replaced");
+        assert_eq!(entry.comment, None);
+    }
+
+    #[test]
+    fn pathological_inputs_parse_in_linear_time() {
+        let long_line = format!("[Desktop Entry]\nName={}\nExec=x\n", "a".repeat(10 << 20));
+        let mut repeated = String::new();
+        for i in 0..20_000 {
+            repeated.push_str(&format!(
+                "[Desktop Entry]\nName=App {i}\nExec=app\n[Desktop Action a{i}]\nName=A\nExec=a\n"
+            ));
+        }
+
+        for input in [long_line, repeated] {
+            let started = std::time::Instant::now();
+            parse_bytes(input.as_bytes()).unwrap();
+            assert!(
+                started.elapsed() < std::time::Duration::from_secs(10),
+                "took {:?}",
+                started.elapsed()
+            );
+        }
+    }
 }
@@ -1,20 +1,141 @@
 use crate::application_finder::ApplicationEntry;
+use crate::config::{ApplicationOverride, LaunchEnvPolicy};
+use crate::dbus;
+use crate::gpu::{GpuDriver, GpuPreference, GpuSettings};
+use crate::mime_pattern;
 use crate::target::LaunchTarget;
 use anyhow::{Context, Result};
-use log::info;
+use log::{info, warn};
+use std::collections::HashMap;
+use std::ffi::OsString;
 use std::os::unix::process::CommandExt;
-use std::process::{Command, Stdio};
+use std::path::{Component, Path, PathBuf};
+use std::process::{Child, Command, Stdio};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Exit status of `openit --wait` when `--wait-timeout` expires, as with `timeout(1)`.
+pub const WAIT_TIMEOUT_EXIT_CODE: u8 = 124;
+
+/// How long a timed-out process group gets between SIGTERM and SIGKILL.
+const KILL_GRACE_PERIOD: Duration = Duration::from_secs(2);
+
+/// How often a waited-on child is polled while a timeout is running.
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// Startup notification variables; only entries with `StartupNotify=true` may see them.
+const STARTUP_VARIABLES: &[&str] = &["DESKTOP_STARTUP_ID", "XDG_ACTIVATION_TOKEN"];
+
+/// Handoff variables openit exports to selector commands; an application launched from
+/// such a command must not inherit them.
+const INTERNAL_VARIABLES: &[&str] = &[
+    "OPENIT_CANDIDATES_FILE",
+    "OPENIT_TARGET",
+    "OPENIT_MIME",
+    "OPENIT_PROMPT",
+];
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum LaunchDisposition {
     Detached,
     InheritTerminal,
+    /// Run in the foreground with the target file on stdin and wait for exit.
+    Piped,
+    /// Run in a new process group and wait for exit, killing the group after the timeout.
+    Wait(Option<Duration>),
 }
 
+/// `--wait-timeout` expired; the handler's process group has been killed.
+#[derive(Debug)]
+pub struct WaitTimedOut {
+    pub program: String,
+    pub timeout: Duration,
+    pub pgid: i32,
+}
+
+impl std::fmt::Display for WaitTimedOut {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "`{}` did not exit within {}s; killed process group {}",
+            self.program,
+            self.timeout.as_secs(),
+            self.pgid
+        )
+    }
+}
+
+impl std::error::Error for WaitTimedOut {}
+
 #[derive(Debug)]
 pub struct ApplicationExecutor {
     app_launch_prefix: Option<String>,
     terminal_exec_args: Option<String>,
+    gpu_override: Option<GpuPreference>,
+    gpu_settings: GpuSettings,
+    /// Driver stack used for GPU offload variables; detected at launch when unset.
+    gpu_driver: Option<GpuDriver>,
+    /// Try `org.freedesktop.Application.Open` for `DBusActivatable=true` entries first.
+    dbus_activation: bool,
+    /// Launch prefixes keyed by MIME pattern; they override `app_launch_prefix`.
+    per_mime_prefix: HashMap<String, String>,
+    /// Per-application settings keyed by desktop ID; they beat both prefix options.
+    app_overrides: HashMap<String, ApplicationOverride>,
+    env_policy: LaunchEnvPolicy,
+}
+
+/// Terminal emulator command that wraps `Terminal=true` applications.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerminalLauncher {
+    pub command: Vec<String>,
+    /// Insert `term_exec_args` (e.g. `-e`) between the terminal and the application.
+    pub exec_args: bool,
+    /// The emulator's desktop file, when the command came from a desktop entry.
+    pub desktop_file: Option<PathBuf>,
+}
+
+impl TerminalLauncher {
+    pub fn new(command: Vec<String>) -> Self {
+        Self {
+            command,
+            exec_args: true,
+            desktop_file: None,
+        }
+    }
+
+    /// For launchers such as `xdg-terminal-exec` that take the command directly.
+    pub fn without_exec_args(command: Vec<String>) -> Self {
+        Self {
+            command,
+            exec_args: false,
+            desktop_file: None,
+        }
+    }
+
+    pub fn with_desktop_file(mut self, desktop_file: PathBuf) -> Self {
+        self.desktop_file = Some(desktop_file);
+        self
+    }
+}
+
+/// Process setup shared by detached and in-place launches.
+struct ProcessOptions<'a> {
+    working_dir: Option<&'a Path>,
+    env: Vec<(String, String)>,
+    /// Inherited variables removed before `env` is applied.
+    env_remove: Vec<OsString>,
+}
+
+impl ProcessOptions<'_> {
+    /// Working directory, then removals, then additions, so added variables always win.
+    fn apply(&self, cmd: &mut Command) {
+        if let Some(dir) = self.working_dir {
+            cmd.current_dir(dir);
+        }
+        for key in &self.env_remove {
+            cmd.env_remove(key);
+        }
+        cmd.envs(self.env.iter().map(|(key, value)| (key, value)));
+    }
 }
 
 impl ApplicationExecutor {
@@ -22,6 +143,13 @@ impl ApplicationExecutor {
         Self {
             app_launch_prefix: None,
             terminal_exec_args: None,
+            gpu_override: None,
+            gpu_settings: GpuSettings::default(),
+            gpu_driver: None,
+            dbus_activation: false,
+            per_mime_prefix: HashMap::new(),
+            app_overrides: HashMap::new(),
+            env_policy: LaunchEnvPolicy::default(),
         }
     }
 
@@ -41,9 +169,71 @@ impl ApplicationExecutor {
         Self {
             app_launch_prefix: normalized_prefix,
             terminal_exec_args,
+            ..Self::new()
         }
     }
 
+    /// Apply `gpu_override` to every launch, or honour `PrefersNonDefaultGPU` when unset.
+    pub fn with_gpu(mut self, gpu_override: Option<GpuPreference>, settings: GpuSettings) -> Self {
+        self.gpu_override = gpu_override;
+        self.gpu_settings = settings;
+        self
+    }
+
+    pub fn with_dbus_activation(mut self, enabled: bool) -> Self {
+        self.dbus_activation = enabled;
+        self
+    }
+
+    pub fn with_per_mime_prefix(mut self, per_mime_prefix: HashMap<String, String>) -> Self {
+        self.per_mime_prefix = per_mime_prefix;
+        self
+    }
+
+    pub fn with_app_overrides(
+        mut self,
+        app_overrides: HashMap<String, ApplicationOverride>,
+    ) -> Self {
+        self.app_overrides = app_overrides;
+        self
+    }
+
+    pub fn with_env_policy(mut self, env_policy: LaunchEnvPolicy) -> Self {
+        self.env_policy = env_policy;
+        self
+    }
+
+    /// Names in `inherited` that launched applications must not see: openit's internal
+    /// variables and whatever `[launch_env_policy]` scrubs.
+    fn scrubbed_variables(&self, inherited: impl IntoIterator<Item = OsString>) -> Vec<OsString> {
+        inherited
+            .into_iter()
+            .filter(|name| {
+                let name = name.to_string_lossy();
+                INTERNAL_VARIABLES.contains(&name.as_ref()) || self.env_policy.scrubs(&name)
+            })
+            .collect()
+    }
+
+    /// The `[applications."<id>"]` table for `app`'s desktop file, if configured.
+    fn app_override(&self, app: &ApplicationEntry) -> Option<&ApplicationOverride> {
+        let desktop_id = app.desktop_file.file_name()?.to_str()?;
+        self.app_overrides.get(desktop_id)
+    }
+
+    /// `Terminal=` of the entry unless the application's override sets `terminal`.
+    pub fn requires_terminal(&self, app: &ApplicationEntry) -> bool {
+        self.app_override(app)
+            .and_then(|settings| settings.terminal)
+            .unwrap_or(app.requires_terminal)
+    }
+
+    #[cfg(test)]
+    pub fn with_gpu_driver(mut self, driver: GpuDriver) -> Self {
+        self.gpu_driver = Some(driver);
+        self
+    }
+
     #[cfg(test)]
     pub fn with_launch_prefix(prefix: Option<String>) -> Self {
         Self::with_options(prefix, None)
@@ -53,69 +243,329 @@ impl ApplicationExecutor {
         &self,
         app: &ApplicationEntry,
         target: &LaunchTarget,
-        terminal_launcher: Option<&[String]>,
+        mime_type: Option<&str>,
+        terminal_launcher: Option<&TerminalLauncher>,
         disposition: LaunchDisposition,
     ) -> Result<()> {
-        let launcher = match disposition {
-            LaunchDisposition::Detached => terminal_launcher.map(|parts| parts.to_vec()),
-            LaunchDisposition::InheritTerminal => {
+        if self.uses_dbus_activation(app, terminal_launcher, disposition) {
+            match dbus::open(&app.desktop_file, target) {
+                Ok(()) => return Ok(()),
+                Err(err) => info!(
+                    "D-Bus activation of {} failed, falling back to Exec: {err:#}",
+                    app.name
+                ),
+            }
+        }
+
+        let launcher = Self::launcher_for(terminal_launcher, disposition)?;
+
+        let stdin_file = if disposition == LaunchDisposition::Piped {
+            Some(target.as_path().ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Cannot pipe `{}` to a handler's stdin: only local files can be piped",
+                    target.as_command_argument()
+                )
+            })?)
+        } else {
+            None
+        };
+
+        // A stale `Path=` should not keep the application from starting at all.
+        let working_dir = app.working_dir.as_deref().filter(|dir| {
+            let exists = dir.is_dir();
+            if !exists {
+                warn!(
+                    "Ignoring working directory `{}` from Path= of {}: it does not exist",
+                    dir.display(),
+                    app.desktop_file.display()
+                );
+            }
+            exists
+        });
+
+        let mut env = self.gpu_environment(app);
+        if let Some(settings) = self.app_override(app) {
+            env.extend(settings.env.clone());
+        }
+        let mut options = ProcessOptions {
+            working_dir,
+            env,
+            env_remove: self.scrubbed_variables(std::env::vars_os().map(|(name, _)| name)),
+        };
+
+        // A piped target reaches the handler on stdin, so no path is substituted.
+        let targets = match stdin_file {
+            Some(_) => &[][..],
+            None => std::slice::from_ref(target),
+        };
+        let prepared_command = self.build_command(app, targets, launcher, mime_type)?;
+        if matches!(
+            disposition,
+            LaunchDisposition::Detached | LaunchDisposition::Wait(_)
+        ) {
+            startup_notification(
+                app,
+                &prepared_command,
+                std::env::var("XDG_ACTIVATION_TOKEN").ok(),
+                &mut options,
+            );
+        }
+        match (disposition, stdin_file) {
+            (LaunchDisposition::Piped, Some(file)) => {
+                Self::run_piped(prepared_command, file, &options)
+            }
+            (LaunchDisposition::InheritTerminal, _) => {
+                Self::exec_in_place(prepared_command, target, &options)
+            }
+            (LaunchDisposition::Wait(timeout), _) => {
+                Self::run_and_wait(prepared_command, target, &options, timeout)
+            }
+            _ => Self::spawn_detached(prepared_command, target, &options),
+        }
+    }
+
+    /// The argv `execute` would spawn, with the terminal launcher and `app_launch_prefix`
+    /// applied, or the `gdbus` call for a D-Bus activated launch; nothing is run.
+    /// `execute` does not call this; both pick the launcher and targets the same way and
+    /// hand them to `build_command`.
+    pub fn command_for(
+        &self,
+        app: &ApplicationEntry,
+        target: &LaunchTarget,
+        mime_type: Option<&str>,
+        terminal_launcher: Option<&TerminalLauncher>,
+        disposition: LaunchDisposition,
+    ) -> Result<Vec<String>> {
+        if self.uses_dbus_activation(app, terminal_launcher, disposition) {
+            match dbus::open_command(&app.desktop_file, target) {
+                Ok(command) => return Ok(command),
+                Err(err) => info!("{} will use Exec: {err:#}", app.name),
+            }
+        }
+        let launcher = Self::launcher_for(terminal_launcher, disposition)?;
+        let targets = match disposition {
+            LaunchDisposition::Piped => &[][..],
+            _ => std::slice::from_ref(target),
+        };
+        self.build_command(app, targets, launcher, mime_type)
+    }
+
+    fn launcher_for(
+        terminal_launcher: Option<&TerminalLauncher>,
+        disposition: LaunchDisposition,
+    ) -> Result<Option<TerminalLauncher>> {
+        match disposition {
+            LaunchDisposition::Detached | LaunchDisposition::Wait(_) => {
+                Ok(terminal_launcher.cloned())
+            }
+            LaunchDisposition::InheritTerminal | LaunchDisposition::Piped => {
                 if terminal_launcher.is_some() {
                     anyhow::bail!(
                         "Terminal launcher cannot be used when inheriting the current terminal"
                     );
                 }
-                None
+                Ok(None)
             }
+        }
+    }
+
+    /// Only plain detached launches of the main entry go over D-Bus; actions, terminals,
+    /// piped input and an `[applications."<id>"]` `launch_prefix` or `env` need the `Exec`
+    /// line, since the activated process would not see them.
+    fn uses_dbus_activation(
+        &self,
+        app: &ApplicationEntry,
+        terminal_launcher: Option<&TerminalLauncher>,
+        disposition: LaunchDisposition,
+    ) -> bool {
+        let overridden = self
+            .app_override(app)
+            .is_some_and(|settings| settings.launch_prefix.is_some() || !settings.env.is_empty());
+        self.dbus_activation
+            && app.dbus_activatable
+            && app.action_id.is_none()
+            && terminal_launcher.is_none()
+            && disposition == LaunchDisposition::Detached
+            && !overridden
+    }
+
+    fn gpu_environment(&self, app: &ApplicationEntry) -> Vec<(String, String)> {
+        let preference = match self.gpu_override {
+            Some(preference) => preference,
+            None if app.prefers_non_default_gpu => GpuPreference::Discrete,
+            None => return Vec::new(),
         };
 
-        let prepared_command = self.build_command(app, target, launcher)?;
-        match disposition {
-            LaunchDisposition::Detached => Self::spawn_detached(prepared_command, target),
-            LaunchDisposition::InheritTerminal => Self::exec_in_place(prepared_command, target),
-        }
+        let driver = self.gpu_driver.unwrap_or_else(GpuDriver::detect);
+        let env = self.gpu_settings.environment(preference, driver);
+        info!(
+            "Launching {} on the {} GPU ({} driver): {}",
+            app.name,
+            preference.as_str(),
+            driver.as_str(),
+            env.iter()
+                .map(|(key, value)| format!("{key}={value}"))
+                .collect::<Vec<_>>()
+                .join(" ")
+        );
+        env
     }
 
+    /// Expand a bare Exec string; `%c`, `%i` and `%k` expand to nothing without an entry.
+    #[cfg(test)]
     pub fn prepare_command(exec: &str, target: &LaunchTarget) -> Result<Vec<String>> {
-        let mut parts = Self::base_command_parts(exec)?;
-        parts.push(target.as_command_argument().into_owned());
+        Self::expand_command(exec, std::slice::from_ref(target), None)
+    }
+
+    /// Expand `app.exec`, also filling `%c` (name), `%i` (`--icon <Icon>`) and `%k`
+    /// (desktop file path) from the entry.
+    ///
+    /// `%f`/`%u` receive the first target and `%F`/`%U` every target, at the position of
+    /// the field code. Only the first file field code is honoured; when none is present the
+    /// targets are appended to the end of the command.
+    pub fn prepare_app_command(
+        app: &ApplicationEntry,
+        targets: &[LaunchTarget],
+    ) -> Result<Vec<String>> {
+        Self::expand_command(&app.exec, targets, Some(app))
+    }
+
+    fn expand_command(
+        exec: &str,
+        targets: &[LaunchTarget],
+        app: Option<&ApplicationEntry>,
+    ) -> Result<Vec<String>> {
+        Self::base_command_parts(exec)?;
+
+        let (mut parts, substituted) = Self::expand_field_codes(exec, targets, app)?;
+        if !substituted {
+            parts.extend(
+                targets
+                    .iter()
+                    .map(|target| target.as_command_argument().into_owned()),
+            );
+        }
         Ok(parts)
     }
 
+    /// Whether `exec` has a `%f`, `%F`, `%u` or `%U` field code to receive the targets.
+    pub fn exec_takes_targets(exec: &str) -> bool {
+        Self::expand_field_codes(exec, &[], None).is_ok_and(|(_, substituted)| substituted)
+    }
+
     pub fn base_command_parts(exec: &str) -> Result<Vec<String>> {
+        let (parts, _) = Self::expand_field_codes(exec, &[], None)?;
+
+        if parts.is_empty() {
+            Err(anyhow::anyhow!("Empty exec command"))
+        } else {
+            Ok(parts)
+        }
+    }
+
+    /// Returns the expanded arguments and whether a file field code consumed the targets.
+    fn expand_field_codes(
+        exec: &str,
+        targets: &[LaunchTarget],
+        app: Option<&ApplicationEntry>,
+    ) -> Result<(Vec<String>, bool)> {
         let raw_parts = shell_words::split(exec)
             .map_err(|e| anyhow::anyhow!("Failed to parse exec command: {e}"))?;
 
-        let mut parts = Vec::with_capacity(raw_parts.len());
+        let name = app.map(|app| app.name.as_str());
+        let icon = app
+            .and_then(|app| app.icon.as_deref())
+            .filter(|icon| !icon.is_empty());
+        let desktop_file = app.map(|app| app.desktop_file.to_string_lossy());
+
+        let mut parts = Vec::with_capacity(raw_parts.len() + targets.len());
+        let mut substituted = false;
+
         for part in raw_parts {
-            let mut cleaned = part.replace("%%", "%");
-            for placeholder in ["%u", "%U", "%f", "%F", "%i", "%c", "%k"] {
-                cleaned = cleaned.replace(placeholder, "");
+            if part == "%i" {
+                if let Some(icon) = icon {
+                    parts.push("--icon".to_string());
+                    parts.push(icon.to_string());
+                }
+                continue;
             }
 
-            if cleaned.trim().is_empty() {
+            if part == "%F" || part == "%U" {
+                if !substituted {
+                    substituted = true;
+                    parts.extend(
+                        targets
+                            .iter()
+                            .map(|target| target.as_command_argument().into_owned()),
+                    );
+                }
                 continue;
             }
 
-            parts.push(cleaned);
-        }
+            let mut expanded = String::with_capacity(part.len());
+            let mut chars = part.chars();
+            while let Some(ch) = chars.next() {
+                if ch != '%' {
+                    expanded.push(ch);
+                    continue;
+                }
 
-        if parts.is_empty() {
-            Err(anyhow::anyhow!("Empty exec command"))
-        } else {
-            Ok(parts)
+                match chars.next() {
+                    Some('%') => expanded.push('%'),
+                    Some('f' | 'u' | 'F' | 'U') => {
+                        if !substituted {
+                            substituted = true;
+                            if let Some(target) = targets.first() {
+                                expanded.push_str(&target.as_command_argument());
+                            }
+                        }
+                    }
+                    Some('c') => expanded.push_str(name.unwrap_or_default()),
+                    Some('i') => expanded.push_str(icon.unwrap_or_default()),
+                    Some('k') => expanded.push_str(desktop_file.as_deref().unwrap_or_default()),
+                    // Deprecated field codes expand to nothing.
+                    Some('d' | 'D' | 'n' | 'N' | 'v' | 'm') => {}
+                    Some(other) => {
+                        expanded.push('%');
+                        expanded.push(other);
+                    }
+                    None => expanded.push('%'),
+                }
+            }
+
+            if expanded.trim().is_empty() {
+                continue;
+            }
+
+            parts.push(expanded);
         }
+
+        Ok((parts, substituted))
     }
 
+    /// The full argv for `app`: terminal launcher, then the launch prefix for `app` and
+    /// `mime_type`.
     fn build_command(
         &self,
         app: &ApplicationEntry,
-        target: &LaunchTarget,
-        terminal_launcher: Option<Vec<String>>,
+        targets: &[LaunchTarget],
+        terminal_launcher: Option<TerminalLauncher>,
+        mime_type: Option<&str>,
     ) -> Result<Vec<String>> {
-        let mut command_parts = Self::prepare_command(&app.exec, target)?;
+        let mut command_parts = Self::prepare_app_command(app, targets)?;
+        if let Some(program) = command_parts.first_mut() {
+            if let Some(resolved) = resolve_relative_program(program, &app.desktop_file) {
+                *program = resolved;
+            }
+        }
 
-        if let Some(mut launcher_parts) = terminal_launcher {
-            if let Some(args) = &self.terminal_exec_args {
+        if let Some(launcher) = terminal_launcher {
+            let mut launcher_parts = launcher.command;
+            if let Some(args) = self
+                .terminal_exec_args
+                .as_ref()
+                .filter(|_| launcher.exec_args)
+            {
                 if !args.is_empty() {
                     let exec_args = shell_words::split(args).map_err(|e| {
                         anyhow::anyhow!("Failed to parse terminal exec args `{}`: {e}", args)
@@ -129,7 +579,7 @@ impl ApplicationExecutor {
             command_parts = launcher_parts;
         }
 
-        if let Some(prefix) = &self.app_launch_prefix {
+        if let Some(prefix) = self.launch_prefix(app, mime_type) {
             let mut prefix_parts = shell_words::split(prefix).map_err(|e| {
                 anyhow::anyhow!("Failed to parse app launch prefix `{}`: {e}", prefix)
             })?;
@@ -149,7 +599,38 @@ impl ApplicationExecutor {
         Ok(command_parts)
     }
 
-    fn spawn_detached(command_parts: Vec<String>, target: &LaunchTarget) -> Result<()> {
+    /// The application's own `launch_prefix` wins. Otherwise the most specific
+    /// `per_mime_prefix` pattern matching `mime_type` does, and without a match
+    /// `app_launch_prefix` applies. An empty override prefix means none.
+    fn launch_prefix(&self, app: &ApplicationEntry, mime_type: Option<&str>) -> Option<&str> {
+        if let Some(prefix) = self
+            .app_override(app)
+            .and_then(|settings| settings.launch_prefix.as_deref())
+        {
+            return Some(prefix.trim()).filter(|prefix| !prefix.is_empty());
+        }
+
+        let per_mime = mime_type.and_then(|mime| {
+            self.per_mime_prefix
+                .iter()
+                .filter(|(pattern, _)| mime_pattern::matches(pattern, mime))
+                .max_by(|(a, _), (b, _)| {
+                    pattern_specificity(a)
+                        .cmp(&pattern_specificity(b))
+                        .then_with(|| b.cmp(a))
+                })
+        });
+        match per_mime {
+            Some((_, prefix)) => Some(prefix.trim()).filter(|prefix| !prefix.is_empty()),
+            None => self.app_launch_prefix.as_deref(),
+        }
+    }
+
+    fn spawn_detached(
+        command_parts: Vec<String>,
+        target: &LaunchTarget,
+        options: &ProcessOptions,
+    ) -> Result<()> {
         if command_parts.is_empty() {
             return Err(anyhow::anyhow!("Empty command"));
         }
@@ -167,6 +648,8 @@ impl ApplicationExecutor {
             cmd.arg(part);
         }
 
+        options.apply(&mut cmd);
+
         // Detach from parent process
         unsafe {
             cmd.pre_exec(|| {
@@ -184,7 +667,90 @@ impl ApplicationExecutor {
         Ok(())
     }
 
-    fn exec_in_place(command_parts: Vec<String>, target: &LaunchTarget) -> Result<()> {
+    fn run_piped(command_parts: Vec<String>, file: &Path, options: &ProcessOptions) -> Result<()> {
+        if command_parts.is_empty() {
+            return Err(anyhow::anyhow!("Empty command"));
+        }
+
+        info!(
+            "Running: {} < \"{}\"",
+            command_parts.join(" "),
+            file.display()
+        );
+
+        let input = std::fs::File::open(file)
+            .with_context(|| format!("Failed to open {} for piping", file.display()))?;
+
+        let mut cmd = Command::new(&command_parts[0]);
+        cmd.args(&command_parts[1..]);
+        options.apply(&mut cmd);
+
+        let status = cmd
+            .stdin(Stdio::from(input))
+            .status()
+            .context("Failed to execute application")?;
+        if !status.success() {
+            anyhow::bail!("`{}` exited with {status}", command_parts[0]);
+        }
+
+        Ok(())
+    }
+
+    /// Run in the foreground and wait. The child leads its own process group, without
+    /// `setsid`'s detaching, so a timeout can take down everything it spawned.
+    fn run_and_wait(
+        command_parts: Vec<String>,
+        target: &LaunchTarget,
+        options: &ProcessOptions,
+        timeout: Option<Duration>,
+    ) -> Result<()> {
+        if command_parts.is_empty() {
+            return Err(anyhow::anyhow!("Empty command"));
+        }
+
+        info!(
+            "Running and waiting: {} \"{}\"",
+            command_parts.join(" "),
+            target.as_command_argument()
+        );
+
+        let mut cmd = Command::new(&command_parts[0]);
+        cmd.args(&command_parts[1..]);
+        options.apply(&mut cmd);
+
+        let mut child = cmd
+            .process_group(0)
+            .stdin(Stdio::null())
+            .spawn()
+            .context("Failed to execute application")?;
+
+        let status = match timeout {
+            None => child.wait().context("Failed to wait for application")?,
+            Some(timeout) => match wait_until(&mut child, Instant::now() + timeout)? {
+                Some(status) => status,
+                None => {
+                    let pgid = kill_process_group(&mut child)?;
+                    return Err(WaitTimedOut {
+                        program: command_parts[0].clone(),
+                        timeout,
+                        pgid,
+                    }
+                    .into());
+                }
+            },
+        };
+        if !status.success() {
+            anyhow::bail!("`{}` exited with {status}", command_parts[0]);
+        }
+
+        Ok(())
+    }
+
+    fn exec_in_place(
+        command_parts: Vec<String>,
+        target: &LaunchTarget,
+        options: &ProcessOptions,
+    ) -> Result<()> {
         if command_parts.is_empty() {
             return Err(anyhow::anyhow!("Empty command"));
         }
@@ -200,11 +766,133 @@ impl ApplicationExecutor {
             cmd.arg(part);
         }
 
+        options.apply(&mut cmd);
+
         let err = cmd.exec();
+        // `exec` resets SIGPIPE to its default before replacing the process; when it fails
+        // we keep running, so restore the ignored disposition the Rust runtime started with.
+        // SAFETY: installs the built-in SIG_IGN disposition, no handler code runs.
+        let _ = unsafe {
+            nix::sys::signal::signal(
+                nix::sys::signal::Signal::SIGPIPE,
+                nix::sys::signal::SigHandler::SigIgn,
+            )
+        };
         Err(anyhow::anyhow!("Failed to exec application: {err}"))
     }
 }
 
+/// Exact types beat wildcards; among wildcards, more literal characters are more specific,
+/// so `application/vnd.*` beats `application/*`, which beats `*/*`.
+fn pattern_specificity(pattern: &str) -> (bool, usize) {
+    let wildcard = |c: char| c == '*' || c == '?';
+    (
+        !pattern.contains(wildcard),
+        pattern.chars().filter(|&c| !wildcard(c)).count(),
+    )
+}
+
+/// Poll `child` until it exits or `deadline` passes; `None` means it is still running.
+fn wait_until(child: &mut Child, deadline: Instant) -> Result<Option<std::process::ExitStatus>> {
+    loop {
+        if let Some(status) = child.try_wait().context("Failed to wait for application")? {
+            return Ok(Some(status));
+        }
+        if Instant::now() >= deadline {
+            return Ok(None);
+        }
+        std::thread::sleep(WAIT_POLL_INTERVAL);
+    }
+}
+
+/// SIGTERM the group `child` leads, then SIGKILL whatever is left after the grace period
+/// so that no process it forked outlives the timeout. Returns the group ID.
+fn kill_process_group(child: &mut Child) -> Result<i32> {
+    use nix::sys::signal::{killpg, Signal};
+    use nix::unistd::Pid;
+
+    let pgid = Pid::from_raw(child.id() as i32);
+    info!("Timed out; sending SIGTERM to process group {pgid}");
+    // ESRCH only means the group is already gone.
+    let _ = killpg(pgid, Signal::SIGTERM);
+    if wait_until(child, Instant::now() + KILL_GRACE_PERIOD)?.is_none() {
+        info!("Process group {pgid} survived SIGTERM; sending SIGKILL");
+    }
+    let _ = killpg(pgid, Signal::SIGKILL);
+    child.wait().context("Failed to reap application")?;
+    Ok(pgid.as_raw())
+}
+
+/// `Exec=./AppRun` and similar paths are relative to the desktop file, as AppImage
+/// integrations ship them; when no such sibling exists the path is left to the cwd.
+fn resolve_relative_program(program: &str, desktop_file: &Path) -> Option<String> {
+    let path = Path::new(program);
+    if !program.contains('/') || path.is_absolute() || !desktop_file.is_absolute() {
+        return None;
+    }
+
+    let relative: PathBuf = path
+        .components()
+        .filter(|component| !matches!(component, Component::CurDir))
+        .collect();
+    let candidate = desktop_file.parent()?.join(relative);
+    if !candidate.is_file() {
+        return None;
+    }
+    let resolved = candidate.to_string_lossy().into_owned();
+    info!(
+        "Resolved `{program}` against {} to {resolved}",
+        desktop_file.display()
+    );
+    Some(resolved)
+}
+
+/// Export a fresh `DESKTOP_STARTUP_ID`, and pass our own `XDG_ACTIVATION_TOKEN` through,
+/// when the entry asks for startup notification; otherwise strip both so the child
+/// cannot consume a token meant for someone else.
+fn startup_notification(
+    app: &ApplicationEntry,
+    command_parts: &[String],
+    activation_token: Option<String>,
+    options: &mut ProcessOptions,
+) {
+    if !app.startup_notify {
+        options
+            .env_remove
+            .extend(STARTUP_VARIABLES.iter().map(OsString::from));
+        return;
+    }
+
+    let startup_id = startup_id(command_parts);
+    info!("Startup notification for {}: {startup_id}", app.name);
+    options
+        .env
+        .push(("DESKTOP_STARTUP_ID".to_string(), startup_id));
+    if let Some(token) = activation_token.filter(|token| !token.is_empty()) {
+        options
+            .env
+            .push(("XDG_ACTIVATION_TOKEN".to_string(), token));
+    }
+}
+
+/// `openit-<pid>-<binary>-<nanos>_TIME<millis>`, following the startup-notification spec.
+fn startup_id(command_parts: &[String]) -> String {
+    let binary = command_parts
+        .first()
+        .and_then(|program| Path::new(program).file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    format!(
+        "openit-{}-{binary}-{}_TIME{}",
+        std::process::id(),
+        now.subsec_nanos(),
+        now.as_millis()
+    )
+}
+
 impl Default for ApplicationExecutor {
     fn default() -> Self {
         Self::new()
@@ -214,7 +902,10 @@ impl Default for ApplicationExecutor {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::path::PathBuf;
+    use crate::application_finder::{ApplicationSource, Association};
+    use crate::test_support::{wait_for_contents, EnvVarGuard, LAUNCH_TIMEOUT};
+    use serial_test::serial;
+    use std::collections::BTreeMap;
     use url::Url;
 
     fn create_test_application(exec: &str) -> ApplicationEntry {
@@ -230,6 +921,20 @@ mod tests {
             action_id: None,
             requires_terminal: false,
             is_terminal_emulator: false,
+            working_dir: None,
+            prefers_non_default_gpu: false,
+            stdin_input: false,
+            association: Association::None,
+            parent_mime: None,
+            startup_notify: false,
+            confirm: false,
+            score: 0.0,
+            dbus_activatable: false,
+            startup_wm_class: None,
+            single_main_window: false,
+            generic_name: None,
+            keywords: Vec::new(),
+            source: ApplicationSource::Available,
         }
     }
 
@@ -271,6 +976,7 @@ mod tests {
             ("app %c", vec!["app", "/home/user/test.txt"]),
             ("app %k", vec!["app", "/home/user/test.txt"]),
             ("app %%", vec!["app", "%", "/home/user/test.txt"]),
+            ("app %d %D %n %N %v %m", vec!["app", "/home/user/test.txt"]),
         ];
 
         for (input, expected) in test_cases {
@@ -321,10 +1027,196 @@ mod tests {
         assert_eq!(result, vec!["simple-editor", "/home/user/test.txt"]);
     }
 
+    #[test]
+    fn test_prepare_command_substitutes_in_place() {
+        let target = LaunchTarget::File(PathBuf::from("/home/user/test.txt"));
+        let result =
+            ApplicationExecutor::prepare_command("wrapper --files %F --flag", &target).unwrap();
+
+        assert_eq!(
+            result,
+            vec!["wrapper", "--files", "/home/user/test.txt", "--flag"]
+        );
+    }
+
+    #[test]
+    fn test_prepare_command_mid_argument_field_code() {
+        let target = LaunchTarget::File(PathBuf::from("/home/user/My File.txt"));
+        let result =
+            ApplicationExecutor::prepare_command("viewer --open=%f --quiet", &target).unwrap();
+
+        assert_eq!(
+            result,
+            vec!["viewer", "--open=/home/user/My File.txt", "--quiet"]
+        );
+
+        let uri = LaunchTarget::Uri(Url::parse("https://example.com/page").unwrap());
+        let result = ApplicationExecutor::prepare_command("browser --url=%u", &uri).unwrap();
+        assert_eq!(result, vec!["browser", "--url=https://example.com/page"]);
+    }
+
+    #[test]
+    fn test_prepare_command_only_first_field_code_honoured() {
+        let target = LaunchTarget::File(PathBuf::from("/tmp/a.txt"));
+        let result = ApplicationExecutor::prepare_command("app %U --x=%f %F", &target).unwrap();
+
+        assert_eq!(result, vec!["app", "/tmp/a.txt", "--x="]);
+    }
+
+    #[test]
+    fn test_prepare_command_for_multiple_targets() {
+        let targets = vec![
+            LaunchTarget::File(PathBuf::from("/tmp/a.txt")),
+            LaunchTarget::File(PathBuf::from("/tmp/b.txt")),
+        ];
+
+        let prepare = |exec: &str| {
+            ApplicationExecutor::prepare_app_command(&create_test_application(exec), &targets)
+                .unwrap()
+        };
+
+        assert_eq!(
+            prepare("app %F --flag"),
+            vec!["app", "/tmp/a.txt", "/tmp/b.txt", "--flag"]
+        );
+        assert_eq!(prepare("app %f"), vec!["app", "/tmp/a.txt"]);
+        assert_eq!(prepare("app"), vec!["app", "/tmp/a.txt", "/tmp/b.txt"]);
+    }
+
+    #[test]
+    fn test_prepare_app_command_expands_entry_codes() {
+        let target = LaunchTarget::File(PathBuf::from("/tmp/a.txt"));
+        let mut app = create_test_application("wine-launcher %i --title %c --desktop=%k %f");
+        app.name = "My Game".to_string();
+
+        let result = ApplicationExecutor::prepare_app_command(&app, &[target]).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                "wine-launcher",
+                "--icon",
+                "testapp-icon",
+                "--title",
+                "My Game",
+                "--desktop=/usr/share/applications/testapp.desktop",
+                "/tmp/a.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_prepare_app_command_without_icon() {
+        let target = LaunchTarget::File(PathBuf::from("/tmp/a.txt"));
+        let mut app = create_test_application("viewer %i %k %u");
+        app.icon = None;
+
+        let result = ApplicationExecutor::prepare_app_command(&app, &[target]).unwrap();
+        assert_eq!(
+            result,
+            vec![
+                "viewer",
+                "/usr/share/applications/testapp.desktop",
+                "/tmp/a.txt"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_build_command_expands_entry_codes() {
+        let target = LaunchTarget::File(PathBuf::from("/tmp/a.txt"));
+        let executor = ApplicationExecutor::new();
+        let app = create_test_application("app %c %f");
+
+        let result = executor
+            .build_command(&app, std::slice::from_ref(&target), None, None)
+            .unwrap();
+        assert_eq!(result, vec!["app", "Test App", "/tmp/a.txt"]);
+    }
+
+    #[test]
+    fn test_build_command_resolves_exec_relative_to_desktop_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let bundle = temp_dir.path().join("bundle");
+        std::fs::create_dir_all(bundle.join("bin")).unwrap();
+        std::fs::write(bundle.join("AppRun"), "#!/bin/sh\n").unwrap();
+        std::fs::write(bundle.join("bin/tool"), "#!/bin/sh\n").unwrap();
+
+        let executor = ApplicationExecutor::new();
+        let target = LaunchTarget::File(PathBuf::from("/tmp/a.txt"));
+        let mut app = create_test_application("./AppRun %f");
+        app.desktop_file = bundle.join("app.desktop");
+
+        let result = executor
+            .build_command(&app, std::slice::from_ref(&target), None, None)
+            .unwrap();
+        assert_eq!(
+            result,
+            vec![
+                bundle.join("AppRun").to_string_lossy().into_owned(),
+                "/tmp/a.txt".to_string()
+            ]
+        );
+
+        app.exec = "bin/tool".to_string();
+        let result = executor.build_command(&app, &[], None, None).unwrap();
+        assert_eq!(
+            result,
+            vec![bundle.join("bin/tool").to_string_lossy().into_owned()]
+        );
+
+        // No sibling: left for the cwd; bare names still go through PATH.
+        app.exec = "./missing %f".to_string();
+        let result = executor.build_command(&app, &[], None, None).unwrap();
+        assert_eq!(result, vec!["./missing"]);
+        app.exec = "AppRun".to_string();
+        let result = executor.build_command(&app, &[], None, None).unwrap();
+        assert_eq!(result, vec!["AppRun"]);
+    }
+
+    #[test]
+    fn test_execute_spawns_script_next_to_desktop_file() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let marker_path = temp_dir.path().join("ran.txt");
+        let script = temp_dir.path().join("AppRun");
+        std::fs::write(
+            &script,
+            format!("#!/bin/sh\necho \"$0\" > {}\n", marker_path.display()),
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .unwrap();
+
+        let mut app = create_test_application("./AppRun %f");
+        app.desktop_file = temp_dir.path().join("app.desktop");
+        let target = LaunchTarget::File(temp_dir.path().join("input.txt"));
+        ApplicationExecutor::new()
+            .execute(&app, &target, None, None, LaunchDisposition::Detached)
+            .unwrap();
+
+        assert_eq!(
+            wait_for_contents(&marker_path, LAUNCH_TIMEOUT).trim(),
+            script.to_string_lossy()
+        );
+    }
+
+    #[test]
+    fn test_base_command_parts_drops_field_codes() {
+        let parts = ApplicationExecutor::base_command_parts("term --opt=%f %U -x").unwrap();
+        assert_eq!(parts, vec!["term", "--opt=", "-x"]);
+    }
+
     #[test]
     fn test_spawn_detached_empty_command() {
         let target = LaunchTarget::File(PathBuf::from("test.txt"));
-        let result = ApplicationExecutor::spawn_detached(vec![], &target);
+        let result = ApplicationExecutor::spawn_detached(
+            vec![],
+            &target,
+            &ProcessOptions {
+                working_dir: None,
+                env: Vec::new(),
+                env_remove: Vec::new(),
+            },
+        );
         assert!(result.is_err());
         assert_eq!(result.unwrap_err().to_string(), "Empty command");
     }
@@ -335,7 +1227,7 @@ mod tests {
         let target = LaunchTarget::File(PathBuf::from("/home/user/test.txt"));
 
         let executor = ApplicationExecutor::new();
-        let result = executor.execute(&app, &target, None, LaunchDisposition::Detached);
+        let result = executor.execute(&app, &target, None, None, LaunchDisposition::Detached);
         assert!(result.is_err());
         assert_eq!(result.unwrap_err().to_string(), "Empty exec command");
     }
@@ -345,12 +1237,13 @@ mod tests {
         let app = create_test_application("echo %f");
         let target = LaunchTarget::File(PathBuf::from("/home/user/test.txt"));
         let executor = ApplicationExecutor::new();
-        let launcher = vec!["kitty".to_string()];
+        let launcher = TerminalLauncher::new(vec!["kitty".to_string()]);
 
         let result = executor.execute(
             &app,
             &target,
-            Some(launcher.as_slice()),
+            None,
+            Some(&launcher),
             LaunchDisposition::InheritTerminal,
         );
 
@@ -368,13 +1261,30 @@ mod tests {
         let target = LaunchTarget::File(PathBuf::from("/home/user/test.txt"));
         let executor = ApplicationExecutor::new();
 
-        let result = executor.execute(&app, &target, None, LaunchDisposition::InheritTerminal);
+        let result = executor.execute(
+            &app,
+            &target,
+            None,
+            None,
+            LaunchDisposition::InheritTerminal,
+        );
 
         assert!(result.is_err());
         assert!(result
             .unwrap_err()
             .to_string()
             .contains("Failed to exec application"));
+
+        // A selector that exits early must not kill us with SIGPIPE after a failed exec.
+        // SAFETY: installs the built-in SIG_IGN disposition, no handler code runs.
+        let previous = unsafe {
+            nix::sys::signal::signal(
+                nix::sys::signal::Signal::SIGPIPE,
+                nix::sys::signal::SigHandler::SigIgn,
+            )
+        }
+        .unwrap();
+        assert_eq!(previous, nix::sys::signal::SigHandler::SigIgn);
     }
 
     #[test]
@@ -415,9 +1325,9 @@ mod tests {
         let result = ApplicationExecutor::prepare_command("app", &target).unwrap();
         assert_eq!(result, vec!["app", "/test.txt"]);
 
-        // Test with escaped percent
+        // Test with escaped percent: `%%f` is a literal `%f`, not a field code
         let result = ApplicationExecutor::prepare_command("app %%f", &target).unwrap();
-        assert_eq!(result, vec!["app", "/test.txt"]);
+        assert_eq!(result, vec!["app", "%f", "/test.txt"]);
     }
 
     #[test]
@@ -453,7 +1363,9 @@ mod tests {
         let executor = ApplicationExecutor::with_launch_prefix(Some("flatpak run".into()));
 
         let app = create_test_application("code %f");
-        let result = executor.build_command(&app, &target, None).unwrap();
+        let result = executor
+            .build_command(&app, std::slice::from_ref(&target), None, None)
+            .unwrap();
 
         assert_eq!(
             result,
@@ -467,18 +1379,224 @@ mod tests {
         let executor = ApplicationExecutor::with_launch_prefix(Some("   ".into()));
 
         let app = create_test_application("app %f");
-        let result = executor.build_command(&app, &target, None).unwrap();
+        let result = executor
+            .build_command(&app, std::slice::from_ref(&target), None, None)
+            .unwrap();
 
         assert_eq!(result, vec!["app", "/home/user/test.txt"]);
     }
 
+    #[test]
+    fn test_build_command_picks_most_specific_mime_prefix() {
+        let target = LaunchTarget::File(PathBuf::from("/home/user/doc"));
+        let executor = ApplicationExecutor::with_launch_prefix(Some("uwsm app --".into()))
+            .with_per_mime_prefix(HashMap::from([
+                ("*/*".to_string(), "nice".to_string()),
+                ("application/*".to_string(), "firejail".to_string()),
+                ("application/pdf".to_string(), "flatpak run".to_string()),
+                ("text/*".to_string(), "".to_string()),
+            ]));
+        let app = create_test_application("app %f");
+        let prefix_for = |mime: Option<&str>| {
+            let command = executor
+                .build_command(&app, std::slice::from_ref(&target), None, mime)
+                .unwrap();
+            command[..command.len() - 2].join(" ")
+        };
+
+        assert_eq!(prefix_for(Some("application/pdf")), "flatpak run");
+        assert_eq!(prefix_for(Some("APPLICATION/PDF")), "flatpak run");
+        assert_eq!(prefix_for(Some("application/epub+zip")), "firejail");
+        assert_eq!(prefix_for(Some("image/png")), "nice");
+        // An empty prefix runs natively instead of falling back to `app_launch_prefix`.
+        assert_eq!(prefix_for(Some("text/plain")), "");
+        assert_eq!(prefix_for(None), "uwsm app --");
+    }
+
+    #[test]
+    fn test_app_override_prefix_beats_global_and_per_mime_prefixes() {
+        let target = LaunchTarget::File(PathBuf::from("/home/user/doc.pdf"));
+        let overrides = |prefix: &str| {
+            HashMap::from([(
+                "testapp.desktop".to_string(),
+                ApplicationOverride {
+                    launch_prefix: Some(prefix.to_string()),
+                    ..ApplicationOverride::default()
+                },
+            )])
+        };
+        let executor = ApplicationExecutor::with_launch_prefix(Some("uwsm app --".into()))
+            .with_per_mime_prefix(HashMap::from([(
+                "application/pdf".to_string(),
+                "firejail".to_string(),
+            )]));
+        let app = create_test_application("app %f");
+        let mut other = create_test_application("app %f");
+        other.desktop_file = PathBuf::from("/usr/share/applications/other.desktop");
+
+        let executor = executor.with_app_overrides(overrides("flatpak run org.example.App"));
+        let command = |app: &ApplicationEntry| {
+            executor
+                .build_command(
+                    app,
+                    std::slice::from_ref(&target),
+                    None,
+                    Some("application/pdf"),
+                )
+                .unwrap()
+                .join(" ")
+        };
+        assert_eq!(
+            command(&app),
+            "flatpak run org.example.App app /home/user/doc.pdf"
+        );
+        assert_eq!(command(&other), "firejail app /home/user/doc.pdf");
+
+        let executor = ApplicationExecutor::with_launch_prefix(Some("uwsm app --".into()))
+            .with_app_overrides(overrides("  "));
+        assert_eq!(
+            executor
+                .build_command(&app, std::slice::from_ref(&target), None, None)
+                .unwrap(),
+            vec!["app", "/home/user/doc.pdf"]
+        );
+    }
+
+    #[test]
+    fn test_app_override_env_reaches_the_process() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dump = temp_dir.path().join("env.txt");
+        let script = temp_dir.path().join("dump-env");
+        std::fs::write(&script, format!("#!/bin/sh\nenv > {}\n", dump.display())).unwrap();
+        std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .unwrap();
+
+        let app = create_test_application(&format!("{} %f", script.display()));
+        let executor = ApplicationExecutor::new().with_app_overrides(HashMap::from([(
+            "testapp.desktop".to_string(),
+            ApplicationOverride {
+                env: BTreeMap::from([(
+                    "ELECTRON_OZONE_PLATFORM_HINT".to_string(),
+                    "wayland".to_string(),
+                )]),
+                ..ApplicationOverride::default()
+            },
+        )]));
+        let target = LaunchTarget::File(temp_dir.path().join("input.txt"));
+        executor
+            .execute(&app, &target, None, None, LaunchDisposition::Wait(None))
+            .unwrap();
+
+        let env = std::fs::read_to_string(&dump).unwrap();
+        assert!(env
+            .lines()
+            .any(|line| line == "ELECTRON_OZONE_PLATFORM_HINT=wayland"));
+    }
+
+    /// Run `app` through an env-dumping script with `policy` and extra inherited variables
+    /// set, and return the child's environment as `NAME=value` lines.
+    fn launched_env(policy: LaunchEnvPolicy) -> Vec<String> {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let dump = temp_dir.path().join("env.txt");
+        let script = temp_dir.path().join("dump-env");
+        std::fs::write(&script, format!("#!/bin/sh\nenv > {}\n", dump.display())).unwrap();
+        std::fs::set_permissions(&script, std::os::unix::fs::PermissionsExt::from_mode(0o755))
+            .unwrap();
+
+        let inherited = [
+            ("OPENIT_SCRUB_TEST_DROP", "1"),
+            ("OPENIT_SCRUB_TEST_KEEP", "1"),
+            ("OPENIT_TARGET", "/stale/target"),
+        ];
+        let _guards: Vec<EnvVarGuard> = inherited
+            .iter()
+            .map(|(key, value)| EnvVarGuard::set(key, value))
+            .collect();
+
+        let app = create_test_application(&format!("/bin/sh {} %f", script.display()));
+        let executor = ApplicationExecutor::new()
+            .with_env_policy(policy)
+            .with_app_overrides(HashMap::from([(
+                "testapp.desktop".to_string(),
+                ApplicationOverride {
+                    env: BTreeMap::from([("OPENIT_SCRUB_TEST_ADDED".to_string(), "1".to_string())]),
+                    ..ApplicationOverride::default()
+                },
+            )]));
+        let target = LaunchTarget::File(temp_dir.path().join("input.txt"));
+        executor
+            .execute(&app, &target, None, None, LaunchDisposition::Wait(None))
+            .unwrap();
+
+        std::fs::read_to_string(&dump)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    fn has(env: &[String], key: &str) -> bool {
+        env.iter().any(|line| line.starts_with(&format!("{key}=")))
+    }
+
+    #[test]
+    #[serial]
+    fn test_env_policy_unset_removes_listed_variables() {
+        let env = launched_env(LaunchEnvPolicy {
+            unset: vec!["OPENIT_SCRUB_TEST_D*".to_string()],
+            keep_only: None,
+        });
+
+        assert!(!has(&env, "OPENIT_SCRUB_TEST_DROP"));
+        assert!(has(&env, "OPENIT_SCRUB_TEST_KEEP"));
+        assert!(has(&env, "OPENIT_SCRUB_TEST_ADDED"));
+        assert!(has(&env, "PATH"));
+        assert!(!has(&env, "OPENIT_TARGET"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_env_policy_keep_only_drops_everything_else() {
+        let env = launched_env(LaunchEnvPolicy {
+            unset: Vec::new(),
+            keep_only: Some(vec![
+                "PATH".to_string(),
+                "OPENIT_SCRUB_TEST_KEEP".to_string(),
+                "OPENIT_TARGET".to_string(),
+            ]),
+        });
+
+        assert!(!has(&env, "OPENIT_SCRUB_TEST_DROP"));
+        assert!(has(&env, "OPENIT_SCRUB_TEST_KEEP"));
+        assert!(has(&env, "OPENIT_SCRUB_TEST_ADDED"));
+        assert!(has(&env, "PATH"));
+        // Internal handoff variables are dropped even when `keep_only` lists them.
+        assert!(!has(&env, "OPENIT_TARGET"));
+    }
+
+    #[test]
+    fn test_app_override_terminal_replaces_entry_key() {
+        let mut app = create_test_application("htop");
+        app.requires_terminal = true;
+        assert!(ApplicationExecutor::new().requires_terminal(&app));
+
+        let executor = ApplicationExecutor::new().with_app_overrides(HashMap::from([(
+            "testapp.desktop".to_string(),
+            ApplicationOverride {
+                terminal: Some(false),
+                ..ApplicationOverride::default()
+            },
+        )]));
+        assert!(!executor.requires_terminal(&app));
+    }
+
     #[test]
     fn test_build_command_invalid_prefix_errors() {
         let target = LaunchTarget::File(PathBuf::from("/home/user/test.txt"));
         let executor = ApplicationExecutor::with_launch_prefix(Some("\"unterminated".into()));
 
         let app = create_test_application("app %f");
-        let result = executor.build_command(&app, &target, None);
+        let result = executor.build_command(&app, std::slice::from_ref(&target), None, None);
 
         assert!(result
             .unwrap_err()
@@ -497,12 +1615,36 @@ mod tests {
         let terminal_launcher = vec!["foot".to_string()];
 
         let result = executor
-            .build_command(&app, &target, Some(terminal_launcher))
+            .build_command(
+                &app,
+                std::slice::from_ref(&target),
+                Some(TerminalLauncher::new(terminal_launcher)),
+                None,
+            )
             .unwrap();
 
         assert_eq!(result, vec!["foot", "-e", "code", "/home/user/test.txt"]);
     }
 
+    #[test]
+    fn test_build_command_launcher_without_exec_args() {
+        let target = LaunchTarget::File(PathBuf::from("/home/user/test.txt"));
+        let executor = ApplicationExecutor::with_options(None, Some("-e".into()));
+
+        let mut app = create_test_application("nvim %f");
+        app.requires_terminal = true;
+
+        let launcher = TerminalLauncher::without_exec_args(vec!["xdg-terminal-exec".to_string()]);
+        let result = executor
+            .build_command(&app, std::slice::from_ref(&target), Some(launcher), None)
+            .unwrap();
+
+        assert_eq!(
+            result,
+            vec!["xdg-terminal-exec", "nvim", "/home/user/test.txt"]
+        );
+    }
+
     #[test]
     fn test_build_command_with_terminal_launcher_no_args() {
         let target = LaunchTarget::File(PathBuf::from("/home/user/test.txt"));
@@ -514,7 +1656,12 @@ mod tests {
         let terminal_launcher = vec!["kitty".to_string(), "--single-instance".to_string()];
 
         let result = executor
-            .build_command(&app, &target, Some(terminal_launcher))
+            .build_command(
+                &app,
+                std::slice::from_ref(&target),
+                Some(TerminalLauncher::new(terminal_launcher)),
+                None,
+            )
             .unwrap();
 
         assert_eq!(
@@ -522,4 +1669,342 @@ mod tests {
             vec!["kitty", "--single-instance", "nvim", "/home/user/test.txt"]
         );
     }
+
+    #[test]
+    fn test_execute_runs_in_path_working_directory() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let work_dir = temp_dir.path().join("work");
+        std::fs::create_dir(&work_dir).unwrap();
+        let marker_path = temp_dir.path().join("pwd.txt");
+
+        let mut app =
+            create_test_application(&format!("sh -c 'pwd > {}' %f", marker_path.display()));
+        app.working_dir = Some(work_dir.clone());
+
+        let target = LaunchTarget::File(temp_dir.path().join("input.txt"));
+        ApplicationExecutor::new()
+            .execute(&app, &target, None, None, LaunchDisposition::Detached)
+            .unwrap();
+
+        let recorded = wait_for_contents(&marker_path, LAUNCH_TIMEOUT);
+        assert_eq!(
+            PathBuf::from(recorded.trim()).canonicalize().unwrap(),
+            work_dir.canonicalize().unwrap()
+        );
+    }
+
+    #[test]
+    #[serial]
+    fn test_dbus_activation_falls_back_to_exec_without_a_bus() {
+        let _bus = EnvVarGuard::set("DBUS_SESSION_BUS_ADDRESS", "unix:path=/nonexistent/bus");
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let marker_path = temp_dir.path().join("fallback.txt");
+
+        let mut app =
+            create_test_application(&format!("sh -c 'echo exec > {}' %f", marker_path.display()));
+        app.desktop_file = PathBuf::from("/usr/share/applications/org.example.Viewer.desktop");
+        app.dbus_activatable = true;
+
+        let target = LaunchTarget::File(temp_dir.path().join("input.txt"));
+        ApplicationExecutor::new()
+            .with_dbus_activation(true)
+            .execute(&app, &target, None, None, LaunchDisposition::Detached)
+            .unwrap();
+
+        assert_eq!(
+            wait_for_contents(&marker_path, LAUNCH_TIMEOUT).trim(),
+            "exec"
+        );
+    }
+
+    #[test]
+    fn test_dbus_activation_skipped_for_actions_and_terminals() {
+        let executor = ApplicationExecutor::new().with_dbus_activation(true);
+        let mut app = create_test_application("viewer %u");
+        app.dbus_activatable = true;
+        assert!(executor.uses_dbus_activation(&app, None, LaunchDisposition::Detached));
+
+        let launcher = TerminalLauncher::new(vec!["foot".to_string()]);
+        assert!(!executor.uses_dbus_activation(&app, Some(&launcher), LaunchDisposition::Detached));
+        assert!(!executor.uses_dbus_activation(&app, None, LaunchDisposition::Piped));
+
+        app.action_id = Some("new-window".to_string());
+        assert!(!executor.uses_dbus_activation(&app, None, LaunchDisposition::Detached));
+
+        app.action_id = None;
+        assert!(!ApplicationExecutor::new().uses_dbus_activation(
+            &app,
+            None,
+            LaunchDisposition::Detached
+        ));
+    }
+
+    #[test]
+    fn test_dbus_activation_yields_to_app_overrides_and_shows_in_dry_run() {
+        let mut app = create_test_application("viewer %u");
+        app.dbus_activatable = true;
+        app.desktop_file = PathBuf::from("/usr/share/applications/org.example.Viewer.desktop");
+        let target = LaunchTarget::Uri(url::Url::parse("https://example.com/").unwrap());
+        let executor = ApplicationExecutor::new().with_dbus_activation(true);
+
+        let command = executor
+            .command_for(&app, &target, None, None, LaunchDisposition::Detached)
+            .unwrap();
+        assert_eq!(command[0], "gdbus");
+        assert!(command.contains(&"org.example.Viewer".to_string()));
+        assert_eq!(command[command.len() - 2], "['https://example.com/']");
+
+        let env_override = ApplicationOverride {
+            env: BTreeMap::from([("GDK_BACKEND".to_string(), "x11".to_string())]),
+            ..ApplicationOverride::default()
+        };
+        let executor = executor.with_app_overrides(HashMap::from([(
+            "org.example.Viewer.desktop".to_string(),
+            env_override,
+        )]));
+        assert!(!executor.uses_dbus_activation(&app, None, LaunchDisposition::Detached));
+        assert_eq!(
+            executor
+                .command_for(&app, &target, None, None, LaunchDisposition::Detached)
+                .unwrap(),
+            vec!["viewer", "https://example.com/"]
+        );
+    }
+
+    #[test]
+    fn test_execute_ignores_missing_path_directory() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let marker_path = temp_dir.path().join("pwd.txt");
+        let mut app =
+            create_test_application(&format!("sh -c 'pwd > {}' %f", marker_path.display()));
+        app.working_dir = Some(temp_dir.path().join("missing"));
+
+        let target = LaunchTarget::File(temp_dir.path().join("input.txt"));
+        ApplicationExecutor::new()
+            .execute(&app, &target, None, None, LaunchDisposition::Wait(None))
+            .unwrap();
+
+        let recorded = std::fs::read_to_string(&marker_path).unwrap();
+        assert_eq!(
+            PathBuf::from(recorded.trim()).canonicalize().unwrap(),
+            std::env::current_dir().unwrap().canonicalize().unwrap()
+        );
+    }
+
+    fn dump_launch_environment(
+        executor: &ApplicationExecutor,
+        app: &mut ApplicationEntry,
+    ) -> String {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let marker_path = temp_dir.path().join("env.txt");
+        app.exec = format!(
+            "sh -c 'env > {}.tmp && mv {0}.tmp {0}' %f",
+            marker_path.display()
+        );
+
+        let target = LaunchTarget::File(temp_dir.path().join("input.txt"));
+        executor
+            .execute(app, &target, None, None, LaunchDisposition::Detached)
+            .unwrap();
+
+        wait_for_contents(&marker_path, LAUNCH_TIMEOUT)
+    }
+
+    #[test]
+    #[serial]
+    fn test_startup_notify_exports_startup_id_and_activation_token() {
+        let _token = EnvVarGuard::set("XDG_ACTIVATION_TOKEN", "token-123");
+        let executor = ApplicationExecutor::new();
+        let mut app = create_test_application("");
+        app.startup_notify = true;
+
+        let env = dump_launch_environment(&executor, &mut app);
+        assert!(env
+            .lines()
+            .any(|line| line.starts_with("DESKTOP_STARTUP_ID=openit-") && line.contains("-sh-")));
+        assert!(env
+            .lines()
+            .any(|line| line == "XDG_ACTIVATION_TOKEN=token-123"));
+    }
+
+    #[test]
+    #[serial]
+    fn test_startup_variables_stripped_without_startup_notify() {
+        let _token = EnvVarGuard::set("XDG_ACTIVATION_TOKEN", "token-123");
+        let _id = EnvVarGuard::set("DESKTOP_STARTUP_ID", "stale");
+        let executor = ApplicationExecutor::new();
+        let mut app = create_test_application("");
+
+        let env = dump_launch_environment(&executor, &mut app);
+        assert!(!env.contains("XDG_ACTIVATION_TOKEN"));
+        assert!(!env.contains("DESKTOP_STARTUP_ID"));
+    }
+
+    #[test]
+    fn test_prefers_non_default_gpu_sets_nvidia_offload() {
+        let executor = ApplicationExecutor::new().with_gpu_driver(GpuDriver::Nvidia);
+        let mut app = create_test_application("");
+        app.prefers_non_default_gpu = true;
+
+        let env = dump_launch_environment(&executor, &mut app);
+        assert!(env.contains("__NV_PRIME_RENDER_OFFLOAD=1"));
+        assert!(env.contains("__GLX_VENDOR_LIBRARY_NAME=nvidia"));
+    }
+
+    #[test]
+    fn test_prefers_non_default_gpu_sets_dri_prime_on_mesa() {
+        let executor = ApplicationExecutor::new().with_gpu_driver(GpuDriver::Mesa);
+        let mut app = create_test_application("");
+        app.prefers_non_default_gpu = true;
+
+        let env = dump_launch_environment(&executor, &mut app);
+        assert!(env.lines().any(|line| line == "DRI_PRIME=1"));
+        assert!(!env.contains("__NV_PRIME_RENDER_OFFLOAD"));
+    }
+
+    #[test]
+    fn test_gpu_override_wins_over_entry_preference() {
+        let executor = ApplicationExecutor::new()
+            .with_gpu(Some(GpuPreference::Integrated), GpuSettings::default())
+            .with_gpu_driver(GpuDriver::Mesa);
+        let mut app = create_test_application("");
+        app.prefers_non_default_gpu = true;
+
+        let env = dump_launch_environment(&executor, &mut app);
+        assert!(env.lines().any(|line| line == "DRI_PRIME=0"));
+    }
+
+    #[test]
+    fn test_no_gpu_variables_without_preference() {
+        let executor = ApplicationExecutor::new().with_gpu_driver(GpuDriver::Mesa);
+        let app = create_test_application("app %f");
+
+        assert!(executor.gpu_environment(&app).is_empty());
+    }
+
+    #[test]
+    fn test_piped_launch_feeds_file_on_stdin() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input = temp_dir.path().join("notes.md");
+        std::fs::write(&input, "# Title\nsome body text\n").unwrap();
+        let marker_path = temp_dir.path().join("count.txt");
+
+        let mut app =
+            create_test_application(&format!("sh -c 'wc -c > {}' %f", marker_path.display()));
+        app.stdin_input = true;
+
+        let target = LaunchTarget::File(input.clone());
+        ApplicationExecutor::new()
+            .execute(&app, &target, None, None, LaunchDisposition::Piped)
+            .unwrap();
+
+        let recorded = std::fs::read_to_string(&marker_path).unwrap();
+        let expected = std::fs::metadata(&input).unwrap().len();
+        assert_eq!(recorded.trim().parse::<u64>().unwrap(), expected);
+    }
+
+    #[test]
+    fn test_piped_launch_omits_path_argument() {
+        let app = create_test_application("jq . %f");
+
+        let result = ApplicationExecutor::new()
+            .build_command(&app, &[], None, None)
+            .unwrap();
+        assert_eq!(result, vec!["jq", "."]);
+    }
+
+    #[test]
+    fn test_piped_launch_rejects_uri_targets() {
+        let app = create_test_application("glow -");
+        let target = LaunchTarget::Uri(Url::parse("https://example.com/readme.md").unwrap());
+
+        let err = ApplicationExecutor::new()
+            .execute(&app, &target, None, None, LaunchDisposition::Piped)
+            .unwrap_err();
+        assert!(err.to_string().contains("only local files can be piped"));
+    }
+
+    #[test]
+    fn test_piped_launch_reports_failed_exit() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let input = temp_dir.path().join("data.txt");
+        std::fs::write(&input, "data").unwrap();
+
+        let app = create_test_application("false");
+        let err = ApplicationExecutor::new()
+            .execute(
+                &app,
+                &LaunchTarget::File(input),
+                None,
+                None,
+                LaunchDisposition::Piped,
+            )
+            .unwrap_err();
+        assert!(err.to_string().contains("exited with"));
+    }
+
+    /// Live (non-zombie) processes whose process group is `pgid`.
+    fn live_group_members(pgid: i32) -> usize {
+        std::fs::read_dir("/proc")
+            .unwrap()
+            .filter_map(|entry| std::fs::read_to_string(entry.ok()?.path().join("stat")).ok())
+            .filter(|stat| {
+                // Fields after the parenthesised command: state, ppid, pgrp, ...
+                let fields: Vec<&str> = stat
+                    .rsplit_once(')')
+                    .map_or("", |(_, rest)| rest)
+                    .split_whitespace()
+                    .collect();
+                fields.len() > 2 && fields[0] != "Z" && fields[2] == pgid.to_string()
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_wait_launch_returns_after_exit() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let marker_path = temp_dir.path().join("done.txt");
+        let app =
+            create_test_application(&format!("sh -c 'echo done > {}'", marker_path.display()));
+
+        ApplicationExecutor::new()
+            .execute(
+                &app,
+                &LaunchTarget::File(temp_dir.path().to_path_buf()),
+                None,
+                None,
+                LaunchDisposition::Wait(None),
+            )
+            .unwrap();
+        assert_eq!(std::fs::read_to_string(&marker_path).unwrap(), "done\n");
+    }
+
+    #[test]
+    fn test_wait_timeout_kills_the_whole_process_group() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let app = create_test_application("sh -c 'sleep 30 & sleep 30'");
+
+        let started = Instant::now();
+        let err = ApplicationExecutor::new()
+            .execute(
+                &app,
+                &LaunchTarget::File(temp_dir.path().to_path_buf()),
+                None,
+                None,
+                LaunchDisposition::Wait(Some(Duration::from_secs(1))),
+            )
+            .unwrap_err();
+        assert!(started.elapsed() < Duration::from_secs(10));
+
+        let timed_out = err.downcast_ref::<WaitTimedOut>().unwrap();
+        assert_eq!(timed_out.program, "sh");
+        assert!(err.to_string().contains("did not exit within 1s"));
+
+        // The backgrounded `sleep` must not survive as an orphan of the killed shell.
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while live_group_members(timed_out.pgid) > 0 && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(live_group_members(timed_out.pgid), 0);
+    }
 }
@@ -1,14 +1,19 @@
 use crate::application_finder::ApplicationEntry;
-use crate::config::{Config, SelectorProfileId, SelectorProfileType};
+use crate::config::{
+    Config, Multiplexer, SelectorProfile, SelectorProfileId, SelectorProfileType, SessionType,
+};
 use crate::template::TemplateEngine;
 use anyhow::{Context, Result};
 use log::info;
 use std::io::Write;
 use std::process::{Command, Stdio};
 
+// Only tests drive this runner; `open` goes through `SelectorRunner`.
 #[derive(Debug, Default)]
+#[cfg_attr(not(test), allow(dead_code))]
 pub struct FuzzyFinderRunner;
 
+#[cfg_attr(not(test), allow(dead_code))]
 impl FuzzyFinderRunner {
     pub fn new() -> Self {
         Self
@@ -22,10 +27,14 @@ impl FuzzyFinderRunner {
         profile_id: &SelectorProfileId,
     ) -> Result<Option<usize>> {
         let profile = config
-            .get_selector_profile(profile_id.as_ref())
+            .resolve_selector_profile(profile_id.as_ref())
             .ok_or_else(|| {
                 anyhow::anyhow!("No configuration found for fuzzy finder: {}", profile_id)
             })?;
+        if config.get_selector_profile(profile_id.as_ref()).is_none() {
+            info!("No profile configured for `{profile_id}`; using it as a dmenu-style selector");
+        }
+        let profile = profile.as_ref();
 
         // Create template engine for substitutions
         let mut template_engine = TemplateEngine::new();
@@ -39,7 +48,8 @@ impl FuzzyFinderRunner {
         let mut cmd = Command::new(&profile.command);
 
         // Apply template substitutions to args using template engine
-        let substituted_args = template_engine.render_args(&profile.args);
+        let substituted_args = template_engine
+            .render_args(&profile.args_for(SessionType::detect(), Multiplexer::detect()));
         let mut logged_args = Vec::new();
         for arg in substituted_args {
             logged_args.push(arg.clone());
@@ -65,28 +75,7 @@ impl FuzzyFinderRunner {
 
         // Write entries using configurable templates
         for app in applications {
-            let marker = if app.is_default {
-                config.get_marker(profile, "default")
-            } else if app.is_xdg {
-                config.get_marker(profile, "xdg")
-            } else {
-                config.get_marker(profile, "available")
-            };
-
-            let comment = app
-                .comment
-                .as_ref()
-                .map_or(String::new(), |c| format!(" - {c}"));
-
-            // Use template engine for entry rendering
-            let mut entry_template_engine = TemplateEngine::new();
-            entry_template_engine
-                .set("marker", marker)
-                .set("name", &app.name)
-                .set("comment", &comment);
-
-            let display = entry_template_engine.render(&profile.entry_template);
-            writeln!(stdin, "{display}")?;
+            writeln!(stdin, "{}", render_entry(config, profile, app))?;
         }
 
         let output = child.wait_with_output()?;
@@ -124,41 +113,16 @@ impl FuzzyFinderRunner {
         }
 
         // Generic matching for other fuzzy finders
-        for (i, app) in applications.iter().enumerate() {
-            let marker = if app.is_default {
-                config.get_marker(profile, "default")
-            } else if app.is_xdg {
-                config.get_marker(profile, "xdg")
-            } else {
-                config.get_marker(profile, "available")
-            };
-
-            let comment = app
-                .comment
-                .as_ref()
-                .map_or(String::new(), |c| format!(" - {c}"));
-
-            // Use template engine for entry matching
-            let mut entry_template_engine = TemplateEngine::new();
-            entry_template_engine
-                .set("marker", marker)
-                .set("name", &app.name)
-                .set("comment", &comment);
-
-            let display = entry_template_engine.render(&profile.entry_template);
-
-            if display == selected {
-                info!(
-                    "Fuzzy finder `{}` selected `{}` ({})",
-                    profile.command,
-                    app.name,
-                    app.desktop_file.display()
-                );
-                return Ok(Some(i));
-            }
+        let index = match_selection(config, profile, applications, &selected);
+        if let Some(app) = index.map(|i| &applications[i]) {
+            info!(
+                "Fuzzy finder `{}` selected `{}` ({})",
+                profile.command,
+                app.name,
+                app.desktop_file.display()
+            );
         }
-
-        Ok(None)
+        Ok(index)
     }
 
     pub fn detect_available(
@@ -181,18 +145,58 @@ impl FuzzyFinderRunner {
 
     fn candidate_available(config: &Config, id: &SelectorProfileId) -> bool {
         if let Some(profile) = config.get_selector_profile(id.as_ref()) {
-            which::which(&profile.command).is_ok()
+            profile.validate().is_ok()
         } else {
             which::which(id.as_str()).is_ok()
         }
     }
 }
 
+/// Render `app` through the profile's `entry_template`.
+fn render_entry(config: &Config, profile: &SelectorProfile, app: &ApplicationEntry) -> String {
+    let marker = if app.is_default {
+        config.get_padded_marker(profile, "default")
+    } else if app.is_xdg {
+        config.get_padded_marker(profile, "xdg")
+    } else {
+        config.get_padded_marker(profile, "available")
+    };
+
+    let comment = app
+        .comment
+        .as_ref()
+        .map_or(String::new(), |c| format!(" - {c}"));
+
+    let mut entry_template_engine = TemplateEngine::new();
+    entry_template_engine
+        .set("marker", &marker)
+        .set("name", &app.name)
+        .set("comment", &comment);
+
+    entry_template_engine.render(&profile.entry_template)
+}
+
+/// Find the entry whose rendered line is `selected`.
+///
+/// Line-based selectors such as wofi and rofi echo the chosen line back, sometimes with
+/// surrounding whitespace, so both sides are compared trimmed; a padded marker like
+/// `"   "` would otherwise never match.
+fn match_selection(
+    config: &Config,
+    profile: &SelectorProfile,
+    applications: &[ApplicationEntry],
+    selected: &str,
+) -> Option<usize> {
+    let selected = selected.trim();
+    applications
+        .iter()
+        .position(|app| render_entry(config, profile, app).trim() == selected)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::application_finder::ApplicationEntry;
-    use crate::config::{SelectorProfile, SelectorProfileId};
+    use crate::application_finder::{ApplicationEntry, ApplicationSource, Association};
     use std::path::PathBuf;
 
     fn create_test_application() -> ApplicationEntry {
@@ -208,6 +212,20 @@ mod tests {
             action_id: None,
             requires_terminal: false,
             is_terminal_emulator: false,
+            working_dir: None,
+            prefers_non_default_gpu: false,
+            stdin_input: false,
+            association: Association::None,
+            parent_mime: None,
+            startup_notify: false,
+            confirm: false,
+            score: 0.0,
+            dbus_activatable: false,
+            startup_wm_class: None,
+            single_main_window: false,
+            generic_name: None,
+            keywords: Vec::new(),
+            source: ApplicationSource::Available,
         }
     }
 
@@ -278,6 +296,40 @@ mod tests {
             .contains("No configuration found for fuzzy finder"));
     }
 
+    #[test]
+    fn test_run_synthesizes_profile_for_unconfigured_default() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let selector = temp_dir.path().join("pick-last");
+        std::fs::write(&selector, "#!/bin/sh\ntail -n 1\n").unwrap();
+        std::fs::set_permissions(&selector, std::fs::Permissions::from_mode(0o755)).unwrap();
+        let selector = selector.to_str().unwrap();
+
+        let mut config = Config::default();
+        config.selector_profiles.clear();
+        config.selector.defaults.gui = SelectorProfileId::from(selector);
+
+        let runner = FuzzyFinderRunner::new();
+        let id = runner
+            .detect_available(&config, SelectorProfileType::Gui)
+            .unwrap();
+        assert_eq!(id.as_str(), selector);
+
+        let mut other = create_test_application();
+        other.name = "Other App".to_string();
+        other.is_default = false;
+        let applications = [create_test_application(), other];
+        let result = runner.run(&config, &applications, "test.txt", &id).unwrap();
+        assert_eq!(result, Some(1));
+
+        let profile = config.resolve_selector_profile(selector).unwrap();
+        assert_eq!(profile.selector_type, SelectorProfileType::Gui);
+        assert!(config
+            .resolve_selector_profile("openit-definitely-missing-selector")
+            .is_none());
+    }
+
     #[test]
     fn test_run_command_construction() {
         // Test that we can construct the command without actually running it
@@ -370,6 +422,20 @@ mod tests {
                 action_id: None,
                 requires_terminal: false,
                 is_terminal_emulator: false,
+                working_dir: None,
+                prefers_non_default_gpu: false,
+                stdin_input: false,
+                association: Association::None,
+                parent_mime: None,
+                startup_notify: false,
+                confirm: false,
+                score: 0.0,
+                dbus_activatable: false,
+                startup_wm_class: None,
+                single_main_window: false,
+                generic_name: None,
+                keywords: Vec::new(),
+                source: ApplicationSource::Available,
             },
         ];
 
@@ -477,4 +543,101 @@ mod tests {
         let has_header = substituted_args.iter().any(|arg| arg.contains("Available"));
         assert!(has_header);
     }
+
+    #[test]
+    fn test_padded_markers_share_display_width() {
+        let config = Config::default();
+        let profile = config.get_selector_profile("fuzzel").unwrap();
+
+        let widths: Vec<usize> = ["default", "xdg", "available"]
+            .iter()
+            .map(|kind| crate::template::display_width(&config.get_padded_marker(profile, kind)))
+            .collect();
+        assert!(widths.iter().all(|width| *width == widths[0]));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn test_wofi_line_output_matches_entry() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let script = temp_dir.path().join("wofi");
+        // Echo the second line back with the trailing whitespace wofi may leave behind.
+        std::fs::write(
+            &script,
+            "#!/bin/sh\nline=$(sed -n 2p)\nprintf '%s  \\n' \"$line\"\n",
+        )
+        .unwrap();
+        std::fs::set_permissions(&script, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut config = Config::default();
+        let wofi = config.selector_profiles.get_mut("wofi").unwrap();
+        assert_eq!(wofi.selector_type, SelectorProfileType::Gui);
+        wofi.command = script.to_string_lossy().into_owned();
+
+        let mut available = create_test_application();
+        available.name = "Second App".to_string();
+        available.is_xdg = false;
+        available.is_default = false;
+        let applications = [create_test_application(), available];
+
+        let runner = FuzzyFinderRunner::new();
+        let selected = runner
+            .run(
+                &config,
+                &applications,
+                "test.txt",
+                &SelectorProfileId::from("wofi"),
+            )
+            .unwrap();
+        assert_eq!(selected, Some(1));
+    }
+
+    #[test]
+    fn test_match_selection_requires_exact_entry() {
+        let config = Config::default();
+        let profile = config.get_selector_profile("wofi").unwrap();
+        let applications = [create_test_application()];
+        let line = render_entry(&config, profile, &applications[0]);
+
+        assert_eq!(
+            match_selection(&config, profile, &applications, &format!("{line} \t")),
+            Some(0)
+        );
+        assert_eq!(
+            match_selection(&config, profile, &applications, "Test App"),
+            None
+        );
+    }
+
+    #[test]
+    fn test_dmenu_round_trips_markers_with_spaces() {
+        let mut config = Config::default();
+        let dmenu = config.selector_profiles.get_mut("dmenu").unwrap();
+        assert_eq!(dmenu.selector_type, SelectorProfileType::Gui);
+        dmenu.marker_default = Some("[ default ] ".to_string());
+        dmenu.marker_available = Some("  ".to_string());
+        let profile = config.get_selector_profile("dmenu").unwrap();
+
+        let mut available = create_test_application();
+        available.name = "Second App".to_string();
+        available.is_xdg = false;
+        available.is_default = false;
+        available.comment = None;
+        let applications = [create_test_application(), available];
+
+        for (index, app) in applications.iter().enumerate() {
+            let line = render_entry(&config, profile, app);
+            assert_eq!(
+                match_selection(&config, profile, &applications, &format!("{line}\n")),
+                Some(index),
+                "line {line:?}"
+            );
+        }
+        assert_eq!(
+            render_entry(&config, profile, &applications[0]),
+            "[ default ] Test App - Test application"
+        );
+    }
 }
@@ -0,0 +1,166 @@
+use crate::application_finder::ApplicationEntry;
+
+const MATCH_POINTS: u32 = 1;
+const CONSECUTIVE_BONUS: u32 = 4;
+const WORD_START_BONUS: u32 = 3;
+const EXACT_BONUS: u32 = 20;
+
+/// Score `query` as a case-insensitive subsequence of `candidate`; `None` when it does not match.
+///
+/// Consecutive characters and characters at the start of a word score higher, so
+/// `fire` prefers `Firefox` over `File Roller`.
+pub fn score(query: &str, candidate: &str) -> Option<u32> {
+    let query: Vec<char> = query.trim().to_lowercase().chars().collect();
+    if query.is_empty() {
+        return None;
+    }
+
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let mut total = 0;
+    let mut next = 0;
+    let mut last_matched: Option<usize> = None;
+
+    for (index, ch) in candidate.iter().enumerate() {
+        if next == query.len() {
+            break;
+        }
+        if *ch != query[next] {
+            continue;
+        }
+
+        total += MATCH_POINTS;
+        if last_matched.is_some_and(|last| last + 1 == index) {
+            total += CONSECUTIVE_BONUS;
+        }
+        if index == 0 || !candidate[index - 1].is_alphanumeric() {
+            total += WORD_START_BONUS;
+        }
+        last_matched = Some(index);
+        next += 1;
+    }
+
+    if next < query.len() {
+        return None;
+    }
+    if candidate == query {
+        total += EXACT_BONUS;
+    }
+    Some(total)
+}
+
+/// Best score of `query` against the entry's name, desktop ID, generic name and keywords.
+pub fn score_entry(query: &str, app: &ApplicationEntry) -> Option<u32> {
+    let desktop_id = app
+        .desktop_file
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+
+    [app.name.as_str(), desktop_id.as_str()]
+        .into_iter()
+        .chain(app.generic_name.as_deref())
+        .chain(app.keywords.iter().map(String::as_str))
+        .filter_map(|candidate| score(query, candidate))
+        .max()
+}
+
+/// Index of the entry that clearly wins for `query`.
+///
+/// The winner must be the only match, or beat the runner-up by at least one point per
+/// query character; anything closer is left to the selector.
+pub fn clear_winner(query: &str, applications: &[ApplicationEntry]) -> Option<usize> {
+    let mut scored: Vec<(usize, u32)> = applications
+        .iter()
+        .enumerate()
+        .filter_map(|(index, app)| score_entry(query, app).map(|score| (index, score)))
+        .collect();
+    scored.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let margin = query.trim().chars().count() as u32;
+    match scored.as_slice() {
+        [(index, _)] => Some(*index),
+        [(index, best), (_, runner_up), ..] if *best >= runner_up + margin => Some(*index),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::application_finder::{ApplicationSource, Association};
+    use std::path::PathBuf;
+
+    fn application_entry(name: &str, desktop_id: &str) -> ApplicationEntry {
+        ApplicationEntry {
+            name: name.to_string(),
+            exec: "app %F".to_string(),
+            desktop_file: PathBuf::from(desktop_id),
+            comment: None,
+            icon: None,
+            is_xdg: false,
+            xdg_priority: -1,
+            is_default: false,
+            action_id: None,
+            requires_terminal: false,
+            is_terminal_emulator: false,
+            working_dir: None,
+            prefers_non_default_gpu: false,
+            stdin_input: false,
+            association: Association::None,
+            parent_mime: None,
+            startup_notify: false,
+            confirm: false,
+            score: 0.0,
+            dbus_activatable: false,
+            startup_wm_class: None,
+            single_main_window: false,
+            generic_name: None,
+            keywords: Vec::new(),
+            source: ApplicationSource::Available,
+        }
+    }
+
+    #[test]
+    fn scores_subsequences_and_rejects_misses() {
+        assert!(score("ffx", "Firefox").is_some());
+        assert!(score("xyz", "Firefox").is_none());
+        assert!(score("", "Firefox").is_none());
+        assert!(score("fire", "Firefox") > score("fire", "File Roller"));
+        assert!(score("vlc", "vlc") > score("vlc", "vlc player"));
+    }
+
+    #[test]
+    fn clear_winner_picks_dominant_match() {
+        let apps = vec![
+            application_entry("File Roller", "org.gnome.FileRoller.desktop"),
+            application_entry("Firefox", "firefox.desktop"),
+            application_entry("Text Editor", "org.gnome.TextEditor.desktop"),
+        ];
+
+        assert_eq!(clear_winner("fire", &apps), Some(1));
+        assert_eq!(clear_winner("texted", &apps), Some(2));
+    }
+
+    #[test]
+    fn generic_names_and_keywords_match_too() {
+        let mut browser = application_entry("Firefox", "firefox.desktop");
+        browser.generic_name = Some("Web Browser".to_string());
+        let mut editor = application_entry("Text Editor", "org.gnome.TextEditor.desktop");
+        editor.keywords = vec!["notepad".to_string()];
+        let apps = vec![browser, editor];
+
+        assert_eq!(clear_winner("browser", &apps), Some(0));
+        assert_eq!(clear_winner("notepad", &apps), Some(1));
+    }
+
+    #[test]
+    fn close_scores_are_ambiguous() {
+        let apps = vec![
+            application_entry("Firefox", "firefox.desktop"),
+            application_entry("Firefox Developer Edition", "firefox-developer.desktop"),
+        ];
+
+        assert_eq!(clear_winner("fire", &apps), None);
+        assert_eq!(clear_winner("nomatch", &apps), None);
+    }
+}
@@ -0,0 +1,149 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// GPU an application should render on.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum GpuPreference {
+    Discrete,
+    Integrated,
+}
+
+impl GpuPreference {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GpuPreference::Discrete => "discrete",
+            GpuPreference::Integrated => "integrated",
+        }
+    }
+}
+
+/// Driver stack that decides which offload variables apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuDriver {
+    /// Proprietary NVIDIA driver with PRIME render offload.
+    Nvidia,
+    /// Mesa PRIME (`DRI_PRIME`).
+    Mesa,
+}
+
+impl GpuDriver {
+    /// Detect the proprietary NVIDIA driver via `/proc/driver/nvidia` or `nvidia-smi`.
+    pub fn detect() -> Self {
+        if Path::new("/proc/driver/nvidia").exists() || which::which("nvidia-smi").is_ok() {
+            GpuDriver::Nvidia
+        } else {
+            GpuDriver::Mesa
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            GpuDriver::Nvidia => "nvidia",
+            GpuDriver::Mesa => "mesa",
+        }
+    }
+}
+
+/// `[gpu]` config table; each map replaces the detected variables for that mode.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct GpuSettings {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub discrete_env: Option<BTreeMap<String, String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub integrated_env: Option<BTreeMap<String, String>>,
+}
+
+impl GpuSettings {
+    /// Environment variables selecting `preference` on `driver`.
+    pub fn environment(
+        &self,
+        preference: GpuPreference,
+        driver: GpuDriver,
+    ) -> Vec<(String, String)> {
+        let configured = match preference {
+            GpuPreference::Discrete => self.discrete_env.as_ref(),
+            GpuPreference::Integrated => self.integrated_env.as_ref(),
+        };
+        if let Some(vars) = configured {
+            return vars
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone()))
+                .collect();
+        }
+
+        let defaults: &[(&str, &str)] = match (preference, driver) {
+            (GpuPreference::Discrete, GpuDriver::Nvidia) => &[
+                ("__NV_PRIME_RENDER_OFFLOAD", "1"),
+                ("__GLX_VENDOR_LIBRARY_NAME", "nvidia"),
+                ("__VK_LAYER_NV_optimus", "NVIDIA_only"),
+            ],
+            (GpuPreference::Discrete, GpuDriver::Mesa) => &[("DRI_PRIME", "1")],
+            (GpuPreference::Integrated, GpuDriver::Nvidia) => &[
+                ("__NV_PRIME_RENDER_OFFLOAD", "0"),
+                ("__GLX_VENDOR_LIBRARY_NAME", "mesa"),
+                ("__VK_LAYER_NV_optimus", "non_NVIDIA_only"),
+            ],
+            (GpuPreference::Integrated, GpuDriver::Mesa) => &[("DRI_PRIME", "0")],
+        };
+
+        defaults
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keys(vars: &[(String, String)]) -> Vec<&str> {
+        vars.iter().map(|(key, _)| key.as_str()).collect()
+    }
+
+    #[test]
+    fn discrete_defaults_follow_driver() {
+        let settings = GpuSettings::default();
+
+        let nvidia = settings.environment(GpuPreference::Discrete, GpuDriver::Nvidia);
+        assert_eq!(
+            keys(&nvidia),
+            vec![
+                "__NV_PRIME_RENDER_OFFLOAD",
+                "__GLX_VENDOR_LIBRARY_NAME",
+                "__VK_LAYER_NV_optimus"
+            ]
+        );
+
+        let mesa = settings.environment(GpuPreference::Discrete, GpuDriver::Mesa);
+        assert_eq!(mesa, vec![("DRI_PRIME".to_string(), "1".to_string())]);
+    }
+
+    #[test]
+    fn integrated_on_mesa_disables_prime() {
+        let vars = GpuSettings::default().environment(GpuPreference::Integrated, GpuDriver::Mesa);
+        assert_eq!(vars, vec![("DRI_PRIME".to_string(), "0".to_string())]);
+    }
+
+    #[test]
+    fn configured_variables_replace_detected_ones() {
+        let settings: GpuSettings = toml::from_str(
+            r#"
+discrete_env = { DRI_PRIME = "pci-0000_01_00_0" }
+"#,
+        )
+        .unwrap();
+
+        let vars = settings.environment(GpuPreference::Discrete, GpuDriver::Nvidia);
+        assert_eq!(
+            vars,
+            vec![("DRI_PRIME".to_string(), "pci-0000_01_00_0".to_string())]
+        );
+
+        let integrated = settings.environment(GpuPreference::Integrated, GpuDriver::Mesa);
+        assert_eq!(keys(&integrated), vec!["DRI_PRIME"]);
+    }
+}
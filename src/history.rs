@@ -0,0 +1,168 @@
+use anyhow::{Context, Result};
+use nix::fcntl::{Flock, FlockArg};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Handlers remembered per MIME type; older launches fall off the end.
+const MAX_ENTRIES_PER_MIME: usize = 10;
+
+/// Desktop IDs launched for each MIME type, most recent first and without duplicates.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LaunchHistory {
+    #[serde(default)]
+    mimes: BTreeMap<String, Vec<String>>,
+}
+
+impl LaunchHistory {
+    /// The history at `path`; a missing file is an empty history.
+    pub fn load(path: &Path) -> Result<Self> {
+        match fs::read_to_string(path) {
+            Ok(contents) => serde_json::from_str(&contents)
+                .with_context(|| format!("Failed to parse launch history {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err)
+                .with_context(|| format!("Failed to read launch history {}", path.display())),
+        }
+    }
+
+    /// Replace the history at `path` through a temporary file, so a reader never sees a
+    /// half-written file.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let parent = parent_dir(path);
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+
+        let json = serde_json::to_string_pretty(self)?;
+        let mut file = tempfile::Builder::new()
+            .prefix(".history.")
+            .tempfile_in(parent)
+            .with_context(|| {
+                format!("Failed to create a temporary file in {}", parent.display())
+            })?;
+        file.write_all(json.as_bytes())
+            .and_then(|()| file.as_file().sync_all())
+            .with_context(|| format!("Failed to write launch history {}", path.display()))?;
+        file.persist(path)
+            .with_context(|| format!("Failed to replace launch history {}", path.display()))?;
+        Ok(())
+    }
+
+    /// Record a launch in the history at `path`, holding `history.json.lock` across the
+    /// load and save so concurrent launches do not drop each other's entries.
+    pub fn record_at(path: &Path, mime: &str, desktop_id: &str) -> Result<()> {
+        let parent = parent_dir(path);
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+
+        let mut lock_name = path.as_os_str().to_os_string();
+        lock_name.push(".lock");
+        let lock_path = PathBuf::from(lock_name);
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+        let _lock = Flock::lock(file, FlockArg::LockExclusive)
+            .map_err(|(_, errno)| errno)
+            .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+
+        let mut history = Self::load(path)?;
+        history.record(mime, desktop_id);
+        history.save(path)
+    }
+
+    /// Move `desktop_id` to the front of the list for `mime`.
+    pub fn record(&mut self, mime: &str, desktop_id: &str) {
+        let entries = self.mimes.entry(mime.to_string()).or_default();
+        entries.retain(|id| id != desktop_id);
+        entries.insert(0, desktop_id.to_string());
+        entries.truncate(MAX_ENTRIES_PER_MIME);
+    }
+
+    /// Handlers launched for `mime`, most recent first.
+    pub fn recent(&self, mime: &str) -> &[String] {
+        self.mimes.get(mime).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    /// The `n`th most recently launched handler for `mime`, counting from 1.
+    pub fn nth(&self, mime: &str, n: usize) -> Result<&str> {
+        let recent = self.recent(mime);
+        if n == 0 {
+            anyhow::bail!("--recent counts from 1");
+        }
+        recent.get(n - 1).map(String::as_str).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Only {} handler(s) in the launch history for {mime}; --recent {n} needs at least {n}",
+                recent.len()
+            )
+        })
+    }
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    path.parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn seeded() -> LaunchHistory {
+        let mut history = LaunchHistory::default();
+        for id in ["gimp.desktop", "feh.desktop", "imv.desktop"] {
+            history.record("image/png", id);
+        }
+        history
+    }
+
+    #[test]
+    fn nth_indexes_from_the_most_recent_launch() {
+        let history = seeded();
+
+        assert_eq!(history.nth("image/png", 1).unwrap(), "imv.desktop");
+        assert_eq!(history.nth("image/png", 2).unwrap(), "feh.desktop");
+
+        let err = history.nth("image/png", 4).unwrap_err().to_string();
+        assert!(err.contains("Only 3 handler(s)"), "{err}");
+        assert!(history.nth("image/png", 0).is_err());
+        assert!(history.nth("text/plain", 1).is_err());
+    }
+
+    #[test]
+    fn record_moves_repeat_launches_to_the_front() {
+        let mut history = seeded();
+        history.record("image/png", "gimp.desktop");
+
+        assert_eq!(
+            history.recent("image/png"),
+            ["gimp.desktop", "imv.desktop", "feh.desktop"]
+        );
+
+        for i in 0..MAX_ENTRIES_PER_MIME + 2 {
+            history.record("image/png", &format!("app{i}.desktop"));
+        }
+        assert_eq!(history.recent("image/png").len(), MAX_ENTRIES_PER_MIME);
+    }
+
+    #[test]
+    fn save_and_load_round_trip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("openit").join("history.json");
+
+        assert_eq!(
+            LaunchHistory::load(&path).unwrap(),
+            LaunchHistory::default()
+        );
+
+        let history = seeded();
+        history.save(&path).unwrap();
+        assert_eq!(LaunchHistory::load(&path).unwrap(), history);
+    }
+}
@@ -1,22 +1,35 @@
-use anyhow::Result;
 use clap::{CommandFactory, Parser};
+use std::process::ExitCode;
 
 mod application_finder;
 mod cache;
 mod cli;
 mod commands;
+mod completion;
 mod config;
+mod dbus;
 mod desktop_parser;
 mod executor;
 mod fuzzy_finder;
+mod fuzzy_match;
+mod gpu;
+mod history;
 mod mime_associations;
+mod mime_detector;
 mod mime_pattern;
+mod mime_relations;
+mod mime_subclasses;
 mod mimeapps;
+mod notify;
 mod open_it;
+mod prompt;
 mod regex_handlers;
 mod selector;
+mod shortcut_files;
 mod target;
 mod template;
+mod timings;
+mod window;
 mod xdg;
 
 #[cfg(test)]
@@ -25,22 +38,41 @@ pub mod test_support;
 // Build info module
 pub mod built_info {
     include!(concat!(env!("OUT_DIR"), "/built.rs"));
+    include!(concat!(env!("OUT_DIR"), "/features.rs"));
 }
 
 use cli::Cli;
 
-fn main() -> Result<()> {
+fn main() -> ExitCode {
     clap_complete::CompleteEnv::with_factory(|| Cli::command().name("openit"))
         .completer("openit")
         .complete();
 
     let cli = Cli::parse();
-    commands::dispatch(cli.into_command())
+    let ctx = cli.context();
+    match commands::dispatch(cli.into_command(), &ctx) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("Error: {err:?}");
+            exit_code(&err)
+        }
+    }
+}
+
+/// `--wait-timeout` expiring gets its own status so scripts can tell it from a failure.
+fn exit_code(err: &anyhow::Error) -> ExitCode {
+    if err.is::<executor::WaitTimedOut>() {
+        ExitCode::from(executor::WAIT_TIMEOUT_EXIT_CODE)
+    } else {
+        ExitCode::FAILURE
+    }
 }
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::application_finder::{ApplicationEntry, ApplicationFinder};
+    use crate::application_finder::{
+        ApplicationEntry, ApplicationFinder, ApplicationSource, Association,
+    };
     use crate::cache::DesktopCache;
     use crate::desktop_parser::DesktopFile;
     use crate::executor::{ApplicationExecutor, LaunchDisposition};
@@ -66,6 +98,20 @@ mod tests {
             action_id: None,
             requires_terminal: false,
             is_terminal_emulator: false,
+            working_dir: None,
+            prefers_non_default_gpu: false,
+            stdin_input: false,
+            association: Association::None,
+            parent_mime: None,
+            startup_notify: false,
+            confirm: false,
+            score: 0.0,
+            dbus_activatable: false,
+            startup_wm_class: None,
+            single_main_window: false,
+            generic_name: None,
+            keywords: Vec::new(),
+            source: ApplicationSource::Available,
         };
 
         let json = serde_json::to_string(&app).unwrap();
@@ -268,21 +314,25 @@ Exec=test";
                 action_id: None,
                 requires_terminal: false,
                 is_terminal_emulator: false,
+                working_dir: None,
+                prefers_non_default_gpu: false,
+                stdin_input: false,
+                association: Association::None,
+                parent_mime: None,
+                startup_notify: false,
+                confirm: false,
+                score: 0.0,
+                dbus_activatable: false,
+                startup_wm_class: None,
+                single_main_window: false,
+                generic_name: None,
+                keywords: Vec::new(),
+                source: ApplicationSource::Available,
             };
 
-            // Extract the cleaning logic to test it
-            let clean_exec = app
-                .exec
-                .replace("%u", "")
-                .replace("%U", "")
-                .replace("%f", "")
-                .replace("%F", "")
-                .replace("%i", "")
-                .replace("%c", "")
-                .replace("%k", "")
-                .replace("%%", "%")
-                .trim()
-                .to_string();
+            let clean_exec = ApplicationExecutor::base_command_parts(&app.exec)
+                .unwrap()
+                .join(" ");
 
             assert_eq!(clean_exec, expected, "Failed for input: {input}");
         }
@@ -302,6 +352,20 @@ Exec=test";
             action_id: None,
             requires_terminal: false,
             is_terminal_emulator: false,
+            working_dir: None,
+            prefers_non_default_gpu: false,
+            stdin_input: false,
+            association: Association::None,
+            parent_mime: None,
+            startup_notify: false,
+            confirm: false,
+            score: 0.0,
+            dbus_activatable: false,
+            startup_wm_class: None,
+            single_main_window: false,
+            generic_name: None,
+            keywords: Vec::new(),
+            source: ApplicationSource::Available,
         };
 
         let temp_dir = TempDir::new().unwrap();
@@ -310,7 +374,7 @@ Exec=test";
         let target = LaunchTarget::File(test_file);
 
         let executor = ApplicationExecutor::new();
-        let result = executor.execute(&app, &target, None, LaunchDisposition::Detached);
+        let result = executor.execute(&app, &target, None, None, LaunchDisposition::Detached);
         assert!(result.is_err());
         assert_eq!(result.unwrap_err().to_string(), "Empty exec command");
     }
@@ -349,4 +413,20 @@ MimeType=text/plain;";
         let xdg_app = apps.iter().find(|a| a.is_xdg).unwrap();
         assert!(xdg_app.desktop_file.ends_with("app1.desktop"));
     }
+
+    #[test]
+    fn wait_timeouts_exit_with_their_own_status() {
+        let timed_out = anyhow::Error::new(executor::WaitTimedOut {
+            program: "sleep".to_string(),
+            timeout: std::time::Duration::from_secs(1),
+            pgid: 1,
+        })
+        .context("Failed to open target");
+
+        assert_eq!(
+            exit_code(&timed_out),
+            ExitCode::from(executor::WAIT_TIMEOUT_EXIT_CODE)
+        );
+        assert_eq!(exit_code(&anyhow::anyhow!("boom")), ExitCode::FAILURE);
+    }
 }
@@ -1,33 +1,129 @@
 use crate::mime_pattern;
-use std::collections::{HashMap, HashSet};
+use crate::mimeapps::{DesktopList, MimeApps};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+/// One MIME key a handler is associated with, as listed in `mimeapps.list`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct HandlerAssociation {
+    pub mime: String,
+    /// Zero-based position among the key's handlers; 0 is the one that opens first.
+    pub position: usize,
+    /// Listed under `[Default Applications]` rather than only added.
+    pub is_default: bool,
+}
+
+/// What one `mimeapps.list` file lists for a MIME type, under its exact key or a
+/// matching wildcard key.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct FileContribution {
+    pub path: PathBuf,
+    pub default_applications: Vec<String>,
+    pub added_associations: Vec<String>,
+    pub removed_associations: Vec<String>,
+}
+
+impl FileContribution {
+    fn read(path: &Path, mime_type: &str) -> Option<Self> {
+        let apps = MimeApps::parse(&fs::read_to_string(path).ok()?);
+        let mime_type = normalize_mime_key(mime_type);
+        let collect = |section: &BTreeMap<String, DesktopList>| {
+            let exact = section.get(&mime_type).into_iter();
+            let wildcards = section
+                .iter()
+                .filter(|(key, _)| **key != mime_type && mime_pattern::matches(key, &mime_type))
+                .map(|(_, handlers)| handlers);
+            let mut handlers: Vec<String> = Vec::new();
+            for handler in exact.chain(wildcards).flat_map(DesktopList::iter) {
+                if !handlers.contains(handler) {
+                    handlers.push(handler.clone());
+                }
+            }
+            handlers
+        };
+
+        Some(Self {
+            path: path.to_path_buf(),
+            default_applications: collect(apps.default_apps()),
+            added_associations: collect(apps.added_associations()),
+            removed_associations: collect(apps.removed_associations()),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.default_applications.is_empty()
+            && self.added_associations.is_empty()
+            && self.removed_associations.is_empty()
+    }
+}
 
 #[derive(Debug, Default)]
 pub struct MimeAssociations {
     associations: HashMap<String, Vec<String>>,
+    /// Desktop IDs listed under `[Default Applications]`, per MIME key.
+    defaults: HashMap<String, HashSet<String>>,
+    /// Desktop IDs listed under `[Removed Associations]`, per MIME key.
+    removed: HashMap<String, HashSet<String>>,
 }
 
 impl MimeAssociations {
     // Also add this for testing
     #[cfg(test)]
     pub fn with_associations(associations: HashMap<String, Vec<String>>) -> Self {
-        Self { associations }
+        Self {
+            associations,
+            ..Self::default()
+        }
+    }
+
+    #[cfg(test)]
+    pub fn from_mimeapps_contents(contents: &str) -> Self {
+        let mut associations = Self::default();
+        associations.parse_sections(contents);
+        associations
     }
+
     pub fn load() -> Self {
-        let mut associations = HashMap::new();
-        let mimeapps_files = crate::xdg::get_mimeapps_list_files();
+        Self::load_from_files(&crate::xdg::get_mimeapps_list_files())
+    }
+
+    /// Merge `mimeapps.list` files given in precedence order (highest first).
+    pub fn load_from_files(mimeapps_files: &[std::path::PathBuf]) -> Self {
+        let mut associations = Self::default();
 
         // Process files in reverse order (later files override earlier ones)
         for file in mimeapps_files.iter().rev() {
             if let Ok(contents) = fs::read_to_string(file) {
-                Self::parse_mimeapps_file(&contents, &mut associations);
+                associations.parse_sections(&contents);
             }
         }
 
-        Self { associations }
+        associations
+    }
+
+    /// Each readable file's entries for `mime_type`, kept apart in the same precedence
+    /// order `load_from_files` merges them in.
+    pub fn contributions(mimeapps_files: &[PathBuf], mime_type: &str) -> Vec<FileContribution> {
+        mimeapps_files
+            .iter()
+            .filter_map(|path| FileContribution::read(path, mime_type))
+            .collect()
     }
 
+    #[cfg(test)]
     fn parse_mimeapps_file(contents: &str, associations: &mut HashMap<String, Vec<String>>) {
+        let mut parsed = Self {
+            associations: std::mem::take(associations),
+            ..Self::default()
+        };
+        parsed.parse_sections(contents);
+        *associations = parsed.associations;
+    }
+
+    fn parse_sections(&mut self, contents: &str) {
         let mut current_section = String::new();
 
         for line in contents.lines() {
@@ -42,35 +138,72 @@ impl MimeAssociations {
                 continue;
             }
 
-            if current_section == "[Default Applications]"
-                || current_section == "[Added Associations]"
-            {
-                if let Some(eq_pos) = line.find('=') {
-                    let mime_type = line[..eq_pos].trim();
-                    let apps = line[eq_pos + 1..]
-                        .split(';')
-                        .filter(|s| !s.is_empty())
-                        .map(|s| s.trim().to_string())
-                        .collect::<Vec<_>>();
-
-                    if !apps.is_empty() {
-                        // For Default Applications, replace existing
-                        // For Added Associations, append
-                        if current_section == "[Default Applications]" {
-                            associations.insert(mime_type.to_string(), apps);
-                        } else {
-                            associations
-                                .entry(mime_type.to_string())
-                                .or_default()
-                                .extend(apps);
+            let Some(eq_pos) = line.find('=') else {
+                continue;
+            };
+            let mime_type = normalize_mime_key(&line[..eq_pos]);
+            let mime_type = mime_type.as_str();
+            let apps = line[eq_pos + 1..]
+                .split(';')
+                .filter(|s| !s.is_empty())
+                .map(|s| s.trim().to_string())
+                .collect::<Vec<_>>();
+
+            if apps.is_empty() {
+                continue;
+            }
+
+            match current_section.as_str() {
+                "[Default Applications]" | "[Added Associations]" => {
+                    // Listing a handler again in a higher-precedence file restores it.
+                    if let Some(removed) = self.removed.get_mut(mime_type) {
+                        for app in &apps {
+                            removed.remove(normalize_desktop_id(app));
                         }
                     }
+
+                    // For Default Applications, replace existing
+                    // For Added Associations, append
+                    if current_section == "[Default Applications]" {
+                        self.defaults.insert(
+                            mime_type.to_string(),
+                            apps.iter()
+                                .map(|app| normalize_desktop_id(app).to_string())
+                                .collect(),
+                        );
+                        self.associations.insert(mime_type.to_string(), apps);
+                    } else {
+                        self.associations
+                            .entry(mime_type.to_string())
+                            .or_default()
+                            .extend(apps);
+                    }
                 }
+                "[Removed Associations]" => {
+                    let removed_ids: HashSet<String> = apps
+                        .iter()
+                        .map(|app| normalize_desktop_id(app).to_string())
+                        .collect();
+
+                    if let Some(handlers) = self.associations.get_mut(mime_type) {
+                        handlers.retain(|app| !removed_ids.contains(normalize_desktop_id(app)));
+                    }
+                    if let Some(defaults) = self.defaults.get_mut(mime_type) {
+                        defaults.retain(|app| !removed_ids.contains(app));
+                    }
+                    self.removed
+                        .entry(mime_type.to_string())
+                        .or_default()
+                        .extend(removed_ids);
+                }
+                _ => {}
             }
         }
     }
 
     pub fn get_associations(&self, mime_type: &str) -> Vec<String> {
+        let mime_type = normalize_mime_key(mime_type);
+        let mime_type = mime_type.as_str();
         let mut results = Vec::new();
         let mut seen = HashSet::new();
 
@@ -83,7 +216,7 @@ impl MimeAssociations {
         }
 
         let mut entries: Vec<(&String, &Vec<String>)> = self.associations.iter().collect();
-        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+        entries.sort_by_key(|(a, _)| *a);
 
         for (pattern, handlers) in entries {
             if pattern == mime_type {
@@ -101,6 +234,95 @@ impl MimeAssociations {
 
         results
     }
+
+    /// Whether `desktop_id` is listed under `[Removed Associations]` for `mime_type`.
+    pub fn is_removed(&self, mime_type: &str, desktop_id: &str) -> bool {
+        self.removed
+            .get(&normalize_mime_key(mime_type))
+            .is_some_and(|ids| ids.contains(normalize_desktop_id(desktop_id)))
+    }
+
+    /// Whether `desktop_id` is listed under `[Default Applications]` for `mime_type`,
+    /// either exactly or through a matching wildcard key.
+    pub fn is_listed_default(&self, mime_type: &str, desktop_id: &str) -> bool {
+        let desktop_id = normalize_desktop_id(desktop_id);
+        self.defaults.iter().any(|(pattern, ids)| {
+            ids.contains(desktop_id) && mime_pattern::matches(pattern, mime_type)
+        })
+    }
+
+    /// The merged default handler of every MIME key with one, sorted by key: the first
+    /// handler still listed under `[Default Applications]` once higher-precedence files have
+    /// overridden and removed entries. Wildcard keys such as `image/*` are kept as written.
+    pub fn effective_defaults(&self) -> BTreeMap<String, String> {
+        self.defaults
+            .iter()
+            .filter_map(|(mime, ids)| {
+                let handlers = self.associations.get(mime)?;
+                let default = handlers
+                    .iter()
+                    .map(|handler| normalize_desktop_id(handler))
+                    .find(|id| ids.contains(*id))?;
+                Some((mime.clone(), default.to_string()))
+            })
+            .collect()
+    }
+
+    /// The reverse of `get_associations`: every associated desktop ID with the MIME keys
+    /// that list it, sorted by MIME key. Wildcard keys such as `image/*` are kept as written.
+    pub fn reverse_index(&self) -> BTreeMap<String, Vec<HandlerAssociation>> {
+        let mut index: BTreeMap<String, Vec<HandlerAssociation>> = BTreeMap::new();
+        for (mime, handlers) in &self.associations {
+            let mut seen = HashSet::new();
+            for (position, handler) in handlers.iter().enumerate() {
+                let desktop_id = normalize_desktop_id(handler);
+                if !seen.insert(desktop_id) {
+                    continue;
+                }
+                index
+                    .entry(desktop_id.to_string())
+                    .or_default()
+                    .push(HandlerAssociation {
+                        mime: mime.clone(),
+                        position,
+                        is_default: self
+                            .defaults
+                            .get(mime)
+                            .is_some_and(|ids| ids.contains(desktop_id)),
+                    });
+            }
+        }
+        for associations in index.values_mut() {
+            associations.sort_by(|a, b| a.mime.cmp(&b.mime));
+        }
+        index
+    }
+}
+
+/// Independent of map iteration order, so the same files hash alike in every process.
+impl Hash for MimeAssociations {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let associations: BTreeMap<&String, &Vec<String>> = self.associations.iter().collect();
+        associations.hash(state);
+        for section in [&self.defaults, &self.removed] {
+            let section: BTreeMap<&String, BTreeSet<&String>> = section
+                .iter()
+                .map(|(mime, ids)| (mime, ids.iter().collect()))
+                .collect();
+            section.hash(state);
+        }
+    }
+}
+
+/// MIME types compare case-insensitively, so keys are stored and looked up in lowercase.
+pub fn normalize_mime_key(mime: &str) -> String {
+    mime.trim().to_ascii_lowercase()
+}
+
+/// Reduce an association value such as `applications/foo.desktop` to its desktop ID.
+pub fn normalize_desktop_id(desktop_id: &str) -> &str {
+    let trimmed = desktop_id.trim();
+    trimmed.rsplit(['/', '\\']).next().unwrap_or(trimmed)
 }
 
 #[cfg(test)]
@@ -226,10 +448,196 @@ image/png=viewer.desktop;";
         );
     }
 
+    #[test]
+    fn test_is_listed_default_tracks_section() {
+        let associations = MimeAssociations::from_mimeapps_contents(
+            "[Default Applications]\n\
+             image/*=applications/viewer.desktop;\n\
+             [Added Associations]\n\
+             image/png=editor.desktop;\n",
+        );
+
+        assert!(associations.is_listed_default("image/png", "viewer.desktop"));
+        assert!(!associations.is_listed_default("image/png", "editor.desktop"));
+        assert!(!associations.is_listed_default("text/plain", "viewer.desktop"));
+    }
+
+    #[test]
+    fn test_reverse_index_lists_every_mime_of_a_handler() {
+        let associations = MimeAssociations::from_mimeapps_contents(
+            "[Default Applications]\n\
+             text/html=applications/firefox.desktop;\n\
+             x-scheme-handler/http=firefox.desktop;\n\
+             [Added Associations]\n\
+             application/pdf=zathura.desktop;firefox.desktop;\n\
+             text/html=firefox.desktop;\n",
+        );
+
+        let index = associations.reverse_index();
+        assert_eq!(
+            index["firefox.desktop"],
+            vec![
+                HandlerAssociation {
+                    mime: "application/pdf".to_string(),
+                    position: 1,
+                    is_default: false,
+                },
+                HandlerAssociation {
+                    mime: "text/html".to_string(),
+                    position: 0,
+                    is_default: true,
+                },
+                HandlerAssociation {
+                    mime: "x-scheme-handler/http".to_string(),
+                    position: 0,
+                    is_default: true,
+                },
+            ]
+        );
+        assert_eq!(index["zathura.desktop"].len(), 1);
+    }
+
+    #[test]
+    fn test_normalize_desktop_id() {
+        assert_eq!(normalize_desktop_id("firefox.desktop"), "firefox.desktop");
+        assert_eq!(
+            normalize_desktop_id(" applications/firefox.desktop "),
+            "firefox.desktop"
+        );
+    }
+
+    #[test]
+    fn test_mime_keys_match_case_insensitively() {
+        let associations = MimeAssociations::from_mimeapps_contents(
+            "[Default Applications]\nText/Plain=helix.desktop;vim.desktop;\n\n[Added Associations]\ntext/PLAIN=code.desktop;\n\n[Removed Associations]\nTEXT/plain=vim.desktop;\n",
+        );
+
+        assert_eq!(
+            associations.get_associations("text/plain"),
+            vec!["helix.desktop", "code.desktop"]
+        );
+        assert!(associations.is_removed("Text/Plain", "vim.desktop"));
+        assert!(associations.is_listed_default("text/plain", "helix.desktop"));
+    }
+
+    #[test]
+    fn test_removed_associations_respect_precedence() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let user = temp_dir.path().join("user.list");
+        let system = temp_dir.path().join("system.list");
+        fs::write(
+            &user,
+            "[Removed Associations]\nimage/png=viewer.desktop;\n\
+             [Added Associations]\nimage/jpeg=viewer.desktop;\n",
+        )
+        .unwrap();
+        fs::write(
+            &system,
+            "[Added Associations]\nimage/png=viewer.desktop;gimp.desktop;\n\
+             [Removed Associations]\nimage/jpeg=viewer.desktop;\n",
+        )
+        .unwrap();
+
+        let associations = MimeAssociations::load_from_files(&[user, system]);
+
+        assert_eq!(
+            associations.get_associations("image/png"),
+            vec!["gimp.desktop"]
+        );
+        assert!(associations.is_removed("image/png", "viewer.desktop"));
+
+        // A higher-precedence file re-adding a handler overrides a lower removal.
+        assert_eq!(
+            associations.get_associations("image/jpeg"),
+            vec!["viewer.desktop"]
+        );
+        assert!(!associations.is_removed("image/jpeg", "viewer.desktop"));
+    }
+
+    /// A user `mimeapps.list` above a system one, as `get_mimeapps_list_files` orders them.
+    fn user_over_system() -> (tempfile::TempDir, Vec<PathBuf>) {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let user = temp_dir.path().join("config/mimeapps.list");
+        let system = temp_dir.path().join("xdg/mimeapps.list");
+        for (path, contents) in [
+            (
+                &user,
+                "[Default Applications]\ntext/plain=helix.desktop;\n\n[Removed Associations]\ntext/plain=nano.desktop;\n",
+            ),
+            (
+                &system,
+                "[Default Applications]\ntext/plain=gedit.desktop;\ntext/*=kate.desktop;\n\n[Added Associations]\ntext/plain=nano.desktop;vim.desktop;\n",
+            ),
+        ] {
+            fs::create_dir_all(path.parent().unwrap()).unwrap();
+            fs::write(path, contents).unwrap();
+        }
+        (temp_dir, vec![user, system])
+    }
+
     #[test]
     fn test_load_from_multiple_files() {
-        // This test would require mocking the file system
-        // For now, we'll test the parsing logic thoroughly above
-        // In a real scenario, you'd use a test fixture directory
+        let (_dir, files) = user_over_system();
+        let associations = MimeAssociations::load_from_files(&files);
+
+        assert_eq!(
+            associations.get_associations("text/plain"),
+            vec!["helix.desktop", "kate.desktop"]
+        );
+        assert!(associations.is_listed_default("text/plain", "helix.desktop"));
+        assert!(!associations.is_listed_default("text/plain", "gedit.desktop"));
+    }
+
+    #[test]
+    fn test_effective_defaults_follow_precedence() {
+        let (dir, mut files) = user_over_system();
+        let extra = dir.path().join("extra/mimeapps.list");
+        fs::create_dir_all(extra.parent().unwrap()).unwrap();
+        fs::write(
+            &extra,
+            "[Default Applications]\nimage/png=applications/imv.desktop;feh.desktop;\nvideo/mp4=mpv.desktop;\n\n\
+             [Removed Associations]\nvideo/mp4=mpv.desktop;\n",
+        )
+        .unwrap();
+        files.insert(0, extra);
+        let associations = MimeAssociations::load_from_files(&files);
+
+        let defaults = associations.effective_defaults();
+        let defaults: Vec<(&str, &str)> = defaults
+            .iter()
+            .map(|(mime, id)| (mime.as_str(), id.as_str()))
+            .collect();
+        assert_eq!(
+            defaults,
+            vec![
+                ("image/png", "imv.desktop"),
+                ("text/*", "kate.desktop"),
+                ("text/plain", "helix.desktop"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_contributions_keep_each_file_apart() {
+        let (dir, mut files) = user_over_system();
+        files.push(dir.path().join("missing/mimeapps.list"));
+
+        let contributions = MimeAssociations::contributions(&files, "Text/Plain");
+        assert_eq!(contributions.len(), 2);
+        assert_eq!(contributions[0].path, files[0]);
+        assert_eq!(contributions[0].default_applications, vec!["helix.desktop"]);
+        assert_eq!(contributions[0].removed_associations, vec!["nano.desktop"]);
+        assert_eq!(
+            contributions[1].default_applications,
+            vec!["gedit.desktop", "kate.desktop"]
+        );
+        assert_eq!(
+            contributions[1].added_associations,
+            vec!["nano.desktop", "vim.desktop"]
+        );
+
+        assert!(MimeAssociations::contributions(&files, "image/png")
+            .iter()
+            .all(FileContribution::is_empty));
     }
 }
@@ -0,0 +1,166 @@
+use crate::open_it::OpenIt;
+use crate::shortcut_files::ShortcutKind;
+use crate::target::LaunchTarget;
+use log::debug;
+use std::fmt::Debug;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Source of MIME types for launch targets.
+///
+/// Embedders can plug in content sniffing (`tree_magic`, `infer`, ...) through
+/// [`OpenIt::with_mime_detector`]. Returning `None` defers to the built-in detection.
+pub trait MimeDetector: Debug {
+    fn detect(&self, target: &LaunchTarget) -> Option<String>;
+}
+
+/// The default detection: `inode/directory`, the file extension, or the URI scheme.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct BuiltinMimeDetector;
+
+impl MimeDetector for BuiltinMimeDetector {
+    fn detect(&self, target: &LaunchTarget) -> Option<String> {
+        Some(OpenIt::mime_for_target(target))
+    }
+}
+
+/// Asks `file --mime-type -b` about regular files whose extension maps to no type and
+/// that are not shortcut files (`mime.use_file_command`); everything else defers to the built-in detection.
+#[derive(Debug, Clone)]
+pub struct FileCommandMimeDetector {
+    program: PathBuf,
+}
+
+impl Default for FileCommandMimeDetector {
+    fn default() -> Self {
+        Self {
+            program: PathBuf::from("file"),
+        }
+    }
+}
+
+impl FileCommandMimeDetector {
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_program(mut self, program: impl Into<PathBuf>) -> Self {
+        self.program = program.into();
+        self
+    }
+
+    fn query(&self, path: &Path) -> Option<String> {
+        let output = Command::new(&self.program)
+            .args(["--mime-type", "-b", "--"])
+            .arg(path)
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+            .inspect_err(|err| debug!("Could not run {}: {err}", self.program.display()))
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+
+        let size = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+        let mime = parse_file_output(&String::from_utf8_lossy(&output.stdout), size);
+        debug!("`file` reports {mime:?} for {}", path.display());
+        mime
+    }
+}
+
+impl MimeDetector for FileCommandMimeDetector {
+    fn detect(&self, target: &LaunchTarget) -> Option<String> {
+        match target {
+            LaunchTarget::File(path)
+                if path.is_file()
+                    && ShortcutKind::from_path(path).is_none()
+                    && mime_guess::from_path(path).first().is_none() =>
+            {
+                self.query(path)
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A `type/subtype` answer worth using, or `None` for errors and non-answers:
+/// `application/octet-stream` adds nothing, and `inode/*` only fits empty files.
+fn parse_file_output(output: &str, size: u64) -> Option<String> {
+    let mime: mime::Mime = output.trim().parse().ok()?;
+    let essence = mime.essence_str();
+    let bogus = essence == "application/octet-stream"
+        || (mime.type_() == "inode" && (size > 0 || essence != "inode/x-empty"));
+    (!bogus).then(|| essence.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::PermissionsExt;
+    use tempfile::TempDir;
+
+    #[test]
+    fn parse_file_output_validates_the_answer() {
+        assert_eq!(
+            parse_file_output("text/x-shellscript\n", 12).as_deref(),
+            Some("text/x-shellscript")
+        );
+        assert_eq!(
+            parse_file_output("inode/x-empty\n", 0).as_deref(),
+            Some("inode/x-empty")
+        );
+        assert_eq!(parse_file_output("inode/x-empty\n", 5), None);
+        assert_eq!(parse_file_output("inode/symlink\n", 0), None);
+        assert_eq!(parse_file_output("application/octet-stream\n", 5), None);
+        assert_eq!(
+            parse_file_output("cannot open `x' (No such file)\n", 0),
+            None
+        );
+        assert_eq!(parse_file_output("", 0), None);
+    }
+
+    fn fake_file_command(dir: &Path, answer: &str) -> PathBuf {
+        let script = dir.join("file");
+        fs::write(&script, format!("#!/bin/sh\necho '{answer}'\n")).unwrap();
+        fs::set_permissions(&script, fs::Permissions::from_mode(0o755)).unwrap();
+        script
+    }
+
+    #[test]
+    fn only_files_without_a_known_extension_reach_the_command() {
+        let temp_dir = TempDir::new().unwrap();
+        let detector = FileCommandMimeDetector::default()
+            .with_program(fake_file_command(temp_dir.path(), "text/x-python"));
+        let script = temp_dir.path().join("build-tool");
+        fs::write(&script, "#!/usr/bin/env python3\n").unwrap();
+        let notes = temp_dir.path().join("notes.md");
+        fs::write(&notes, "# Notes").unwrap();
+        let shortcut = temp_dir.path().join("Docs.webloc");
+        fs::write(&shortcut, "<plist></plist>").unwrap();
+
+        assert_eq!(
+            detector.detect(&LaunchTarget::File(script)).as_deref(),
+            Some("text/x-python")
+        );
+        assert_eq!(detector.detect(&LaunchTarget::File(notes)), None);
+        assert_eq!(detector.detect(&LaunchTarget::File(shortcut)), None);
+        assert_eq!(
+            detector.detect(&LaunchTarget::File(temp_dir.path().to_path_buf())),
+            None
+        );
+    }
+
+    #[test]
+    fn missing_command_or_bogus_answer_defers_to_the_builtin() {
+        let temp_dir = TempDir::new().unwrap();
+        let target = temp_dir.path().join("blob");
+        fs::write(&target, "data").unwrap();
+
+        let missing = FileCommandMimeDetector::default()
+            .with_program(temp_dir.path().join("no-such-file-command"));
+        assert_eq!(missing.detect(&LaunchTarget::File(target.clone())), None);
+
+        let bogus = FileCommandMimeDetector::default()
+            .with_program(fake_file_command(temp_dir.path(), "inode/x-empty"));
+        assert_eq!(bogus.detect(&LaunchTarget::File(target)), None);
+    }
+}
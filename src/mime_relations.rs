@@ -0,0 +1,222 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+static SYSTEM_RELATIONS: LazyLock<MimeRelations> =
+    LazyLock::new(|| MimeRelations::load_from_dirs(&crate::xdg::get_mime_database_dirs()));
+
+/// Prefix GNOME and Firefox use to register handlers for bare file extensions.
+const EXTENSION_PSEUDO_PREFIX: &str = "application/x-extension-";
+
+/// How a `mimeapps.list` key relates to the type file detection actually produces.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RelationKind {
+    /// Listed in shared-mime-info's `aliases` file.
+    Alias,
+    /// `application/x-extension-<ext>`, named after a file extension instead of a type.
+    ExtensionPseudoType,
+}
+
+/// The real type behind an alias or pseudo-type.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MimeRelation {
+    pub real: String,
+    pub kind: RelationKind,
+}
+
+impl MimeRelation {
+    /// Short note for text output, e.g. `pseudo-type for text/html`.
+    pub fn describe(&self) -> String {
+        match self.kind {
+            RelationKind::Alias => format!("alias of {}", self.real),
+            RelationKind::ExtensionPseudoType => format!("pseudo-type for {}", self.real),
+        }
+    }
+}
+
+/// Aliases and known types parsed from the shared-mime-info database.
+#[derive(Debug, Clone, Default)]
+pub struct MimeRelations {
+    aliases: HashMap<String, String>,
+    known: HashSet<String>,
+}
+
+impl MimeRelations {
+    /// Returns the database for the XDG data directories, parsed once per process.
+    pub fn load() -> Self {
+        SYSTEM_RELATIONS.clone()
+    }
+
+    /// Read `aliases` and `types` from each `mime` directory; earlier directories win.
+    pub fn load_from_dirs(dirs: &[PathBuf]) -> Self {
+        let mut relations = Self::default();
+        for dir in dirs {
+            if let Ok(contents) = fs::read_to_string(dir.join("aliases")) {
+                relations.parse_aliases(&contents);
+            }
+            if let Ok(contents) = fs::read_to_string(dir.join("types")) {
+                relations.parse_types(&contents);
+            }
+        }
+        relations
+    }
+
+    fn parse_aliases(&mut self, contents: &str) {
+        for line in data_lines(contents) {
+            let mut fields = line.split_whitespace();
+            if let (Some(alias), Some(canonical)) = (fields.next(), fields.next()) {
+                self.aliases
+                    .entry(alias.to_ascii_lowercase())
+                    .or_insert_with(|| canonical.to_ascii_lowercase());
+            }
+        }
+    }
+
+    fn parse_types(&mut self, contents: &str) {
+        for line in data_lines(contents) {
+            self.known.insert(line.to_ascii_lowercase());
+        }
+    }
+
+    /// The real type `mime` stands for, or `None` when it is already canonical.
+    pub fn related(&self, mime: &str) -> Option<MimeRelation> {
+        let mime = mime.trim().to_ascii_lowercase();
+        if let Some(real) = self.aliases.get(&mime) {
+            return Some(MimeRelation {
+                real: real.clone(),
+                kind: RelationKind::Alias,
+            });
+        }
+
+        let extension = mime.strip_prefix(EXTENSION_PSEUDO_PREFIX)?;
+        let guess = mime_guess::from_ext(extension).first()?;
+        let real = guess.essence_str();
+        let real = self
+            .aliases
+            .get(real)
+            .map_or_else(|| real.to_string(), Clone::clone);
+        Some(MimeRelation {
+            real,
+            kind: RelationKind::ExtensionPseudoType,
+        })
+    }
+
+    /// Whether the database knows `mime`. Scheme handlers and wildcards are not MIME
+    /// database entries and always pass, as does everything when no database is installed.
+    pub fn is_known(&self, mime: &str) -> bool {
+        let mime = mime.trim().to_ascii_lowercase();
+        self.known.is_empty()
+            || mime.starts_with("x-scheme-handler/")
+            || mime.contains('*')
+            || self.known.contains(&mime)
+            || self.aliases.contains_key(&mime)
+    }
+
+    /// Why writing a default for `mime` may not take effect, if the database doesn't know it.
+    pub fn unknown_type_warning(&self, mime: &str) -> Option<String> {
+        if self.is_known(mime) {
+            return None;
+        }
+        Some(match self.related(mime) {
+            Some(relation) => format!(
+                "{mime} is not in the shared MIME database; files are detected as {}, so that \
+                 type may need a default too",
+                relation.real
+            ),
+            None => format!(
+                "{mime} is not in the shared MIME database; no file will be detected as this type"
+            ),
+        })
+    }
+}
+
+fn data_lines(contents: &str) -> impl Iterator<Item = &str> {
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn database() -> (TempDir, MimeRelations) {
+        let temp_dir = TempDir::new().unwrap();
+        let mime_dir = temp_dir.path().join("mime");
+        fs::create_dir_all(&mime_dir).unwrap();
+        fs::write(
+            mime_dir.join("aliases"),
+            "application/x-pdf application/pdf\ntext/x-markdown text/markdown\n",
+        )
+        .unwrap();
+        fs::write(
+            mime_dir.join("types"),
+            "application/pdf\ntext/html\ntext/markdown\napplication/xhtml+xml\n",
+        )
+        .unwrap();
+        let relations = MimeRelations::load_from_dirs(&[mime_dir]);
+        (temp_dir, relations)
+    }
+
+    #[test]
+    fn extension_pseudo_types_map_to_real_types() {
+        let (_dir, relations) = database();
+
+        assert_eq!(
+            relations.related("application/x-extension-html"),
+            Some(MimeRelation {
+                real: "text/html".to_string(),
+                kind: RelationKind::ExtensionPseudoType,
+            })
+        );
+        assert_eq!(
+            relations
+                .related("Application/X-Extension-XHTML")
+                .map(|relation| relation.real),
+            Some("application/xhtml+xml".to_string())
+        );
+        assert_eq!(relations.related("application/x-extension-zzzz"), None);
+        assert_eq!(relations.related("text/html"), None);
+    }
+
+    #[test]
+    fn aliases_come_from_the_database() {
+        let (_dir, relations) = database();
+
+        let relation = relations.related("application/x-pdf").unwrap();
+        assert_eq!(relation.real, "application/pdf");
+        assert_eq!(relation.describe(), "alias of application/pdf");
+    }
+
+    #[test]
+    fn unknown_types_are_reported_only_with_a_database() {
+        let (_dir, relations) = database();
+
+        assert!(relations.is_known("text/html"));
+        assert!(relations.is_known("text/x-markdown"));
+        assert!(relations.is_known("x-scheme-handler/about"));
+        assert!(relations.is_known("image/*"));
+        assert!(!relations.is_known("application/x-extension-html"));
+        assert!(MimeRelations::default().is_known("application/x-extension-html"));
+    }
+
+    #[test]
+    fn unknown_type_warning_suggests_the_real_type() {
+        let (_dir, relations) = database();
+
+        assert_eq!(relations.unknown_type_warning("text/html"), None);
+        assert!(relations
+            .unknown_type_warning("application/x-extension-html")
+            .unwrap()
+            .contains("files are detected as text/html"));
+        assert!(relations
+            .unknown_type_warning("application/x-openit-bogus")
+            .unwrap()
+            .ends_with("no file will be detected as this type"));
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+static SYSTEM_SUBCLASSES: LazyLock<MimeSubclasses> =
+    LazyLock::new(|| MimeSubclasses::load_from_files(&crate::xdg::get_mime_subclasses_files()));
+
+/// Parent map parsed from shared-mime-info `subclasses` files.
+#[derive(Debug, Clone, Default)]
+pub struct MimeSubclasses {
+    parents: HashMap<String, Vec<String>>,
+}
+
+impl MimeSubclasses {
+    #[cfg(test)]
+    pub fn with_parents(parents: HashMap<String, Vec<String>>) -> Self {
+        Self { parents }
+    }
+
+    /// Returns the parent map for the XDG data directories, parsed once per process.
+    pub fn load() -> Self {
+        SYSTEM_SUBCLASSES.clone()
+    }
+
+    /// Merge the given `subclasses` files; earlier files take precedence in parent order.
+    pub fn load_from_files(files: &[PathBuf]) -> Self {
+        let mut subclasses = Self::default();
+        for file in files {
+            if let Ok(contents) = fs::read_to_string(file) {
+                subclasses.parse(&contents);
+            }
+        }
+        subclasses
+    }
+
+    fn parse(&mut self, contents: &str) {
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.split_whitespace();
+            let (Some(child), Some(parent)) = (fields.next(), fields.next()) else {
+                continue;
+            };
+
+            let parents = self.parents.entry(child.to_ascii_lowercase()).or_default();
+            let parent = parent.to_ascii_lowercase();
+            if !parents.contains(&parent) {
+                parents.push(parent);
+            }
+        }
+    }
+
+    /// All ancestors of `mime_type`, nearest first, without repeats or the type itself.
+    pub fn ancestors(&self, mime_type: &str) -> Vec<String> {
+        let start = mime_type.trim().to_ascii_lowercase();
+        let mut visited = HashSet::from([start.clone()]);
+        let mut queue = VecDeque::from([start]);
+        let mut ancestors = Vec::new();
+
+        while let Some(current) = queue.pop_front() {
+            let Some(parents) = self.parents.get(&current) else {
+                continue;
+            };
+
+            for parent in parents {
+                if visited.insert(parent.clone()) {
+                    ancestors.push(parent.clone());
+                    queue.push_back(parent.clone());
+                }
+            }
+        }
+
+        ancestors
+    }
+}
+
+/// Independent of map iteration order, so the same files hash alike in every process.
+impl Hash for MimeSubclasses {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let parents: BTreeMap<&String, &Vec<String>> = self.parents.iter().collect();
+        parents.hash(state);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn from_str(contents: &str) -> MimeSubclasses {
+        let mut subclasses = MimeSubclasses::default();
+        subclasses.parse(contents);
+        subclasses
+    }
+
+    #[test]
+    fn ancestors_are_breadth_first() {
+        let subclasses = from_str(
+            "text/x-python3 text/x-python\n\
+             text/x-python application/x-executable\n\
+             text/x-python text/plain\n",
+        );
+
+        assert_eq!(
+            subclasses.ancestors("text/x-python3"),
+            vec!["text/x-python", "application/x-executable", "text/plain"]
+        );
+        assert!(subclasses.ancestors("text/plain").is_empty());
+    }
+
+    #[test]
+    fn cyclic_definitions_terminate() {
+        let subclasses = from_str("a/one a/two\na/two a/three\na/three a/one\n");
+
+        assert_eq!(subclasses.ancestors("a/one"), vec!["a/two", "a/three"]);
+    }
+
+    #[test]
+    fn missing_files_yield_empty_map() {
+        let temp_dir = TempDir::new().unwrap();
+        let subclasses = MimeSubclasses::load_from_files(&[temp_dir.path().join("subclasses")]);
+
+        assert!(subclasses.ancestors("text/x-python").is_empty());
+    }
+
+    #[test]
+    fn user_file_is_merged_before_system() {
+        let temp_dir = TempDir::new().unwrap();
+        let user = temp_dir.path().join("user");
+        let system = temp_dir.path().join("system");
+        fs::write(&user, "text/x-custom text/x-python\n").unwrap();
+        fs::write(&system, "# comment\ntext/x-custom text/plain\n").unwrap();
+
+        let subclasses = MimeSubclasses::load_from_files(&[user, system]);
+        assert_eq!(
+            subclasses.ancestors("TEXT/X-CUSTOM"),
+            vec!["text/x-python", "text/plain"]
+        );
+    }
+}
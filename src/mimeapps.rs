@@ -1,19 +1,25 @@
+use crate::mime_associations::normalize_mime_key;
 use anyhow::{Context, Result};
 use itertools::Itertools;
-use std::collections::{BTreeMap, VecDeque};
+use nix::fcntl::{Flock, FlockArg};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet, HashSet, VecDeque};
+use std::ffi::OsString;
 use std::fs;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use wildmatch::WildMatch;
 
 const DEFAULT_SECTION: &str = "[Default Applications]";
 const ADDED_SECTION: &str = "[Added Associations]";
+const REMOVED_SECTION: &str = "[Removed Associations]";
 
 /// Represents the user's `mimeapps.list` associations.
 #[derive(Debug, Default, Clone)]
 pub struct MimeApps {
     default_apps: BTreeMap<String, DesktopList>,
     added_associations: BTreeMap<String, DesktopList>,
+    removed_associations: BTreeMap<String, DesktopList>,
 }
 
 impl MimeApps {
@@ -32,33 +38,81 @@ impl MimeApps {
     }
 
     /// Write the current associations back to disk.
-    pub fn save_to_disk(&self, path: Option<PathBuf>) -> Result<()> {
-        let path = path.unwrap_or_else(Self::default_path);
-
-        if let Some(parent) = path.parent() {
-            fs::create_dir_all(parent)
-                .with_context(|| format!("Failed to create {}", parent.display()))?;
+    ///
+    /// The file is replaced atomically through a temporary file in the same directory.
+    /// When `mimeapps.list` is a symlink, as dotfile managers leave it, the file it points
+    /// to is replaced and the link kept. With `backup`, the previous contents are kept in
+    /// `mimeapps.list.bak` next to `path`.
+    pub fn save_to_disk(&self, path: Option<PathBuf>, backup: bool) -> Result<()> {
+        let link = path.unwrap_or_else(Self::default_path);
+        fs::create_dir_all(parent_dir(&link))
+            .with_context(|| format!("Failed to create {}", parent_dir(&link).display()))?;
+
+        let previous = fs::metadata(&link).ok();
+        if backup && previous.is_some() {
+            let backup_path = sidecar_path(&link, "bak");
+            fs::copy(&link, &backup_path)
+                .with_context(|| format!("Failed to back up to {}", backup_path.display()))?;
         }
 
-        let mut file = fs::File::create(&path)
-            .with_context(|| format!("Failed to create {}", path.display()))?;
+        let path = resolve_symlinks(&link);
+        let parent = parent_dir(&path);
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+
+        let mut file = tempfile::Builder::new()
+            .prefix(".mimeapps.list.")
+            .tempfile_in(parent)
+            .with_context(|| {
+                format!("Failed to create a temporary file in {}", parent.display())
+            })?;
         self.write(&mut file)?;
+        if let Some(metadata) = previous {
+            fs::set_permissions(file.path(), metadata.permissions())?;
+        }
+        file.as_file()
+            .sync_all()
+            .with_context(|| format!("Failed to flush {}", file.path().display()))?;
+        file.persist(&path)
+            .with_context(|| format!("Failed to replace {}", path.display()))?;
         Ok(())
     }
 
+    /// Take an exclusive advisory lock on `mimeapps.list.lock`, waiting for concurrent
+    /// edits to finish. Hold it across load, edit and save.
+    pub fn lock(path: Option<PathBuf>) -> Result<MimeAppsLock> {
+        let path = path.unwrap_or_else(Self::default_path);
+        let parent = parent_dir(&path);
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+
+        let lock_path = sidecar_path(&path, "lock");
+        let file = fs::OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(&lock_path)
+            .with_context(|| format!("Failed to open {}", lock_path.display()))?;
+        let lock = Flock::lock(file, FlockArg::LockExclusive)
+            .map_err(|(_, errno)| errno)
+            .with_context(|| format!("Failed to lock {}", lock_path.display()))?;
+        Ok(MimeAppsLock { _lock: lock })
+    }
+
     /// Parse associations from a raw INI string.
     pub fn parse(contents: &str) -> Self {
         let mut current_section = None;
         let mut default_apps = BTreeMap::new();
         let mut added_associations = BTreeMap::new();
+        let mut removed_associations = BTreeMap::new();
 
         for line in contents.lines().map(str::trim) {
             if line.is_empty() || line.starts_with('#') {
                 continue;
             }
 
-            if let Some(section) = parse_section_header(line) {
-                current_section = Some(section);
+            if line.starts_with('[') && line.ends_with(']') {
+                current_section = parse_section_header(line);
                 continue;
             }
 
@@ -66,20 +120,30 @@ impl MimeApps {
                 let target_map: &mut BTreeMap<String, DesktopList> = match current_section {
                     Some(Section::DefaultApplications) => &mut default_apps,
                     Some(Section::AddedAssociations) => &mut added_associations,
-                    _ => continue,
+                    Some(Section::RemovedAssociations) => &mut removed_associations,
+                    None => continue,
                 };
 
                 if !handlers.is_empty() {
-                    let list = target_map.entry(mime.to_string()).or_default();
+                    let list = target_map.entry(normalize_mime_key(mime)).or_default();
                     list.extend(handlers.into_iter().map(str::to_owned));
-                    list.dedup();
                 }
             }
         }
 
+        // Once at the end rather than per line, so repeated keys stay linear.
+        for list in default_apps
+            .values_mut()
+            .chain(added_associations.values_mut())
+            .chain(removed_associations.values_mut())
+        {
+            list.dedup();
+        }
+
         Self {
             default_apps,
             added_associations,
+            removed_associations,
         }
     }
 
@@ -108,9 +172,58 @@ impl MimeApps {
 
         write_section(writer, DEFAULT_SECTION, &self.default_apps)?;
         write_section(writer, ADDED_SECTION, &self.added_associations)?;
+        write_section(writer, REMOVED_SECTION, &self.removed_associations)?;
         Ok(())
     }
 
+    /// Entries that differ between `self` (before) and `other` (after), section by section
+    /// and in MIME order; an empty list counts as no entry.
+    pub fn diff(&self, other: &MimeApps) -> Vec<MimeDiff> {
+        let sections = [
+            (DEFAULT_SECTION, &self.default_apps, &other.default_apps),
+            (
+                ADDED_SECTION,
+                &self.added_associations,
+                &other.added_associations,
+            ),
+            (
+                REMOVED_SECTION,
+                &self.removed_associations,
+                &other.removed_associations,
+            ),
+        ];
+
+        let mut diffs = Vec::new();
+        for (header, old, new) in sections {
+            let mimes: BTreeSet<&String> = old.keys().chain(new.keys()).collect();
+            for mime in mimes {
+                let handlers = |section: &BTreeMap<String, DesktopList>| -> Vec<String> {
+                    section
+                        .get(mime)
+                        .map(|list| list.iter().cloned().collect())
+                        .unwrap_or_default()
+                };
+                let old_handlers = handlers(old);
+                let new_handlers = handlers(new);
+                let kind = match (old_handlers.is_empty(), new_handlers.is_empty()) {
+                    (true, true) => continue,
+                    (true, false) => DiffKind::Added,
+                    (false, true) => DiffKind::Removed,
+                    _ if old_handlers == new_handlers => continue,
+                    _ => DiffKind::Changed,
+                };
+                diffs.push(MimeDiff {
+                    section: header.trim_start_matches('[').trim_end_matches(']'),
+                    mime: mime.clone(),
+                    kind,
+                    old_handlers,
+                    new_handlers,
+                });
+            }
+        }
+        diffs
+    }
+
     /// Replace the list of handlers for the provided mimetype pattern.
     pub fn set_handler(&mut self, pattern: &str, handlers: Vec<String>, expand_wildcards: bool) {
         self.apply_to_mimes(pattern, expand_wildcards, |entry| {
@@ -129,6 +242,21 @@ impl MimeApps {
         });
     }
 
+    /// Insert a handler at `position` (clamped to the list length), moving it there if it
+    /// is already listed.
+    pub fn insert_handler_at(
+        &mut self,
+        pattern: &str,
+        handler: String,
+        position: usize,
+        expand_wildcards: bool,
+    ) {
+        self.apply_to_mimes(pattern, expand_wildcards, |entry| {
+            entry.retain(|h| *h != handler);
+            entry.insert(position, handler.clone());
+        });
+    }
+
     /// Remove a handler from the mimetype pattern. When `handler` is `None`, the entire entry is removed.
     pub fn remove_handler(&mut self, pattern: &str, handler: Option<&str>, expand_wildcards: bool) {
         self.apply_to_mimes(pattern, expand_wildcards, |entry| {
@@ -143,9 +271,13 @@ impl MimeApps {
     }
 
     /// Return the handlers configured for the given MIME type.
-    #[cfg_attr(not(test), allow(dead_code))]
     pub fn handlers_for(&self, mime: &str) -> Option<&DesktopList> {
-        self.default_apps.get(mime)
+        self.default_apps.get(&normalize_mime_key(mime))
+    }
+
+    /// Return the `[Added Associations]` list for the given MIME type.
+    pub fn added_handlers_for(&self, mime: &str) -> Option<&DesktopList> {
+        self.added_associations.get(&normalize_mime_key(mime))
     }
 
     /// Expose the default applications map.
@@ -158,14 +290,21 @@ impl MimeApps {
         &self.added_associations
     }
 
+    /// Expose Removed Associations for consumers.
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn removed_associations(&self) -> &BTreeMap<String, DesktopList> {
+        &self.removed_associations
+    }
+
     fn apply_to_mimes<F>(&mut self, pattern: &str, expand_wildcards: bool, mut f: F)
     where
         F: FnMut(&mut DesktopList),
     {
-        let targets = self.resolve_targets(pattern, expand_wildcards);
+        let pattern = normalize_mime_key(pattern);
+        let targets = self.resolve_targets(&pattern, expand_wildcards);
 
         if targets.is_empty() && !expand_wildcards {
-            let entry = self.default_apps.entry(pattern.to_string()).or_default();
+            let entry = self.default_apps.entry(pattern).or_default();
             f(entry);
             return;
         }
@@ -190,13 +329,68 @@ impl MimeApps {
             .collect()
     }
 
-    fn default_path() -> PathBuf {
+    pub fn default_path() -> PathBuf {
         dirs::config_dir()
             .unwrap_or_else(|| PathBuf::from("."))
             .join("mimeapps.list")
     }
 }
 
+/// Advisory lock on `mimeapps.list`, released on drop.
+pub struct MimeAppsLock {
+    _lock: Flock<fs::File>,
+}
+
+/// The file `path` finally points to. A dangling link resolves to its target, so saving
+/// creates the file there instead of replacing the link.
+fn resolve_symlinks(path: &Path) -> PathBuf {
+    if let Ok(resolved) = fs::canonicalize(path) {
+        return resolved;
+    }
+    let mut current = path.to_path_buf();
+    for _ in 0..16 {
+        let Ok(target) = fs::read_link(&current) else {
+            break;
+        };
+        current = parent_dir(&current).join(target);
+    }
+    current
+}
+
+fn parent_dir(path: &Path) -> &Path {
+    path.parent()
+        .filter(|parent| !parent.as_os_str().is_empty())
+        .unwrap_or(Path::new("."))
+}
+
+/// `mimeapps.list` -> `mimeapps.list.<extension>`.
+fn sidecar_path(path: &Path, extension: &str) -> PathBuf {
+    let mut name = path.file_name().map(OsString::from).unwrap_or_default();
+    name.push(".");
+    name.push(extension);
+    path.with_file_name(name)
+}
+
+/// How one `MimeDiff` entry changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DiffKind {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// One entry that differs between two versions of `mimeapps.list`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct MimeDiff {
+    /// Section name without brackets, e.g. `Default Applications`.
+    pub section: &'static str,
+    pub mime: String,
+    pub kind: DiffKind,
+    pub old_handlers: Vec<String>,
+    pub new_handlers: Vec<String>,
+}
+
 /// Wrapper around the handler queue captured for a MIME type.
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct DesktopList(VecDeque<String>);
@@ -213,6 +407,11 @@ impl DesktopList {
         self.0.push_back(handler);
     }
 
+    /// Insert at `index`, or at the end when `index` is past it.
+    pub fn insert(&mut self, index: usize, handler: String) {
+        self.0.insert(index.min(self.0.len()), handler);
+    }
+
     pub fn clear(&mut self) {
         self.0.clear();
     }
@@ -237,15 +436,8 @@ impl DesktopList {
     }
 
     pub fn dedup(&mut self) {
-        let mut seen = Vec::new();
-        self.0.retain(|handler| {
-            if seen.contains(handler) {
-                false
-            } else {
-                seen.push(handler.clone());
-                true
-            }
-        });
+        let mut seen = HashSet::new();
+        self.0.retain(|handler| seen.insert(handler.clone()));
     }
 }
 
@@ -253,6 +445,7 @@ fn parse_section_header(line: &str) -> Option<Section> {
     match line {
         DEFAULT_SECTION => Some(Section::DefaultApplications),
         ADDED_SECTION => Some(Section::AddedAssociations),
+        REMOVED_SECTION => Some(Section::RemovedAssociations),
         _ => None,
     }
 }
@@ -271,13 +464,46 @@ fn parse_assignment(line: &str) -> Option<(&str, Vec<&str>)> {
 enum Section {
     DefaultApplications,
     AddedAssociations,
+    RemovedAssociations,
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::Xorshift;
     use tempfile::TempDir;
 
+    #[test]
+    fn mixed_case_keys_are_found_by_lowercase_queries() {
+        let apps = MimeApps::parse(
+            "[Default Applications]\nText/Plain=helix.desktop;vim.desktop;\n\n[Added Associations]\nIMAGE/png=gimp.desktop;\n",
+        );
+
+        assert_eq!(
+            apps.handlers_for("text/plain")
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec!["helix.desktop", "vim.desktop"]
+        );
+        assert!(apps.handlers_for("TEXT/PLAIN").is_some());
+        assert!(apps.added_handlers_for("image/png").is_some());
+    }
+
+    #[test]
+    fn writing_normalizes_mime_keys() {
+        let mut apps = MimeApps::parse("[Default Applications]\nText/Plain=helix.desktop;\n");
+        apps.add_handler("TEXT/plain", "vim.desktop".to_string(), false);
+        apps.set_handler("Image/PNG", vec!["imv.desktop".to_string()], false);
+
+        let mut output = Vec::new();
+        apps.write(&mut output).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "[Default Applications]\nimage/png=imv.desktop;\ntext/plain=helix.desktop;vim.desktop;\n\n"
+        );
+    }
+
     #[test]
     fn parse_and_write_round_trip() {
         let input = r#"[Default Applications]
@@ -296,6 +522,172 @@ text/plain=code.desktop;
         assert!(output_str.contains("text/plain=code.desktop;"));
     }
 
+    const FRAGMENTS: &[&str] = &[
+        "[Default Applications]",
+        "[Added Associations]",
+        "[Removed Associations]",
+        "[Other]",
+        "[",
+        "]",
+        "=",
+        ";",
+        ";;",
+        "#",
+        " ",
+        "\t",
+        "\r",
+        "text/plain",
+        "Image/PNG",
+        "*",
+        "a.desktop",
+        "b.desktop",
+        "\u{e9}",
+        "\u{0}",
+    ];
+
+    fn serialized(apps: &MimeApps) -> String {
+        let mut out = Vec::new();
+        apps.write(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn parse_write_parse_is_a_fixpoint() {
+        for seed in 1..=300 {
+            let mut rng = Xorshift::new(seed);
+            let input = rng.ini_like(FRAGMENTS, 12);
+
+            let first = MimeApps::parse(&input);
+            let written = serialized(&first);
+            let second = MimeApps::parse(&written);
+
+            assert_eq!(first.default_apps(), second.default_apps(), "seed {seed}");
+            assert_eq!(
+                first.added_associations(),
+                second.added_associations(),
+                "seed {seed}"
+            );
+            assert_eq!(
+                first.removed_associations(),
+                second.removed_associations(),
+                "seed {seed}"
+            );
+            assert_eq!(written, serialized(&second), "seed {seed}");
+        }
+    }
+
+    #[test]
+    fn arbitrary_bytes_parse_without_panicking() {
+        for seed in 1..=200 {
+            let mut rng = Xorshift::new(seed);
+            let len = rng.below(2048);
+            let input = String::from_utf8_lossy(&rng.bytes(len)).into_owned();
+            serialized(&MimeApps::parse(&input));
+        }
+    }
+
+    #[test]
+    fn many_handlers_under_one_key_parse_in_linear_time() {
+        let mut input = String::from("[Default Applications]\n");
+        for i in 0..50_000 {
+            input.push_str(&format!(
+                "text/plain=app{i}.desktop;app{}.desktop;\n",
+                i / 2
+            ));
+        }
+
+        let started = std::time::Instant::now();
+        let apps = MimeApps::parse(&input);
+        assert_eq!(
+            apps.handlers_for("text/plain").unwrap().iter().count(),
+            50_000
+        );
+        assert!(
+            started.elapsed() < std::time::Duration::from_secs(10),
+            "took {:?}",
+            started.elapsed()
+        );
+    }
+
+    #[test]
+    fn diff_reports_added_removed_and_changed_entries() {
+        let before = MimeApps::parse(
+            "[Default Applications]\ntext/plain=helix.desktop;\nimage/png=imv.desktop;\nvideo/mp4=mpv.desktop;\n\n[Added Associations]\nimage/png=gimp.desktop;\n",
+        );
+        let mut after = before.clone();
+        after.set_handler("text/plain", vec!["nvim.desktop".into()], false);
+        after.remove_handler("video/mp4", None, false);
+        after.add_handler("application/pdf", "zathura.desktop".into(), false);
+        after.add_handler("image/png", "imv.desktop".into(), false);
+
+        let diffs = before.diff(&after);
+        assert_eq!(
+            diffs,
+            vec![
+                MimeDiff {
+                    section: "Default Applications",
+                    mime: "application/pdf".into(),
+                    kind: DiffKind::Added,
+                    old_handlers: vec![],
+                    new_handlers: vec!["zathura.desktop".into()],
+                },
+                MimeDiff {
+                    section: "Default Applications",
+                    mime: "text/plain".into(),
+                    kind: DiffKind::Changed,
+                    old_handlers: vec!["helix.desktop".into()],
+                    new_handlers: vec!["nvim.desktop".into()],
+                },
+                MimeDiff {
+                    section: "Default Applications",
+                    mime: "video/mp4".into(),
+                    kind: DiffKind::Removed,
+                    old_handlers: vec!["mpv.desktop".into()],
+                    new_handlers: vec![],
+                },
+            ]
+        );
+        assert!(before.diff(&before).is_empty());
+
+        let json = serde_json::to_value(&diffs[1]).unwrap();
+        assert_eq!(json["kind"], "changed");
+        assert_eq!(json["section"], "Default Applications");
+    }
+
+    #[test]
+    fn removed_associations_survive_round_trip() {
+        let input = r#"[Added Associations]
+image/png=gimp.desktop;
+
+[Removed Associations]
+image/png=viewer.desktop;
+
+[Unknown Section]
+image/png=ignored.desktop;
+"#;
+
+        let apps = MimeApps::parse(input);
+        assert_eq!(
+            apps.added_associations()
+                .get("image/png")
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec!["gimp.desktop"]
+        );
+        assert!(apps
+            .removed_associations()
+            .get("image/png")
+            .unwrap()
+            .contains("viewer.desktop"));
+
+        let mut output = Vec::new();
+        apps.write(&mut output).unwrap();
+        let output_str = String::from_utf8(output).unwrap();
+        assert!(output_str.contains("[Removed Associations]\nimage/png=viewer.desktop;"));
+        assert!(!output_str.contains("ignored.desktop"));
+    }
+
     #[test]
     fn load_and_save_disk() {
         let tmp = TempDir::new().unwrap();
@@ -304,10 +696,156 @@ text/plain=code.desktop;
 
         let mut apps = MimeApps::load_from_disk(Some(path.clone())).unwrap();
         apps.add_handler("text/plain", "code.desktop".to_string(), false);
-        apps.save_to_disk(Some(path.clone())).unwrap();
+        apps.save_to_disk(Some(path.clone()), false).unwrap();
 
-        let contents = fs::read_to_string(path).unwrap();
+        let contents = fs::read_to_string(&path).unwrap();
         assert!(contents.contains("code.desktop"));
+        assert!(!tmp.path().join("mimeapps.list.bak").exists());
+    }
+
+    #[test]
+    fn insert_handler_at_places_handler_by_priority() {
+        let mut apps = MimeApps::default();
+        for handler in ["a.desktop", "b.desktop", "c.desktop"] {
+            apps.add_handler("text/plain", handler.to_string(), false);
+        }
+
+        apps.insert_handler_at("text/plain", "new.desktop".to_string(), 1, false);
+        let mut output = Vec::new();
+        apps.write(&mut output).unwrap();
+        assert!(String::from_utf8(output)
+            .unwrap()
+            .contains("text/plain=a.desktop;new.desktop;b.desktop;c.desktop;"));
+
+        apps.insert_handler_at("text/plain", "c.desktop".to_string(), 0, false);
+        apps.insert_handler_at("text/plain", "z.desktop".to_string(), 99, false);
+        assert_eq!(
+            apps.handlers_for("text/plain")
+                .unwrap()
+                .iter()
+                .collect::<Vec<_>>(),
+            vec![
+                "c.desktop",
+                "a.desktop",
+                "new.desktop",
+                "b.desktop",
+                "z.desktop"
+            ]
+        );
+    }
+
+    #[test]
+    fn save_keeps_backup_of_previous_contents() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("mimeapps.list");
+        let original = "[Default Applications]\ntext/plain=helix.desktop;\n";
+        fs::write(&path, original).unwrap();
+
+        let mut apps = MimeApps::load_from_disk(Some(path.clone())).unwrap();
+        apps.set_handler("text/plain", vec!["code.desktop".to_string()], false);
+        apps.save_to_disk(Some(path.clone()), true).unwrap();
+
+        let backup = fs::read_to_string(tmp.path().join("mimeapps.list.bak")).unwrap();
+        assert_eq!(backup, original);
+        assert!(fs::read_to_string(&path).unwrap().contains("code.desktop"));
+        // No temporary files are left behind.
+        let mut names: Vec<_> = fs::read_dir(tmp.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["mimeapps.list", "mimeapps.list.bak"]);
+    }
+
+    #[test]
+    fn save_writes_through_a_symlinked_mimeapps_list() {
+        let tmp = TempDir::new().unwrap();
+        let dotfiles = tmp.path().join("dotfiles");
+        let config = tmp.path().join("config");
+        fs::create_dir_all(&dotfiles).unwrap();
+        fs::create_dir_all(&config).unwrap();
+        let real = dotfiles.join("mimeapps.list");
+        fs::write(&real, "[Default Applications]\ntext/plain=helix.desktop;\n").unwrap();
+        let link = config.join("mimeapps.list");
+        std::os::unix::fs::symlink(&real, &link).unwrap();
+
+        let mut apps = MimeApps::load_from_disk(Some(link.clone())).unwrap();
+        apps.set_handler("text/plain", vec!["code.desktop".to_string()], false);
+        apps.save_to_disk(Some(link.clone()), true).unwrap();
+
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert!(fs::read_to_string(&real).unwrap().contains("code.desktop"));
+        assert!(fs::read_to_string(config.join("mimeapps.list.bak"))
+            .unwrap()
+            .contains("helix.desktop"));
+
+        // A dangling link gets its target created.
+        fs::remove_file(&real).unwrap();
+        apps.save_to_disk(Some(link.clone()), false).unwrap();
+        assert!(fs::symlink_metadata(&link)
+            .unwrap()
+            .file_type()
+            .is_symlink());
+        assert!(fs::read_to_string(&real).unwrap().contains("code.desktop"));
+    }
+
+    #[test]
+    fn concurrent_saves_never_expose_an_empty_file() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("mimeapps.list");
+        let mut apps = MimeApps::default();
+        for index in 0..200 {
+            apps.add_handler(
+                &format!("application/x-test-{index}"),
+                "a.desktop".into(),
+                false,
+            );
+        }
+        apps.save_to_disk(Some(path.clone()), false).unwrap();
+
+        let writer = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                for _ in 0..50 {
+                    apps.save_to_disk(Some(path.clone()), false).unwrap();
+                }
+            })
+        };
+
+        while !writer.is_finished() {
+            let contents = fs::read_to_string(&path).unwrap();
+            assert!(contents.contains("application/x-test-199"));
+        }
+        writer.join().unwrap();
+    }
+
+    #[test]
+    fn lock_serializes_edits() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("mimeapps.list");
+
+        let lock = MimeApps::lock(Some(path.clone())).unwrap();
+        let (sender, receiver) = std::sync::mpsc::channel();
+        let waiter = {
+            let path = path.clone();
+            std::thread::spawn(move || {
+                let _lock = MimeApps::lock(Some(path)).unwrap();
+                sender.send(()).unwrap();
+            })
+        };
+
+        assert!(receiver
+            .recv_timeout(std::time::Duration::from_millis(200))
+            .is_err());
+        drop(lock);
+        receiver
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .unwrap();
+        waiter.join().unwrap();
+        assert!(tmp.path().join("mimeapps.list.lock").exists());
     }
 
     #[test]
@@ -0,0 +1,67 @@
+use crate::template::run_template_command;
+use anyhow::Result;
+use log::debug;
+
+/// Used when `notify_on_launch` is set without a `notify_command`.
+pub const DEFAULT_NOTIFY_COMMAND: &str = "notify-send --app-name=openit {message}";
+
+/// Tells the user which application a target was handed to.
+pub trait LaunchNotifier: std::fmt::Debug {
+    fn notify(&self, app: &str, target: &str) -> Result<()>;
+}
+
+/// The configured `notify_command`: `{app}` is the handler's name, `{target}` the file
+/// name or URI, and `{message}` reads "Opening {target} with {app}".
+#[derive(Debug, Clone)]
+pub struct CommandNotifier {
+    template: String,
+}
+
+impl CommandNotifier {
+    pub fn new(template: impl Into<String>) -> Self {
+        Self {
+            template: template.into(),
+        }
+    }
+}
+
+impl LaunchNotifier for CommandNotifier {
+    fn notify(&self, app: &str, target: &str) -> Result<()> {
+        let message = format!("Opening {target} with {app}");
+        let status = run_template_command(
+            "notify_command",
+            &self.template,
+            &[("app", app), ("target", target), ("message", &message)],
+        )?;
+        debug!("notify_command for {app} exited with {status}");
+        if !status.success() {
+            anyhow::bail!("notify_command `{}` exited with {status}", self.template);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::TempDir;
+
+    #[test]
+    fn placeholders_expand_into_single_arguments() {
+        let temp_dir = TempDir::new().unwrap();
+        let log = temp_dir.path().join("notified");
+        let notifier = CommandNotifier::new(format!(
+            "sh -c 'printf \"%s|%s|%s\" \"$0\" \"$1\" \"$2\" > {}' {{app}} {{target}} {{message}}",
+            log.display()
+        ));
+
+        notifier.notify("Mozilla Firefox", "notes.txt").unwrap();
+        assert_eq!(
+            fs::read_to_string(&log).unwrap(),
+            "Mozilla Firefox|notes.txt|Opening notes.txt with Mozilla Firefox"
+        );
+
+        assert!(CommandNotifier::new("false").notify("a", "b").is_err());
+    }
+}
@@ -1,31 +1,46 @@
-use crate::cache::{DesktopCache, FileSystemCache};
+use crate::cache::{desktop_files_in, DesktopCache, FileSystemCache};
 use crate::cli::OpenArgs;
 use crate::config;
 use crate::desktop_parser::DesktopFile;
+use crate::mime_detector::{BuiltinMimeDetector, FileCommandMimeDetector, MimeDetector};
+use crate::timings::PhaseTimer;
 use anyhow::{Context, Result};
 use log::{debug, info};
 use std::env;
 use std::fs;
 use std::io;
 use std::path::PathBuf;
-use walkdir::WalkDir;
 
 pub(super) struct BootstrapOutcome {
     pub desktop_cache: Box<dyn DesktopCache>,
     pub config: config::Config,
+    pub mime_detector: Box<dyn MimeDetector>,
 }
 
-pub(super) fn initialize(args: &OpenArgs) -> Result<BootstrapOutcome> {
-    let config = config::Config::load(args.config.clone()).with_context(|| {
-        args.config
-            .as_ref()
-            .map(|path| format!("Failed to load configuration from {}", path.display()))
-            .unwrap_or_else(|| "Failed to load configuration".to_string())
-    })?;
+pub(super) fn initialize(args: &OpenArgs, timer: &PhaseTimer) -> Result<BootstrapOutcome> {
+    let config = timer
+        .measure("config load", || config::Config::load(args.config.clone()))
+        .with_context(|| {
+            args.config
+                .as_ref()
+                .map(|path| format!("Failed to load configuration from {}", path.display()))
+                .unwrap_or_else(|| "Failed to load configuration".to_string())
+        })?;
+
+    let mime_detector: Box<dyn MimeDetector> = if config.mime.use_file_command {
+        Box::new(FileCommandMimeDetector::default())
+    } else {
+        Box::new(BuiltinMimeDetector)
+    };
 
     Ok(BootstrapOutcome {
-        desktop_cache: load_desktop_cache(),
+        desktop_cache: load_desktop_cache_timed(
+            timer,
+            config.recursive_scan,
+            config.cache_max_entries,
+        ),
         config,
+        mime_detector,
     })
 }
 
@@ -45,11 +60,21 @@ pub(crate) fn clear_cache() -> Result<()> {
     Ok(())
 }
 
-pub(crate) fn load_desktop_cache() -> Box<dyn DesktopCache> {
-    let cache_path = cache_path();
-    let mut cache = FileSystemCache::new(cache_path);
+pub(crate) fn load_desktop_cache(
+    recursive: bool,
+    max_entries: Option<usize>,
+) -> Box<dyn DesktopCache> {
+    load_desktop_cache_timed(&PhaseTimer::default(), recursive, max_entries)
+}
+
+fn load_desktop_cache_timed(
+    timer: &PhaseTimer,
+    recursive: bool,
+    max_entries: Option<usize>,
+) -> Box<dyn DesktopCache> {
+    let mut cache = FileSystemCache::with_max_entries(cache_path(), max_entries);
 
-    if let Err(e) = cache.load() {
+    if let Err(e) = timer.measure("cache load", || cache.load()) {
         debug!("Failed to load cache: {e}");
     }
 
@@ -58,16 +83,22 @@ pub(crate) fn load_desktop_cache() -> Box<dyn DesktopCache> {
     let rebuild = cache.needs_invalidation() || cache.is_empty();
 
     if rebuild {
-        debug!("Building desktop file cache");
-        cache.clear();
-        cache_updated |= populate_cache_from_dirs(&mut cache, &desktop_dirs, true);
+        cache_updated |= timer.measure("cache rebuild", || {
+            repopulate(&mut cache, &desktop_dirs, recursive)
+        });
     } else {
         debug!("Loaded desktop cache from disk");
-        cache_updated |= populate_cache_from_dirs(&mut cache, &desktop_dirs, false);
+        // Files a `cache_max_entries` cap kept out of the saved cache are parsed again
+        // unchanged on every run; only a new generation is worth writing back.
+        let generation = cache.generation();
+        timer.measure("cache populate", || {
+            populate_cache_from_dirs(&mut cache, &desktop_dirs, false, recursive)
+        });
+        cache_updated |= cache.generation() != generation;
     }
 
     if rebuild || cache_updated {
-        if let Err(e) = cache.save() {
+        if let Err(e) = timer.measure("cache save", || cache.save()) {
             debug!("Failed to save cache: {e}");
         }
     }
@@ -75,58 +106,76 @@ pub(crate) fn load_desktop_cache() -> Box<dyn DesktopCache> {
     Box::new(cache)
 }
 
+/// Reparse every desktop file under `desktop_dirs` into a fresh cache and save it, however
+/// stale or broken the old one was.
+pub(crate) fn rebuild_desktop_cache(
+    desktop_dirs: &[PathBuf],
+    recursive: bool,
+    max_entries: Option<usize>,
+) -> Result<FileSystemCache> {
+    let mut cache = FileSystemCache::with_max_entries(cache_path(), max_entries);
+    repopulate(&mut cache, desktop_dirs, recursive);
+    cache.save()?;
+    Ok(cache)
+}
+
+fn repopulate(cache: &mut FileSystemCache, desktop_dirs: &[PathBuf], recursive: bool) -> bool {
+    debug!("Building desktop file cache");
+    cache.clear();
+    populate_cache_from_dirs(cache, desktop_dirs, true, recursive)
+}
+
+/// Add the `.desktop` files under `desktop_dirs` to `cache`, reparsing cached ones when
+/// `force` is set. Cache keys keep the nested path, e.g. `applications/kde/foo.desktop`.
 pub(crate) fn populate_cache_from_dirs(
     cache: &mut FileSystemCache,
     desktop_dirs: &[PathBuf],
     force: bool,
+    recursive: bool,
 ) -> bool {
     let mut updated = false;
 
+    // Entries a previous recursive scan found in subdirectories.
+    if !recursive {
+        let nested: Vec<PathBuf> = cache
+            .iter()
+            .map(|(path, _)| path.clone())
+            .filter(|path| {
+                desktop_dirs.iter().any(|dir| {
+                    path.starts_with(dir) && path.parent().is_some_and(|parent| parent != dir)
+                })
+            })
+            .collect();
+        for path in nested {
+            DesktopCache::remove(cache, &path);
+            updated = true;
+        }
+    }
+
     for dir in desktop_dirs {
         if !dir.exists() {
             debug!("Directory does not exist: {}", dir.display());
             continue;
         }
 
-        for entry in WalkDir::new(dir)
-            .follow_links(false)
-            .into_iter()
-            .filter_entry(|e| {
-                e.file_name()
-                    .to_str()
-                    .map(|s| !s.starts_with('.'))
-                    .unwrap_or(false)
-            })
-            .filter_map(|e| e.ok())
-        {
-            let path = entry.path();
-
-            if !path.is_file() {
-                continue;
-            }
-
-            if path.extension().and_then(|s| s.to_str()) != Some("desktop") {
-                continue;
-            }
-
+        for path in desktop_files_in(dir, recursive) {
             let already_cached = if force {
                 false
             } else {
-                DesktopCache::get(&*cache, path).is_some()
+                DesktopCache::get(&*cache, &path).is_some()
             };
 
             if already_cached {
                 continue;
             }
 
-            match DesktopFile::parse(path) {
+            match DesktopFile::parse(&path) {
                 Ok(desktop_file) => {
-                    DesktopCache::insert(cache, path.to_path_buf(), desktop_file);
+                    DesktopCache::insert(cache, path, desktop_file);
                     updated = true;
                 }
-                Err(e) => {
-                    debug!("Failed to parse {}: {}", path.display(), e);
-                }
+                // One broken file must not abort the scan of the rest.
+                Err(err) => debug!("Skipping {}: {err:#}", path.display()),
             }
         }
     }
@@ -144,3 +193,13 @@ pub(crate) fn cache_path() -> PathBuf {
         .join("openit")
         .join("desktop_cache.json")
 }
+
+/// Negative lookups for `persist_no_handler_mimes`, kept beside the desktop cache.
+pub(crate) fn no_handler_mimes_path() -> PathBuf {
+    cache_path().with_file_name("no_handler_mimes.json")
+}
+
+/// Launch history, kept beside the desktop cache.
+pub(crate) fn history_path() -> PathBuf {
+    cache_path().with_file_name("history.json")
+}
@@ -1,78 +1,437 @@
 use super::OpenIt;
-use crate::application_finder::{ApplicationEntry, ApplicationSource};
-use crate::config::TerminalExecution;
-use crate::executor::{ApplicationExecutor, LaunchDisposition};
+use crate::application_finder::ApplicationFinder;
+use crate::application_finder::{ApplicationEntry, ApplicationSource, Association};
+use crate::config::{Config, TerminalExecution, TerminalPurpose};
+use crate::executor::{ApplicationExecutor, LaunchDisposition, TerminalLauncher};
+use crate::history::LaunchHistory;
 use crate::regex_handlers::RegexHandler;
 use crate::target::LaunchTarget;
 use anyhow::{Context, Result};
-use log::info;
+use log::{debug, info, warn};
+use std::ffi::OsString;
+use std::io::{self, IsTerminal};
 use std::path::PathBuf;
+use std::time::Duration;
 
 impl OpenIt {
     pub(super) fn execute_application(
         &self,
         app: &ApplicationEntry,
         target: &LaunchTarget,
+        mime_type: &str,
     ) -> Result<()> {
-        if app.requires_terminal {
-            match self.config.terminal_execution {
-                TerminalExecution::Current => {
-                    self.executor
-                        .execute(app, target, None, LaunchDisposition::InheritTerminal)
-                }
-                TerminalExecution::Launcher => {
-                    let launcher = self.resolve_terminal_launcher()?;
-                    self.executor.execute(
-                        app,
-                        target,
-                        Some(launcher.as_slice()),
-                        LaunchDisposition::Detached,
-                    )
-                }
+        if self.args.dry_run {
+            println!("{}", self.dry_run_line(app, target, mime_type)?);
+            return Ok(());
+        }
+
+        if self.focus_existing(app) {
+            return Ok(());
+        }
+
+        if app.confirm {
+            let question = format!("Open {} with `{}`?", target.as_command_argument(), app.exec);
+            if !crate::prompt::confirm(&question, self.assume_yes)? {
+                info!("Launch of `{}` declined", app.name);
+                return Ok(());
             }
-        } else {
-            self.executor
-                .execute(app, target, None, LaunchDisposition::Detached)
         }
+
+        let (launcher, disposition) = self.launch_mode(app, target)?;
+        let claimed = self.claim_launch(app);
+        debug_assert!(
+            claimed,
+            "openit tried to launch `{}` after an application was already launched",
+            app.name
+        );
+        if !claimed {
+            return Ok(());
+        }
+        // The disposition alone decides how the target is passed; `launch_mode` never pairs
+        // a terminal launcher with a piped launch.
+        self.executor
+            .execute(app, target, Some(mime_type), launcher.as_ref(), disposition)?;
+        self.record_launch(app, mime_type);
+        self.notify_launch(app, target);
+        Ok(())
+    }
+
+    /// Mark this invocation as having launched; `false` when an application already was.
+    /// Claimed right before spawning, so a dry run, a focused window or a declined prompt
+    /// leave the launch available. A spawn that fails still counts.
+    pub(super) fn claim_launch(&self, app: &ApplicationEntry) -> bool {
+        if !self.launched.replace(true) {
+            return true;
+        }
+        warn!(
+            "Skipping launch of `{}`: an application was already launched",
+            app.name
+        );
+        false
+    }
+
+    /// A notification that cannot be sent is logged; the launch already succeeded.
+    fn notify_launch(&self, app: &ApplicationEntry, target: &LaunchTarget) {
+        let Some(notifier) = &self.notifier else {
+            return;
+        };
+        if let Err(err) = notifier.notify(&app.name, &target.display_name()) {
+            warn!("Could not send the launch notification: {err:#}");
+        }
+    }
+
+    /// `SingleMainWindow=true`: raise the application's open window instead of starting
+    /// another copy. Only entries whose `Exec` takes no file or URL are focused; the others
+    /// launch as usual, since a single-instance application forwards the target to its
+    /// open window and focusing would drop it. Actions and `--pipe` launches always start
+    /// a process.
+    pub(super) fn focus_existing(&self, app: &ApplicationEntry) -> bool {
+        if !app.single_main_window
+            || app.action_id.is_some()
+            || self.args.pipe
+            || ApplicationExecutor::exec_takes_targets(&app.exec)
+        {
+            return false;
+        }
+        let (Some(query), Some(class)) = (&self.window_query, app.window_class()) else {
+            return false;
+        };
+
+        match query.focus(&class) {
+            Ok(true) => {
+                info!(
+                    "Focused the open window of `{}` (class {class}) instead of launching it",
+                    app.name
+                );
+                true
+            }
+            Ok(false) => {
+                debug!(
+                    "No open window with class {class}; launching `{}`",
+                    app.name
+                );
+                false
+            }
+            Err(err) => {
+                warn!("Could not focus an open window of `{}`: {err:#}", app.name);
+                false
+            }
+        }
+    }
+
+    /// Remember `app` for `--recent`. Regex handlers and custom commands have no desktop
+    /// file to relaunch, and a history that cannot be written never fails the launch.
+    pub(super) fn record_launch(&self, app: &ApplicationEntry, mime_type: &str) {
+        if !self.config.record_history {
+            return;
+        }
+        let Some(path) = &self.history_path else {
+            return;
+        };
+        if !app.desktop_file.is_absolute() {
+            return;
+        }
+        let Some(desktop_id) = app.desktop_file.file_name().and_then(|name| name.to_str()) else {
+            return;
+        };
+
+        if let Err(err) = LaunchHistory::record_at(path, mime_type, desktop_id) {
+            warn!("Could not update the launch history: {err:#}");
+        }
+    }
+
+    /// `--dry-run` output: the argv launching `app` would run, quoted for a shell.
+    pub(super) fn dry_run_line(
+        &self,
+        app: &ApplicationEntry,
+        target: &LaunchTarget,
+        mime_type: &str,
+    ) -> Result<String> {
+        Ok(shell_words::join(
+            self.launch_command(app, target, mime_type)?,
+        ))
     }
 
-    pub(crate) fn resolve_terminal_launcher(&self) -> Result<Vec<String>> {
-        let mut candidates = self
-            .application_finder
-            .find_for_mime("x-scheme-handler/terminal", false);
+    /// The argv launching `app` would run, for `--dry-run`.
+    pub(super) fn launch_command(
+        &self,
+        app: &ApplicationEntry,
+        target: &LaunchTarget,
+        mime_type: &str,
+    ) -> Result<Vec<String>> {
+        let (launcher, disposition) = self.launch_mode(app, target)?;
+        self.executor
+            .command_for(app, target, Some(mime_type), launcher.as_ref(), disposition)
+    }
 
-        if candidates.is_empty() {
-            candidates = self.application_finder.find_terminal_emulators();
+    /// Piped for stdin handlers and `--pipe`; terminal apps run in the current terminal or
+    /// through a resolved terminal launcher; everything else is detached, or waited on
+    /// with `--wait`.
+    fn launch_mode(
+        &self,
+        app: &ApplicationEntry,
+        target: &LaunchTarget,
+    ) -> Result<(Option<TerminalLauncher>, LaunchDisposition)> {
+        if app.stdin_input || self.args.pipe {
+            self.check_piped_terminal(app, io::stdout().is_terminal())?;
+            return Ok((None, LaunchDisposition::Piped));
+        }
+        let background = if self.args.wait {
+            let timeout = self.args.wait_timeout.or(self.config.wait_timeout);
+            LaunchDisposition::Wait(timeout.map(Duration::from_secs))
+        } else {
+            LaunchDisposition::Detached
+        };
+        if !self.executor.requires_terminal(app) {
+            return Ok((None, background));
         }
 
-        if candidates.is_empty() {
+        match self.config.terminal_execution {
+            TerminalExecution::Current => Ok((None, LaunchDisposition::InheritTerminal)),
+            TerminalExecution::Launcher => {
+                let purpose = if target.as_path().is_some_and(|path| path.is_dir()) {
+                    TerminalPurpose::Directory
+                } else {
+                    TerminalPurpose::Handler
+                };
+                let launcher = self.resolve_terminal_launcher(purpose)?;
+                Ok((Some(launcher), background))
+            }
+        }
+    }
+
+    /// A piped handler runs in the foreground with the file on stdin, so a terminal
+    /// launcher cannot be put around it; a `Terminal=true` one needs `openit` itself to
+    /// run in a terminal.
+    pub(super) fn check_piped_terminal(
+        &self,
+        app: &ApplicationEntry,
+        stdout_is_terminal: bool,
+    ) -> Result<()> {
+        if self.executor.requires_terminal(app) && !stdout_is_terminal {
             anyhow::bail!(
-                "No terminal emulator found. Install a terminal or associate one with x-scheme-handler/terminal."
+                "`{}` needs a terminal, but a piped handler can only use the one openit runs in, and openit is not running in a terminal",
+                app.name
             );
         }
+        Ok(())
+    }
 
-        let terminal_app = candidates
-            .iter()
-            .find(|app| !app.requires_terminal)
-            .or_else(|| candidates.first())
-            .ok_or_else(|| anyhow::anyhow!("No suitable terminal emulator found"))?;
+    pub(crate) fn resolve_terminal_launcher(
+        &self,
+        purpose: TerminalPurpose,
+    ) -> Result<TerminalLauncher> {
+        self.resolve_terminal_launcher_in(&TerminalEnv::current(), purpose)
+    }
 
+    /// `resolve_terminal_launcher_with` for this invocation's config and desktop entries.
+    pub(crate) fn resolve_terminal_launcher_in(
+        &self,
+        env: &TerminalEnv,
+        purpose: TerminalPurpose,
+    ) -> Result<TerminalLauncher> {
+        resolve_terminal_launcher_with(&self.config, &self.application_finder, env, purpose)
+    }
+}
+
+/// Resolution order: the terminal pinned for `purpose` (`terminal_for_handlers` or
+/// `terminal_for_directory`), the `terminal` config key, `xdg-terminal-exec` on `PATH`,
+/// `$TERMINAL`, the `x-scheme-handler/terminal` association, then any
+/// `TerminalEmulator` entry.
+pub(crate) fn resolve_terminal_launcher_with(
+    config: &Config,
+    finder: &ApplicationFinder,
+    env: &TerminalEnv,
+    purpose: TerminalPurpose,
+) -> Result<TerminalLauncher> {
+    info!(
+        "Terminal resolution order: pinned terminal, config `terminal`, xdg-terminal-exec, \
+         $TERMINAL, x-scheme-handler/terminal, TerminalEmulator category"
+    );
+
+    if let Some((key, desktop_id)) = config.pinned_terminal(purpose) {
+        let terminal_app = pinned_terminal(finder, key, desktop_id)?;
         info!(
-            "Using terminal emulator `{}` ({})",
+            "Using terminal emulator `{}` from `{key}` ({})",
             terminal_app.name,
             terminal_app.desktop_file.display()
         );
+        return terminal_from_entry(&terminal_app);
+    }
+
+    if let Some(configured) = config
+        .terminal
+        .as_deref()
+        .map(str::trim)
+        .filter(|value| !value.is_empty())
+    {
+        return configured_terminal(finder, configured);
+    }
+
+    if let Some(path) = env.which("xdg-terminal-exec") {
+        info!(
+            "Using terminal launcher {} (xdg-terminal-exec)",
+            path.display()
+        );
+        return Ok(TerminalLauncher::without_exec_args(vec![path
+            .to_string_lossy()
+            .into_owned()]));
+    }
 
-        ApplicationExecutor::base_command_parts(&terminal_app.exec).with_context(|| {
+    if let Some(terminal) = env
+        .terminal
+        .as_deref()
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+    {
+        match shell_words::split(terminal) {
+            Ok(parts) if parts.first().is_some_and(|cmd| env.which(cmd).is_some()) => {
+                info!("Using terminal `{terminal}` from $TERMINAL");
+                return Ok(TerminalLauncher::new(parts));
+            }
+            _ => debug!("Ignoring $TERMINAL `{terminal}`: command not found"),
+        }
+    }
+
+    let mut candidates = finder.find_for_mime("x-scheme-handler/terminal", false);
+
+    if candidates.is_empty() {
+        candidates = finder.find_terminal_emulators();
+    }
+
+    if candidates.is_empty() {
+        anyhow::bail!(
+            "No terminal emulator found. Install a terminal or associate one with x-scheme-handler/terminal."
+        );
+    }
+
+    let terminal_app = candidates
+        .iter()
+        .find(|app| !app.requires_terminal)
+        .or_else(|| candidates.first())
+        .ok_or_else(|| anyhow::anyhow!("No suitable terminal emulator found"))?;
+
+    info!(
+        "Using terminal emulator `{}` ({})",
+        terminal_app.name,
+        terminal_app.desktop_file.display()
+    );
+
+    terminal_from_entry(terminal_app)
+}
+
+fn configured_terminal(finder: &ApplicationFinder, configured: &str) -> Result<TerminalLauncher> {
+    if configured.ends_with(".desktop") {
+        let (path, desktop_file) = finder.find_desktop_file(configured).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Configured terminal `{configured}` not found in available applications"
+            )
+        })?;
+        let entry = desktop_file.main_entry.as_ref().ok_or_else(|| {
+            anyhow::anyhow!("Configured terminal `{configured}` has no [Desktop Entry] group")
+        })?;
+        let terminal_app = ApplicationEntry::from_desktop_entry(entry, path.clone());
+        info!(
+            "Using terminal emulator `{}` from config ({})",
+            terminal_app.name,
+            path.display()
+        );
+        return terminal_from_entry(&terminal_app);
+    }
+
+    let parts = shell_words::split(configured)
+        .map_err(|e| anyhow::anyhow!("Failed to parse configured terminal `{configured}`: {e}"))?;
+    if parts.is_empty() {
+        anyhow::bail!("Configured terminal `{configured}` produced no command parts");
+    }
+    info!("Using terminal `{configured}` from config");
+    Ok(TerminalLauncher::new(parts))
+}
+
+/// One message per `terminal_for_handlers` or `terminal_for_directory` pin that names no
+/// detected emulator. Launches only fail on a stale pin when they need that terminal.
+pub(super) fn stale_pinned_terminals(config: &Config, finder: &ApplicationFinder) -> Vec<String> {
+    [TerminalPurpose::Handler, TerminalPurpose::Directory]
+        .into_iter()
+        .filter_map(|purpose| config.pinned_terminal(purpose))
+        .filter_map(|(key, desktop_id)| pinned_terminal(finder, key, desktop_id).err())
+        .map(|err| err.to_string())
+        .collect()
+}
+
+/// The detected terminal emulator whose desktop ID is `desktop_id` (`.desktop` optional).
+fn pinned_terminal(
+    finder: &ApplicationFinder,
+    key: &str,
+    desktop_id: &str,
+) -> Result<ApplicationEntry> {
+    let wanted = if desktop_id.ends_with(".desktop") {
+        desktop_id.to_string()
+    } else {
+        format!("{desktop_id}.desktop")
+    };
+
+    let emulators = finder.find_terminal_emulators();
+    if let Some(app) = emulators.iter().find(|app| {
+        app.desktop_file
+            .file_name()
+            .is_some_and(|name| name == wanted.as_str())
+    }) {
+        return Ok(app.clone());
+    }
+
+    let mut detected: Vec<String> = emulators
+        .iter()
+        .filter_map(|app| app.desktop_file.file_name())
+        .map(|name| name.to_string_lossy().into_owned())
+        .collect();
+    detected.sort();
+    let detected = if detected.is_empty() {
+        "none".to_string()
+    } else {
+        detected.join(", ")
+    };
+    anyhow::bail!(
+        "Config `{key}` names unknown terminal `{desktop_id}`; detected terminals: {detected}"
+    )
+}
+
+fn terminal_from_entry(terminal_app: &ApplicationEntry) -> Result<TerminalLauncher> {
+    ApplicationExecutor::base_command_parts(&terminal_app.exec)
+        .map(|command| {
+            TerminalLauncher::new(command).with_desktop_file(terminal_app.desktop_file.clone())
+        })
+        .with_context(|| {
             format!(
                 "Failed to prepare terminal command from `{}`",
                 terminal_app.exec
             )
         })
+}
+
+/// Environment consulted while resolving the terminal launcher.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct TerminalEnv {
+    pub path: Option<OsString>,
+    pub terminal: Option<String>,
+}
+
+impl TerminalEnv {
+    pub fn current() -> Self {
+        Self {
+            path: std::env::var_os("PATH"),
+            terminal: std::env::var("TERMINAL").ok(),
+        }
+    }
+
+    fn which(&self, command: &str) -> Option<PathBuf> {
+        let cwd = std::env::current_dir().ok()?;
+        which::which_in(command, self.path.as_ref(), cwd).ok()
     }
 }
 
-pub(super) fn application_from_regex(handler: &RegexHandler) -> ApplicationEntry {
+/// Candidate entry for a matched regex handler; `exec` has its capture groups expanded.
+pub(super) fn application_from_regex(handler: &RegexHandler, exec: String) -> ApplicationEntry {
     let patterns = handler.patterns().join(", ");
     let name = handler
         .notes
@@ -80,14 +439,14 @@ pub(super) fn application_from_regex(handler: &RegexHandler) -> ApplicationEntry
         .unwrap_or_else(|| format!("Regex handler (prio {})", handler.priority));
 
     let comment = if patterns.is_empty() {
-        format!("Regex handler -> {}", handler.exec)
+        format!("Regex handler -> {exec}")
     } else {
-        format!("Regex handler -> {} [{patterns}]", handler.exec)
+        format!("Regex handler -> {exec} [{patterns}]")
     };
 
     ApplicationEntry {
         name,
-        exec: handler.exec.clone(),
+        exec,
         desktop_file: PathBuf::from(format!("regex-handler-{}.desktop", handler.priority)),
         comment: Some(comment),
         icon: None,
@@ -97,6 +456,20 @@ pub(super) fn application_from_regex(handler: &RegexHandler) -> ApplicationEntry
         action_id: None,
         requires_terminal: handler.terminal,
         is_terminal_emulator: false,
+        working_dir: None,
+        prefers_non_default_gpu: false,
+        stdin_input: handler.stdin_input,
+        association: Association::None,
+        parent_mime: None,
+        startup_notify: false,
+        confirm: handler.confirm,
+        score: 0.0,
+        dbus_activatable: false,
+        startup_wm_class: None,
+        single_main_window: false,
+        generic_name: None,
+        keywords: Vec::new(),
+        source: ApplicationSource::Available,
     }
     .with_source(ApplicationSource::Regex {
         priority: handler.priority,
@@ -0,0 +1,117 @@
+use crate::application_finder::{ApplicationEntry, ApplicationSource, Association};
+use std::path::PathBuf;
+use wildmatch::WildMatch;
+
+/// A filter that reads the file on stdin, offered when no desktop entry handles it.
+struct FallbackOpener {
+    /// MIME type pattern; `*` matches any subtype.
+    mime: &'static str,
+    name: &'static str,
+    exec: &'static str,
+    /// Pagers need the terminal `openit` runs in.
+    terminal: bool,
+}
+
+/// Most specific first; every installed match is offered.
+const FALLBACK_OPENERS: &[FallbackOpener] = &[
+    FallbackOpener {
+        mime: "text/markdown",
+        name: "glow",
+        exec: "glow -",
+        terminal: false,
+    },
+    FallbackOpener {
+        mime: "application/json",
+        name: "jq",
+        exec: "jq .",
+        terminal: false,
+    },
+    FallbackOpener {
+        mime: "text/*",
+        name: "less",
+        exec: "less",
+        terminal: true,
+    },
+    FallbackOpener {
+        mime: "application/json",
+        name: "less",
+        exec: "less",
+        terminal: true,
+    },
+];
+
+/// Piped entries for the fallback openers matching `mime_type` whose program is installed.
+pub(super) fn fallback_openers(
+    mime_type: &str,
+    is_installed: impl Fn(&str) -> bool,
+) -> Vec<ApplicationEntry> {
+    let mut openers: Vec<ApplicationEntry> = Vec::new();
+    for opener in FALLBACK_OPENERS {
+        let taken = openers.iter().any(|app| app.exec == opener.exec);
+        if taken || !WildMatch::new(opener.mime).matches(mime_type) || !is_installed(opener.name) {
+            continue;
+        }
+        openers.push(entry(opener));
+    }
+    openers
+}
+
+fn entry(opener: &FallbackOpener) -> ApplicationEntry {
+    ApplicationEntry {
+        name: opener.name.to_string(),
+        exec: opener.exec.to_string(),
+        desktop_file: PathBuf::from(format!("fallback-{}.desktop", opener.name)),
+        comment: Some(format!("Built-in fallback -> {} < file", opener.exec)),
+        icon: None,
+        is_xdg: false,
+        xdg_priority: -1,
+        is_default: false,
+        action_id: None,
+        requires_terminal: opener.terminal,
+        is_terminal_emulator: false,
+        working_dir: None,
+        prefers_non_default_gpu: false,
+        stdin_input: true,
+        association: Association::None,
+        parent_mime: None,
+        startup_notify: false,
+        confirm: false,
+        score: 0.0,
+        dbus_activatable: false,
+        startup_wm_class: None,
+        single_main_window: false,
+        generic_name: None,
+        keywords: Vec::new(),
+        source: ApplicationSource::Fallback,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(openers: &[ApplicationEntry]) -> Vec<&str> {
+        openers.iter().map(|app| app.name.as_str()).collect()
+    }
+
+    #[test]
+    fn installed_matches_are_offered_most_specific_first() {
+        let everything = |_: &str| true;
+        assert_eq!(
+            names(&fallback_openers("text/markdown", everything)),
+            ["glow", "less"]
+        );
+        assert_eq!(
+            names(&fallback_openers("application/json", everything)),
+            ["jq", "less"]
+        );
+        assert!(fallback_openers("image/png", everything).is_empty());
+
+        let only_less = |program: &str| program == "less";
+        let openers = fallback_openers("text/markdown", only_less);
+        assert_eq!(names(&openers), ["less"]);
+        assert!(openers[0].stdin_input);
+        assert!(openers[0].requires_terminal);
+        assert!(openers[0].is_fallback_opener());
+    }
+}
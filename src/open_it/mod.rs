@@ -1,31 +1,38 @@
-#[cfg(test)]
-use crate::application_finder::ApplicationEntry;
-use crate::application_finder::ApplicationFinder;
-use crate::cache::DesktopCache;
-#[cfg(test)]
-use crate::cache::FileSystemCache;
-use crate::cli::OpenArgs;
-use crate::config;
+use crate::application_finder::{ApplicationEntry, ApplicationFinder};
+use crate::cache::{DesktopCache, FileSystemCache};
+use crate::cli::{AnyHandlerArg, OpenArgs};
+use crate::config::{self, UriMatchNormalize};
+use crate::desktop_parser::DesktopEntry;
 use crate::executor::ApplicationExecutor;
+use crate::history::LaunchHistory;
 use crate::mime_associations::MimeAssociations;
+use crate::mime_detector::MimeDetector;
+use crate::mime_subclasses::MimeSubclasses;
+use crate::notify::{CommandNotifier, LaunchNotifier, DEFAULT_NOTIFY_COMMAND};
 use crate::regex_handlers::RegexHandlerStore;
 use crate::selector::SelectorRunner;
+use crate::shortcut_files;
 use crate::target::LaunchTarget;
+use crate::timings::PhaseTimer;
+use crate::window::{CommandWindowQuery, WindowQuery};
 use anyhow::Result;
-use log::{debug, info};
+use log::{debug, info, warn};
 use serde_json::json;
+use std::cell::Cell;
 use std::io::{self, IsTerminal};
-#[cfg(test)]
 use std::path::PathBuf;
 
 mod bootstrap;
 mod execution;
+mod fallback;
 mod selection;
 mod target;
 
 use bootstrap::BootstrapOutcome;
 use execution::application_from_regex;
+pub(crate) use execution::{resolve_terminal_launcher_with, TerminalEnv};
 use selection::LaunchContext;
+pub(crate) use selection::CUSTOM_COMMAND_ID;
 
 #[derive(Debug)]
 pub struct OpenIt {
@@ -35,18 +42,39 @@ pub struct OpenIt {
     pub(crate) config: config::Config,
     pub(crate) regex_handlers: RegexHandlerStore,
     pub(crate) args: OpenArgs,
+    pub(crate) assume_yes: bool,
+    /// Set by the first `execute_application`; an invocation spawns at most one application.
+    pub(crate) launched: Cell<bool>,
+    pub(crate) mime_detector: Box<dyn MimeDetector>,
+    /// Where launches are recorded for `--recent`; `None` keeps no history.
+    pub(crate) history_path: Option<PathBuf>,
+    /// Raises the open window of a `SingleMainWindow=true` handler; `None` always launches.
+    pub(crate) window_query: Option<Box<dyn WindowQuery>>,
+    /// Announces successful launches when `notify_on_launch` is set.
+    pub(crate) notifier: Option<Box<dyn LaunchNotifier>>,
+}
+
+/// How `dispatch` handles a prepared launch; each variant reaches at most one launch site.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Decision {
+    Json,
+    Launch(usize),
+    Selector,
 }
 
 impl OpenIt {
     pub fn new(args: OpenArgs) -> Result<Self> {
         if args.clear_cache {
+            warn!("--clear-cache is deprecated; use `openit cache clear`");
             Self::clear_cache()?;
         }
 
+        let timer = PhaseTimer::new(args.timings_enabled());
         let BootstrapOutcome {
             desktop_cache,
             mut config,
-        } = bootstrap::initialize(&args)?;
+            mime_detector,
+        } = bootstrap::initialize(&args, &timer)?;
 
         if let Some(open_with) = args.open_with_override() {
             config.selector.open_with = open_with;
@@ -60,38 +88,234 @@ impl OpenIt {
             };
         }
 
+        if args.strip_query {
+            config.uri_match_normalize = UriMatchNormalize::StripQuery;
+        }
+
         if let Some(terminal_mode) = args.terminal_mode_override() {
             config.terminal_execution = terminal_mode;
         }
 
-        let application_finder = ApplicationFinder::new(desktop_cache, MimeAssociations::load());
+        Self::validate_requested_selector(&config, &args)?;
+        config.launch_env_policy.validate()?;
+
+        let (mime_associations, mime_subclasses) = timer.measure("association load", || {
+            (MimeAssociations::load(), MimeSubclasses::load())
+        });
+        let mut application_finder = ApplicationFinder::new(desktop_cache, mime_associations)
+            .with_mime_subclasses(mime_subclasses)
+            .with_tie_breaker(config.tie_breaker)
+            .with_wildcard_handlers(config.allow_wildcard_handlers)
+            .with_parent_fallback(config.mime_fallback)
+            .with_opener_wrappers_hidden(config.hide_xdg_open_wrappers)
+            .with_preferred_apps(config.preferred_apps.clone())
+            .with_skip_unavailable(args.skip_unavailable)
+            .with_all_desktops(args.include_all_desktops || config.include_all_desktops);
+        if config.persist_no_handler_mimes {
+            application_finder = application_finder
+                .with_persisted_no_handler_mimes(bootstrap::no_handler_mimes_path());
+        }
 
         let executor = ApplicationExecutor::with_options(
             config.app_launch_prefix.clone(),
             config.selector.term_exec_args.clone(),
-        );
+        )
+        .with_gpu(args.gpu_override(), config.gpu.clone())
+        .with_dbus_activation(config.dbus_activation)
+        .with_per_mime_prefix(config.per_mime_prefix.clone())
+        .with_app_overrides(config.applications.clone())
+        .with_env_policy(config.launch_env_policy.clone());
+
+        let regex_handlers = timer
+            .measure("regex handler load", || RegexHandlerStore::load(None))?
+            .with_uri_match_normalize(config.uri_match_normalize);
+        let window_query = config
+            .focus_command
+            .as_deref()
+            .map(|command| Box::new(CommandWindowQuery::new(command)) as Box<dyn WindowQuery>);
+        let notifier = config.notify_on_launch.then(|| {
+            let command = config
+                .notify_command
+                .as_deref()
+                .unwrap_or(DEFAULT_NOTIFY_COMMAND);
+            Box::new(CommandNotifier::new(command)) as Box<dyn LaunchNotifier>
+        });
 
         Ok(Self {
             application_finder,
             selector_runner: SelectorRunner::new(),
             executor,
             config,
-            regex_handlers: RegexHandlerStore::load(None)?,
+            regex_handlers,
             args,
+            assume_yes: false,
+            launched: Cell::new(false),
+            mime_detector,
+            history_path: Some(bootstrap::history_path()),
+            window_query,
+            notifier,
         })
     }
 
+    /// Answer confirmation prompts with "yes" instead of reading stdin.
+    pub fn with_assume_yes(mut self, assume_yes: bool) -> Self {
+        self.assume_yes = assume_yes;
+        self
+    }
+
+    pub(super) fn timer(&self) -> PhaseTimer {
+        PhaseTimer::new(self.args.timings_enabled())
+    }
+
     pub fn run(self) -> Result<()> {
         if self.args.clear_cache && self.args.target.is_none() {
             return Ok(());
         }
 
+        if let Some(desktop_id) = &self.args.with_handler {
+            let (target, app, mime_type) = self.prepare_explicit_launch(desktop_id)?;
+            let app = self.with_requested_action(app)?;
+            info!(
+                "Launching `{}` ({}) as requested by --with",
+                app.name,
+                app.desktop_file.display()
+            );
+            return self.execute_application(&app, &target, &mime_type);
+        }
+
+        if let Some(n) = self.args.recent {
+            let (target, app, mime_type) = self.prepare_recent_launch(n)?;
+            let app = self.with_requested_action(app)?;
+            info!(
+                "Launching `{}` ({}), #{n} in the launch history for {mime_type}",
+                app.name,
+                app.desktop_file.display()
+            );
+            return self.execute_application(&app, &target, &mime_type);
+        }
+
         let context = self.prepare_launch()?;
+        if let Some(action_id) = &self.args.action {
+            let app = self.action_of_winner(&context.applications, action_id)?;
+            info!(
+                "Launching action `{action_id}` of {}",
+                app.desktop_file.display()
+            );
+            return self.execute_application(&app, &context.target, &context.mime_type);
+        }
+        self.dispatch(&context)
+    }
+
+    /// `--action` for `--with` and `--recent`: swap the resolved entry for its action.
+    fn with_requested_action(&self, app: ApplicationEntry) -> Result<ApplicationEntry> {
+        match &self.args.action {
+            Some(action_id) => self.desktop_action_for(&app, action_id),
+            None => Ok(app),
+        }
+    }
+
+    /// The `action_id` action of the best-ranked desktop entry. Regex handlers and action
+    /// entries listed by `--actions` have no actions of their own, and neither do the
+    /// built-in fallback openers, so they are passed over.
+    fn action_of_winner(
+        &self,
+        applications: &[ApplicationEntry],
+        action_id: &str,
+    ) -> Result<ApplicationEntry> {
+        let winner = applications
+            .iter()
+            .find(|app| {
+                app.action_id.is_none() && !app.is_regex_handler() && !app.is_fallback_opener()
+            })
+            .ok_or_else(|| anyhow::anyhow!("No desktop entry to run action `{action_id}` of"))?;
+        self.desktop_action_for(winner, action_id)
+    }
+
+    /// The action `action_id` declared in the desktop file `app` was read from.
+    fn desktop_action_for(
+        &self,
+        app: &ApplicationEntry,
+        action_id: &str,
+    ) -> Result<ApplicationEntry> {
+        let (path, desktop_file) = self
+            .application_finder
+            .find_desktop_file(&app.desktop_file.to_string_lossy())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Desktop file {} not found in available applications",
+                    app.desktop_file.display()
+                )
+            })?;
+        let entry = desktop_file.main_entry.as_ref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Desktop file {} has no [Desktop Entry] group",
+                path.display()
+            )
+        })?;
+
+        let Some(action) = desktop_file.actions.get(action_id) else {
+            let available: Vec<&str> = desktop_file
+                .ordered_actions()
+                .into_iter()
+                .map(|(id, _)| id.as_str())
+                .collect();
+            if available.is_empty() {
+                anyhow::bail!(
+                    "`{}` ({}) declares no desktop actions",
+                    entry.name,
+                    path.display()
+                );
+            }
+            anyhow::bail!(
+                "`{}` ({}) has no action `{action_id}`; available actions: {}",
+                entry.name,
+                path.display(),
+                available.join(", ")
+            );
+        };
+        Ok(ApplicationEntry::from_desktop_action(
+            entry,
+            action_id,
+            action,
+            path.clone(),
+        ))
+    }
+
+    /// Run the decision for a prepared launch: JSON output, a direct launch, or the selector.
+    fn dispatch(&self, context: &LaunchContext) -> Result<()> {
+        match self.decide(context) {
+            Decision::Json => self.output_json(context),
+            Decision::Launch(index) => self.execute_application(
+                &context.applications[index],
+                &context.target,
+                &context.mime_type,
+            ),
+            Decision::Selector => self.run_selector_flow(context),
+        }
+    }
 
-        let force_json =
-            self.args.json || (!io::stdout().is_terminal() && self.config.selector.open_with);
-        if force_json {
-            return self.output_json(&context);
+    /// Pick a handler for a prepared launch: JSON output, `--match`, direct launch, or the selector.
+    fn decide(&self, context: &LaunchContext) -> Decision {
+        if self.args.json {
+            return Decision::Json;
+        }
+
+        // A clear `--match` winner launches even when stdout is not a terminal.
+        if let Some(query) = &self.args.match_query {
+            if let Some(index) = crate::fuzzy_match::clear_winner(query, &context.applications) {
+                let app = &context.applications[index];
+                info!(
+                    "`{}` ({}) clearly matches --match `{query}`",
+                    app.name,
+                    app.desktop_file.display()
+                );
+                return Decision::Launch(index);
+            }
+            info!("--match `{query}` is ambiguous; seeding the selector with it");
+        }
+
+        if !io::stdout().is_terminal() && self.config.selector.open_with {
+            return Decision::Json;
         }
 
         if !self.config.selector.open_with {
@@ -105,15 +329,15 @@ impl OpenIt {
                     first_app.desktop_file.display()
                 );
             }
-            return self.execute_application(first_app, &context.target);
+            return Decision::Launch(0);
         }
 
         if context.applications.len() == 1 {
             info!("Auto-opening the only available application");
-            return self.execute_application(&context.applications[0], &context.target);
+            return Decision::Launch(0);
         }
 
-        self.run_selector_flow(&context)
+        Decision::Selector
     }
 
     fn prepare_launch(&self) -> Result<LaunchContext> {
@@ -123,7 +347,13 @@ impl OpenIt {
             .as_ref()
             .ok_or_else(|| anyhow::anyhow!("No target provided"))?;
 
-        let target = Self::resolve_launch_target(raw_target)?;
+        let timer = self.timer();
+        let (target, mime_type) = timer.measure("MIME detection", || {
+            self.resolve_target(raw_target).map(|target| {
+                let mime_type = self.detect_mime(&target);
+                (target, mime_type)
+            })
+        })?;
 
         if let Some(path) = target.as_path() {
             if path.is_dir() {
@@ -135,13 +365,13 @@ impl OpenIt {
             info!("URI: {}", target.as_command_argument());
         }
 
-        let mime_type = Self::mime_for_target(&target);
         info!("MIME type: {mime_type}");
 
-        let candidate = target.as_command_argument().into_owned();
-        let mut applications = self
-            .application_finder
-            .find_for_mime(&mime_type, self.args.actions);
+        let candidate = self.regex_handlers.candidate(&target);
+        let mut applications = timer.measure("finder query", || {
+            self.application_finder
+                .find_for_mime(&mime_type, self.args.actions)
+        });
 
         if let Some(handler) = self.regex_handlers.find_handler(&candidate) {
             info!(
@@ -152,7 +382,8 @@ impl OpenIt {
                 info!("Regex handler requests terminal execution");
             }
 
-            applications.insert(0, application_from_regex(handler));
+            let exec = handler.expand_exec(&candidate)?;
+            applications.insert(0, application_from_regex(handler, exec));
         }
 
         debug!(
@@ -161,34 +392,164 @@ impl OpenIt {
             self.regex_handlers.len()
         );
 
+        let all_from = match self.args.any_handler {
+            Some(AnyHandlerArg::Always) => self.append_all_applications(&mut applications),
+            Some(AnyHandlerArg::Empty) if applications.is_empty() => {
+                info!("Nothing handles {mime_type}; offering all applications");
+                self.append_all_applications(&mut applications)
+            }
+            _ => None,
+        };
+
+        if applications.is_empty() && self.config.fallback_openers && target.as_path().is_some() {
+            applications =
+                fallback::fallback_openers(&mime_type, |program| which::which(program).is_ok());
+            if !applications.is_empty() {
+                info!("Nothing handles {mime_type}; offering the built-in fallback openers");
+            }
+        }
+
         if applications.is_empty() {
             anyhow::bail!("No applications found for MIME type: {}", mime_type);
         }
 
-        Ok(LaunchContext::new(target, mime_type, applications))
+        Ok(LaunchContext::new(target, mime_type, applications)
+            .with_all_applications_from(all_from.as_deref()))
     }
 
-    fn output_json(&self, context: &LaunchContext) -> Result<()> {
-        let resource = context.target.as_command_argument().into_owned();
-        let target_kind = match context.target {
-            LaunchTarget::File(_) => "file",
-            LaunchTarget::Uri(_) => "uri",
-        };
-
-        let output = json!({
-            "target": resource,
-            "target_kind": target_kind,
-            "mimetype": context.mime_type,
-            "xdg_associations": Vec::<String>::new(),
-            "applications": context.applications,
-        });
+    /// `--any-handler`: append every visible application not offered yet and return the
+    /// desktop file of the first one added.
+    fn append_all_applications(&self, applications: &mut Vec<ApplicationEntry>) -> Option<PathBuf> {
+        let additions: Vec<ApplicationEntry> = self
+            .application_finder
+            .all_applications()
+            .into_iter()
+            .filter(|app| {
+                !applications
+                    .iter()
+                    .any(|offered| offered.desktop_file == app.desktop_file)
+            })
+            .collect();
+        let first = additions.first().map(|app| app.desktop_file.clone());
+        applications.extend(additions);
+        first
+    }
+
+    /// The target, the `--with` entry and the target's MIME type, which also picks the
+    /// launch prefix.
+    fn prepare_explicit_launch(
+        &self,
+        desktop_id: &str,
+    ) -> Result<(LaunchTarget, ApplicationEntry, String)> {
+        let raw_target = self
+            .args
+            .target
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No target provided"))?;
+
+        let target = self.resolve_target(raw_target)?;
+        let (entry, path) = self.desktop_entry_for(desktop_id)?;
+
+        let mime_type = self.detect_mime(&target);
+        if !self.args.force && !self.application_finder.supports_mime(entry, &mime_type) {
+            anyhow::bail!(
+                "Desktop handler `{}` does not support MIME type {}; use --force to launch it anyway",
+                desktop_id,
+                mime_type
+            );
+        }
+
+        Ok((
+            target,
+            ApplicationEntry::from_desktop_entry(entry, path.clone()),
+            mime_type,
+        ))
+    }
 
-        println!("{}", serde_json::to_string_pretty(&output)?);
+    /// The target, the `--recent N` entry and the target's MIME type. The handler was
+    /// launched for this MIME type before, so it is not checked against `MimeType=` again.
+    fn prepare_recent_launch(&self, n: usize) -> Result<(LaunchTarget, ApplicationEntry, String)> {
+        let raw_target = self
+            .args
+            .target
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("No target provided"))?;
+
+        let target = self.resolve_target(raw_target)?;
+        let mime_type = self.detect_mime(&target);
+
+        let history = match &self.history_path {
+            Some(path) => LaunchHistory::load(path)?,
+            None => LaunchHistory::default(),
+        };
+        let desktop_id = history.nth(&mime_type, n)?;
+        let (entry, path) = self.desktop_entry_for(desktop_id)?;
+
+        Ok((
+            target,
+            ApplicationEntry::from_desktop_entry(entry, path.clone()),
+            mime_type,
+        ))
+    }
+
+    fn desktop_entry_for(&self, desktop_id: &str) -> Result<(&DesktopEntry, &PathBuf)> {
+        let (path, desktop_file) = self
+            .application_finder
+            .find_desktop_file(desktop_id)
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "Desktop handler `{}` not found in available applications",
+                    desktop_id
+                )
+            })?;
+        let entry = desktop_file.main_entry.as_ref().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Desktop handler `{}` has no [Desktop Entry] group",
+                desktop_id
+            )
+        })?;
+        Ok((entry, path))
+    }
+
+    fn output_json(&self, context: &LaunchContext) -> Result<()> {
+        let mut document = candidates_json(context);
+        if self.args.dry_run {
+            self.add_launch_commands(&mut document, context);
+        }
+        println!("{}", serde_json::to_string_pretty(&document)?);
         Ok(())
     }
 
-    pub fn load_desktop_cache() -> Box<dyn DesktopCache> {
-        bootstrap::load_desktop_cache()
+    /// `--dry-run --json`: give each entry of `applications` the argv it would run, or
+    /// `null` when it cannot be built (e.g. no terminal for a `Terminal=true` entry).
+    fn add_launch_commands(&self, document: &mut serde_json::Value, context: &LaunchContext) {
+        let Some(entries) = document["applications"].as_array_mut() else {
+            return;
+        };
+        for (entry, app) in entries.iter_mut().zip(&context.applications) {
+            entry["command"] = match self.launch_command(app, &context.target, &context.mime_type) {
+                Ok(command) => json!(command),
+                Err(err) => {
+                    warn!("Cannot build the command for {}: {err:#}", app.name);
+                    serde_json::Value::Null
+                }
+            };
+        }
+    }
+
+    pub fn load_desktop_cache(
+        recursive: bool,
+        max_entries: Option<usize>,
+    ) -> Box<dyn DesktopCache> {
+        bootstrap::load_desktop_cache(recursive, max_entries)
+    }
+
+    pub fn rebuild_desktop_cache(
+        desktop_dirs: &[PathBuf],
+        recursive: bool,
+        max_entries: Option<usize>,
+    ) -> Result<FileSystemCache> {
+        bootstrap::rebuild_desktop_cache(desktop_dirs, recursive, max_entries)
     }
 
     #[cfg(test)]
@@ -196,27 +557,73 @@ impl OpenIt {
         cache: &mut FileSystemCache,
         desktop_dirs: &[PathBuf],
         force: bool,
+        recursive: bool,
     ) -> bool {
-        bootstrap::populate_cache_from_dirs(cache, desktop_dirs, force)
+        bootstrap::populate_cache_from_dirs(cache, desktop_dirs, force, recursive)
     }
 
-    #[cfg(test)]
     pub fn cache_path() -> PathBuf {
         bootstrap::cache_path()
     }
 
+    pub fn no_handler_mimes_path() -> PathBuf {
+        bootstrap::no_handler_mimes_path()
+    }
+
     pub fn clear_cache() -> Result<()> {
         bootstrap::clear_cache()
     }
 
+    /// Problems with `terminal_for_handlers`/`terminal_for_directory`, for `config validate`
+    /// and `doctor`.
+    pub fn stale_pinned_terminals(
+        config: &config::Config,
+        finder: &ApplicationFinder,
+    ) -> Vec<String> {
+        execution::stale_pinned_terminals(config, finder)
+    }
+
     pub fn resolve_launch_target(raw: &str) -> Result<LaunchTarget> {
         target::resolve_launch_target(raw)
     }
 
+    /// Like [`Self::resolve_launch_target`], but a `.url`, `.webloc` or `Type=Link`
+    /// `.desktop` file resolves to the URL inside it unless `--literal` is set. Only one
+    /// hop is followed, and a shortcut whose URL cannot be read is opened as a file.
+    fn resolve_target(&self, raw: &str) -> Result<LaunchTarget> {
+        let target = Self::resolve_launch_target(raw)?;
+        if self.args.literal {
+            return Ok(target);
+        }
+        let Some(url) = target.as_path().and_then(shortcut_files::embedded_url) else {
+            return Ok(target);
+        };
+        info!("Following shortcut {raw} to {url}");
+        Self::resolve_launch_target(url.as_str())
+    }
+
+    /// Replace MIME detection for this instance; see [`MimeDetector`].
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn with_mime_detector(mut self, detector: Box<dyn MimeDetector>) -> Self {
+        self.mime_detector = detector;
+        self
+    }
+
+    /// MIME type from the configured detector, or the built-in one when it has no answer.
+    fn detect_mime(&self, target: &LaunchTarget) -> String {
+        self.mime_detector
+            .detect(target)
+            .unwrap_or_else(|| Self::mime_for_target(target))
+    }
+
     pub fn mime_for_target(target: &LaunchTarget) -> String {
         target::mime_for_target(target)
     }
 
+    pub fn mime_detection_for_target(target: &LaunchTarget) -> &'static str {
+        target::mime_detection_for_target(target)
+    }
+
     #[cfg(test)]
     pub(crate) fn output_json_for_test(
         &self,
@@ -229,24 +636,50 @@ impl OpenIt {
     }
 }
 
+/// The `open --json` document, also handed to custom selectors via `OPENIT_CANDIDATES_FILE`.
+fn candidates_json(context: &LaunchContext) -> serde_json::Value {
+    let resource = context.target.as_command_argument().into_owned();
+    let target_kind = match context.target {
+        LaunchTarget::File(_) => "file",
+        LaunchTarget::Uri(_) => "uri",
+    };
+
+    let mut document = json!({
+        "target": resource,
+        "target_kind": target_kind,
+        "mimetype": context.mime_type,
+        "xdg_associations": Vec::<String>::new(),
+        "applications": context.applications,
+        "handlers": context.handler_groups(),
+    });
+    if let Some(index) = context.all_applications_from {
+        document["all_applications_from"] = json!(index);
+    }
+    document
+}
+
 #[cfg(test)]
 mod tests {
+    use super::execution::TerminalEnv;
     use super::*;
-    use crate::application_finder::ApplicationEntry;
+    use crate::application_finder::{ApplicationEntry, ApplicationSource, Association};
     use crate::cache::{DesktopCache, FileSystemCache};
-    use crate::cli::{OpenArgs, SelectorKind};
-    use crate::config::Config;
+    use crate::cli::{AnyHandlerArg, OpenArgs, SelectorKind};
+    use crate::config::{Config, TerminalPurpose};
     use crate::desktop_parser::{DesktopEntry, DesktopFile};
     use crate::executor::ApplicationExecutor;
+    use crate::mime_detector::BuiltinMimeDetector;
     use crate::regex_handlers::RegexHandlerStore;
     use crate::selector::SelectorRunner;
     use crate::target::LaunchTarget;
-    use crate::test_support::{create_test_desktop_file, CacheEnvGuard, ConfigEnvGuard};
+    use crate::test_support::{
+        captured_logs, create_test_desktop_file, init_capture_logger, wait_for_contents,
+        wait_for_file, CacheEnvGuard, ConfigEnvGuard, EnvVarGuard, LAUNCH_TIMEOUT,
+    };
     use serial_test::serial;
     use std::collections::HashMap;
-    use std::env;
     use std::fs;
-    use std::path::PathBuf;
+    use std::path::{Path, PathBuf};
     use std::process::{Command as ProcessCommand, Stdio};
     use std::time::Duration;
     use tempfile::TempDir;
@@ -272,6 +705,22 @@ mod tests {
             selector_command: None,
             term_exec_args: None,
             terminal_mode: None,
+            skip_unavailable: false,
+            with_handler: None,
+            force: false,
+            timings: false,
+            gpu: None,
+            pipe: false,
+            match_query: None,
+            dry_run: false,
+            wait: false,
+            wait_timeout: None,
+            any_handler: None,
+            recent: None,
+            strip_query: false,
+            action: None,
+            literal: false,
+            include_all_desktops: false,
         }
     }
 
@@ -309,6 +758,22 @@ mod tests {
             selector_command: Some(script_path.to_string_lossy().to_string()),
             term_exec_args: None,
             terminal_mode: None,
+            skip_unavailable: false,
+            with_handler: None,
+            force: false,
+            timings: false,
+            gpu: None,
+            pipe: false,
+            match_query: None,
+            dry_run: false,
+            wait: false,
+            wait_timeout: None,
+            any_handler: None,
+            recent: None,
+            strip_query: false,
+            action: None,
+            literal: false,
+            include_all_desktops: false,
         };
 
         let applications = vec![
@@ -324,6 +789,20 @@ mod tests {
                 action_id: None,
                 requires_terminal: false,
                 is_terminal_emulator: false,
+                working_dir: None,
+                prefers_non_default_gpu: false,
+                stdin_input: false,
+                association: Association::None,
+                parent_mime: None,
+                startup_notify: false,
+                confirm: false,
+                score: 0.0,
+                dbus_activatable: false,
+                startup_wm_class: None,
+                single_main_window: false,
+                generic_name: None,
+                keywords: Vec::new(),
+                source: ApplicationSource::Available,
             },
             ApplicationEntry {
                 name: "Beta".to_string(),
@@ -337,6 +816,20 @@ mod tests {
                 action_id: None,
                 requires_terminal: false,
                 is_terminal_emulator: false,
+                working_dir: None,
+                prefers_non_default_gpu: false,
+                stdin_input: false,
+                association: Association::None,
+                parent_mime: None,
+                startup_notify: false,
+                confirm: false,
+                score: 0.0,
+                dbus_activatable: false,
+                startup_wm_class: None,
+                single_main_window: false,
+                generic_name: None,
+                keywords: Vec::new(),
+                source: ApplicationSource::Available,
             },
         ];
 
@@ -356,11 +849,44 @@ mod tests {
             config,
             regex_handlers: RegexHandlerStore::load(None).unwrap(),
             args,
+            assume_yes: false,
+            launched: Cell::new(false),
+            mime_detector: Box::new(BuiltinMimeDetector),
+            history_path: None,
+            window_query: None,
+            notifier: None,
         };
 
         (open_with, context, temp_dir)
     }
 
+    #[test]
+    fn requested_selector_is_validated_against_path() {
+        let mut config = Config::default();
+        config.selector_profiles.insert(
+            "missing".into(),
+            crate::config::SelectorProfile {
+                command: "definitely-missing-selector".to_string(),
+                ..Default::default()
+            },
+        );
+
+        let mut args = create_test_args_json(Some(PathBuf::from("test.txt")));
+        args.json = false;
+        args.selector = SelectorKind::Named("missing".to_string());
+
+        let err = OpenIt::validate_requested_selector(&config, &args).unwrap_err();
+        assert!(format!("{err:#}").contains("Selector profile `missing` is not usable"));
+        assert!(format!("{err:#}").contains("definitely-missing-selector"));
+
+        args.json = true;
+        assert!(OpenIt::validate_requested_selector(&config, &args).is_ok());
+
+        args.json = false;
+        args.selector = SelectorKind::Auto;
+        assert!(OpenIt::validate_requested_selector(&config, &args).is_ok());
+    }
+
     #[test]
     fn populate_cache_adds_new_entries_without_rebuild() {
         let temp_dir = TempDir::new().unwrap();
@@ -378,7 +904,8 @@ mod tests {
 
         assert!(OpenIt::populate_cache_from_dirs(
             &mut cache,
-            &[apps_dir.clone()],
+            std::slice::from_ref(&apps_dir),
+            true,
             true
         ));
         assert!(DesktopCache::get(&cache, &existing).is_some());
@@ -391,12 +918,116 @@ mod tests {
 
         assert!(OpenIt::populate_cache_from_dirs(
             &mut cache,
-            &[apps_dir.clone()],
-            false
+            std::slice::from_ref(&apps_dir),
+            false,
+            true
         ));
         assert!(DesktopCache::get(&cache, &new_entry_path).is_some());
     }
 
+    #[test]
+    fn populate_cache_recurses_only_when_enabled() {
+        let temp_dir = TempDir::new().unwrap();
+        let apps_dir = temp_dir.path().join("applications");
+        let kde_dir = apps_dir.join("kde");
+        fs::create_dir_all(&kde_dir).unwrap();
+
+        let top = create_test_desktop_file(
+            &apps_dir,
+            "top.desktop",
+            &basic_desktop_content("Top", "top %F", "text/plain"),
+        );
+        let nested = create_test_desktop_file(
+            &kde_dir,
+            "okular.desktop",
+            &basic_desktop_content("Okular", "okular %F", "application/pdf"),
+        );
+        let dirs = std::slice::from_ref(&apps_dir);
+
+        let mut flat = FileSystemCache::new(temp_dir.path().join("flat.json"));
+        assert!(OpenIt::populate_cache_from_dirs(
+            &mut flat, dirs, true, false
+        ));
+        assert!(DesktopCache::get(&flat, &top).is_some());
+        assert!(DesktopCache::get(&flat, &nested).is_none());
+
+        let mut cache = FileSystemCache::new(temp_dir.path().join("cache.json"));
+        assert!(OpenIt::populate_cache_from_dirs(
+            &mut cache, dirs, true, true
+        ));
+        assert!(DesktopCache::get(&cache, &top).is_some());
+        assert!(DesktopCache::get(&cache, &nested).is_some());
+
+        // Turning recursion off drops entries an earlier recursive scan cached.
+        assert!(OpenIt::populate_cache_from_dirs(
+            &mut cache, dirs, false, false
+        ));
+        assert!(DesktopCache::get(&cache, &top).is_some());
+        assert!(DesktopCache::get(&cache, &nested).is_none());
+    }
+
+    #[test]
+    fn capped_cache_keeps_every_application_across_runs() {
+        let temp_dir = TempDir::new().unwrap();
+        let apps_dir = temp_dir.path().join("applications");
+        fs::create_dir_all(&apps_dir).unwrap();
+        let cache_path = temp_dir.path().join("cache.json");
+        let apps: Vec<PathBuf> = ["one", "two", "three", "four"]
+            .iter()
+            .map(|name| {
+                create_test_desktop_file(
+                    &apps_dir,
+                    &format!("{name}.desktop"),
+                    &basic_desktop_content(name, &format!("{name} %f"), "text/plain"),
+                )
+            })
+            .collect();
+        let dirs = std::slice::from_ref(&apps_dir);
+
+        let mut first = FileSystemCache::with_max_entries(cache_path.clone(), Some(2));
+        OpenIt::populate_cache_from_dirs(&mut first, dirs, false, true);
+        assert_eq!(first.len(), apps.len());
+        first.save().unwrap();
+
+        // The next run parses the two files the save left out, without a new generation.
+        let mut second = FileSystemCache::with_max_entries(cache_path, Some(2));
+        second.load().unwrap();
+        let generation = second.generation();
+        OpenIt::populate_cache_from_dirs(&mut second, dirs, false, true);
+        assert_eq!(second.generation(), generation);
+        for app in &apps {
+            assert!(DesktopCache::get(&second, app).is_some());
+        }
+    }
+
+    #[test]
+    fn populate_cache_skips_unparsable_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let apps_dir = temp_dir.path().join("applications");
+        fs::create_dir_all(&apps_dir).unwrap();
+
+        let viewer = create_test_desktop_file(
+            &apps_dir,
+            "viewer.desktop",
+            &basic_desktop_content("Viewer", "viewer %f", "text/plain"),
+        );
+        let nameless =
+            create_test_desktop_file(&apps_dir, "nameless.desktop", "[Desktop Entry]\nExec=x\n");
+        let latin1 = apps_dir.join("latin1.desktop");
+        fs::write(&latin1, b"[Desktop Entry]\nName=Caf\xe9\nExec=cafe\n").unwrap();
+
+        let mut cache = FileSystemCache::new(temp_dir.path().join("cache.json"));
+        assert!(OpenIt::populate_cache_from_dirs(
+            &mut cache,
+            std::slice::from_ref(&apps_dir),
+            true,
+            true
+        ));
+        assert!(DesktopCache::get(&cache, &viewer).is_some());
+        assert!(DesktopCache::get(&cache, &nameless).is_none());
+        assert!(DesktopCache::get(&cache, &latin1).is_some());
+    }
+
     #[test]
     fn cache_path_creation() {
         let cache_path = OpenIt::cache_path();
@@ -425,12 +1056,25 @@ mod tests {
             selector_command: None,
             term_exec_args: None,
             terminal_mode: None,
+            skip_unavailable: false,
+            with_handler: None,
+            force: false,
+            timings: false,
+            gpu: None,
+            pipe: false,
+            match_query: None,
+            dry_run: false,
+            wait: false,
+            wait_timeout: None,
+            any_handler: None,
+            recent: None,
+            strip_query: false,
+            action: None,
+            literal: false,
+            include_all_desktops: false,
         };
 
-        let _ = env_logger::builder()
-            .is_test(true)
-            .filter_level(log::LevelFilter::Debug)
-            .try_init();
+        init_capture_logger();
 
         let result = OpenIt::new(args);
         assert!(result.is_ok());
@@ -481,6 +1125,20 @@ mod tests {
             action_id: None,
             requires_terminal: false,
             is_terminal_emulator: false,
+            working_dir: None,
+            prefers_non_default_gpu: false,
+            stdin_input: false,
+            association: Association::None,
+            parent_mime: None,
+            startup_notify: false,
+            confirm: false,
+            score: 0.0,
+            dbus_activatable: false,
+            startup_wm_class: None,
+            single_main_window: false,
+            generic_name: None,
+            keywords: Vec::new(),
+            source: ApplicationSource::Available,
         }];
 
         let mime_type = "text/plain";
@@ -537,6 +1195,22 @@ mod tests {
             selector_command: None,
             term_exec_args: None,
             terminal_mode: None,
+            skip_unavailable: false,
+            with_handler: None,
+            force: false,
+            timings: false,
+            gpu: None,
+            pipe: false,
+            match_query: None,
+            dry_run: false,
+            wait: false,
+            wait_timeout: None,
+            any_handler: None,
+            recent: None,
+            strip_query: false,
+            action: None,
+            literal: false,
+            include_all_desktops: false,
         };
 
         let app = OpenIt::new(args).unwrap();
@@ -562,6 +1236,22 @@ mod tests {
             selector_command: None,
             term_exec_args: None,
             terminal_mode: None,
+            skip_unavailable: false,
+            with_handler: None,
+            force: false,
+            timings: false,
+            gpu: None,
+            pipe: false,
+            match_query: None,
+            dry_run: false,
+            wait: false,
+            wait_timeout: None,
+            any_handler: None,
+            recent: None,
+            strip_query: false,
+            action: None,
+            literal: false,
+            include_all_desktops: false,
         };
 
         let app = OpenIt::new(args).unwrap();
@@ -595,6 +1285,22 @@ mod tests {
             selector_command: None,
             term_exec_args: None,
             terminal_mode: None,
+            skip_unavailable: false,
+            with_handler: None,
+            force: false,
+            timings: false,
+            gpu: None,
+            pipe: false,
+            match_query: None,
+            dry_run: false,
+            wait: false,
+            wait_timeout: None,
+            any_handler: None,
+            recent: None,
+            strip_query: false,
+            action: None,
+            literal: false,
+            include_all_desktops: false,
         };
 
         let app = OpenIt::new(args).unwrap();
@@ -620,6 +1326,22 @@ mod tests {
             selector_command: None,
             term_exec_args: None,
             terminal_mode: None,
+            skip_unavailable: false,
+            with_handler: None,
+            force: false,
+            timings: false,
+            gpu: None,
+            pipe: false,
+            match_query: None,
+            dry_run: false,
+            wait: false,
+            wait_timeout: None,
+            any_handler: None,
+            recent: None,
+            strip_query: false,
+            action: None,
+            literal: false,
+            include_all_desktops: false,
         };
 
         let app = OpenIt::new(args).unwrap();
@@ -647,6 +1369,22 @@ mod tests {
             selector_command: None,
             term_exec_args: None,
             terminal_mode: None,
+            skip_unavailable: false,
+            with_handler: None,
+            force: false,
+            timings: false,
+            gpu: None,
+            pipe: false,
+            match_query: None,
+            dry_run: false,
+            wait: false,
+            wait_timeout: None,
+            any_handler: None,
+            recent: None,
+            strip_query: false,
+            action: None,
+            literal: false,
+            include_all_desktops: false,
         };
 
         let app = OpenIt::new(args).unwrap();
@@ -655,6 +1393,7 @@ mod tests {
     }
 
     #[test]
+    #[serial]
     fn load_desktop_cache_with_invalid_file_recovers() {
         let temp_dir = TempDir::new().unwrap();
         let cache_dir = temp_dir.path().join("openit");
@@ -663,9 +1402,10 @@ mod tests {
         let cache_file = cache_dir.join("desktop_cache.json");
         fs::write(&cache_file, "invalid json content").unwrap();
 
-        env::set_var("HOME", temp_dir.path());
-        let cache = OpenIt::load_desktop_cache();
-        env::remove_var("HOME");
+        let cache = {
+            let _home = EnvVarGuard::set("HOME", temp_dir.path().to_str().unwrap());
+            OpenIt::load_desktop_cache(true, None)
+        };
 
         assert!(cache.is_empty() || !cache.is_empty());
     }
@@ -682,6 +1422,7 @@ mod tests {
     }
 
     #[test]
+    #[serial]
     fn cache_save_failure_handling() {
         let temp_dir = TempDir::new().unwrap();
         let readonly_dir = temp_dir.path().join("readonly");
@@ -695,9 +1436,10 @@ mod tests {
             fs::set_permissions(&readonly_dir, perms).unwrap();
         }
 
-        env::set_var("HOME", &readonly_dir);
-        let cache = OpenIt::load_desktop_cache();
-        env::remove_var("HOME");
+        let cache = {
+            let _home = EnvVarGuard::set("HOME", readonly_dir.to_str().unwrap());
+            OpenIt::load_desktop_cache(true, None)
+        };
 
         #[cfg(unix)]
         {
@@ -736,6 +1478,20 @@ mod tests {
             action_id: None,
             requires_terminal: false,
             is_terminal_emulator: false,
+            working_dir: None,
+            prefers_non_default_gpu: false,
+            stdin_input: false,
+            association: Association::None,
+            parent_mime: None,
+            startup_notify: false,
+            confirm: false,
+            score: 0.0,
+            dbus_activatable: false,
+            startup_wm_class: None,
+            single_main_window: false,
+            generic_name: None,
+            keywords: Vec::new(),
+            source: ApplicationSource::Available,
         }];
 
         let mime_type = "text/plain";
@@ -745,12 +1501,41 @@ mod tests {
             .unwrap();
     }
 
+    #[test]
+    #[serial]
+    fn timings_log_major_phases() {
+        init_capture_logger();
+
+        let temp_dir = TempDir::new().unwrap();
+        let _cache_env = CacheEnvGuard::set(&temp_dir.path().join("desktop_cache.json"));
+        let test_file = temp_dir.path().join("notes.txt");
+        fs::write(&test_file, "hello").unwrap();
+
+        let mut args = create_test_args_json(Some(test_file));
+        args.timings = true;
+
+        let app = OpenIt::new(args).unwrap();
+        let _ = app.run();
+
+        let logs = captured_logs(crate::timings::TARGET);
+        for phase in [
+            "config load",
+            "cache load",
+            "association load",
+            "MIME detection",
+            "finder query",
+        ] {
+            assert!(
+                logs.iter()
+                    .any(|line| line.starts_with(&format!("{phase}: "))),
+                "missing timing for `{phase}` in {logs:?}"
+            );
+        }
+    }
+
     #[test]
     fn run_with_verbose_logging_handles_errors() {
-        let _ = env_logger::builder()
-            .is_test(true)
-            .filter_level(log::LevelFilter::Info)
-            .try_init();
+        init_capture_logger();
 
         let temp_dir = TempDir::new().unwrap();
         let test_file = temp_dir.path().join("test.reallyunknowntype");
@@ -771,6 +1556,22 @@ mod tests {
             selector_command: None,
             term_exec_args: None,
             terminal_mode: None,
+            skip_unavailable: false,
+            with_handler: None,
+            force: false,
+            timings: false,
+            gpu: None,
+            pipe: false,
+            match_query: None,
+            dry_run: false,
+            wait: false,
+            wait_timeout: None,
+            any_handler: None,
+            recent: None,
+            strip_query: false,
+            action: None,
+            literal: false,
+            include_all_desktops: false,
         };
 
         let app = OpenIt::new(args).unwrap();
@@ -801,69 +1602,363 @@ mod tests {
 
     #[test]
     #[cfg(unix)]
-    #[serial]
-    fn regex_handler_executes_command() {
-        use std::os::unix::fs::PermissionsExt;
+    fn selector_command_reads_candidates_file_and_returns_desktop_id() {
+        let (open_with, mut context, temp_dir) = build_selector_test_environment(
+            "#!/bin/sh\n\
+             [ \"$OPENIT_MIME\" = text/plain ] || exit 1\n\
+             [ \"$OPENIT_TARGET\" = dummy.txt ] || exit 1\n\
+             grep -q '\"desktop_file\": \"beta.desktop\"' \"$OPENIT_CANDIDATES_FILE\" || exit 1\n\
+             printf beta.desktop\n",
+        );
+        let launched = temp_dir.path().join("launched");
+        context.applications[1].exec = format!("sh -c \"touch {}\" sh %F", launched.display());
 
-        let temp_dir = TempDir::new().unwrap();
-        let config_dir = temp_dir.path().join("config");
-        fs::create_dir_all(config_dir.join("openit")).unwrap();
-        let _guard = ConfigEnvGuard::set(&config_dir);
+        open_with.run_selector_flow(&context).unwrap();
 
-        let marker_path = temp_dir.path().join("regex_touched");
-        let script_path = temp_dir.path().join("regex_script.sh");
-        fs::write(
-            &script_path,
-            format!("#!/bin/sh\ntouch {}\n", marker_path.display()),
+        assert!(wait_for_file(&launched, LAUNCH_TIMEOUT));
+    }
+
+    /// Picks "Open with command…" from the list, then answers the command prompt with
+    /// `answer`.
+    fn custom_command_selector(answer: &str) -> String {
+        format!(
+            "#!/bin/sh\n\
+             if [ \"$OPENIT_PROMPT\" = command ]; then\n\
+             cat <<'CMD'\n{answer}\nCMD\n\
+             exit 0\n\
+             fi\n\
+             tail -n 1\n"
         )
-        .unwrap();
-        let mut perms = fs::metadata(&script_path).unwrap().permissions();
-        perms.set_mode(0o755);
-        fs::set_permissions(&script_path, perms).unwrap();
+    }
 
-        let regex_path = config_dir.join("openit").join("regex_handlers.toml");
+    #[test]
+    #[cfg(unix)]
+    fn custom_command_entry_launches_the_typed_command_with_the_target() {
+        let temp_dir = TempDir::new().unwrap();
+        let marker = temp_dir.path().join("opened-with");
+        let (mut open_with, context, _selector_dir) = build_selector_test_environment(
+            &custom_command_selector(&format!(r#"sh -c 'echo "$1" > {}' sh"#, marker.display())),
+        );
+        open_with.config.custom_command_entry = true;
+
+        open_with.run_selector_flow(&context).unwrap();
+
+        assert_eq!(
+            wait_for_contents(&marker, LAUNCH_TIMEOUT).trim(),
+            "dummy.txt"
+        );
+    }
+
+    #[test]
+    fn saved_custom_command_becomes_an_extension_handler() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("regex_handlers.toml");
+        let original = "# Video sites\n[[handlers]]\nexec = \"mpv %u\"   # keep the player\nregexes = ['youtu']";
+        fs::write(&path, original).unwrap();
+
+        selection::save_command_handler(&path, "glow -p", "md").unwrap();
+
+        let saved = fs::read_to_string(&path).unwrap();
+        assert!(
+            saved.starts_with(&format!("{original}\n\n[[handlers]]\n")),
+            "{saved}"
+        );
+        let store = RegexHandlerStore::load(Some(path)).unwrap();
+        assert_eq!(store.len(), 2);
+        let handler = store.find_handler("/home/me/README.MD").unwrap();
+        assert_eq!(handler.exec, "glow -p");
+        assert!(store.find_handler("/home/me/notes.mdx").is_none());
+
+        let inline = temp_dir.path().join("inline.toml");
         fs::write(
-            &regex_path,
-            format!(
-                "[[handlers]]\nexec = \"sh {}\"\nregexes = [\".*\\\\.txt$\"]\npriority = 5\n",
-                script_path.display()
-            ),
+            &inline,
+            "handlers = [{ exec = \"mpv %u\", regexes = ['youtu'] }]\n",
         )
         .unwrap();
+        let err = selection::save_command_handler(&inline, "glow -p", "md").unwrap_err();
+        assert!(err.to_string().contains("edit it by hand"), "{err}");
+        assert!(fs::read_to_string(&inline)
+            .unwrap()
+            .starts_with("handlers = ["));
+    }
 
-        let target_path = temp_dir.path().join("match.txt");
-        fs::write(&target_path, "hello").unwrap();
+    #[test]
+    #[cfg(unix)]
+    fn custom_command_prompt_cancellation_launches_nothing() {
+        let (mut open_with, context, _temp_dir) =
+            build_selector_test_environment(&custom_command_selector(""));
+        open_with.config.custom_command_entry = true;
 
-        let args = OpenArgs {
-            target: Some(target_path.to_string_lossy().to_string()),
-            selector: SelectorKind::Auto,
-            json: false,
-            actions: false,
-            clear_cache: false,
-            verbose: 0,
-            build_info: false,
-            generate_config: false,
-            config: None,
-            open_with: false,
-            no_open_with: true,
-            selector_command: None,
-            term_exec_args: None,
-            terminal_mode: None,
-        };
+        open_with.run_selector_flow(&context).unwrap();
+        assert!(!open_with.launched.get());
+    }
 
-        let app = OpenIt::new(args).unwrap();
-        app.run().unwrap();
+    #[test]
+    #[cfg(unix)]
+    fn custom_command_entry_is_only_offered_when_enabled() {
+        // Without the entry, the last line is an ordinary application.
+        let (open_with, mut context, temp_dir) =
+            build_selector_test_environment(&custom_command_selector("false"));
+        let launched = temp_dir.path().join("launched");
+        context.applications[1].exec = format!("sh -c \"touch {}\" sh %F", launched.display());
 
-        let mut attempts = 0;
-        while attempts < 20 {
-            if marker_path.exists() {
-                break;
-            }
-            std::thread::sleep(Duration::from_millis(50));
-            attempts += 1;
-        }
+        open_with.run_selector_flow(&context).unwrap();
 
-        assert!(marker_path.exists());
+        assert!(wait_for_file(&launched, LAUNCH_TIMEOUT));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn match_query_launches_clear_winner_without_selector() {
+        let (mut open_with, mut context, temp_dir) =
+            build_selector_test_environment("#!/bin/sh\nprintf \"Unknown\"\n");
+        let launched = temp_dir.path().join("launched");
+        context.applications[0].exec = format!("sh -c \"touch {}\" sh %F", launched.display());
+        open_with.args.match_query = Some("alp".to_string());
+
+        open_with.dispatch(&context).unwrap();
+
+        assert!(wait_for_file(&launched, LAUNCH_TIMEOUT));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn assume_yes_launches_confirm_gated_handler() {
+        let (open_with, mut context, temp_dir) =
+            build_selector_test_environment("#!/bin/sh\nexit 0\n");
+        let launched = temp_dir.path().join("launched");
+        context.applications[0].exec = format!("sh -c \"touch {}\" sh %F", launched.display());
+        context.applications[0].confirm = true;
+
+        let open_with = open_with.with_assume_yes(true);
+        open_with
+            .execute_application(
+                &context.applications[0],
+                &context.target,
+                &context.mime_type,
+            )
+            .unwrap();
+
+        assert!(wait_for_file(&launched, LAUNCH_TIMEOUT));
+    }
+
+    /// Give every candidate an `Exec` that appends a line to `log`, returning the log path.
+    fn record_launches(context: &mut LaunchContext, temp_dir: &TempDir) -> PathBuf {
+        let log = temp_dir.path().join("launches.log");
+        for app in &mut context.applications {
+            app.exec = format!("sh -c \"echo {} >> {}\" sh %F", app.name, log.display());
+        }
+        log
+    }
+
+    fn recorded_launches(log: &Path) -> Vec<String> {
+        wait_for_file(log, LAUNCH_TIMEOUT);
+        // Give a second, unwanted spawn time to show up.
+        std::thread::sleep(Duration::from_millis(150));
+        fs::read_to_string(log)
+            .map(|contents| contents.lines().map(str::to_string).collect())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn dispatch_launches_exactly_once_across_flag_combinations() {
+        // (selector enabled, --match query, regex handler first, single candidate)
+        let cases = [
+            (false, None, false, false),
+            (false, None, true, false),
+            (false, None, false, true),
+            (true, Some("alp"), false, false),
+            (true, Some("alp"), true, false),
+            (false, Some("beta"), true, false),
+        ];
+
+        for (open_with_enabled, query, regex_first, single) in cases {
+            let (mut open_with, mut context, temp_dir) =
+                build_selector_test_environment("#!/bin/sh\nexit 0\n");
+            open_with.config.selector.open_with = open_with_enabled;
+            open_with.args.match_query = query.map(str::to_string);
+            if regex_first {
+                context.applications[0].source = ApplicationSource::Regex { priority: 5 };
+            }
+            if single {
+                context.applications.truncate(1);
+            }
+            let log = record_launches(&mut context, &temp_dir);
+            let case = (open_with_enabled, query, regex_first, single);
+
+            assert!(
+                matches!(open_with.decide(&context), Decision::Launch(_)),
+                "case {case:?}"
+            );
+            open_with.dispatch(&context).unwrap();
+
+            assert_eq!(recorded_launches(&log).len(), 1, "case {case:?}");
+            assert!(open_with.launched.get(), "case {case:?}");
+        }
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn selector_choice_launches_exactly_once() {
+        let (open_with, mut context, temp_dir) =
+            build_selector_test_environment("#!/bin/sh\necho 1\n");
+        let log = record_launches(&mut context, &temp_dir);
+
+        open_with.run_selector_flow(&context).unwrap();
+        assert_eq!(recorded_launches(&log), vec!["Beta"]);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    #[cfg_attr(debug_assertions, should_panic(expected = "already launched"))]
+    fn retry_after_failed_launch_never_spawns_again() {
+        let (mut open_with, mut context, temp_dir) =
+            build_selector_test_environment("#!/bin/sh\nexit 0\n");
+        open_with.config.selector.open_with = false;
+        let log = record_launches(&mut context, &temp_dir);
+        context.applications[0].exec = "openit-definitely-missing-handler %F".to_string();
+
+        assert!(open_with.dispatch(&context).is_err());
+
+        // A retry in the same invocation, e.g. falling back to the next candidate.
+        context.applications.remove(0);
+        open_with.dispatch(&context).unwrap();
+        assert!(recorded_launches(&log).is_empty());
+    }
+
+    #[test]
+    fn only_a_real_spawn_claims_the_launch() {
+        let temp_dir = TempDir::new().unwrap();
+        let notes = temp_dir.path().join("notes.txt");
+        fs::write(&notes, "hello").unwrap();
+        let target = LaunchTarget::File(notes.clone());
+        let mut app = build_explicit_handler_app(&notes, "code.desktop", false);
+        let mut viewer = plain_entry("openit-no-such-viewer %f");
+
+        app.args.dry_run = true;
+        app.execute_application(&viewer, &target, "text/plain")
+            .unwrap();
+        assert!(!app.launched.get());
+
+        // stdin is not a terminal, so the prompt fails before anything is spawned.
+        app.args.dry_run = false;
+        viewer.confirm = true;
+        assert!(app
+            .execute_application(&viewer, &target, "text/plain")
+            .is_err());
+        assert!(!app.launched.get());
+
+        // What release builds do instead of the debug assertion: refuse the second launch.
+        assert!(app.claim_launch(&viewer));
+        assert!(!app.claim_launch(&viewer));
+        assert!(app.launched.get());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn ambiguous_match_query_does_not_launch_directly() {
+        let (mut open_with, mut context, temp_dir) =
+            build_selector_test_environment("#!/bin/sh\nexit 0\n");
+        let launched = temp_dir.path().join("launched");
+        for app in &mut context.applications {
+            app.exec = format!("sh -c \"touch {}\" sh %F", launched.display());
+        }
+        context.applications[0].name = "Alpha One".to_string();
+        context.applications[1].name = "Alpha Two".to_string();
+        open_with.args.match_query = Some("alph".to_string());
+
+        open_with.dispatch(&context).unwrap();
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(!launched.exists());
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn match_query_seeds_selector_profile() {
+        let (mut open_with, context, _temp_dir) =
+            build_selector_test_environment("#!/bin/sh\nexit 0\n");
+        open_with.args.selector_command = None;
+        open_with.args.selector = SelectorKind::Named("fzf".to_string());
+        open_with.args.match_query = Some("fire".to_string());
+
+        let (command, args) = open_with.build_selector_command(&context).unwrap();
+        assert_eq!(command, "fzf");
+        assert!(args.ends_with(&["--query".to_string(), "fire".to_string()]));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    #[serial]
+    fn regex_handler_executes_command() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let temp_dir = TempDir::new().unwrap();
+        let config_dir = temp_dir.path().join("config");
+        fs::create_dir_all(config_dir.join("openit")).unwrap();
+        let _guard = ConfigEnvGuard::set(&config_dir);
+
+        let marker_path = temp_dir.path().join("regex_touched");
+        let script_path = temp_dir.path().join("regex_script.sh");
+        fs::write(
+            &script_path,
+            format!("#!/bin/sh\ntouch {}\n", marker_path.display()),
+        )
+        .unwrap();
+        let mut perms = fs::metadata(&script_path).unwrap().permissions();
+        perms.set_mode(0o755);
+        fs::set_permissions(&script_path, perms).unwrap();
+
+        let regex_path = config_dir.join("openit").join("regex_handlers.toml");
+        fs::write(
+            &regex_path,
+            format!(
+                "[[handlers]]\nexec = \"sh {}\"\nregexes = [\".*\\\\.txt$\"]\npriority = 5\n",
+                script_path.display()
+            ),
+        )
+        .unwrap();
+
+        let target_path = temp_dir.path().join("match.txt");
+        fs::write(&target_path, "hello").unwrap();
+
+        let args = OpenArgs {
+            target: Some(target_path.to_string_lossy().to_string()),
+            selector: SelectorKind::Auto,
+            json: false,
+            actions: false,
+            clear_cache: false,
+            verbose: 0,
+            build_info: false,
+            generate_config: false,
+            config: None,
+            open_with: false,
+            no_open_with: true,
+            selector_command: None,
+            term_exec_args: None,
+            terminal_mode: None,
+            skip_unavailable: false,
+            with_handler: None,
+            force: false,
+            timings: false,
+            gpu: None,
+            pipe: false,
+            match_query: None,
+            dry_run: false,
+            wait: false,
+            wait_timeout: None,
+            any_handler: None,
+            recent: None,
+            strip_query: false,
+            action: None,
+            literal: false,
+            include_all_desktops: false,
+        };
+
+        let app = OpenIt::new(args).unwrap();
+        app.run().unwrap();
+
+        assert!(wait_for_file(&marker_path, LAUNCH_TIMEOUT));
     }
 
     #[test]
@@ -911,10 +2006,18 @@ mod tests {
             config,
             regex_handlers,
             args,
+            assume_yes: false,
+            launched: Cell::new(false),
+            mime_detector: Box::new(BuiltinMimeDetector),
+            history_path: None,
+            window_query: None,
+            notifier: None,
         };
 
-        let launcher = open_with.resolve_terminal_launcher().unwrap();
-        assert_eq!(launcher, vec!["foot"]);
+        let launcher = open_with
+            .resolve_terminal_launcher_in(&TerminalEnv::default(), TerminalPurpose::Handler)
+            .unwrap();
+        assert_eq!(launcher.command, vec!["foot"]);
     }
 
     #[test]
@@ -959,10 +2062,1073 @@ mod tests {
             config,
             regex_handlers,
             args,
+            assume_yes: false,
+            launched: Cell::new(false),
+            mime_detector: Box::new(BuiltinMimeDetector),
+            history_path: None,
+            window_query: None,
+            notifier: None,
+        };
+
+        let launcher = open_with
+            .resolve_terminal_launcher_in(&TerminalEnv::default(), TerminalPurpose::Handler)
+            .unwrap();
+        assert_eq!(launcher.command, vec!["kitty", "--single-instance"]);
+
+        // `$TERMINAL` wins over the category fallback when its binary is installed.
+        let bin = fake_bin_dir(&["alacritty"]);
+        let env = TerminalEnv {
+            path: Some(bin.path().as_os_str().to_owned()),
+            terminal: Some("alacritty".to_string()),
+        };
+        let launcher = open_with
+            .resolve_terminal_launcher_in(&env, TerminalPurpose::Handler)
+            .unwrap();
+        assert_eq!(launcher.command, vec!["alacritty"]);
+    }
+
+    /// Finder with `foot.desktop` as the terminal scheme handler and kitty as a category match.
+    fn build_terminal_resolution_app(config: Config) -> OpenIt {
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        cache.insert(
+            PathBuf::from("/usr/share/applications/foot.desktop"),
+            DesktopFile {
+                main_entry: Some(DesktopEntry {
+                    name: "Foot".to_string(),
+                    exec: "foot".to_string(),
+                    mime_types: vec!["x-scheme-handler/terminal".to_string()],
+                    categories: vec!["TerminalEmulator".to_string()],
+                    ..DesktopEntry::default()
+                }),
+                actions: HashMap::new(),
+            },
+        );
+        cache.insert(
+            PathBuf::from("/usr/share/applications/kitty.desktop"),
+            DesktopFile {
+                main_entry: Some(DesktopEntry {
+                    name: "Kitty".to_string(),
+                    exec: "kitty --single-instance".to_string(),
+                    categories: vec!["TerminalEmulator".to_string()],
+                    ..DesktopEntry::default()
+                }),
+                actions: HashMap::new(),
+            },
+        );
+
+        let mut associations = HashMap::new();
+        associations.insert(
+            "x-scheme-handler/terminal".to_string(),
+            vec!["foot.desktop".to_string()],
+        );
+
+        OpenIt {
+            application_finder: ApplicationFinder::new(
+                cache,
+                MimeAssociations::with_associations(associations),
+            ),
+            selector_runner: SelectorRunner::new(),
+            executor: ApplicationExecutor::new(),
+            config,
+            regex_handlers: RegexHandlerStore::load(None).unwrap(),
+            args: create_test_args_json(Some(PathBuf::from("test.txt"))),
+            assume_yes: false,
+            launched: Cell::new(false),
+            mime_detector: Box::new(BuiltinMimeDetector),
+            history_path: None,
+            window_query: None,
+            notifier: None,
+        }
+    }
+
+    fn dry_run_app(executor: ApplicationExecutor) -> OpenIt {
+        let config = Config {
+            terminal: Some("kitty.desktop".to_string()),
+            ..Config::default()
+        };
+        let mut app = build_terminal_resolution_app(config);
+        app.executor = executor;
+        app.args.dry_run = true;
+        app
+    }
+
+    fn plain_entry(exec: &str) -> ApplicationEntry {
+        ApplicationEntry::from_desktop_entry(
+            &DesktopEntry {
+                name: "Viewer".to_string(),
+                exec: exec.to_string(),
+                ..DesktopEntry::default()
+            },
+            PathBuf::from("/usr/share/applications/viewer.desktop"),
+        )
+    }
+
+    #[test]
+    fn dry_run_command_wraps_terminal_apps_in_the_launcher() {
+        let app = dry_run_app(ApplicationExecutor::with_options(
+            None,
+            Some("-e".to_string()),
+        ));
+        let mut entry = plain_entry("htop %f");
+        entry.requires_terminal = true;
+        let target = LaunchTarget::File(PathBuf::from("/tmp/my notes.txt"));
+
+        let command = app.launch_command(&entry, &target, "text/plain").unwrap();
+        assert_eq!(
+            command,
+            vec![
+                "kitty",
+                "--single-instance",
+                "-e",
+                "htop",
+                "/tmp/my notes.txt"
+            ]
+        );
+        assert_eq!(
+            shell_words::join(&command),
+            "kitty --single-instance -e htop '/tmp/my notes.txt'"
+        );
+    }
+
+    #[test]
+    fn dry_run_command_uses_expanded_regex_exec() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("regex_handlers.toml");
+        fs::write(
+            &path,
+            "[[handlers]]\nexec = \"mpv --title=${id} %u\"\nregexes = ['youtu\\.be/(?P<id>\\w+)']\n",
+        )
+        .unwrap();
+        let store = RegexHandlerStore::load(Some(path)).unwrap();
+        let url = "https://youtu.be/abc123";
+        let handler = store.find_handler(url).unwrap();
+        let entry = application_from_regex(handler, handler.expand_exec(url).unwrap());
+
+        let app = dry_run_app(ApplicationExecutor::new());
+        let target = LaunchTarget::Uri(Url::parse(url).unwrap());
+        assert_eq!(
+            app.launch_command(&entry, &target, "x-scheme-handler/https")
+                .unwrap(),
+            vec!["mpv", "--title=abc123", url]
+        );
+    }
+
+    #[test]
+    fn strip_query_matches_regex_handlers_but_launches_the_full_uri() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("regex_handlers.toml");
+        fs::write(
+            &path,
+            "[[handlers]]\nexec = \"mpv --title=${id} %u\"\nregexes = ['^https://example\\.com/watch/(?P<id>\\w+)$']\n",
+        )
+        .unwrap();
+        let url = "https://example.com/watch/abc123?utm_source=feed";
+        let target = LaunchTarget::Uri(Url::parse(url).unwrap());
+
+        let store = RegexHandlerStore::load(Some(path)).unwrap();
+        assert!(store.find_handler(&store.candidate(&target)).is_none());
+
+        let store = store.with_uri_match_normalize(UriMatchNormalize::StripQuery);
+        let candidate = store.candidate(&target);
+        assert_eq!(candidate, "https://example.com/watch/abc123");
+        let handler = store.find_handler(&candidate).unwrap();
+        let entry = application_from_regex(handler, handler.expand_exec(&candidate).unwrap());
+
+        let app = dry_run_app(ApplicationExecutor::new());
+        assert_eq!(
+            app.launch_command(&entry, &target, "x-scheme-handler/https")
+                .unwrap(),
+            vec!["mpv", "--title=abc123", url]
+        );
+
+        let with_fragment =
+            LaunchTarget::Uri(Url::parse("https://example.com/watch/abc123#t=30").unwrap());
+        assert_eq!(
+            with_fragment.match_candidate(UriMatchNormalize::StripFragment),
+            "https://example.com/watch/abc123"
+        );
+        assert_eq!(
+            with_fragment.match_candidate(UriMatchNormalize::StripQuery),
+            "https://example.com/watch/abc123#t=30"
+        );
+    }
+
+    #[test]
+    fn dry_run_command_includes_launch_prefix_and_spawns_nothing() {
+        let app = dry_run_app(ApplicationExecutor::with_options(
+            Some("uwsm app --".to_string()),
+            None,
+        ));
+        let entry = plain_entry("openit-definitely-missing-binary %f");
+        let target = LaunchTarget::File(PathBuf::from("/tmp/photo.png"));
+
+        assert_eq!(
+            app.launch_command(&entry, &target, "image/png").unwrap(),
+            vec![
+                "uwsm",
+                "app",
+                "--",
+                "openit-definitely-missing-binary",
+                "/tmp/photo.png"
+            ]
+        );
+        // Spawning the missing binary would fail; a dry run only prints.
+        app.execute_application(&entry, &target, "image/png")
+            .unwrap();
+    }
+
+    #[test]
+    fn dry_run_command_uses_the_prefix_for_the_target_mime() {
+        let app = dry_run_app(
+            ApplicationExecutor::with_options(Some("uwsm app --".to_string()), None)
+                .with_per_mime_prefix(HashMap::from([(
+                    "application/pdf".to_string(),
+                    "flatpak run --command=zathura org.pwmt.zathura".to_string(),
+                )])),
+        );
+        let entry = plain_entry("zathura %f");
+        let pdf = LaunchTarget::File(PathBuf::from("/tmp/paper.pdf"));
+        let png = LaunchTarget::File(PathBuf::from("/tmp/photo.png"));
+
+        assert_eq!(
+            app.launch_command(&entry, &pdf, "application/pdf")
+                .unwrap()
+                .join(" "),
+            "flatpak run --command=zathura org.pwmt.zathura zathura /tmp/paper.pdf"
+        );
+        assert_eq!(
+            app.launch_command(&entry, &png, "image/png")
+                .unwrap()
+                .join(" "),
+            "uwsm app -- zathura /tmp/photo.png"
+        );
+    }
+
+    #[test]
+    fn dry_run_json_adds_a_command_per_application() {
+        let app = dry_run_app(ApplicationExecutor::new());
+        let mut terminal = plain_entry("htop");
+        terminal.requires_terminal = true;
+        let context = LaunchContext::new(
+            LaunchTarget::File(PathBuf::from("/tmp/photo.png")),
+            "image/png".to_string(),
+            vec![plain_entry("imv %f"), terminal],
+        );
+
+        let mut document = candidates_json(&context);
+        app.add_launch_commands(&mut document, &context);
+        assert_eq!(
+            document["applications"][0]["command"],
+            json!(["imv", "/tmp/photo.png"])
+        );
+        assert_eq!(
+            document["applications"][1]["command"],
+            json!(["kitty", "--single-instance", "htop", "/tmp/photo.png"])
+        );
+    }
+
+    fn fake_bin_dir(commands: &[&str]) -> TempDir {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = TempDir::new().unwrap();
+        for command in commands {
+            let path = dir.path().join(command);
+            fs::write(&path, "#!/bin/sh\n").unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn resolve_terminal_launcher_uses_configured_desktop_id() {
+        let bin = fake_bin_dir(&["xdg-terminal-exec"]);
+        let config = Config {
+            terminal: Some("kitty.desktop".to_string()),
+            ..Config::default()
         };
+        let app = build_terminal_resolution_app(config);
+
+        let env = TerminalEnv {
+            path: Some(bin.path().as_os_str().to_owned()),
+            terminal: Some("foot".to_string()),
+        };
+        let launcher = app
+            .resolve_terminal_launcher_in(&env, TerminalPurpose::Handler)
+            .unwrap();
+        assert_eq!(launcher.command, vec!["kitty", "--single-instance"]);
+        assert!(launcher.exec_args);
+    }
+
+    #[test]
+    fn resolve_terminal_launcher_configured_unknown_desktop_id_errors() {
+        let config = Config {
+            terminal: Some("missing.desktop".to_string()),
+            ..Config::default()
+        };
+        let app = build_terminal_resolution_app(config);
+
+        let err = app
+            .resolve_terminal_launcher_in(&TerminalEnv::default(), TerminalPurpose::Handler)
+            .unwrap_err();
+        assert!(err.to_string().contains("missing.desktop"));
+    }
+
+    #[test]
+    fn resolve_terminal_launcher_uses_purpose_specific_pins() {
+        let bin = fake_bin_dir(&["xdg-terminal-exec"]);
+        let config = Config {
+            terminal: Some("foot.desktop".to_string()),
+            terminal_for_directory: Some("kitty".to_string()),
+            ..Config::default()
+        };
+        let app = build_terminal_resolution_app(config);
+        let env = TerminalEnv {
+            path: Some(bin.path().as_os_str().to_owned()),
+            terminal: None,
+        };
+
+        let directory = app
+            .resolve_terminal_launcher_in(&env, TerminalPurpose::Directory)
+            .unwrap();
+        assert_eq!(directory.command, vec!["kitty", "--single-instance"]);
+
+        let handler = app
+            .resolve_terminal_launcher_in(&env, TerminalPurpose::Handler)
+            .unwrap();
+        assert_eq!(handler.command, vec!["foot"]);
+    }
+
+    #[test]
+    fn pinned_terminal_validation_lists_detected_emulators() {
+        let config = Config {
+            terminal_for_handlers: Some("wezterm.desktop".to_string()),
+            terminal: Some("foot".to_string()),
+            ..Config::default()
+        };
+        let app = build_terminal_resolution_app(config);
+
+        let expected = "Config `terminal_for_handlers` names unknown terminal `wezterm.desktop`; \
+             detected terminals: foot.desktop, kitty.desktop";
+        assert_eq!(
+            OpenIt::stale_pinned_terminals(&app.config, &app.application_finder),
+            vec![expected]
+        );
+
+        // Only launches that need the stale pin fail.
+        let env = TerminalEnv::default();
+        let err = app
+            .resolve_terminal_launcher_in(&env, TerminalPurpose::Handler)
+            .unwrap_err();
+        assert_eq!(err.to_string(), expected);
+        assert!(app
+            .resolve_terminal_launcher_in(&env, TerminalPurpose::Directory)
+            .is_ok());
+
+        let valid = Config {
+            terminal_for_handlers: Some("foot.desktop".to_string()),
+            ..Config::default()
+        };
+        assert!(OpenIt::stale_pinned_terminals(&valid, &app.application_finder).is_empty());
+    }
+
+    #[test]
+    fn resolve_terminal_launcher_prefers_xdg_terminal_exec() {
+        let bin = fake_bin_dir(&["xdg-terminal-exec", "alacritty"]);
+        let app = build_terminal_resolution_app(Config::default());
+
+        let env = TerminalEnv {
+            path: Some(bin.path().as_os_str().to_owned()),
+            terminal: Some("alacritty".to_string()),
+        };
+        let launcher = app
+            .resolve_terminal_launcher_in(&env, TerminalPurpose::Handler)
+            .unwrap();
+        assert_eq!(
+            launcher.command,
+            vec![bin
+                .path()
+                .join("xdg-terminal-exec")
+                .to_string_lossy()
+                .into_owned()]
+        );
+        assert!(!launcher.exec_args);
+    }
+
+    #[test]
+    fn resolve_terminal_launcher_uses_terminal_env() {
+        let bin = fake_bin_dir(&["alacritty"]);
+        let app = build_terminal_resolution_app(Config::default());
+
+        let env = TerminalEnv {
+            path: Some(bin.path().as_os_str().to_owned()),
+            terminal: Some("alacritty --class openit".to_string()),
+        };
+        let launcher = app
+            .resolve_terminal_launcher_in(&env, TerminalPurpose::Handler)
+            .unwrap();
+        assert_eq!(launcher.command, vec!["alacritty", "--class", "openit"]);
+        assert!(launcher.exec_args);
+    }
+
+    #[test]
+    fn resolve_terminal_launcher_ignores_missing_terminal_env_binary() {
+        let bin = fake_bin_dir(&[]);
+        let app = build_terminal_resolution_app(Config::default());
+
+        let env = TerminalEnv {
+            path: Some(bin.path().as_os_str().to_owned()),
+            terminal: Some("alacritty".to_string()),
+        };
+        let launcher = app
+            .resolve_terminal_launcher_in(&env, TerminalPurpose::Handler)
+            .unwrap();
+        assert_eq!(launcher.command, vec!["foot"]);
+    }
+
+    #[test]
+    #[serial]
+    fn terminal_env_reads_terminal_variable() {
+        let _guard = EnvVarGuard::set("TERMINAL", "wezterm start");
+
+        assert_eq!(
+            TerminalEnv::current().terminal.as_deref(),
+            Some("wezterm start")
+        );
+    }
+
+    fn build_explicit_handler_app(target: &Path, handler: &str, force: bool) -> OpenIt {
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        cache.insert(
+            PathBuf::from("/usr/share/applications/code.desktop"),
+            DesktopFile {
+                main_entry: Some(DesktopEntry {
+                    name: "Code".to_string(),
+                    exec: "code %F".to_string(),
+                    mime_types: vec!["text/plain".to_string()],
+                    ..DesktopEntry::default()
+                }),
+                actions: HashMap::new(),
+            },
+        );
+
+        let mut args = create_test_args_json(Some(target.to_path_buf()));
+        args.with_handler = Some(handler.to_string());
+        args.force = force;
+
+        let config = Config::default();
+        OpenIt {
+            application_finder: ApplicationFinder::new(cache, MimeAssociations::default()),
+            selector_runner: SelectorRunner::new(),
+            executor: ApplicationExecutor::new(),
+            config,
+            regex_handlers: RegexHandlerStore::load(None).unwrap(),
+            args,
+            assume_yes: false,
+            launched: Cell::new(false),
+            mime_detector: Box::new(BuiltinMimeDetector),
+            history_path: None,
+            window_query: None,
+            notifier: None,
+        }
+    }
+
+    #[test]
+    fn dry_run_prints_the_command_for_with_and_auto_selected_handlers() {
+        let temp_dir = TempDir::new().unwrap();
+        let notes = temp_dir.path().join("my notes.txt");
+        fs::write(&notes, "hello").unwrap();
+        let expected = format!("uwsm app -- code '{}'", notes.display());
+
+        let mut app = build_explicit_handler_app(&notes, "code.desktop", false);
+        app.executor = ApplicationExecutor::with_options(Some("uwsm app --".to_string()), None);
+        app.args.dry_run = true;
+        let (target, entry, mime_type) = app.prepare_explicit_launch("code.desktop").unwrap();
+        assert_eq!(
+            app.dry_run_line(&entry, &target, &mime_type).unwrap(),
+            expected
+        );
+
+        // Without --with, the only candidate is picked without a selector.
+        app.args.with_handler = None;
+        app.args.json = false;
+        app.config.selector.open_with = false;
+        let context = app.prepare_launch().unwrap();
+        assert_eq!(app.decide(&context), Decision::Launch(0));
+        assert_eq!(
+            app.dry_run_line(
+                &context.applications[0],
+                &context.target,
+                &context.mime_type
+            )
+            .unwrap(),
+            expected
+        );
+        // `code` is not installed, so a real launch would fail.
+        app.run().unwrap();
+    }
+
+    #[test]
+    fn explicit_handler_resolves_desktop_id() {
+        let temp_dir = TempDir::new().unwrap();
+        let notes = temp_dir.path().join("notes.txt");
+        fs::write(&notes, "hello").unwrap();
+
+        let app = build_explicit_handler_app(&notes, "code.desktop", false);
+        let (target, entry, _) = app.prepare_explicit_launch("code.desktop").unwrap();
+
+        assert_eq!(entry.name, "Code");
+        assert_eq!(
+            entry.desktop_file,
+            PathBuf::from("/usr/share/applications/code.desktop")
+        );
+        assert_eq!(target.as_path(), Some(notes.as_path()));
+    }
+
+    /// `code`, `nvim` and `gedit` in the launch history for `text/plain`, most recent first.
+    fn build_recent_handler_app(target: &Path, history_path: &Path) -> OpenIt {
+        let mut app = build_explicit_handler_app(target, "code.desktop", false);
+        app.args.with_handler = None;
+        let mut history = LaunchHistory::default();
+        for id in ["gedit.desktop", "nvim.desktop", "code.desktop"] {
+            history.record("text/plain", id);
+        }
+        history.save(history_path).unwrap();
+        app.history_path = Some(history_path.to_path_buf());
+        app
+    }
+
+    #[test]
+    fn recent_handler_indexes_the_launch_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let notes = temp_dir.path().join("notes.txt");
+        fs::write(&notes, "hello").unwrap();
+        let app = build_recent_handler_app(&notes, &temp_dir.path().join("history.json"));
+
+        let (_, entry, mime_type) = app.prepare_recent_launch(1).unwrap();
+        assert_eq!(entry.name, "Code");
+        assert_eq!(mime_type, "text/plain");
+
+        // `nvim.desktop` is in the history but no longer installed.
+        let err = app.prepare_recent_launch(2).err().unwrap().to_string();
+        assert!(err.contains("`nvim.desktop` not found"), "{err}");
+
+        let err = app.prepare_recent_launch(4).err().unwrap().to_string();
+        assert!(err.contains("Only 3 handler(s)"), "{err}");
+    }
+
+    #[test]
+    fn launches_of_desktop_entries_are_recorded() {
+        let temp_dir = TempDir::new().unwrap();
+        let notes = temp_dir.path().join("notes.txt");
+        fs::write(&notes, "hello").unwrap();
+        let history_path = temp_dir.path().join("history.json");
+        let mut app = build_recent_handler_app(&notes, &history_path);
+
+        let mut gedit = plain_entry("gedit %F");
+        gedit.desktop_file = PathBuf::from("/usr/share/applications/gedit.desktop");
+        app.record_launch(&gedit, "text/plain");
+        let mut regex = plain_entry("gedit %F");
+        regex.desktop_file = PathBuf::from("regex-handler-10.desktop");
+        app.record_launch(&regex, "text/plain");
+
+        let history = LaunchHistory::load(&history_path).unwrap();
+        assert_eq!(
+            history.recent("text/plain"),
+            ["gedit.desktop", "code.desktop", "nvim.desktop"]
+        );
+        let mut files: Vec<_> = fs::read_dir(temp_dir.path())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        files.sort();
+        assert_eq!(files, ["history.json", "history.json.lock", "notes.txt"]);
+
+        app.config.record_history = false;
+        let mut code = plain_entry("code %F");
+        code.desktop_file = PathBuf::from("/usr/share/applications/code.desktop");
+        app.record_launch(&code, "text/plain");
+        let history = LaunchHistory::load(&history_path).unwrap();
+        assert_eq!(history.recent("text/plain")[0], "gedit.desktop");
+    }
+
+    /// Window query stub that reports these classes as open windows.
+    #[derive(Debug)]
+    struct StubWindows(Vec<&'static str>);
+
+    impl WindowQuery for StubWindows {
+        fn focus(&self, class: &str) -> Result<bool> {
+            Ok(self.0.contains(&class))
+        }
+    }
+
+    fn build_focusing_app(target: &Path) -> OpenIt {
+        let mut app = build_explicit_handler_app(target, "code.desktop", false);
+        app.window_query = Some(Box::new(StubWindows(vec!["viewer"])));
+        app
+    }
+
+    #[test]
+    fn single_main_window_entry_focuses_its_open_window_instead_of_launching() {
+        let temp_dir = TempDir::new().unwrap();
+        let notes = temp_dir.path().join("notes.txt");
+        fs::write(&notes, "hello").unwrap();
+        let history_path = temp_dir.path().join("history.json");
+        let mut app = build_focusing_app(&notes);
+        app.history_path = Some(history_path.clone());
+        let target = LaunchTarget::File(notes);
+
+        // Launching would fail: the binary does not exist.
+        let mut viewer = plain_entry("openit-no-such-viewer");
+        viewer.single_main_window = true;
+        app.execute_application(&viewer, &target, "text/plain")
+            .unwrap();
+        assert!(!history_path.exists());
+
+        // An entry taking the file must receive it, so it is launched, not focused.
+        app.launched.set(false);
+        viewer.exec = "openit-no-such-viewer %f".to_string();
+        assert!(app
+            .execute_application(&viewer, &target, "text/plain")
+            .is_err());
+    }
+
+    #[test]
+    fn focus_is_skipped_without_the_hint_an_open_window_or_for_actions() {
+        let app = build_focusing_app(Path::new("/tmp/notes.txt"));
+        let mut viewer = plain_entry("viewer");
+        assert!(!app.focus_existing(&viewer));
+
+        viewer.single_main_window = true;
+        assert!(app.focus_existing(&viewer));
+        viewer.exec = "viewer %U".to_string();
+        assert!(!app.focus_existing(&viewer));
+        viewer.exec = "viewer".to_string();
+
+        viewer.startup_wm_class = Some("Viewer-Main".to_string());
+        assert!(!app.focus_existing(&viewer));
+
+        viewer.startup_wm_class = None;
+        viewer.action_id = Some("new-window".to_string());
+        assert!(!app.focus_existing(&viewer));
+    }
+
+    #[test]
+    fn successful_launch_sends_a_notification_naming_handler_and_target() {
+        let temp_dir = TempDir::new().unwrap();
+        let notes = temp_dir.path().join("notes.txt");
+        fs::write(&notes, "hello").unwrap();
+        let log = temp_dir.path().join("notified");
+        let mut app = build_explicit_handler_app(&notes, "code.desktop", false);
+        app.notifier = Some(Box::new(CommandNotifier::new(format!(
+            "sh -c 'printf %s \"$0\" > {}' {{message}}",
+            log.display()
+        ))));
+
+        app.execute_application(
+            &plain_entry("true %f"),
+            &LaunchTarget::File(notes.clone()),
+            "text/plain",
+        )
+        .unwrap();
+        assert_eq!(
+            fs::read_to_string(&log).unwrap(),
+            "Opening notes.txt with Viewer"
+        );
+
+        // A failed launch is not announced.
+        let mut app = build_explicit_handler_app(&notes, "code.desktop", false);
+        app.notifier = Some(Box::new(CommandNotifier::new(format!(
+            "touch {}",
+            temp_dir.path().join("failed").display()
+        ))));
+        assert!(app
+            .execute_application(
+                &plain_entry("openit-no-such-viewer %f"),
+                &LaunchTarget::File(notes),
+                "text/plain",
+            )
+            .is_err());
+        assert!(!temp_dir.path().join("failed").exists());
+    }
+
+    /// Three image apps declaring their actions out of alphabetical order; `Gamma` is the
+    /// mimeapps default, so its main entry outranks everything else.
+    fn multi_action_finder() -> ApplicationFinder {
+        use crate::desktop_parser::DesktopAction;
+
+        let mut cache = Box::new(crate::cache::MemoryCache::new());
+        for (id, name, declared) in [
+            ("alpha", "Alpha", ["zoom", "edit", "print"]),
+            ("beta", "Beta", ["send", "crop", "rotate"]),
+            ("gamma", "Gamma", ["slideshow", "annotate", "export"]),
+        ] {
+            let actions: HashMap<String, DesktopAction> = declared
+                .iter()
+                .map(|action| {
+                    (
+                        action.to_string(),
+                        DesktopAction {
+                            name: action.to_string(),
+                            exec: format!("{id} --{action} %f"),
+                            icon: None,
+                        },
+                    )
+                })
+                .collect();
+            cache.insert(
+                PathBuf::from(format!("/usr/share/applications/{id}.desktop")),
+                DesktopFile {
+                    main_entry: Some(DesktopEntry {
+                        name: name.to_string(),
+                        exec: format!("{id} %f"),
+                        mime_types: vec!["image/png".to_string()],
+                        actions: declared.iter().map(|action| action.to_string()).collect(),
+                        ..DesktopEntry::default()
+                    }),
+                    actions,
+                },
+            );
+        }
+
+        let associations = MimeAssociations::from_mimeapps_contents(
+            "[Default Applications]\nimage/png=gamma.desktop;\n",
+        );
+        ApplicationFinder::new(cache, associations)
+    }
+
+    fn multi_action_context() -> LaunchContext {
+        let applications = multi_action_finder().find_for_mime("image/png", true);
+        LaunchContext::new(
+            LaunchTarget::File(PathBuf::from("/tmp/photo.png")),
+            "image/png".to_string(),
+            applications,
+        )
+    }
+
+    #[test]
+    fn requested_action_comes_from_the_winning_desktop_file() {
+        let mut app =
+            build_explicit_handler_app(Path::new("/tmp/photo.png"), "code.desktop", false);
+        app.application_finder = multi_action_finder();
+        let context = multi_action_context();
+
+        let action = app
+            .action_of_winner(&context.applications, "annotate")
+            .unwrap();
+        assert_eq!(action.action_id.as_deref(), Some("annotate"));
+        assert_eq!(action.exec, "gamma --annotate %f");
+        assert_eq!(
+            action.desktop_file,
+            PathBuf::from("/usr/share/applications/gamma.desktop")
+        );
+
+        // A regex handler or fallback opener ranked first has no actions, so the next
+        // desktop entry wins.
+        let mut applications = context.applications.clone();
+        let mut regex = plain_entry("xdg-open %u");
+        regex.source = ApplicationSource::Regex { priority: 10 };
+        applications.insert(0, regex);
+        applications.insert(
+            0,
+            fallback::fallback_openers("text/plain", |_| true).remove(0),
+        );
+        let action = app.action_of_winner(&applications, "export").unwrap();
+        assert_eq!(action.exec, "gamma --export %f");
+
+        // An entry known by its desktop ID alone is still a desktop entry.
+        let mut by_id = plain_entry("beta %f");
+        by_id.desktop_file = PathBuf::from("beta.desktop");
+        let action = app.action_of_winner(&[by_id], "crop").unwrap();
+        assert_eq!(action.exec, "beta --crop %f");
+    }
+
+    #[test]
+    fn unknown_action_lists_the_available_ones() {
+        let mut app =
+            build_explicit_handler_app(Path::new("/tmp/photo.png"), "code.desktop", false);
+        app.application_finder = multi_action_finder();
+        let context = multi_action_context();
+
+        let err = app
+            .action_of_winner(&context.applications, "crop")
+            .unwrap_err()
+            .to_string();
+        assert!(
+            err.contains("has no action `crop`; available actions: slideshow, annotate, export"),
+            "{err}"
+        );
+
+        let code = build_explicit_handler_app(Path::new("/tmp/notes.txt"), "code.desktop", false);
+        let (entry, path) = code.desktop_entry_for("code.desktop").unwrap();
+        let entry = ApplicationEntry::from_desktop_entry(entry, path.clone());
+        let err = code
+            .desktop_action_for(&entry, "edit")
+            .unwrap_err()
+            .to_string();
+        assert!(err.contains("declares no desktop actions"), "{err}");
+    }
+
+    #[test]
+    fn actions_follow_their_handler_in_declaration_order() {
+        let context = multi_action_context();
+        let labels: Vec<String> = context
+            .applications
+            .iter()
+            .map(|app| match &app.action_id {
+                Some(action) => format!("{}:{action}", desktop_id_of_path(&app.desktop_file)),
+                None => desktop_id_of_path(&app.desktop_file),
+            })
+            .collect();
+
+        assert_eq!(
+            labels,
+            vec![
+                "gamma",
+                "gamma:slideshow",
+                "gamma:annotate",
+                "gamma:export",
+                "alpha",
+                "alpha:zoom",
+                "alpha:edit",
+                "alpha:print",
+                "beta",
+                "beta:send",
+                "beta:crop",
+                "beta:rotate",
+            ]
+        );
+    }
+
+    #[test]
+    fn ordering_is_stable_across_runs() {
+        let first: Vec<String> = multi_action_context()
+            .applications
+            .iter()
+            .map(|app| app.name.clone())
+            .collect();
+        for _ in 0..10 {
+            let again: Vec<String> = multi_action_context()
+                .applications
+                .iter()
+                .map(|app| app.name.clone())
+                .collect();
+            assert_eq!(again, first);
+        }
+    }
+
+    #[test]
+    fn json_nests_actions_under_their_handler() {
+        let json = candidates_json(&multi_action_context());
+
+        assert_eq!(json["applications"].as_array().unwrap().len(), 12);
+        let handlers = json["handlers"].as_array().unwrap();
+        let names: Vec<&str> = handlers
+            .iter()
+            .map(|handler| handler["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(names, vec!["Gamma", "Alpha", "Beta"]);
+
+        let alpha_actions: Vec<&str> = handlers[1]["actions"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|action| action["action_id"].as_str().unwrap())
+            .collect();
+        assert_eq!(alpha_actions, vec!["zoom", "edit", "print"]);
+        assert!(handlers[0]["action_id"].is_null());
+    }
+
+    fn desktop_id_of_path(path: &Path) -> String {
+        path.file_stem().unwrap().to_string_lossy().into_owned()
+    }
+
+    #[derive(Debug)]
+    struct ForcedMime(&'static str);
+
+    impl MimeDetector for ForcedMime {
+        fn detect(&self, _target: &LaunchTarget) -> Option<String> {
+            Some(self.0.to_string())
+        }
+    }
+
+    #[test]
+    fn custom_mime_detector_drives_handler_lookup() {
+        let temp_dir = TempDir::new().unwrap();
+        let image = temp_dir.path().join("photo.png");
+        fs::write(&image, "actually text").unwrap();
+
+        let builtin = build_explicit_handler_app(&image, "code.desktop", false);
+        let err = builtin.prepare_launch().err().unwrap();
+        assert!(err.to_string().contains("image/png"), "{err}");
+
+        let app = build_explicit_handler_app(&image, "code.desktop", false)
+            .with_mime_detector(Box::new(ForcedMime("text/plain")));
+        let context = app.prepare_launch().unwrap();
+        assert_eq!(context.mime_type, "text/plain");
+        assert_eq!(context.applications[0].name, "Code");
+
+        let (_, entry, _) = app.prepare_explicit_launch("code.desktop").unwrap();
+        assert_eq!(entry.name, "Code");
+    }
+
+    #[test]
+    fn shortcut_files_open_their_url_unless_literal() {
+        let temp_dir = TempDir::new().unwrap();
+        let shortcut = temp_dir.path().join("Docs.url");
+        fs::write(&shortcut, include_str!("../testdata/shortcuts/example.url")).unwrap();
+        let raw = shortcut.to_string_lossy();
+
+        let mut app = build_explicit_handler_app(&shortcut, "code.desktop", false);
+        let target = app.resolve_target(&raw).unwrap();
+        assert_eq!(
+            target.as_command_argument(),
+            "https://example.com/docs?page=2"
+        );
+        assert_eq!(app.detect_mime(&target), "x-scheme-handler/https");
+
+        app.args.literal = true;
+        let target = app.resolve_target(&raw).unwrap();
+        assert_eq!(target.as_path(), Some(shortcut.as_path()));
+        assert_eq!(app.detect_mime(&target), "application/x-mswinurl");
+
+        // A shortcut without a usable URL is just a file.
+        let broken = temp_dir.path().join("broken.webloc");
+        fs::write(
+            &broken,
+            include_str!("../testdata/shortcuts/malformed.webloc"),
+        )
+        .unwrap();
+        app.args.literal = false;
+        let target = app.resolve_target(&broken.to_string_lossy()).unwrap();
+        assert_eq!(target.as_path(), Some(broken.as_path()));
+        assert_eq!(app.detect_mime(&target), "application/x-webloc");
+
+        // Nor is one pointing at a local file.
+        let local = temp_dir.path().join("Local.url");
+        fs::write(&local, "[InternetShortcut]\nURL=file:///etc/passwd\n").unwrap();
+        let target = app.resolve_target(&local.to_string_lossy()).unwrap();
+        assert_eq!(target.as_path(), Some(local.as_path()));
+    }
+
+    fn any_handler_app(mime: &'static str, any_handler: Option<AnyHandlerArg>) -> OpenIt {
+        let mut app = build_terminal_resolution_app(Config::default())
+            .with_mime_detector(Box::new(ForcedMime(mime)));
+        app.args.target = Some("https://example.com/blob.weird".to_string());
+        app.args.any_handler = any_handler;
+        app.args.selector = SelectorKind::Named("fzf".to_string());
+        app
+    }
+
+    fn app_names(context: &LaunchContext) -> Vec<&str> {
+        context
+            .applications
+            .iter()
+            .map(|app| app.name.as_str())
+            .collect()
+    }
+
+    #[test]
+    #[serial]
+    fn unhandled_local_files_offer_installed_fallback_openers() {
+        use std::os::unix::fs::PermissionsExt;
+        let temp_dir = TempDir::new().unwrap();
+        let bin = temp_dir.path().join("bin");
+        fs::create_dir(&bin).unwrap();
+        for program in ["jq", "less"] {
+            let path = bin.join(program);
+            fs::write(&path, "#!/bin/sh\n").unwrap();
+            fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).unwrap();
+        }
+        let _path = EnvVarGuard::set("PATH", &bin.to_string_lossy());
+        let data = temp_dir.path().join("data.json");
+        fs::write(&data, "{}").unwrap();
+
+        let mut app = any_handler_app("application/json", None);
+        app.args.target = Some(data.to_string_lossy().into_owned());
+        let context = app.prepare_launch().unwrap();
+        assert_eq!(app_names(&context), vec!["jq", "less"]);
+        assert!(context.applications.iter().all(|entry| entry.stdin_input));
+
+        // `less` needs the terminal openit runs in, since its stdin is the file.
+        let less = &context.applications[1];
+        assert!(app.check_piped_terminal(less, true).is_ok());
+        let err = app.check_piped_terminal(less, false).unwrap_err();
+        assert!(err.to_string().contains("`less` needs a terminal"), "{err}");
+        assert!(app
+            .check_piped_terminal(&context.applications[0], false)
+            .is_ok());
+
+        app.config.fallback_openers = false;
+        let err = app.prepare_launch().err().unwrap();
+        assert!(err.to_string().contains("No applications found"));
+    }
+
+    #[test]
+    fn any_handler_offers_all_applications_when_nothing_matches() {
+        let err = any_handler_app("application/x-weird", None)
+            .prepare_launch()
+            .err()
+            .unwrap();
+        assert!(err.to_string().contains("No applications found"));
+
+        let app = any_handler_app("application/x-weird", Some(AnyHandlerArg::Empty));
+        let context = app.prepare_launch().unwrap();
+        assert_eq!(app_names(&context), vec!["Foot", "Kitty"]);
+        assert_eq!(context.all_applications_from, Some(0));
+        let (_, args) = app.build_selector_command(&context).unwrap();
+        assert!(args.contains(&"--header=All applications".to_string()));
+        assert_eq!(candidates_json(&context)["all_applications_from"], 0);
+
+        // The default mode leaves a non-empty candidate list alone.
+        let app = any_handler_app("x-scheme-handler/terminal", Some(AnyHandlerArg::Empty));
+        let context = app.prepare_launch().unwrap();
+        assert_eq!(app_names(&context), vec!["Foot"]);
+        assert_eq!(context.all_applications_from, None);
+        assert!(candidates_json(&context)
+            .get("all_applications_from")
+            .is_none());
+    }
+
+    #[test]
+    fn any_handler_always_appends_the_rest_after_matches() {
+        let app = any_handler_app("x-scheme-handler/terminal", Some(AnyHandlerArg::Always));
+        let context = app.prepare_launch().unwrap();
+
+        assert_eq!(app_names(&context), vec!["Foot", "Kitty"]);
+        assert_eq!(context.all_applications_from, Some(1));
+        let (_, args) = app.build_selector_command(&context).unwrap();
+        assert!(args
+            .iter()
+            .any(|arg| arg.ends_with("|  1 matching, then all applications")));
+    }
+
+    #[test]
+    fn explicit_handler_unknown_id_errors() {
+        let temp_dir = TempDir::new().unwrap();
+        let notes = temp_dir.path().join("notes.txt");
+        fs::write(&notes, "hello").unwrap();
+
+        let app = build_explicit_handler_app(&notes, "missing.desktop", false);
+        let err = app.prepare_explicit_launch("missing.desktop").unwrap_err();
+
+        assert!(err.to_string().contains("not found"));
+    }
+
+    #[test]
+    fn explicit_handler_checks_mime_unless_forced() {
+        let temp_dir = TempDir::new().unwrap();
+        let image = temp_dir.path().join("photo.png");
+        fs::write(&image, "png").unwrap();
+
+        let app = build_explicit_handler_app(&image, "code.desktop", false);
+        let err = app.prepare_explicit_launch("code.desktop").unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("does not support MIME type image/png"));
 
-        let launcher = open_with.resolve_terminal_launcher().unwrap();
-        assert_eq!(launcher, vec!["kitty", "--single-instance"]);
+        let forced = build_explicit_handler_app(&image, "code.desktop", true);
+        let (_, entry, _) = forced.prepare_explicit_launch("code.desktop").unwrap();
+        assert_eq!(entry.name, "Code");
     }
 
     #[test]
@@ -986,9 +3152,16 @@ mod tests {
             config,
             regex_handlers,
             args,
+            assume_yes: false,
+            launched: Cell::new(false),
+            mime_detector: Box::new(BuiltinMimeDetector),
+            history_path: None,
+            window_query: None,
+            notifier: None,
         };
 
-        let result = open_with.resolve_terminal_launcher();
+        let result = open_with
+            .resolve_terminal_launcher_in(&TerminalEnv::default(), TerminalPurpose::Handler);
         assert!(result.is_err());
     }
 
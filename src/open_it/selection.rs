@@ -1,18 +1,37 @@
 use super::OpenIt;
-use crate::application_finder::ApplicationEntry;
+use crate::application_finder::{ApplicationEntry, ApplicationSource, Association};
 use crate::cli::SelectorKind;
-use crate::config::{SelectorProfileId, SelectorProfileType};
+use crate::config::{
+    Multiplexer, SelectorProfile, SelectorProfileId, SelectorProfileType, SessionType,
+};
+use crate::executor::ApplicationExecutor;
+use crate::regex_handlers::{RegexHandlerDefinition, RegexHandlerStore};
 use crate::target::LaunchTarget;
 use crate::template::TemplateEngine;
-use anyhow::Result;
+use anyhow::{Context, Result};
 use log::info;
+use serde::Serialize;
 use shell_words::split;
-use std::io::{self, IsTerminal};
+use std::borrow::Cow;
+use std::io::{self, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
 
 pub(super) struct LaunchContext {
     pub target: LaunchTarget,
     pub mime_type: String,
+    /// Flattened from [`Self::handler_groups`]: each handler is followed by its actions.
     pub applications: Vec<ApplicationEntry>,
+    /// Index where the `--any-handler` list of every installed application starts.
+    pub all_applications_from: Option<usize>,
+}
+
+/// A main entry and the actions of the same desktop file, in `Actions=` order.
+#[derive(Debug, Serialize)]
+pub(super) struct HandlerGroup<'a> {
+    #[serde(flatten)]
+    pub entry: &'a ApplicationEntry,
+    pub actions: Vec<&'a ApplicationEntry>,
 }
 
 impl LaunchContext {
@@ -21,23 +40,80 @@ impl LaunchContext {
         mime_type: String,
         applications: Vec<ApplicationEntry>,
     ) -> Self {
+        let applications = group_by_handler(applications)
+            .into_iter()
+            .flat_map(|(entry, actions)| std::iter::once(entry).chain(actions))
+            .collect();
         Self {
             target,
             mime_type,
             applications,
+            all_applications_from: None,
+        }
+    }
+
+    /// Mark the entries from the main entry of `first_desktop_file` on as the
+    /// `--any-handler` list.
+    pub fn with_all_applications_from(mut self, first_desktop_file: Option<&Path>) -> Self {
+        self.all_applications_from = first_desktop_file.and_then(|first| {
+            self.applications
+                .iter()
+                .position(|app| app.action_id.is_none() && app.desktop_file == first)
+        });
+        self
+    }
+
+    /// Applications nested by handler, for JSON consumers that want primary entries only.
+    pub fn handler_groups(&self) -> Vec<HandlerGroup<'_>> {
+        let mut groups: Vec<HandlerGroup<'_>> = Vec::new();
+        for app in &self.applications {
+            match groups.last_mut() {
+                Some(group)
+                    if app.action_id.is_some() && group.entry.desktop_file == app.desktop_file =>
+                {
+                    group.actions.push(app)
+                }
+                _ => groups.push(HandlerGroup {
+                    entry: app,
+                    actions: Vec::new(),
+                }),
+            }
         }
+        groups
     }
 
     pub fn first_is_regex_handler(&self) -> bool {
         self.applications
             .first()
-            .map(|app| {
-                app.desktop_file
-                    .to_string_lossy()
-                    .starts_with("regex-handler-")
-            })
-            .unwrap_or(false)
+            .is_some_and(ApplicationEntry::is_regex_handler)
+    }
+}
+
+/// Attach every action to its main entry, keeping the order handlers and actions first
+/// appear in; an action whose main entry is missing follows the handlers on its own.
+fn group_by_handler(
+    applications: Vec<ApplicationEntry>,
+) -> Vec<(ApplicationEntry, Vec<ApplicationEntry>)> {
+    let mut groups: Vec<(ApplicationEntry, Vec<ApplicationEntry>)> = Vec::new();
+    let mut actions: Vec<ApplicationEntry> = Vec::new();
+    for app in applications {
+        if app.action_id.is_some() {
+            actions.push(app);
+        } else {
+            groups.push((app, Vec::new()));
+        }
+    }
+
+    for action in actions {
+        let parent = groups.iter_mut().find(|(entry, _)| {
+            entry.action_id.is_none() && entry.desktop_file == action.desktop_file
+        });
+        match parent {
+            Some((_, siblings)) => siblings.push(action),
+            None => groups.push((action, Vec::new())),
+        }
     }
+    groups
 }
 
 impl OpenIt {
@@ -51,10 +127,27 @@ impl OpenIt {
 
         info!("Launching selector: {}", log_command);
 
-        match self
-            .selector_runner
-            .run(&selector_cmd, &selector_args, &context.applications)
-        {
+        // Keep the handoff file alive until the selector exits.
+        let handoff = match &self.args.selector_command {
+            Some(_) => Some(write_candidates_file(context)?),
+            None => None,
+        };
+        let env = handoff
+            .as_ref()
+            .map(|file| handoff_env(context, file))
+            .unwrap_or_default();
+
+        let choices = self.selector_choices(context);
+        let selection = self.timer().measure("selector run", || {
+            self.selector_runner
+                .run(&selector_cmd, &selector_args, &choices, &env)
+        });
+
+        match selection {
+            Ok(Some(index)) if index == context.applications.len() => {
+                info!("Selector chose to enter a command");
+                self.run_custom_command(context, &selector_cmd, &selector_args, env)
+            }
             Ok(Some(index)) => {
                 if let Some(app) = context.applications.get(index) {
                     info!(
@@ -63,7 +156,11 @@ impl OpenIt {
                         app.desktop_file.display()
                     );
                 }
-                self.execute_application(&context.applications[index], &context.target)
+                self.execute_application(
+                    &context.applications[index],
+                    &context.target,
+                    &context.mime_type,
+                )
             }
             Ok(None) => {
                 info!("Selector produced no choice; exiting without launching application");
@@ -79,17 +176,125 @@ impl OpenIt {
         }
     }
 
-    fn build_selector_command(&self, context: &LaunchContext) -> Result<(String, Vec<String>)> {
+    /// The discovered applications, plus the "Open with command…" entry when
+    /// `custom_command_entry` is enabled.
+    fn selector_choices<'a>(&self, context: &'a LaunchContext) -> Cow<'a, [ApplicationEntry]> {
+        if !self.config.custom_command_entry {
+            return Cow::Borrowed(&context.applications);
+        }
+        let mut choices = context.applications.clone();
+        choices.push(custom_command_choice());
+        Cow::Owned(choices)
+    }
+
+    /// Ask the selector again, with no candidates, for a command line and launch it with
+    /// the target appended unless it places the target with a field code. An empty answer
+    /// cancels.
+    fn run_custom_command(
+        &self,
+        context: &LaunchContext,
+        selector_cmd: &str,
+        selector_args: &[String],
+        mut env: Vec<(String, String)>,
+    ) -> Result<()> {
+        let mut args = selector_args.to_vec();
+        if let Some(profile) = self.selected_profile() {
+            args.extend(profile.command_args.iter().cloned());
+        }
+        env.push((PROMPT_ENV.to_string(), "command".to_string()));
+
+        let Some(command) = self.selector_runner.prompt(selector_cmd, &args, &env)? else {
+            info!("No command entered; exiting without launching application");
+            return Ok(());
+        };
+        // Checked here so a typo fails before anything is launched or saved.
+        ApplicationExecutor::base_command_parts(&command)?;
+
+        let app = custom_command_application(&command);
+        self.execute_application(&app, &context.target, &context.mime_type)?;
+        if !self.args.dry_run {
+            self.offer_to_save_command(&command, &context.target)?;
+        }
+        Ok(())
+    }
+
+    /// On an interactive terminal, offer to keep `command` as a regex handler for the
+    /// target's extension.
+    fn offer_to_save_command(&self, command: &str, target: &LaunchTarget) -> Result<()> {
+        let Some(extension) = target
+            .as_path()
+            .and_then(|path| path.extension())
+            .and_then(|ext| ext.to_str())
+        else {
+            return Ok(());
+        };
+        if !io::stdin().is_terminal() {
+            return Ok(());
+        }
+
+        let question = format!("Save `{command}` as the handler for .{extension} files?");
+        if !crate::prompt::confirm(&question, self.assume_yes)? {
+            return Ok(());
+        }
+        let path = RegexHandlerStore::config_path();
+        save_command_handler(&path, command, extension)?;
+        info!(
+            "Saved `{command}` for .{extension} files to {}",
+            path.display()
+        );
+        Ok(())
+    }
+
+    /// The profile `build_selector_command` takes its command from, if any.
+    fn selected_profile(&self) -> Option<&SelectorProfile> {
+        if self.args.selector_command.is_some() {
+            return None;
+        }
+        match &self.args.selector {
+            SelectorKind::Named(name) => self.config.get_selector_profile(name),
+            SelectorKind::Auto => self
+                .selector_name_candidates()
+                .into_iter()
+                .next()
+                .and_then(|name| self.config.get_selector_profile(name.as_ref())),
+        }
+    }
+
+    /// Fail early when `--selector` names a profile whose command is not installed.
+    pub(super) fn validate_requested_selector(
+        config: &crate::config::Config,
+        args: &crate::cli::OpenArgs,
+    ) -> Result<()> {
+        if args.json || args.selector_command.is_some() {
+            return Ok(());
+        }
+
+        let SelectorKind::Named(name) = &args.selector else {
+            return Ok(());
+        };
+
+        match config.get_selector_profile(name) {
+            Some(profile) => profile
+                .validate()
+                .with_context(|| format!("Selector profile `{name}` is not usable")),
+            None => Ok(()),
+        }
+    }
+
+    pub(super) fn build_selector_command(
+        &self,
+        context: &LaunchContext,
+    ) -> Result<(String, Vec<String>)> {
         if let Some(command_spec) = &self.args.selector_command {
             return self.selector_command_from_string(command_spec, false);
         }
 
         match &self.args.selector {
-            SelectorKind::Auto => self.resolve_auto_selector_command(&context.target, true),
+            SelectorKind::Auto => self.resolve_auto_selector_command(context, true),
             SelectorKind::Named(name) => {
                 let profile_id = SelectorProfileId::from(name.as_str());
                 if let Some((cmd, args)) =
-                    self.selector_command_from_profile(&profile_id, &context.target, false)?
+                    self.selector_command_from_profile(&profile_id, context, false)?
                 {
                     Ok((cmd, args))
                 } else {
@@ -102,7 +307,7 @@ impl OpenIt {
     fn selector_command_from_profile(
         &self,
         profile_id: &SelectorProfileId,
-        target: &LaunchTarget,
+        context: &LaunchContext,
         append_term_args: bool,
     ) -> Result<Option<(String, Vec<String>)>> {
         let profile = if let Some(profile) = self.config.get_selector_profile(profile_id.as_ref()) {
@@ -111,15 +316,28 @@ impl OpenIt {
             return Ok(None);
         };
 
-        let display_name = target.display_name();
+        let display_name = context.target.display_name();
         let mut template_engine = TemplateEngine::new();
         template_engine.set("file", display_name.as_ref());
         let prompt = template_engine.render(self.config.get_prompt_template(profile));
-        let header = template_engine.render(self.config.get_header_template(profile));
+        let mut header = template_engine.render(self.config.get_header_template(profile));
+        match context.all_applications_from {
+            Some(0) => header = "All applications".to_string(),
+            Some(matched) => {
+                header = format!("{header}  |  {matched} matching, then all applications")
+            }
+            None => {}
+        }
         template_engine
             .set("prompt", &prompt)
             .set("header", &header);
-        let mut args = template_engine.render_args(&profile.args);
+        let mut args = template_engine
+            .render_args(&profile.args_for(SessionType::detect(), Multiplexer::detect()));
+
+        if let Some(query) = &self.args.match_query {
+            template_engine.set("query", query);
+            args.extend(template_engine.render_args(&profile.query_args));
+        }
 
         if append_term_args {
             if let Some(extra) = &self.config.selector.term_exec_args {
@@ -175,14 +393,14 @@ impl OpenIt {
 
     fn resolve_auto_selector_command(
         &self,
-        target: &LaunchTarget,
+        context: &LaunchContext,
         append_term_args: bool,
     ) -> Result<(String, Vec<String>)> {
         let candidates = self.selector_name_candidates();
         let mut last_error: Option<anyhow::Error> = None;
 
         for name in candidates {
-            match self.selector_command_from_profile(&name, target, append_term_args)? {
+            match self.selector_command_from_profile(&name, context, append_term_args)? {
                 Some(result) => return Ok(result),
                 None => match self.selector_command_from_string(name.as_ref(), append_term_args) {
                     Ok(result) => return Ok(result),
@@ -198,3 +416,87 @@ impl OpenIt {
         }
     }
 }
+
+/// Set to `command` for the selector run that reads an "Open with command…" command line.
+const PROMPT_ENV: &str = "OPENIT_PROMPT";
+
+/// Pseudo desktop file of the "Open with command…" selector entry.
+pub(crate) const CUSTOM_COMMAND_ID: &str = "custom-command";
+
+fn custom_command_choice() -> ApplicationEntry {
+    ApplicationEntry {
+        name: "Open with command…".to_string(),
+        comment: Some("Type a command line to open the file with".to_string()),
+        ..custom_command_application("")
+    }
+}
+
+/// A typed command line as a launchable entry; not a desktop file, so no `%c`/`%k` values.
+fn custom_command_application(command: &str) -> ApplicationEntry {
+    ApplicationEntry {
+        name: command.to_string(),
+        exec: command.to_string(),
+        desktop_file: PathBuf::from(CUSTOM_COMMAND_ID),
+        comment: None,
+        icon: None,
+        is_xdg: false,
+        xdg_priority: -1,
+        is_default: false,
+        action_id: None,
+        requires_terminal: false,
+        is_terminal_emulator: false,
+        working_dir: None,
+        prefers_non_default_gpu: false,
+        stdin_input: false,
+        association: Association::None,
+        parent_mime: None,
+        startup_notify: false,
+        confirm: false,
+        score: 0.0,
+        dbus_activatable: false,
+        startup_wm_class: None,
+        single_main_window: false,
+        generic_name: None,
+        keywords: Vec::new(),
+        source: ApplicationSource::Available,
+    }
+}
+
+/// Append a handler running `command` for files ending in `.extension` to `path`.
+pub(super) fn save_command_handler(path: &Path, command: &str, extension: &str) -> Result<()> {
+    RegexHandlerStore::append_definitions(
+        path,
+        &[RegexHandlerDefinition {
+            exec: command.to_string(),
+            regexes: vec![format!(r"(?i)\.{}$", regex::escape(extension))],
+            notes: Some("Saved from \"Open with command…\"".to_string()),
+            ..RegexHandlerDefinition::default()
+        }],
+    )
+}
+
+/// Write the `open --json` document for a custom `selector_command` to read.
+fn write_candidates_file(context: &LaunchContext) -> Result<NamedTempFile> {
+    let mut file = tempfile::Builder::new()
+        .prefix("openit-candidates-")
+        .suffix(".json")
+        .tempfile()
+        .context("Failed to create candidates file for selector")?;
+    serde_json::to_writer_pretty(&mut file, &super::candidates_json(context))?;
+    file.flush()?;
+    Ok(file)
+}
+
+fn handoff_env(context: &LaunchContext, file: &NamedTempFile) -> Vec<(String, String)> {
+    vec![
+        (
+            "OPENIT_CANDIDATES_FILE".to_string(),
+            file.path().to_string_lossy().into_owned(),
+        ),
+        (
+            "OPENIT_TARGET".to_string(),
+            context.target.as_command_argument().into_owned(),
+        ),
+        ("OPENIT_MIME".to_string(), context.mime_type.clone()),
+    ]
+}
@@ -1,34 +1,93 @@
+use crate::shortcut_files::ShortcutKind;
 use crate::target::LaunchTarget;
 use anyhow::{Context, Result};
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
 use url::Url;
 
+/// Maximum number of links listed when reporting a broken symlink chain.
+const MAX_SYMLINK_DEPTH: usize = 16;
+
 pub(super) fn resolve_launch_target(raw: &str) -> Result<LaunchTarget> {
     if let Ok(uri) = Url::parse(raw) {
         if uri.scheme() == "file" {
             let path = uri
                 .to_file_path()
                 .map_err(|_| anyhow::anyhow!("Invalid file URI: {raw}"))?;
-            let path = path
-                .canonicalize()
-                .with_context(|| format!("Failed to resolve file path: {}", path.display()))?;
-            return Ok(LaunchTarget::File(path));
+            return canonicalize_target(&path).map(LaunchTarget::File);
         }
         return Ok(LaunchTarget::Uri(uri));
     }
 
-    let path = PathBuf::from(raw);
-    let path = path
-        .canonicalize()
-        .with_context(|| format!("Failed to resolve file path: {}", path.display()))?;
-    Ok(LaunchTarget::File(path))
+    canonicalize_target(Path::new(raw)).map(LaunchTarget::File)
+}
+
+fn canonicalize_target(path: &Path) -> Result<PathBuf> {
+    let is_broken_symlink = fs::symlink_metadata(path).is_ok_and(|meta| meta.is_symlink())
+        && fs::metadata(path).is_err();
+    if is_broken_symlink {
+        anyhow::bail!(broken_symlink_message(path));
+    }
+
+    path.canonicalize()
+        .with_context(|| format!("Failed to resolve file path: {}", path.display()))
+}
+
+fn broken_symlink_message(path: &Path) -> String {
+    let mut message = format!("Broken symlink: {}", path.display());
+    let mut current = path.to_path_buf();
+
+    for _ in 0..MAX_SYMLINK_DEPTH {
+        let Ok(link) = fs::read_link(&current) else {
+            break;
+        };
+        let next = if link.is_absolute() {
+            link
+        } else {
+            current
+                .parent()
+                .map_or_else(|| link.clone(), |parent| parent.join(&link))
+        };
+
+        let status = match fs::symlink_metadata(&next) {
+            Ok(meta) if meta.is_symlink() => "",
+            Ok(_) => " (exists)",
+            Err(_) => " (missing)",
+        };
+        message.push_str(&format!("\n  -> {}{status}", next.display()));
+
+        if !status.is_empty() {
+            return message + &repoint_suggestion(path);
+        }
+        current = next;
+    }
+
+    message
+        + &format!("\nStopped after {MAX_SYMLINK_DEPTH} links; the chain may be cyclic.")
+        + &repoint_suggestion(path)
+}
+
+/// Closing hint for a broken chain; links are always followed, so even `--with` cannot
+/// open the dangling link itself.
+fn repoint_suggestion(link: &Path) -> String {
+    format!(
+        "\nopenit opens what a symlink points to, not the link itself; recreate the target \
+         or repoint the link with `ln -sfn NEW_TARGET {}`.",
+        link.display()
+    )
 }
 
+/// MIME type of a target: `inode/directory`, the extension's type, or the URI scheme.
+///
+/// Files are never read, so an empty `notes.json` resolves like a full one and only an
+/// unknown extension falls back to `application/octet-stream`.
 pub(super) fn mime_for_target(target: &LaunchTarget) -> String {
     match target {
         LaunchTarget::File(path) => {
             if path.is_dir() {
                 "inode/directory".to_string()
+            } else if let Some(kind) = ShortcutKind::from_path(path) {
+                kind.mime_type().to_string()
             } else {
                 mime_guess::from_path(path)
                     .first_or_octet_stream()
@@ -38,3 +97,122 @@ pub(super) fn mime_for_target(target: &LaunchTarget) -> String {
         LaunchTarget::Uri(uri) => format!("x-scheme-handler/{}", uri.scheme()),
     }
 }
+
+/// Describe which strategy `mime_for_target` uses for the given target.
+pub(super) fn mime_detection_for_target(target: &LaunchTarget) -> &'static str {
+    match target {
+        LaunchTarget::File(path) => {
+            if path.is_dir() {
+                "directory"
+            } else if ShortcutKind::from_path(path).is_some()
+                || mime_guess::from_path(path).first().is_some()
+            {
+                "file extension"
+            } else {
+                "fallback (application/octet-stream)"
+            }
+        }
+        LaunchTarget::Uri(_) => "URI scheme",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+    use tempfile::TempDir;
+
+    #[test]
+    fn broken_symlink_reports_chain() {
+        let temp_dir = TempDir::new().unwrap();
+        let missing = temp_dir.path().join("deleted.conf");
+        let link = temp_dir.path().join("tool.conf");
+        symlink(&missing, &link).unwrap();
+
+        let err = resolve_launch_target(&link.to_string_lossy()).unwrap_err();
+        let message = err.to_string();
+        assert!(message.starts_with(&format!("Broken symlink: {}", link.display())));
+        assert!(message.contains(&format!("-> {} (missing)", missing.display())));
+        assert!(message.ends_with(&format!(
+            "repoint the link with `ln -sfn NEW_TARGET {}`.",
+            link.display()
+        )));
+    }
+
+    #[test]
+    fn broken_symlink_chain_lists_every_hop() {
+        let temp_dir = TempDir::new().unwrap();
+        let first = temp_dir.path().join("first");
+        let second = temp_dir.path().join("second");
+        symlink("second", &first).unwrap();
+        symlink("gone", &second).unwrap();
+
+        let message = resolve_launch_target(&first.to_string_lossy())
+            .unwrap_err()
+            .to_string();
+        let lines: Vec<&str> = message.lines().collect();
+        assert_eq!(lines[1], format!("  -> {}", second.display()));
+        assert_eq!(
+            lines[2],
+            format!("  -> {} (missing)", temp_dir.path().join("gone").display())
+        );
+    }
+
+    #[test]
+    fn cyclic_symlinks_stop_at_depth_limit() {
+        let temp_dir = TempDir::new().unwrap();
+        let a = temp_dir.path().join("a");
+        let b = temp_dir.path().join("b");
+        symlink("b", &a).unwrap();
+        symlink("a", &b).unwrap();
+
+        let message = resolve_launch_target(&a.to_string_lossy())
+            .unwrap_err()
+            .to_string();
+        assert!(message.contains("the chain may be cyclic"));
+        assert!(message.contains(&format!("`ln -sfn NEW_TARGET {}`", a.display())));
+    }
+
+    #[test]
+    fn symlink_to_directory_is_a_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let dir = temp_dir.path().join("real");
+        fs::create_dir(&dir).unwrap();
+        let link = temp_dir.path().join("link");
+        symlink(&dir, &link).unwrap();
+
+        let target = resolve_launch_target(&link.to_string_lossy()).unwrap();
+        assert_eq!(
+            target.as_path(),
+            Some(dir.canonicalize().unwrap().as_path())
+        );
+        assert_eq!(mime_for_target(&target), "inode/directory");
+        assert_eq!(mime_detection_for_target(&target), "directory");
+    }
+
+    #[test]
+    fn empty_files_resolve_by_extension() {
+        let temp_dir = TempDir::new().unwrap();
+        let json = temp_dir.path().join("foo.json");
+        let bare = temp_dir.path().join("foo");
+        fs::write(&json, "").unwrap();
+        fs::write(&bare, "").unwrap();
+
+        let target = resolve_launch_target(&json.to_string_lossy()).unwrap();
+        assert_eq!(mime_for_target(&target), "application/json");
+        assert_eq!(mime_detection_for_target(&target), "file extension");
+
+        let target = resolve_launch_target(&bare.to_string_lossy()).unwrap();
+        assert_eq!(mime_for_target(&target), "application/octet-stream");
+        assert_eq!(
+            mime_detection_for_target(&target),
+            "fallback (application/octet-stream)"
+        );
+    }
+
+    #[test]
+    fn missing_path_keeps_generic_error() {
+        let err = resolve_launch_target("/definitely/missing/file.txt").unwrap_err();
+        assert!(err.to_string().contains("Failed to resolve file path"));
+    }
+}
@@ -0,0 +1,89 @@
+use anyhow::{Context, Result};
+use std::io::{self, BufRead, IsTerminal, Write};
+
+/// Environment variable that answers every prompt with "yes", like `--assume-yes`.
+pub const ASSUME_YES_ENV: &str = "OPENIT_ASSUME_YES";
+
+/// Whether `OPENIT_ASSUME_YES` is set to a truthy value.
+pub fn assume_yes_from_env() -> bool {
+    std::env::var(ASSUME_YES_ENV).is_ok_and(|value| {
+        matches!(
+            value.trim().to_ascii_lowercase().as_str(),
+            "1" | "true" | "yes" | "on"
+        )
+    })
+}
+
+/// Ask `question` on stderr and read the answer from stdin.
+///
+/// With `assume_yes` the answer is "yes" and stdin is never touched. Without it, a
+/// non-interactive stdin is an error rather than a silent "no" or a blocking read.
+pub fn confirm(question: &str, assume_yes: bool) -> Result<bool> {
+    if assume_yes {
+        return Ok(true);
+    }
+
+    let stdin = io::stdin();
+    if !stdin.is_terminal() {
+        anyhow::bail!(
+            "`{question}` needs confirmation but stdin is not a terminal; pass --assume-yes or set {ASSUME_YES_ENV}=1"
+        );
+    }
+
+    confirm_with(question, &mut stdin.lock(), &mut io::stderr())
+}
+
+fn confirm_with(question: &str, input: &mut dyn BufRead, output: &mut dyn Write) -> Result<bool> {
+    write!(output, "{question} [y/N] ")?;
+    output.flush()?;
+
+    let mut answer = String::new();
+    input
+        .read_line(&mut answer)
+        .context("Failed to read confirmation")?;
+    Ok(matches!(
+        answer.trim().to_ascii_lowercase().as_str(),
+        "y" | "yes"
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::EnvVarGuard;
+    use serial_test::serial;
+
+    #[test]
+    fn assume_yes_answers_without_reading() {
+        assert!(confirm("Launch?", true).unwrap());
+    }
+
+    #[test]
+    fn only_explicit_yes_confirms() {
+        for (answer, expected) in [
+            ("y\n", true),
+            ("Yes\n", true),
+            ("\n", false),
+            ("no\n", false),
+        ] {
+            let mut input = answer.as_bytes();
+            let mut output = Vec::new();
+            assert_eq!(
+                confirm_with("Launch?", &mut input, &mut output).unwrap(),
+                expected,
+                "answer {answer:?}"
+            );
+            assert_eq!(output, b"Launch? [y/N] ");
+        }
+    }
+
+    #[test]
+    #[serial]
+    fn env_variable_enables_assume_yes() {
+        let _guard = EnvVarGuard::set(ASSUME_YES_ENV, "1");
+        assert!(assume_yes_from_env());
+
+        let _guard = EnvVarGuard::set(ASSUME_YES_ENV, "0");
+        assert!(!assume_yes_from_env());
+    }
+}
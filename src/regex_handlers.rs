@@ -1,10 +1,11 @@
-use crate::config::Config;
+use crate::config::{Config, UriMatchNormalize};
+use crate::target::LaunchTarget;
 use anyhow::{Context, Result};
 use log::debug;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 struct RegexHandlersFile {
@@ -20,14 +21,21 @@ pub struct RegexHandlerDefinition {
     pub terminal: bool,
     pub priority: i32,
     pub notes: Option<String>,
+    /// Connect the target file to the handler's stdin instead of passing its path.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub stdin_input: bool,
+    /// Ask before launching; `--assume-yes` answers for non-interactive runs.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub confirm: bool,
 }
 
 #[derive(Debug, Clone)]
 pub struct RegexHandler {
-    #[allow(dead_code)]
     pub exec: String,
     #[allow(dead_code)]
     pub terminal: bool,
+    pub stdin_input: bool,
+    pub confirm: bool,
     pub priority: i32,
     #[allow(dead_code)]
     pub notes: Option<String>,
@@ -46,6 +54,143 @@ impl RegexHandler {
     pub fn patterns(&self) -> &[String] {
         &self.patterns
     }
+
+    /// `exec` with `$N`, `${N}` and `${name}` replaced by the groups the first matching
+    /// regex captured from `candidate`; `$0` is the whole match and `$$` a literal `$`.
+    ///
+    /// `exec` is split into arguments first, so a capture always stays inside the argument
+    /// it was referenced from, whatever quotes or spaces the target contains. `${NAME}` that
+    /// names no group, such as `${HOME}`, is left as written.
+    pub fn expand_exec(&self, candidate: &str) -> Result<String> {
+        let Some((regex, captures)) = self.first_captures(candidate) else {
+            return Ok(self.exec.clone());
+        };
+        expand_captures(&self.exec, regex, &captures)
+    }
+
+    /// The first regex matching `candidate` and every group it defines, for debugging.
+    pub fn capture_groups(&self, candidate: &str) -> Option<(&str, Vec<CaptureGroup>)> {
+        let (regex, captures) = self.first_captures(candidate)?;
+        let groups = regex
+            .capture_names()
+            .enumerate()
+            .map(|(index, name)| CaptureGroup {
+                index,
+                name: name.map(str::to_string),
+                value: captures.get(index).map(|group| group.as_str().to_string()),
+            })
+            .collect();
+        Some((regex.as_str(), groups))
+    }
+
+    fn first_captures<'c>(&self, candidate: &'c str) -> Option<(&Regex, regex::Captures<'c>)> {
+        self.compiled
+            .iter()
+            .find_map(|regex| regex.captures(candidate).map(|captures| (regex, captures)))
+    }
+}
+
+/// One group of a matching regex; `value` is `None` when the group did not participate.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct CaptureGroup {
+    pub index: usize,
+    pub name: Option<String>,
+    pub value: Option<String>,
+}
+
+/// Re-joined with shell quoting, and with `%` in captured text doubled so the executor's
+/// field-code expansion leaves it alone.
+fn expand_captures(exec: &str, regex: &Regex, captures: &regex::Captures<'_>) -> Result<String> {
+    let args = shell_words::split(exec)
+        .with_context(|| format!("Failed to parse regex handler exec `{exec}`"))?;
+    let expanded = args
+        .iter()
+        .map(|arg| expand_argument(exec, arg, regex, captures))
+        .collect::<Result<Vec<_>>>()?;
+    Ok(shell_words::join(expanded))
+}
+
+fn expand_argument(
+    exec: &str,
+    arg: &str,
+    regex: &Regex,
+    captures: &regex::Captures<'_>,
+) -> Result<String> {
+    let mut expanded = String::with_capacity(arg.len());
+    let mut rest = arg;
+
+    while let Some(position) = rest.find('$') {
+        expanded.push_str(&rest[..position]);
+        let after = &rest[position + 1..];
+
+        if let Some(tail) = after.strip_prefix('$') {
+            expanded.push('$');
+            rest = tail;
+            continue;
+        }
+
+        let (reference, tail) = if let Some(inner) = after.strip_prefix('{') {
+            let end = inner.find('}').ok_or_else(|| {
+                anyhow::anyhow!("Regex handler `{exec}` has an unterminated `${{` reference")
+            })?;
+            (&inner[..end], &inner[end + 1..])
+        } else {
+            let digits = after.bytes().take_while(u8::is_ascii_digit).count();
+            if digits == 0 {
+                // `$HOME` and friends are not capture references.
+                expanded.push('$');
+                rest = after;
+                continue;
+            }
+            after.split_at(digits)
+        };
+
+        let group = match reference.parse::<usize>() {
+            Ok(index) if index < regex.captures_len() => captures.get(index),
+            Ok(_) => anyhow::bail!(
+                "Regex handler `{exec}` references capture group `{reference}`, but `{}` has no such group",
+                regex.as_str()
+            ),
+            Err(_) if regex.capture_names().flatten().any(|name| name == reference) => {
+                captures.name(reference)
+            }
+            Err(_) => {
+                expanded.push_str(&rest[position..rest.len() - tail.len()]);
+                rest = tail;
+                continue;
+            }
+        };
+        // Optional groups that did not participate expand to nothing.
+        expanded.push_str(&group.map_or("", |group| group.as_str()).replace('%', "%%"));
+        rest = tail;
+    }
+
+    expanded.push_str(rest);
+    Ok(expanded)
+}
+
+/// Reject `\N` and `{N}`, the backreference styles of other tools, which would otherwise
+/// reach the handler as literal text.
+fn check_capture_syntax(exec: &str) -> Result<()> {
+    for (position, marker) in exec.match_indices(['\\', '{']) {
+        let after = &exec[position + 1..];
+        let digits = after.bytes().take_while(u8::is_ascii_digit).count();
+        if digits == 0 {
+            continue;
+        }
+        let group = &after[..digits];
+        let written = if marker == "\\" {
+            format!("\\{group}")
+        } else if after[digits..].starts_with('}') && !exec[..position].ends_with('$') {
+            format!("{{{group}}}")
+        } else {
+            continue;
+        };
+        anyhow::bail!(
+            "Regex handler `{exec}` uses `{written}`; capture groups are written `${group}` or `${{{group}}}`"
+        );
+    }
+    Ok(())
 }
 
 #[derive(Debug, Clone)]
@@ -53,46 +198,103 @@ pub struct RegexHandlerStore {
     #[allow(dead_code)]
     definitions: Vec<RegexHandlerDefinition>,
     handlers: Vec<RegexHandler>,
+    uri_match_normalize: UriMatchNormalize,
 }
 
 impl RegexHandlerStore {
+    /// Rewrite URI targets with `normalize` before matching; see [`Self::candidate`].
+    pub fn with_uri_match_normalize(mut self, normalize: UriMatchNormalize) -> Self {
+        self.uri_match_normalize = normalize;
+        self
+    }
+
+    /// The string handlers match and capture from for `target`.
+    pub fn candidate(&self, target: &LaunchTarget) -> String {
+        target.match_candidate(self.uri_match_normalize)
+    }
+
+    /// Load `regex_handlers.toml` plus every `*.toml` in the sibling `regex_handlers.d/`
+    /// directory. Handlers from all files are ordered by priority.
     pub fn load(custom_path: Option<PathBuf>) -> Result<Self> {
         let path = custom_path.unwrap_or_else(Self::config_path);
+        let drop_ins = Self::load_drop_in_dir(&Self::drop_in_dir(&path))?;
 
         if path.exists() {
-            let contents = fs::read_to_string(&path).with_context(|| {
-                format!("Failed to read regex handler file at {}", path.display())
-            })?;
-            let file: RegexHandlersFile = toml::from_str(&contents).with_context(|| {
-                format!("Failed to parse regex handler file at {}", path.display())
-            })?;
-            return Self::from_definitions(file.handlers);
+            let file = Self::read_file(&path)?;
+            return Self::from_definitions(file.handlers, drop_ins);
         }
 
-        if let Some(handlers) = Self::load_handlr_handlers()? {
-            return Self::from_definitions(handlers);
+        if drop_ins.is_empty() {
+            if let Some(handlers) = Self::load_handlr_handlers()? {
+                return Self::from_definitions(handlers, Vec::new());
+            }
         }
 
-        Ok(Self {
-            definitions: Vec::new(),
-            handlers: Vec::new(),
-        })
+        Self::from_definitions(Vec::new(), drop_ins)
     }
 
     #[allow(dead_code)]
     pub fn save(&self, custom_path: Option<PathBuf>) -> Result<()> {
         let path = custom_path.unwrap_or_else(Self::config_path);
+        Self::write_definitions(&path, &self.definitions)
+    }
+
+    /// Handlers defined in `path` alone, without drop-ins or the handlr fallback.
+    pub fn read_definitions(path: &Path) -> Result<Vec<RegexHandlerDefinition>> {
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+        Self::read_file(path).map(|file| file.handlers)
+    }
 
+    /// Replace the contents of `path` with `definitions`.
+    pub fn write_definitions(path: &Path, definitions: &[RegexHandlerDefinition]) -> Result<()> {
         if let Some(parent) = path.parent() {
             fs::create_dir_all(parent)?;
         }
 
         let file = RegexHandlersFile {
-            handlers: self.definitions.clone(),
+            handlers: definitions.to_vec(),
         };
 
         let contents = toml::to_string_pretty(&file)?;
-        fs::write(&path, contents)?;
+        fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// Add `definitions` to the end of `path` as new `[[handlers]]` tables. The existing
+    /// text, comments and formatting included, is kept byte for byte.
+    pub fn append_definitions(path: &Path, definitions: &[RegexHandlerDefinition]) -> Result<()> {
+        let mut contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => {
+                return Err(err).with_context(|| {
+                    format!("Failed to read regex handler file at {}", path.display())
+                })
+            }
+        };
+        if !contents.is_empty() {
+            if !contents.ends_with('\n') {
+                contents.push('\n');
+            }
+            contents.push('\n');
+        }
+        contents.push_str(&toml::to_string_pretty(&RegexHandlersFile {
+            handlers: definitions.to_vec(),
+        })?);
+        // An inline `handlers = [...]` array cannot be extended with `[[handlers]]`.
+        toml::from_str::<RegexHandlersFile>(&contents).with_context(|| {
+            format!(
+                "Cannot add a handler to {}; edit it by hand",
+                path.display()
+            )
+        })?;
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, contents)?;
         Ok(())
     }
 
@@ -103,6 +305,43 @@ impl RegexHandlerStore {
             .join("regex_handlers.toml")
     }
 
+    /// `regex_handlers.toml` -> `regex_handlers.d`
+    fn drop_in_dir(path: &Path) -> PathBuf {
+        path.with_extension("d")
+    }
+
+    fn read_file(path: &Path) -> Result<RegexHandlersFile> {
+        let contents = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read regex handler file at {}", path.display()))?;
+        toml::from_str(&contents)
+            .with_context(|| format!("Failed to parse regex handler file at {}", path.display()))
+    }
+
+    fn load_drop_in_dir(dir: &Path) -> Result<Vec<RegexHandlerDefinition>> {
+        let entries = match fs::read_dir(dir) {
+            Ok(entries) => entries,
+            Err(_) => return Ok(Vec::new()),
+        };
+
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.is_file() && path.extension().is_some_and(|ext| ext == "toml"))
+            .collect();
+        files.sort();
+
+        let mut definitions = Vec::new();
+        for file in files {
+            let parsed = Self::read_file(&file)?;
+            debug!(
+                "Loaded {} regex handler(s) from {}",
+                parsed.handlers.len(),
+                file.display()
+            );
+            definitions.extend(parsed.handlers);
+        }
+        Ok(definitions)
+    }
+
     pub fn len(&self) -> usize {
         self.handlers.len()
     }
@@ -124,10 +363,36 @@ impl RegexHandlerStore {
             .find(|handler| handler.matches(candidate))
     }
 
-    fn from_definitions(definitions: Vec<RegexHandlerDefinition>) -> Result<Self> {
+    /// Every handler matching `candidate`, highest priority first; the first one wins.
+    pub fn matching<'s>(&'s self, candidate: &'s str) -> impl Iterator<Item = &'s RegexHandler> {
+        self.handlers
+            .iter()
+            .filter(move |handler| handler.matches(candidate))
+    }
+
+    /// Check that every handler's command is installed; returns `(exec, error)` for each
+    /// handler whose program is missing or whose `exec` cannot be parsed.
+    pub fn validate_all(&self) -> Vec<(String, anyhow::Error)> {
+        self.handlers
+            .iter()
+            .filter_map(|handler| {
+                validate_exec(&handler.exec)
+                    .err()
+                    .map(|err| (handler.exec.clone(), err))
+            })
+            .collect()
+    }
+
+    /// Compile `definitions` (saved back by [`Self::save`]) together with read-only
+    /// `drop_ins` from `regex_handlers.d/`.
+    fn from_definitions(
+        definitions: Vec<RegexHandlerDefinition>,
+        drop_ins: Vec<RegexHandlerDefinition>,
+    ) -> Result<Self> {
         let mut compiled_handlers = Vec::new();
 
-        for definition in &definitions {
+        for definition in definitions.iter().chain(&drop_ins) {
+            check_capture_syntax(&definition.exec)?;
             let mut compiled_patterns = Vec::new();
             for pattern in &definition.regexes {
                 let regex = Regex::new(pattern).with_context(|| {
@@ -142,6 +407,8 @@ impl RegexHandlerStore {
             compiled_handlers.push(RegexHandler {
                 exec: definition.exec.clone(),
                 terminal: definition.terminal,
+                stdin_input: definition.stdin_input,
+                confirm: definition.confirm,
                 priority: definition.priority,
                 notes: definition.notes.clone(),
                 patterns: definition.regexes.clone(),
@@ -149,13 +416,14 @@ impl RegexHandlerStore {
             });
         }
 
-        compiled_handlers.sort_by(|a, b| b.priority.cmp(&a.priority));
+        compiled_handlers.sort_by_key(|handler| std::cmp::Reverse(handler.priority));
 
         debug!("Loaded {} regex handler(s)", compiled_handlers.len());
 
         Ok(Self {
             definitions,
             handlers: compiled_handlers,
+            uri_match_normalize: UriMatchNormalize::default(),
         })
     }
 
@@ -201,6 +469,27 @@ impl RegexHandlerStore {
     }
 }
 
+/// Resolve the program of `exec`: a path containing `/` must be an existing file,
+/// anything else is looked up on `PATH`.
+fn validate_exec(exec: &str) -> Result<()> {
+    let parts = shell_words::split(exec)
+        .map_err(|err| anyhow::anyhow!("Failed to parse exec `{exec}`: {err}"))?;
+    let program = parts
+        .first()
+        .ok_or_else(|| anyhow::anyhow!("Handler has an empty exec"))?;
+
+    if program.contains('/') {
+        if !Path::new(program).is_file() {
+            anyhow::bail!("`{program}` does not exist");
+        }
+        return Ok(());
+    }
+
+    which::which(program)
+        .map(|_| ())
+        .map_err(|_| anyhow::anyhow!("`{program}` was not found on PATH"))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -216,6 +505,208 @@ mod tests {
         assert!(store.is_empty());
     }
 
+    #[test]
+    fn validate_all_reports_missing_programs() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("regex_handlers.toml");
+        fs::write(
+            &path,
+            r#"
+[[handlers]]
+exec = "sh -c 'echo %u'"
+regexes = ["^a"]
+
+[[handlers]]
+exec = "openit-definitely-missing-binary %u"
+regexes = ["^b"]
+
+[[handlers]]
+exec = "/nonexistent/bin/viewer %f"
+regexes = ["^c"]
+"#,
+        )
+        .unwrap();
+
+        let store = RegexHandlerStore::load(Some(path)).unwrap();
+        let invalid = store.validate_all();
+        let execs: Vec<&str> = invalid.iter().map(|(exec, _)| exec.as_str()).collect();
+        assert_eq!(
+            execs,
+            vec![
+                "openit-definitely-missing-binary %u",
+                "/nonexistent/bin/viewer %f"
+            ]
+        );
+        assert!(invalid[0].1.to_string().contains("not found on PATH"));
+        assert!(invalid[1].1.to_string().contains("does not exist"));
+    }
+
+    fn github_handler(exec: &str) -> RegexHandler {
+        let store = RegexHandlerStore::from_definitions(
+            vec![RegexHandlerDefinition {
+                exec: exec.to_string(),
+                regexes: vec![
+                    r"^https://github\.com/(?P<owner>[^/]+)/(?P<repo>[^/]+)(/tree/(\w+))?$"
+                        .to_string(),
+                ],
+                ..RegexHandlerDefinition::default()
+            }],
+            Vec::new(),
+        )
+        .unwrap();
+        store.handlers()[0].clone()
+    }
+
+    #[test]
+    fn expand_exec_substitutes_numbered_and_named_groups() {
+        let url = "https://github.com/CaddyGlow/open-with";
+
+        assert_eq!(
+            github_handler("git-clone ${owner} ${repo}")
+                .expand_exec(url)
+                .unwrap(),
+            "git-clone CaddyGlow open-with"
+        );
+        assert_eq!(
+            github_handler("echo $1/$2 ${2}x").expand_exec(url).unwrap(),
+            "echo CaddyGlow/open-with open-withx"
+        );
+        assert_eq!(
+            github_handler("browse $0").expand_exec(url).unwrap(),
+            format!("browse {url}")
+        );
+    }
+
+    #[test]
+    fn capture_groups_report_names_and_unmatched_groups() {
+        let handler = github_handler("firefox %u");
+        let (pattern, groups) = handler
+            .capture_groups("https://github.com/CaddyGlow/open-with")
+            .unwrap();
+
+        assert!(pattern.starts_with("^https://github"));
+        assert_eq!(groups.len(), 5);
+        assert_eq!(groups[1].name.as_deref(), Some("owner"));
+        assert_eq!(groups[1].value.as_deref(), Some("CaddyGlow"));
+        assert_eq!(groups[3].value, None);
+        assert!(handler.capture_groups("https://example.com").is_none());
+    }
+
+    #[test]
+    fn expand_exec_handles_literals_and_unmatched_groups() {
+        let url = "https://github.com/a/b";
+
+        assert_eq!(
+            github_handler("sh -c 'echo $$HOME $HOME' %u")
+                .expand_exec(url)
+                .unwrap(),
+            "sh -c 'echo $HOME $HOME' '%u'"
+        );
+        // Group 4 exists but did not take part in this match.
+        assert_eq!(
+            github_handler("checkout v$4").expand_exec(url).unwrap(),
+            "checkout v"
+        );
+        assert_eq!(
+            github_handler("cd ${HOME}/src/$2")
+                .expand_exec(url)
+                .unwrap(),
+            "cd '${HOME}/src/b'"
+        );
+        assert_eq!(
+            github_handler("open $1")
+                .expand_exec("/not/a/match")
+                .unwrap(),
+            "open $1"
+        );
+    }
+
+    #[test]
+    fn expand_exec_replaces_whole_matches_and_several_groups() {
+        let handler = |exec: &str, regex: &str| {
+            RegexHandlerStore::from_definitions(
+                vec![RegexHandlerDefinition {
+                    exec: exec.to_string(),
+                    regexes: vec![regex.to_string()],
+                    ..RegexHandlerDefinition::default()
+                }],
+                Vec::new(),
+            )
+            .unwrap()
+            .handlers()[0]
+                .clone()
+        };
+        let magnet = "magnet:?xt=urn:btih:abc&dn=debian.iso";
+
+        assert_eq!(
+            handler("transmission-gtk $0", r"magnet:(\?.+)")
+                .expand_exec(magnet)
+                .unwrap(),
+            format!("transmission-gtk '{magnet}'")
+        );
+        assert_eq!(
+            handler("aria2c --query '$1'", r"magnet:(\?.+)")
+                .expand_exec(magnet)
+                .unwrap(),
+            "aria2c --query '?xt=urn:btih:abc&dn=debian.iso'"
+        );
+        // Quotes, spaces and field codes in the target cannot leave their argument.
+        let hostile = "magnet:?dn=x' --output=/tmp/owned 'y %f";
+        let exec = handler("aria2c --query '$1'", r"magnet:(\?.+)")
+            .expand_exec(hostile)
+            .unwrap();
+        assert_eq!(
+            shell_words::split(&exec).unwrap(),
+            vec!["aria2c", "--query", "?dn=x' --output=/tmp/owned 'y %%f"]
+        );
+        let exec = handler(
+            "mpv --title=$2 --sub-file=/home/$1/subs/$2.srt $0",
+            r"^/home/(\w+)/videos/(.+)\.(mkv|mp4)$",
+        )
+        .expand_exec("/home/ana/videos/talk.mkv")
+        .unwrap();
+        assert_eq!(
+            shell_words::split(&exec).unwrap(),
+            vec![
+                "mpv",
+                "--title=talk",
+                "--sub-file=/home/ana/subs/talk.srt",
+                "/home/ana/videos/talk.mkv"
+            ]
+        );
+    }
+
+    #[test]
+    fn expand_exec_rejects_unknown_numbered_groups() {
+        let url = "https://github.com/a/b";
+
+        let err = github_handler("run $9").expand_exec(url).unwrap_err();
+        assert!(err
+            .to_string()
+            .contains("references capture group `9`, but"));
+
+        let err = github_handler("run ${owner").expand_exec(url).unwrap_err();
+        assert!(err.to_string().contains("unterminated"));
+    }
+
+    #[test]
+    fn other_backreference_styles_are_rejected_at_load() {
+        for (exec, written) in [("transmission-gtk {0}", "{0}"), (r"open-repo \1 \2", r"\1")] {
+            let mut file = NamedTempFile::new().unwrap();
+            writeln!(file, "[[handlers]]\nexec = '{exec}'\nregexes = [\"(.+)\"]").unwrap();
+
+            let err = RegexHandlerStore::load(Some(file.path().to_path_buf()))
+                .unwrap_err()
+                .to_string();
+            assert!(err.contains(&format!("uses `{written}`")), "{err}");
+            assert!(err.contains("capture groups are written `$"), "{err}");
+        }
+
+        for exec in ["run ${1} {} ${name}", r"printf '%s\n' $1"] {
+            assert!(check_capture_syntax(exec).is_ok(), "{exec}");
+        }
+    }
+
     #[test]
     fn test_load_and_match_handler() {
         let mut file = NamedTempFile::new().unwrap();
@@ -244,6 +735,31 @@ notes = "Open HTTPS URLs"
         assert!(!handler.terminal);
         assert!(handler.matches("https://example.com"));
         assert!(!handler.matches("http://example.com"));
+        assert!(!handler.stdin_input);
+        assert!(!handler.confirm);
+    }
+
+    #[test]
+    fn test_stdin_input_flag_is_loaded() {
+        let mut file = NamedTempFile::new().unwrap();
+        write!(
+            file,
+            r#"
+[[handlers]]
+exec = "glow -"
+regexes = [".*\\.md$"]
+terminal = true
+stdin_input = true
+confirm = true
+"#
+        )
+        .unwrap();
+
+        let store = RegexHandlerStore::load(Some(file.path().to_path_buf())).unwrap();
+        let handler = store.find_handler("/tmp/readme.md").unwrap();
+        assert!(handler.stdin_input);
+        assert!(handler.terminal);
+        assert!(handler.confirm);
     }
 
     #[test]
@@ -297,4 +813,89 @@ priority = 10
             .find_handler("https://youtu.be/dQw4w9WgXcQ")
             .is_some());
     }
+
+    #[test]
+    fn test_drop_in_directory_handlers_are_merged_by_priority() {
+        let temp_dir = TempDir::new().unwrap();
+        let main_path = temp_dir.path().join("regex_handlers.toml");
+        fs::write(
+            &main_path,
+            r#"
+[[handlers]]
+exec = "main %u"
+regexes = ["https://.*"]
+priority = 5
+"#,
+        )
+        .unwrap();
+
+        let drop_in_dir = temp_dir.path().join("regex_handlers.d");
+        fs::create_dir(&drop_in_dir).unwrap();
+        fs::write(
+            drop_in_dir.join("a-low.toml"),
+            r#"
+[[handlers]]
+exec = "low %u"
+regexes = ["https://.*"]
+priority = 1
+"#,
+        )
+        .unwrap();
+        fs::write(
+            drop_in_dir.join("b-high.toml"),
+            r#"
+[[handlers]]
+exec = "high %u"
+regexes = ["https://.*"]
+priority = 10
+"#,
+        )
+        .unwrap();
+        fs::write(drop_in_dir.join("notes.txt"), "not a handler file").unwrap();
+
+        let store = RegexHandlerStore::load(Some(main_path)).unwrap();
+        let order: Vec<&str> = store
+            .handlers()
+            .iter()
+            .map(|handler| handler.exec.as_str())
+            .collect();
+        assert_eq!(order, vec!["high %u", "main %u", "low %u"]);
+        assert_eq!(
+            store.find_handler("https://example.com").unwrap().exec,
+            "high %u"
+        );
+    }
+
+    #[test]
+    fn test_drop_in_directory_without_main_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let drop_in_dir = temp_dir.path().join("regex_handlers.d");
+        fs::create_dir(&drop_in_dir).unwrap();
+        fs::write(
+            drop_in_dir.join("video.toml"),
+            r#"
+[[handlers]]
+exec = "mpv %u"
+regexes = ["https://youtu\\.be/.*"]
+"#,
+        )
+        .unwrap();
+
+        let store =
+            RegexHandlerStore::load(Some(temp_dir.path().join("regex_handlers.toml"))).unwrap();
+        assert_eq!(store.len(), 1);
+        assert!(store.find_handler("https://youtu.be/abc").is_some());
+    }
+
+    #[test]
+    fn test_invalid_drop_in_file_reports_path() {
+        let temp_dir = TempDir::new().unwrap();
+        let drop_in_dir = temp_dir.path().join("regex_handlers.d");
+        fs::create_dir(&drop_in_dir).unwrap();
+        fs::write(drop_in_dir.join("broken.toml"), "[[handlers]\n").unwrap();
+
+        let err =
+            RegexHandlerStore::load(Some(temp_dir.path().join("regex_handlers.toml"))).unwrap_err();
+        assert!(err.to_string().contains("broken.toml"));
+    }
 }
@@ -18,6 +18,7 @@ impl SelectorRunner {
         command: &str,
         args: &[String],
         applications: &[ApplicationEntry],
+        env: &[(String, String)],
     ) -> Result<Option<usize>> {
         if applications.is_empty() {
             return Ok(None);
@@ -32,6 +33,7 @@ impl SelectorRunner {
         for arg in args {
             cmd.arg(arg);
         }
+        cmd.envs(env.iter().map(|(key, value)| (key, value)));
 
         cmd.stdin(Stdio::piped()).stdout(Stdio::piped());
 
@@ -44,9 +46,14 @@ impl SelectorRunner {
             .take()
             .ok_or_else(|| anyhow::anyhow!("Selector command `{}` has no stdin", command_spec))?;
 
+        // A selector reading `OPENIT_CANDIDATES_FILE` may exit without draining stdin.
         for app in applications {
-            writeln!(stdin, "{}", format_entry(app))?;
+            match writeln!(stdin, "{}", format_entry(app)) {
+                Err(err) if err.kind() == std::io::ErrorKind::BrokenPipe => break,
+                result => result?,
+            }
         }
+        drop(stdin);
 
         let output = child
             .wait_with_output()
@@ -72,25 +79,89 @@ impl SelectorRunner {
             return Ok(None);
         }
 
-        let selection_cleaned = strip_marker(&selection);
-
-        let index = applications
-            .iter()
-            .position(|app| app.name == selection_cleaned)
-            .ok_or_else(|| {
-                anyhow::anyhow!(
-                    "Selector returned unknown selection `{selection}` (expected one of [{}])",
-                    applications.iter().map(|app| app.name.as_str()).join(", ")
-                )
-            })?;
+        let index = resolve_selection(&selection, applications).ok_or_else(|| {
+            anyhow::anyhow!(
+                "Selector returned unknown selection `{selection}` (expected one of [{}])",
+                applications.iter().map(|app| app.name.as_str()).join(", ")
+            )
+        })?;
 
         Ok(Some(index))
     }
+
+    /// Run the selector with no candidates and return the first line it prints, the text
+    /// typed into it. `None` when it prints nothing or is cancelled.
+    ///
+    /// fzf `--print-query` exits with 1 when the query matches nothing, so that status
+    /// still counts as an answer.
+    pub fn prompt(
+        &self,
+        command: &str,
+        args: &[String],
+        env: &[(String, String)],
+    ) -> Result<Option<String>> {
+        let command_spec = command.trim();
+        if command_spec.is_empty() {
+            return Err(anyhow::anyhow!("Selector command is empty"));
+        }
+
+        let output = Command::new(command_spec)
+            .args(args)
+            .envs(env.iter().map(|(key, value)| (key, value)))
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .output()
+            .with_context(|| format!("Failed to spawn selector command `{}`", command_spec))?;
+
+        if !matches!(output.status.code(), Some(0 | 1)) {
+            info!(
+                "Selector command `{}` exited with status {:?}",
+                command_spec,
+                output.status.code()
+            );
+            return Ok(None);
+        }
+
+        let answer = String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .next()
+            .map(str::trim)
+            .unwrap_or_default()
+            .to_string();
+        Ok(Some(answer).filter(|answer| !answer.is_empty()))
+    }
+}
+
+/// Accept an index into `applications`, a desktop ID, or a rendered line, in that order.
+fn resolve_selection(selection: &str, applications: &[ApplicationEntry]) -> Option<usize> {
+    if let Ok(index) = selection.parse::<usize>() {
+        if index < applications.len() {
+            return Some(index);
+        }
+    }
+
+    let by_id = applications.iter().position(|app| {
+        app.action_id.is_none()
+            && app
+                .desktop_file
+                .file_name()
+                .is_some_and(|name| name == selection)
+    });
+    if by_id.is_some() {
+        return by_id;
+    }
+
+    let selection_cleaned = strip_marker(selection);
+    applications
+        .iter()
+        .position(|app| app.name == selection_cleaned)
 }
 
 fn marker_for_app(app: &ApplicationEntry) -> &'static str {
-    if app.desktop_file.starts_with("regex-handler-") {
+    if app.is_regex_handler() {
         "[regex]"
+    } else if app.desktop_file.as_os_str() == crate::open_it::CUSTOM_COMMAND_ID {
+        "[command]"
     } else if app.is_default {
         "[default]"
     } else if app.is_xdg {
@@ -100,8 +171,15 @@ fn marker_for_app(app: &ApplicationEntry) -> &'static str {
     }
 }
 
+/// Width of the longest marker, `[available]`, so names line up in a column.
+const MARKER_WIDTH: usize = 11;
+
 fn format_entry(app: &ApplicationEntry) -> String {
-    format!("{} {}", marker_for_app(app), app.name)
+    format!(
+        "{} {}",
+        crate::template::pad_to_width(marker_for_app(app), MARKER_WIDTH),
+        app.name
+    )
 }
 
 fn strip_marker(selection: &str) -> String {
@@ -116,6 +194,7 @@ fn strip_marker(selection: &str) -> String {
 #[cfg(all(test, unix))]
 mod tests {
     use super::*;
+    use crate::application_finder::{ApplicationSource, Association};
     use std::fs;
     use std::os::unix::fs::PermissionsExt;
     use tempfile::TempDir;
@@ -133,6 +212,20 @@ mod tests {
             action_id: None,
             requires_terminal: false,
             is_terminal_emulator: false,
+            working_dir: None,
+            prefers_non_default_gpu: false,
+            stdin_input: false,
+            association: Association::None,
+            parent_mime: None,
+            startup_notify: false,
+            confirm: false,
+            score: 0.0,
+            dbus_activatable: false,
+            startup_wm_class: None,
+            single_main_window: false,
+            generic_name: None,
+            keywords: Vec::new(),
+            source: ApplicationSource::Available,
         }
     }
 
@@ -161,9 +254,9 @@ printf "%s" "$second"
         let args = vec![script_path.clone()];
 
         let mut apps = vec![test_app("First"), test_app("Second")];
-        apps[1].desktop_file = std::path::PathBuf::from("regex-handler-1.desktop");
+        apps[1].source = ApplicationSource::Regex { priority: 1 };
 
-        let index = runner.run(command, &args, &apps).unwrap();
+        let index = runner.run(command, &args, &apps, &[]).unwrap();
         assert_eq!(index, Some(1));
     }
 
@@ -182,7 +275,7 @@ exit 0
 
         let apps = vec![test_app("Only")];
 
-        let index = runner.run(command, &args, &apps).unwrap();
+        let index = runner.run(command, &args, &apps, &[]).unwrap();
         assert_eq!(index, None);
     }
 
@@ -208,7 +301,7 @@ printf "%s" "$choice"
 
         let apps = vec![test_app("Only")];
 
-        let index = runner.run(command, &args, &apps).unwrap();
+        let index = runner.run(command, &args, &apps, &[]).unwrap();
         assert_eq!(index, Some(0));
 
         let args_contents = fs::read_to_string(output_path).unwrap();
@@ -229,9 +322,69 @@ printf "Unknown"
 
         let apps = vec![test_app("One")];
 
-        let err = runner.run(command, &args, &apps).unwrap_err();
+        let err = runner.run(command, &args, &apps, &[]).unwrap_err();
         assert!(err
             .to_string()
             .contains("Selector returned unknown selection"));
     }
+
+    #[test]
+    fn test_selection_accepts_index_desktop_id_or_line() {
+        let apps = vec![test_app("One"), test_app("Two")];
+
+        assert_eq!(resolve_selection("1", &apps), Some(1));
+        assert_eq!(resolve_selection("Two.desktop", &apps), Some(1));
+        assert_eq!(resolve_selection("[available] One", &apps), Some(0));
+        assert_eq!(resolve_selection("7", &apps), None);
+    }
+
+    #[test]
+    fn test_selector_runner_tolerates_a_selector_that_ignores_stdin() {
+        // Reads the candidates from `OPENIT_CANDIDATES_FILE` instead and exits at once,
+        // long before the candidate lines would fit in the pipe buffer.
+        let script = r#"#!/bin/sh
+printf "App7.desktop"
+"#;
+
+        let (_dir, script_path) = create_script(script);
+
+        let runner = SelectorRunner::new();
+        let apps: Vec<ApplicationEntry> = (0..5000).map(|i| test_app(&format!("App{i}"))).collect();
+
+        let index = runner.run("sh", &[script_path], &apps, &[]).unwrap();
+        assert_eq!(index, Some(7));
+    }
+
+    #[test]
+    fn test_selector_runner_exports_environment() {
+        let script = r#"#!/bin/sh
+printf "%s" "$OPENIT_PICK"
+"#;
+
+        let (_dir, script_path) = create_script(script);
+
+        let runner = SelectorRunner::new();
+        let apps = vec![test_app("One"), test_app("Two")];
+        let env = vec![("OPENIT_PICK".to_string(), "Two.desktop".to_string())];
+
+        let index = runner.run("sh", &[script_path], &apps, &env).unwrap();
+        assert_eq!(index, Some(1));
+    }
+
+    #[test]
+    fn test_prompt_returns_typed_text_and_accepts_no_match_status() {
+        // fzf --print-query: the query on the first line, exit 1 when nothing matched.
+        let (_dir, script_path) = create_script("#!/bin/sh\nprintf 'mpv --loop\\n'\nexit 1\n");
+        let runner = SelectorRunner::new();
+        assert_eq!(
+            runner.prompt("sh", &[script_path], &[]).unwrap().as_deref(),
+            Some("mpv --loop")
+        );
+
+        let (_dir, script_path) = create_script("#!/bin/sh\nprintf 'ignored\\n'\nexit 130\n");
+        assert_eq!(runner.prompt("sh", &[script_path], &[]).unwrap(), None);
+
+        let (_dir, script_path) = create_script("#!/bin/sh\nprintf '  \\n'\n");
+        assert_eq!(runner.prompt("sh", &[script_path], &[]).unwrap(), None);
+    }
 }
@@ -0,0 +1,197 @@
+use std::fs;
+use std::path::Path;
+use url::Url;
+
+/// Shortcut files are a few hundred bytes; anything far larger is not one.
+const MAX_SHORTCUT_SIZE: u64 = 64 * 1024;
+
+/// The kinds of file that only point at a URL.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ShortcutKind {
+    /// Windows `.url`: an INI file with `URL=` under `[InternetShortcut]`.
+    WindowsUrl,
+    /// macOS `.webloc`: an XML property list with a `URL` string.
+    Webloc,
+    /// A `.desktop` file with `Type=Link` and a `URL=` key.
+    DesktopLink,
+}
+
+impl ShortcutKind {
+    /// Decided by extension alone, case-insensitively.
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "url" => Some(Self::WindowsUrl),
+            "webloc" => Some(Self::Webloc),
+            "desktop" => Some(Self::DesktopLink),
+            _ => None,
+        }
+    }
+
+    /// The shared-mime-info type for the extension, which `mime_guess` does not know.
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            Self::WindowsUrl => "application/x-mswinurl",
+            Self::Webloc => "application/x-webloc",
+            Self::DesktopLink => "application/x-desktop",
+        }
+    }
+}
+
+/// The URL a shortcut file at `path` points to; `None` when the extension is not a
+/// shortcut's or the contents do not hold a valid absolute URL.
+pub fn embedded_url(path: &Path) -> Option<Url> {
+    let kind = ShortcutKind::from_path(path)?;
+    let metadata = fs::metadata(path).ok()?;
+    if !metadata.is_file() || metadata.len() > MAX_SHORTCUT_SIZE {
+        return None;
+    }
+
+    let contents = String::from_utf8_lossy(&fs::read(path).ok()?).into_owned();
+    match kind {
+        ShortcutKind::WindowsUrl => parse_url_file(&contents),
+        ShortcutKind::Webloc => parse_webloc(&contents),
+        ShortcutKind::DesktopLink => parse_desktop_link(&contents),
+    }
+}
+
+/// `URL=` from the `[InternetShortcut]` section; other sections are ignored.
+pub fn parse_url_file(contents: &str) -> Option<Url> {
+    parse_absolute_url(ini_value(contents, "InternetShortcut", "URL")?)
+}
+
+/// `URL=` from a `[Desktop Entry]` with `Type=Link`. `DesktopFile::parse` rejects these
+/// entries because they have no `Exec=`, so the keys are read here.
+pub fn parse_desktop_link(contents: &str) -> Option<Url> {
+    if ini_value(contents, "Desktop Entry", "Type")? != "Link" {
+        return None;
+    }
+    parse_absolute_url(ini_value(contents, "Desktop Entry", "URL")?)
+}
+
+/// The first `key=` in `[section]`; both names compare case-insensitively.
+fn ini_value<'a>(contents: &'a str, section: &str, key: &str) -> Option<&'a str> {
+    let mut in_section = false;
+    for line in contents.lines() {
+        let line = line.trim().trim_start_matches('\u{feff}');
+        if line.starts_with('[') && line.ends_with(']') {
+            in_section = line[1..line.len() - 1].eq_ignore_ascii_case(section);
+            continue;
+        }
+        if !in_section {
+            continue;
+        }
+        let Some((name, value)) = line.split_once('=') else {
+            continue;
+        };
+        if name.trim().eq_ignore_ascii_case(key) {
+            return Some(value.trim());
+        }
+    }
+    None
+}
+
+/// The `<string>` following `<key>URL</key>` in an XML plist. Binary plists are not
+/// read and yield `None`.
+pub fn parse_webloc(contents: &str) -> Option<Url> {
+    if !contents.contains("<plist") {
+        return None;
+    }
+    let after_key = &contents[contents.find("<key>URL</key>")? + "<key>URL</key>".len()..];
+    let value = after_key.trim_start().strip_prefix("<string>")?;
+    let value = &value[..value.find("</string>")?];
+    parse_absolute_url(&unescape_xml(value.trim()))
+}
+
+/// Schemes a shortcut may point at. A `file://`, `javascript:` or custom-scheme target
+/// is not followed, and neither is a relative path or a drive letter such as `C:\`.
+const FOLLOWED_SCHEMES: &[&str] = &["http", "https", "ftp", "mailto"];
+
+fn parse_absolute_url(value: &str) -> Option<Url> {
+    let url = Url::parse(value).ok()?;
+    FOLLOWED_SCHEMES.contains(&url.scheme()).then_some(url)
+}
+
+fn unescape_xml(value: &str) -> String {
+    value
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&apos;", "'")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    const WINDOWS_URL: &str = include_str!("testdata/shortcuts/example.url");
+    const WEBLOC: &str = include_str!("testdata/shortcuts/example.webloc");
+    const MALFORMED_URL: &str = include_str!("testdata/shortcuts/malformed.url");
+    const MALFORMED_WEBLOC: &str = include_str!("testdata/shortcuts/malformed.webloc");
+
+    #[test]
+    fn url_files_yield_the_internet_shortcut_url() {
+        assert_eq!(
+            parse_url_file(WINDOWS_URL).unwrap().as_str(),
+            "https://example.com/docs?page=2"
+        );
+        assert!(parse_url_file(MALFORMED_URL).is_none());
+        // `URL=` outside `[InternetShortcut]` is not the shortcut's target.
+        assert!(parse_url_file("[Other]\nURL=https://example.com\n").is_none());
+        assert!(parse_url_file("[InternetShortcut]\nURL=docs/index.html\n").is_none());
+        assert!(parse_url_file("[InternetShortcut]\nURL=mailto:me@example.com\n").is_some());
+        for url in [
+            "file:///etc/passwd",
+            "javascript:alert(1)",
+            "steam://run/42",
+            "C:\\Docs\\a.txt",
+        ] {
+            let contents = format!("[InternetShortcut]\nURL={url}\n");
+            assert!(parse_url_file(&contents).is_none(), "{url}");
+        }
+    }
+
+    #[test]
+    fn webloc_files_yield_the_plist_url() {
+        assert_eq!(
+            parse_webloc(WEBLOC).unwrap().as_str(),
+            "https://example.org/a?b=1&c=2"
+        );
+        assert!(parse_webloc(MALFORMED_WEBLOC).is_none());
+        assert!(parse_webloc("bplist00\u{1}\u{2}URL").is_none());
+    }
+
+    #[test]
+    fn embedded_url_checks_extension_and_contents() {
+        let temp_dir = TempDir::new().unwrap();
+        let write = |name: &str, contents: &str| {
+            let path = temp_dir.path().join(name);
+            fs::write(&path, contents).unwrap();
+            path
+        };
+
+        let url = write("Docs.URL", WINDOWS_URL);
+        assert_eq!(
+            embedded_url(&url).unwrap().as_str(),
+            "https://example.com/docs?page=2"
+        );
+        assert!(embedded_url(&write("notes.txt", WINDOWS_URL)).is_none());
+        assert!(embedded_url(&write("broken.webloc", MALFORMED_WEBLOC)).is_none());
+
+        let link = write(
+            "site.desktop",
+            "[Desktop Entry]\nType=Link\nName=Site\nURL=https://example.net/\n",
+        );
+        assert_eq!(
+            embedded_url(&link).unwrap().as_str(),
+            "https://example.net/"
+        );
+        let app = write(
+            "app.desktop",
+            "[Desktop Entry]\nType=Application\nName=App\nExec=app\nURL=https://example.net/\n",
+        );
+        assert!(embedded_url(&app).is_none());
+    }
+}
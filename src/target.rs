@@ -3,6 +3,8 @@ use std::path::{Path, PathBuf};
 
 use url::Url;
 
+use crate::config::UriMatchNormalize;
+
 /// Represents the resource that should be opened by the application executor.
 #[derive(Debug, Clone, PartialEq)]
 pub enum LaunchTarget {
@@ -33,6 +35,22 @@ impl LaunchTarget {
         }
     }
 
+    /// The string regex handlers match: the path, or the URI rewritten by `normalize`.
+    pub fn match_candidate(&self, normalize: UriMatchNormalize) -> String {
+        match self {
+            LaunchTarget::File(path) => path.to_string_lossy().into_owned(),
+            LaunchTarget::Uri(uri) => {
+                let mut uri = uri.clone();
+                match normalize {
+                    UriMatchNormalize::None => {}
+                    UriMatchNormalize::StripQuery => uri.set_query(None),
+                    UriMatchNormalize::StripFragment => uri.set_fragment(None),
+                }
+                uri.into()
+            }
+        }
+    }
+
     /// Returns the underlying path if this target represents a file.
     pub fn as_path(&self) -> Option<&Path> {
         match self {
@@ -1,4 +1,8 @@
+use anyhow::{Context, Result};
 use std::collections::HashMap;
+use std::process::{Command, ExitStatus, Stdio};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
 
 /// A simple template engine for string substitution
 #[derive(Debug, Clone)]
@@ -70,6 +74,9 @@ impl TemplateEngine {
                                 i += 1;
                             }
                         }
+                    } else {
+                        // Unterminated placeholder - keep the remaining text as-is
+                        result.extend(&chars[start..]);
                     }
                 }
             } else {
@@ -93,27 +100,44 @@ impl TemplateEngine {
             return None;
         }
 
-        let mut value = self.variables.get(key)?.clone();
+        // An unset variable stays unset through other filters until `default` fills it in.
+        let mut value = self.variables.get(key).cloned();
 
         for modifier in parts {
             let modifier = modifier.trim();
-            if let Some(arg) = modifier.strip_prefix("truncate:") {
-                if let Ok(limit) = arg.trim().parse::<usize>() {
-                    if limit == 0 {
-                        value.clear();
-                    } else {
-                        let char_count = value.chars().count();
-                        let mut truncated: String = value.chars().take(limit).collect();
-                        if char_count > limit {
-                            truncated.push_str("...");
-                        }
-                        value = truncated;
-                    }
-                }
-            }
+            value = match modifier.strip_prefix("default:") {
+                Some(fallback) => match value {
+                    Some(value) if !value.is_empty() => Some(value),
+                    _ => Some(fallback.trim().to_string()),
+                },
+                None => value.map(|value| Self::apply_filter(value, modifier)),
+            };
         }
 
-        Some(value)
+        value
+    }
+
+    /// Apply a single `name` or `name:arg` filter; unknown filters leave the value unchanged.
+    fn apply_filter(value: String, filter: &str) -> String {
+        let (name, arg) = match filter.split_once(':') {
+            Some((name, arg)) => (name.trim(), Some(arg.trim())),
+            None => (filter, None),
+        };
+
+        match name {
+            "truncate" => match arg.and_then(|arg| arg.parse::<usize>().ok()) {
+                Some(0) => String::new(),
+                Some(limit) => truncate_to_width(&value, limit),
+                None => value,
+            },
+            "basename" => std::path::Path::new(&value)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or(value),
+            "upper" => value.to_uppercase(),
+            "lower" => value.to_lowercase(),
+            _ => value,
+        }
     }
 
     /// Clear all variables
@@ -141,6 +165,64 @@ impl Default for TemplateEngine {
     }
 }
 
+/// Run the command configured as `setting`, with `vars` filled into its `{name}`
+/// placeholders; each placeholder stays a single argument and no shell is involved.
+pub fn run_template_command(
+    setting: &str,
+    template: &str,
+    vars: &[(&str, &str)],
+) -> Result<ExitStatus> {
+    let args = shell_words::split(template)
+        .with_context(|| format!("Failed to parse {setting} `{template}`"))?;
+    let mut engine = TemplateEngine::new();
+    for (key, value) in vars {
+        engine.set(*key, *value);
+    }
+    let args = engine.render_args(&args);
+    let (program, rest) = args
+        .split_first()
+        .ok_or_else(|| anyhow::anyhow!("{setting} is empty"))?;
+
+    Command::new(program)
+        .args(rest)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .with_context(|| format!("Failed to run {setting} `{program}`"))
+}
+
+/// Terminal column width of `text`; wide CJK characters count as two.
+pub fn display_width(text: &str) -> usize {
+    text.width()
+}
+
+/// Right-pad `text` with spaces to `width` columns.
+pub fn pad_to_width(text: &str, width: usize) -> String {
+    let padding = width.saturating_sub(display_width(text));
+    format!("{text}{}", " ".repeat(padding))
+}
+
+/// Keep whole grapheme clusters that fit in `limit` columns, appending `...` when cut.
+fn truncate_to_width(value: &str, limit: usize) -> String {
+    if display_width(value) <= limit {
+        return value.to_string();
+    }
+
+    let mut truncated = String::new();
+    let mut width = 0;
+    for grapheme in value.graphemes(true) {
+        let grapheme_width = grapheme.width();
+        if width + grapheme_width > limit {
+            break;
+        }
+        width += grapheme_width;
+        truncated.push_str(grapheme);
+    }
+    truncated.push_str("...");
+    truncated
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -151,6 +233,23 @@ mod tests {
         assert!(engine.variables.is_empty());
     }
 
+    #[test]
+    fn test_run_template_command_names_the_setting_in_errors() {
+        let status =
+            run_template_command("focus_command", "test {class} = 'a b'", &[("class", "a b")])
+                .unwrap();
+        assert!(status.success());
+
+        let err = run_template_command("focus_command", "  ", &[]).unwrap_err();
+        assert_eq!(err.to_string(), "focus_command is empty");
+        let err = run_template_command("notify_command", "notify 'open", &[]).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Failed to parse notify_command"),
+            "{err}"
+        );
+    }
+
     #[test]
     fn test_default_template_engine() {
         let engine = TemplateEngine::default();
@@ -389,6 +488,123 @@ mod tests {
         assert_eq!(result, "short");
     }
 
+    #[test]
+    fn test_render_default_prompt_truncates_long_filename() {
+        let mut engine = TemplateEngine::new();
+        engine.set("file", "a-really-long-report-name-2024.pdf");
+
+        let prompt = crate::config::Config::default().prompt_template;
+        assert_eq!(
+            engine.render(&prompt),
+            "Open 'a-really-long-report...' with: "
+        );
+    }
+
+    #[test]
+    fn test_render_basename_upper_lower_filters() {
+        let mut engine = TemplateEngine::new();
+        engine.set("path", "/home/user/Notes.TXT");
+
+        assert_eq!(engine.render("{path|basename}"), "Notes.TXT");
+        assert_eq!(engine.render("{path|basename|upper}"), "NOTES.TXT");
+        assert_eq!(engine.render("{path|basename|lower}"), "notes.txt");
+        assert_eq!(engine.render("{path | basename | truncate:3}"), "Not...");
+    }
+
+    #[test]
+    fn test_render_default_filter_fills_missing_and_empty_variables() {
+        let mut engine = TemplateEngine::new();
+        engine.set("name", "Firefox").set("comment", "");
+
+        assert_eq!(engine.render("{missing|default:n/a}"), "n/a");
+        assert_eq!(engine.render("{comment|default:none}"), "none");
+        assert_eq!(engine.render("{name|default:unused}"), "Firefox");
+        assert_eq!(engine.render("[{missing|default:}]"), "[]");
+        assert_eq!(engine.render("{name}{comment|default:}"), "Firefox");
+        assert_eq!(engine.render("{missing|default:a:b}"), "a:b");
+    }
+
+    #[test]
+    fn test_render_default_filter_chains_with_other_filters() {
+        let mut engine = TemplateEngine::new();
+        assert_eq!(engine.render("{file|basename|default:unknown}"), "unknown");
+        assert_eq!(engine.render("{file|default:unknown|upper}"), "UNKNOWN");
+        assert_eq!(engine.render("{file|upper}"), "{file|upper}");
+
+        engine.set("file", "/tmp/report.pdf");
+        assert_eq!(
+            engine.render("{file|basename|default:unknown}"),
+            "report.pdf"
+        );
+
+        // A filter that empties the value lets a later `default` apply.
+        assert_eq!(engine.render("{file|truncate:0|default:-}"), "-");
+    }
+
+    #[test]
+    fn test_render_truncate_counts_display_columns() {
+        let mut engine = TemplateEngine::new();
+        engine.set("file", "日本語のファイル名");
+
+        // Each CJK character is two columns wide; a half-fitting one is dropped.
+        assert_eq!(engine.render("{file|truncate:6}"), "日本語...");
+        assert_eq!(engine.render("{file|truncate:7}"), "日本語...");
+        assert_eq!(display_width("日本語"), 6);
+    }
+
+    #[test]
+    fn test_render_truncate_keeps_grapheme_clusters() {
+        let mut engine = TemplateEngine::new();
+        // Family emoji (ZWJ sequence) followed by an `e` with a combining acute accent.
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        engine.set("file", format!("{family}e\u{301}tude.txt"));
+
+        assert_eq!(engine.render("{file|truncate:2}"), format!("{family}..."));
+        assert_eq!(
+            engine.render("{file|truncate:3}"),
+            format!("{family}e\u{301}...")
+        );
+    }
+
+    #[test]
+    fn test_render_truncate_rtl_filename() {
+        let mut engine = TemplateEngine::new();
+        engine.set("file", "מסמך חשוב.pdf");
+
+        let rendered = engine.render("{file|truncate:4}");
+        assert_eq!(rendered, "מסמך...");
+        assert_eq!(display_width(rendered.trim_end_matches("...")), 4);
+    }
+
+    #[test]
+    fn test_render_handles_replacement_characters() {
+        let lossy = String::from_utf8_lossy(b"bad-\xff\xfe-name.txt").into_owned();
+        let mut engine = TemplateEngine::new();
+        engine.set("file", lossy);
+
+        assert_eq!(
+            engine.render("Open '{file|truncate:5}' with: "),
+            "Open 'bad-\u{FFFD}...' with: "
+        );
+        assert_eq!(engine.render("{marker}{file"), "{marker}{file");
+    }
+
+    #[test]
+    fn test_pad_to_width_aligns_wide_markers() {
+        assert_eq!(pad_to_width("★", 3), "★  ");
+        assert_eq!(pad_to_width("日", 3), "日 ");
+        assert_eq!(pad_to_width("[available]", 3), "[available]");
+    }
+
+    #[test]
+    fn test_render_invalid_truncate_argument_passes_through() {
+        let mut engine = TemplateEngine::new();
+        engine.set("file", "document");
+
+        assert_eq!(engine.render("{file|truncate:abc}"), "document");
+        assert_eq!(engine.render("{file|truncate}"), "document");
+    }
+
     #[test]
     fn test_many_variables() {
         let mut engine = TemplateEngine::new();
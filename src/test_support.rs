@@ -4,6 +4,50 @@ use std::env;
 use std::ffi::OsString;
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::sync::{Mutex, Once};
+use std::time::{Duration, Instant};
+
+static CAPTURED_LOGS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+/// Global logger that records every message as `target: message`.
+struct CaptureLogger;
+
+impl log::Log for CaptureLogger {
+    fn enabled(&self, _metadata: &log::Metadata<'_>) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record<'_>) {
+        if let Ok(mut logs) = CAPTURED_LOGS.lock() {
+            logs.push(format!("{}: {}", record.target(), record.args()));
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// Install the capturing logger; safe to call from every test that needs logs.
+pub fn init_capture_logger() {
+    static INIT: Once = Once::new();
+    INIT.call_once(|| {
+        if log::set_logger(&CaptureLogger).is_ok() {
+            log::set_max_level(log::LevelFilter::Debug);
+        }
+    });
+}
+
+/// Messages logged so far under `target`.
+pub fn captured_logs(target: &str) -> Vec<String> {
+    CAPTURED_LOGS
+        .lock()
+        .map(|logs| {
+            logs.iter()
+                .filter_map(|line| line.strip_prefix(target)?.strip_prefix(": "))
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
 
 pub struct CacheEnvGuard {
     original: Option<OsString>,
@@ -77,8 +121,109 @@ impl Drop for ValidationEnvGuard {
     }
 }
 
+/// Sets or clears an arbitrary environment variable, restoring it on drop.
+pub struct EnvVarGuard {
+    key: &'static str,
+    original: Option<OsString>,
+}
+
+impl EnvVarGuard {
+    pub fn set(key: &'static str, value: &str) -> Self {
+        let original = env::var_os(key);
+        env::set_var(key, value);
+        Self { key, original }
+    }
+
+    pub fn unset(key: &'static str) -> Self {
+        let original = env::var_os(key);
+        env::remove_var(key);
+        Self { key, original }
+    }
+}
+
+impl Drop for EnvVarGuard {
+    fn drop(&mut self) {
+        if let Some(original) = self.original.take() {
+            env::set_var(self.key, original);
+        } else {
+            env::remove_var(self.key);
+        }
+    }
+}
+
 pub fn create_test_desktop_file(dir: &Path, name: &str, content: &str) -> PathBuf {
     let file_path = dir.join(name);
     fs::write(&file_path, content).expect("failed to write desktop file");
     file_path
 }
+
+/// How long tests wait for a detached launch to leave its marker file.
+pub const LAUNCH_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Poll until `path` exists, for at most `timeout`; whether it appeared.
+pub fn wait_for_file(path: &Path, timeout: Duration) -> bool {
+    let deadline = Instant::now() + timeout;
+    while !path.exists() {
+        if Instant::now() >= deadline {
+            return false;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+    true
+}
+
+/// The contents of `path` once a launch has written something to it, or whatever it
+/// holds when `timeout` runs out; a missing file reads as empty.
+pub fn wait_for_contents(path: &Path, timeout: Duration) -> String {
+    let deadline = Instant::now() + timeout;
+    loop {
+        let contents = fs::read_to_string(path).unwrap_or_default();
+        if !contents.is_empty() || Instant::now() >= deadline {
+            return contents;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+/// Seeded xorshift generator for property-style tests; the same seed gives the same
+/// inputs, so a failure reproduces from the seed in its message.
+pub struct Xorshift(u64);
+
+impl Xorshift {
+    pub fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    pub fn below(&mut self, bound: usize) -> usize {
+        (self.next_u64() % bound as u64) as usize
+    }
+
+    pub fn pick<'a, T>(&mut self, items: &'a [T]) -> &'a T {
+        &items[self.below(items.len())]
+    }
+
+    pub fn bytes(&mut self, len: usize) -> Vec<u8> {
+        (0..len).map(|_| self.next_u64() as u8).collect()
+    }
+
+    /// Lines assembled from `fragments`, so generated input hits the parser's syntax
+    /// rather than being uniformly random noise.
+    pub fn ini_like(&mut self, fragments: &[&str], lines: usize) -> String {
+        let mut out = String::new();
+        for _ in 0..lines {
+            for _ in 0..1 + self.below(4) {
+                let fragment = *self.pick(fragments);
+                out.push_str(fragment);
+            }
+            out.push('\n');
+        }
+        out
+    }
+}
@@ -0,0 +1,39 @@
+use log::info;
+use std::time::Instant;
+
+/// Log target used for phase timings so they can be enabled on their own.
+pub const TARGET: &str = "openit::timings";
+
+/// Measures startup phases and logs their elapsed time when enabled.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PhaseTimer {
+    enabled: bool,
+}
+
+impl PhaseTimer {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+
+    pub fn measure<T>(&self, phase: &str, f: impl FnOnce() -> T) -> T {
+        if !self.enabled {
+            return f();
+        }
+
+        let start = Instant::now();
+        let value = f();
+        info!(target: TARGET, "{phase}: {:.2?}", start.elapsed());
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn measure_returns_closure_value() {
+        assert_eq!(PhaseTimer::new(true).measure("phase", || 42), 42);
+        assert_eq!(PhaseTimer::default().measure("phase", || "skip"), "skip");
+    }
+}
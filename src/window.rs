@@ -0,0 +1,51 @@
+use crate::template::run_template_command;
+use anyhow::Result;
+use log::debug;
+
+/// Finds and raises open windows, so a single-window application is focused instead of
+/// started again.
+pub trait WindowQuery: std::fmt::Debug {
+    /// Focus an open window whose class is `class`; `Ok(false)` when none is open.
+    fn focus(&self, class: &str) -> Result<bool>;
+}
+
+/// The configured `focus_command`, e.g. `swaymsg [app_id={class}] focus` or
+/// `wmctrl -x -a {class}`; a zero exit status means a window was focused.
+#[derive(Debug, Clone)]
+pub struct CommandWindowQuery {
+    template: String,
+}
+
+impl CommandWindowQuery {
+    pub fn new(template: impl Into<String>) -> Self {
+        Self {
+            template: template.into(),
+        }
+    }
+}
+
+impl WindowQuery for CommandWindowQuery {
+    fn focus(&self, class: &str) -> Result<bool> {
+        let status = run_template_command("focus_command", &self.template, &[("class", class)])?;
+        debug!("focus_command for {class} exited with {status}");
+        Ok(status.success())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exit_status_reports_whether_a_window_was_focused() {
+        let query = CommandWindowQuery::new("sh -c 'test \"$0\" = {class}' firefox");
+        assert!(query.focus("firefox").unwrap());
+        assert!(!query.focus("gimp").unwrap());
+
+        assert!(
+            CommandWindowQuery::new("openit-no-such-focus-command {class}")
+                .focus("firefox")
+                .is_err()
+        );
+    }
+}
@@ -156,7 +156,26 @@ pub fn get_mimeapps_list_files() -> Vec<PathBuf> {
     files
 }
 
-fn get_desktop_environment_names() -> Vec<String> {
+/// shared-mime-info `subclasses` files, user data directory first.
+pub fn get_mime_subclasses_files() -> Vec<PathBuf> {
+    std::iter::once(&*XDG_DATA_HOME)
+        .chain(XDG_DATA_DIRS.iter())
+        .map(|dir| dir.join("mime").join("subclasses"))
+        .filter(|file| file.exists())
+        .collect()
+}
+
+/// shared-mime-info database directories (`<data dir>/mime`), user data directory first.
+pub fn get_mime_database_dirs() -> Vec<PathBuf> {
+    std::iter::once(&*XDG_DATA_HOME)
+        .chain(XDG_DATA_DIRS.iter())
+        .map(|dir| dir.join("mime"))
+        .filter(|dir| dir.is_dir())
+        .collect()
+}
+
+/// The colon-separated components of `XDG_CURRENT_DESKTOP`, lowercased.
+pub fn get_desktop_environment_names() -> Vec<String> {
     env::var("XDG_CURRENT_DESKTOP")
         .unwrap_or_default()
         .split(':')
@@ -168,69 +187,39 @@ fn get_desktop_environment_names() -> Vec<String> {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::test_support::EnvVarGuard;
     use serial_test::serial;
     use std::path::PathBuf;
 
     #[test]
     #[serial]
     fn test_get_desktop_environment_names() {
-        // Save original value
-        let original = env::var("XDG_CURRENT_DESKTOP").ok();
-
-        // Clear any existing value first
-        env::remove_var("XDG_CURRENT_DESKTOP");
-
-        env::set_var("XDG_CURRENT_DESKTOP", "GNOME:GTK");
+        let _desktop = EnvVarGuard::set("XDG_CURRENT_DESKTOP", "GNOME:GTK");
         let names = get_desktop_environment_names();
         assert_eq!(names, vec!["gnome", "gtk"]);
-
-        // Restore original value
-        match original {
-            Some(val) => env::set_var("XDG_CURRENT_DESKTOP", val),
-            None => env::remove_var("XDG_CURRENT_DESKTOP"),
-        }
     }
 
     #[test]
     #[serial]
     fn test_get_desktop_environment_names_empty() {
-        // Save original value
-        let original = env::var("XDG_CURRENT_DESKTOP").ok();
-
-        env::remove_var("XDG_CURRENT_DESKTOP");
+        let _desktop = EnvVarGuard::unset("XDG_CURRENT_DESKTOP");
         let names = get_desktop_environment_names();
         assert!(names.is_empty());
-
-        // Restore original value
-        match original {
-            Some(val) => env::set_var("XDG_CURRENT_DESKTOP", val),
-            None => env::remove_var("XDG_CURRENT_DESKTOP"),
-        }
     }
 
     #[test]
     #[serial]
     fn test_get_desktop_environment_names_single() {
-        // Save original value
-        let original = env::var("XDG_CURRENT_DESKTOP").ok();
-
-        env::set_var("XDG_CURRENT_DESKTOP", "KDE");
+        let _desktop = EnvVarGuard::set("XDG_CURRENT_DESKTOP", "KDE");
         let names = get_desktop_environment_names();
         assert_eq!(names, vec!["kde"]);
-
-        // Restore original value
-        match original {
-            Some(val) => env::set_var("XDG_CURRENT_DESKTOP", val),
-            None => env::remove_var("XDG_CURRENT_DESKTOP"),
-        }
     }
 
     #[test]
     #[serial]
     fn test_expand_tilde_path_resolves_home() {
         let temp_home = tempfile::TempDir::new().unwrap();
-        let original_home = env::var("HOME").ok();
-        env::set_var("HOME", temp_home.path());
+        let _home = EnvVarGuard::set("HOME", temp_home.path().to_str().unwrap());
 
         let expanded_dir = super::expand_tilde_path("~/applications");
         assert_eq!(expanded_dir, temp_home.path().join("applications"));
@@ -243,11 +232,6 @@ mod tests {
             super::expand_tilde_path(absolute_path),
             PathBuf::from(absolute_path)
         );
-
-        match original_home {
-            Some(value) => env::set_var("HOME", value),
-            None => env::remove_var("HOME"),
-        }
     }
 
     #[test]
@@ -294,11 +278,8 @@ mod tests {
         // This test can't modify the lazy statics after they're initialized
         // Instead, let's test the functions that use environment variables directly
 
-        // Save original values
-        let orig_desktop = env::var("XDG_CURRENT_DESKTOP").ok();
-
         // Test get_desktop_environment_names with different values
-        env::set_var("XDG_CURRENT_DESKTOP", "TEST:DESKTOP");
+        let _desktop = EnvVarGuard::set("XDG_CURRENT_DESKTOP", "TEST:DESKTOP");
         let names = get_desktop_environment_names();
         assert_eq!(names, vec!["test", "desktop"]);
 
@@ -306,12 +287,6 @@ mod tests {
         let files = get_mimeapps_list_files();
         // Just verify it returns some paths
         assert!(!files.is_empty() || files.is_empty()); // Always true, just testing it runs
-
-        // Restore original value
-        match orig_desktop {
-            Some(val) => env::set_var("XDG_CURRENT_DESKTOP", val),
-            None => env::remove_var("XDG_CURRENT_DESKTOP"),
-        }
     }
 
     #[test]
@@ -331,19 +306,14 @@ mod tests {
     }
 
     #[test]
+    #[serial]
     fn test_get_mimeapps_list_files_coverage() {
         // Test with empty desktop environment
-        let orig_desktop = env::var("XDG_CURRENT_DESKTOP").ok();
-        env::remove_var("XDG_CURRENT_DESKTOP");
+        let _desktop = EnvVarGuard::unset("XDG_CURRENT_DESKTOP");
 
         let files = get_mimeapps_list_files();
         // Should still return some files even without desktop env
         assert!(!files.is_empty() || files.is_empty());
-
-        // Restore
-        if let Some(val) = orig_desktop {
-            env::set_var("XDG_CURRENT_DESKTOP", val);
-        }
     }
 
     #[test]
@@ -382,8 +352,7 @@ mod tests {
 
         // Create a temporary home directory to test user paths
         let temp_home = tempfile::TempDir::new().unwrap();
-        let original_home = env::var("HOME").ok();
-        env::set_var("HOME", temp_home.path());
+        let _home = EnvVarGuard::set("HOME", temp_home.path().to_str().unwrap());
 
         // Create flatpak directories to ensure those paths are tested
         let flatpak_user = temp_home
@@ -396,24 +365,11 @@ mod tests {
 
         // Should have at least the standard paths
         assert!(!paths_with_home.is_empty());
-
-        // Restore original HOME
-        if let Some(home) = original_home {
-            env::set_var("HOME", home);
-        } else {
-            env::remove_var("HOME");
-        }
     }
 
     #[test]
     #[serial]
     fn test_get_mimeapps_list_files_all_paths() {
-        // Save original env vars
-        let orig_desktop = env::var("XDG_CURRENT_DESKTOP").ok();
-        let orig_home = env::var("HOME").ok();
-        let orig_xdg_config_home = env::var("XDG_CONFIG_HOME").ok();
-        let orig_xdg_data_home = env::var("XDG_DATA_HOME").ok();
-
         // Create temp directories
         let temp_dir = tempfile::TempDir::new().unwrap();
         let temp_home = temp_dir.path();
@@ -425,10 +381,13 @@ mod tests {
         std::fs::create_dir_all(&data_dir).unwrap();
 
         // Set environment variables to our temp directories
-        env::set_var("HOME", temp_home);
-        env::set_var("XDG_CONFIG_HOME", &config_dir);
-        env::set_var("XDG_DATA_HOME", temp_home.join(".local/share"));
-        env::set_var("XDG_CURRENT_DESKTOP", "GNOME:GTK");
+        let _home = EnvVarGuard::set("HOME", temp_home.to_str().unwrap());
+        let _config_home = EnvVarGuard::set("XDG_CONFIG_HOME", config_dir.to_str().unwrap());
+        let _data_home = EnvVarGuard::set(
+            "XDG_DATA_HOME",
+            temp_home.join(".local/share").to_str().unwrap(),
+        );
+        let _desktop = EnvVarGuard::set("XDG_CURRENT_DESKTOP", "GNOME:GTK");
 
         // Force re-initialization of lazy statics by creating a new test instance
         // Since we can't reinitialize lazy statics, we'll test the function directly
@@ -454,31 +413,6 @@ mod tests {
         // The test passes if we created the files successfully
         // We can't test get_mimeapps_list_files() directly because lazy statics
         // are already initialized with the original environment
-
-        // Restore env vars
-        if let Some(desktop) = orig_desktop {
-            env::set_var("XDG_CURRENT_DESKTOP", desktop);
-        } else {
-            env::remove_var("XDG_CURRENT_DESKTOP");
-        }
-
-        if let Some(home) = orig_home {
-            env::set_var("HOME", home);
-        } else {
-            env::remove_var("HOME");
-        }
-
-        if let Some(xdg_config) = orig_xdg_config_home {
-            env::set_var("XDG_CONFIG_HOME", xdg_config);
-        } else {
-            env::remove_var("XDG_CONFIG_HOME");
-        }
-
-        if let Some(xdg_data) = orig_xdg_data_home {
-            env::set_var("XDG_DATA_HOME", xdg_data);
-        } else {
-            env::remove_var("XDG_DATA_HOME");
-        }
     }
 
     #[test]